@@ -0,0 +1,52 @@
+#!/bin/bash
+# <xbar.title>Rusty Pomo</xbar.title>
+# <xbar.desc>Remaining time of a timer started with --serve, with pause and skip.</xbar.desc>
+# <xbar.dependencies>curl</xbar.dependencies>
+# <xbar.var>string(RUSTY_POMO_URL="http://127.0.0.1:7878"): Address given to --serve.</xbar.var>
+# <xbar.var>string(RUSTY_POMO_TOKEN=""): The --serve-token, needed for pause and skip.</xbar.var>
+#
+# Menu bar companion for SwiftBar or xbar: copy it into the plugin folder and
+# run the timer with `--serve 127.0.0.1:7878`, in a terminal or with --background.
+
+URL="${RUSTY_POMO_URL:-http://127.0.0.1:7878}"
+TOKEN="${RUSTY_POMO_TOKEN:-}"
+
+if ! status=$(curl -fsS --max-time 1 "$URL/api/status" 2>/dev/null); then
+  echo "🍅"
+  echo "---"
+  echo "No timer serving on $URL"
+  exit 0
+fi
+
+# plutil reads JSON on macOS 12 and later
+field() { printf '%s' "$status" | plutil -extract "$1" raw -o - - 2>/dev/null; }
+
+remaining=$(field remaining_secs)
+clock=$(printf '%02d:%02d' $((remaining / 60)) $((remaining % 60)))
+case "$(field phase)" in
+  focus) icon="🍅" ;;
+  *) icon="☕" ;;
+esac
+paused=$(field paused)
+if [ "$paused" = "true" ]; then
+  echo "$icon $clock ⏸"
+else
+  echo "$icon $clock"
+fi
+
+echo "---"
+task=$(field task)
+[ -n "$task" ] && echo "$task"
+if [ -z "$TOKEN" ]; then
+  echo "Set RUSTY_POMO_TOKEN to pause and skip"
+  exit 0
+fi
+post() {
+  echo "$1 | bash=/usr/bin/curl param1=-fsS param2=-X param3=POST param4=-H param5=\"Authorization: Bearer $TOKEN\" param6=$URL/api/$2 terminal=false refresh=true"
+}
+if [ "$paused" = "true" ]; then
+  post "Resume" resume
+else
+  post "Pause" pause
+fi
+post "Skip to next phase" skip
@@ -0,0 +1,254 @@
+//! Achievements earned by the history, such as a hundred focus sessions or a
+//! week-long streak. Each is kept in `achievements.json` with when it was
+//! unlocked, so it stays even if the history is trimmed later.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::args::Args;
+use crate::history::{SessionRecord, StoreKind};
+use crate::i18n::tf;
+use crate::json::{self, Value};
+use crate::paths;
+use crate::state::PhaseKind;
+
+/// Something to earn: `goal` or more by `progress` unlocks it.
+pub struct Rule {
+    pub key: &'static str,
+    pub goal: u64,
+    progress: fn(&[SessionRecord]) -> u64,
+}
+
+impl Rule {
+    /// How far the history has come toward the goal, at most the goal.
+    pub fn progress(&self, records: &[SessionRecord]) -> u64 {
+        (self.progress)(records).min(self.goal)
+    }
+
+    pub fn name(&self) -> String {
+        tf(&format!("achievement.{}", self.key), &[])
+    }
+
+    /// What it takes, as a sentence.
+    pub fn about(&self) -> String {
+        tf(&format!("achievement_about.{}", self.key), &[])
+    }
+}
+
+/// In the order the achievements screen lists them.
+pub const RULES: [Rule; 6] = [
+    Rule {
+        key: "first_focus",
+        goal: 1,
+        progress: finished,
+    },
+    Rule {
+        key: "ten_focus",
+        goal: 10,
+        progress: finished,
+    },
+    Rule {
+        key: "hundred_focus",
+        goal: 100,
+        progress: finished,
+    },
+    Rule {
+        key: "streak_7",
+        goal: 7,
+        progress: longest_streak,
+    },
+    Rule {
+        key: "early_bird",
+        goal: 5,
+        progress: before_nine,
+    },
+    Rule {
+        key: "full_day",
+        goal: 8,
+        progress: best_day,
+    },
+];
+
+/// Focus sessions that count as done.
+fn done(records: &[SessionRecord]) -> impl Iterator<Item = &SessionRecord> {
+    records
+        .iter()
+        .filter(|r| r.kind == PhaseKind::Focus && r.completed)
+}
+
+fn finished(records: &[SessionRecord]) -> u64 {
+    done(records).count() as u64
+}
+
+/// Most local days in a row with a finished focus session.
+fn longest_streak(records: &[SessionRecord]) -> u64 {
+    let mut days: Vec<i64> = done(records).map(|r| r.day()).collect();
+    days.sort_unstable();
+    days.dedup();
+    let (mut longest, mut run) = (0, 0);
+    for (i, day) in days.iter().enumerate() {
+        run = if i > 0 && days[i - 1] + 1 == *day {
+            run + 1
+        } else {
+            1
+        };
+        longest = longest.max(run);
+    }
+    longest
+}
+
+fn before_nine(records: &[SessionRecord]) -> u64 {
+    done(records)
+        .filter(|r| r.seconds_of_day() < 9 * 3600)
+        .count() as u64
+}
+
+/// Most finished focus sessions in one local day.
+fn best_day(records: &[SessionRecord]) -> u64 {
+    let mut days: Vec<i64> = done(records).map(|r| r.day()).collect();
+    days.sort_unstable();
+    days.chunk_by(|a, b| a == b)
+        .map(|day| day.len() as u64)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The achievements unlocked so far, by key, with when.
+#[derive(Debug, Default)]
+pub struct Achievements {
+    unlocked: Vec<(String, u64)>,
+    /// Where they're saved; in memory only without one.
+    path: Option<PathBuf>,
+}
+
+impl Achievements {
+    /// Saved next to the history, or kept in memory along with it. `None`
+    /// with `--history=false`, where nothing counts toward them.
+    pub fn from_args(args: &Args) -> Option<Self> {
+        if !args.history {
+            return None;
+        }
+        if args.history_backend == StoreKind::Memory {
+            return Some(Self::default());
+        }
+        Some(paths::achievements_file().map_or_else(Self::default, Self::open))
+    }
+
+    pub fn open(path: PathBuf) -> Self {
+        let unlocked = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| json::parse(&contents).ok())
+            .map(|value| match value {
+                Value::Object(fields) => fields
+                    .into_iter()
+                    .filter_map(|(key, at)| Some((key, at.as_u64()?)))
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .unwrap_or_default();
+        Self {
+            unlocked,
+            path: Some(path),
+        }
+    }
+
+    /// When the achievement for `key` was unlocked, if it was.
+    pub fn unlocked_at(&self, key: &str) -> Option<u64> {
+        self.unlocked
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, at)| *at)
+    }
+
+    /// Unlocks at `now` what `records` have earned and wasn't yet, and returns
+    /// those rules.
+    pub fn check(&mut self, records: &[SessionRecord], now: u64) -> Vec<&'static Rule> {
+        let earned: Vec<&'static Rule> = RULES
+            .iter()
+            .filter(|rule| self.unlocked_at(rule.key).is_none())
+            .filter(|rule| rule.progress(records) >= rule.goal)
+            .collect();
+        if earned.is_empty() {
+            return earned;
+        }
+        self.unlocked
+            .extend(earned.iter().map(|rule| (rule.key.to_string(), now)));
+        if let Err(e) = self.save() {
+            tracing::warn!(error = %e, "cannot save the achievements");
+        }
+        earned
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let value = Value::Object(
+            self.unlocked
+                .iter()
+                .map(|(key, at)| (key.clone(), (*at).into()))
+                .collect(),
+        );
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, format!("{value}\n"))?;
+        fs::rename(tmp, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallclock;
+
+    fn focus(start: u64) -> SessionRecord {
+        SessionRecord::test_focus(start, 1500)
+    }
+
+    #[test]
+    fn unlocks_what_the_history_earns_once_and_keeps_it() {
+        let midnight = 1_700_000_000 - wallclock::seconds_of_local_day(1_700_000_000);
+        let day = 86_400;
+        let mut records: Vec<_> = (0..7)
+            .map(|d| focus(midnight + d * day + 8 * 3600))
+            .collect();
+        // Day 9 breaks the streak
+        records.extend((0..8).map(|i| focus(midnight + 9 * day + 10 * 3600 + i * 1800)));
+        let mut skipped = focus(midnight + 9 * day + 7 * 3600);
+        skipped.completed = false;
+        records.push(skipped);
+        assert_eq!(longest_streak(&records), 7);
+        assert_eq!(before_nine(&records), 7);
+        assert_eq!(best_day(&records), 8);
+        assert_eq!(RULES[2].progress(&records), 15);
+
+        let path = std::env::temp_dir().join(format!(
+            "rusty_pomo_achievements_{}.json",
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        let mut achievements = Achievements::open(path.clone());
+        let first: Vec<_> = achievements
+            .check(&records[..1], 10)
+            .iter()
+            .map(|r| r.key)
+            .collect();
+        assert_eq!(first, ["first_focus"]);
+        let rest: Vec<_> = achievements
+            .check(&records, 20)
+            .iter()
+            .map(|r| r.key)
+            .collect();
+        assert_eq!(rest, ["ten_focus", "streak_7", "early_bird", "full_day"]);
+        assert!(achievements.check(&records, 30).is_empty());
+
+        let reopened = Achievements::open(path.clone());
+        assert_eq!(reopened.unlocked_at("first_focus"), Some(10));
+        assert_eq!(reopened.unlocked_at("full_day"), Some(20));
+        assert_eq!(reopened.unlocked_at("hundred_focus"), None);
+        fs::remove_file(path).unwrap();
+    }
+}
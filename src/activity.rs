@@ -0,0 +1,345 @@
+//! `[distractions]`: during focus, looks at the active window every few seconds
+//! and counts the time spent in the listed apps, which each focus session keeps
+//! in the history. `--track-rest`: during breaks, looks at how long ago the last
+//! key or mouse input was, to tell rested breaks from ones worked through.
+//! Only those counts are kept, never window titles, and nothing leaves the
+//! machine. Reading the active window and input needs the `activity` feature.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::args::Args;
+use crate::doctor::Probe;
+use crate::events::{PhaseEvent, Subscriber};
+use crate::state::PhaseKind;
+use crate::wallclock;
+
+/// Time between looks at the active window and input; each match counts this
+/// much.
+const SAMPLE: Duration = Duration::from_secs(5);
+
+/// What was seen on a thread of its own, in Unix seconds: the apps from
+/// `[distractions]` in the active window during focus, and whether there was
+/// input since the last look during breaks.
+#[derive(Debug, Default)]
+struct Seen {
+    apps: Vec<(u64, String)>,
+    input: Vec<(u64, bool)>,
+}
+
+#[derive(Debug)]
+pub struct Activity {
+    seen: Arc<Mutex<Seen>>,
+    focus: Arc<AtomicBool>,
+    resting: Arc<AtomicBool>,
+}
+
+impl Activity {
+    /// `None` without apps to look for or `--track-rest`, or when built without
+    /// `activity`.
+    pub fn spawn(args: &Args) -> Option<Self> {
+        let (apps, track_rest) = (args.distraction_apps.clone(), args.track_rest);
+        if (apps.is_empty() && !track_rest) || !cfg!(feature = "activity") {
+            return None;
+        }
+        let seen = Arc::new(Mutex::new(Seen::default()));
+        let focus = Arc::new(AtomicBool::new(false));
+        let resting = Arc::new(AtomicBool::new(false));
+        let (found, in_focus, on_break) = (Arc::downgrade(&seen), focus.clone(), resting.clone());
+        thread::spawn(move || {
+            loop {
+                thread::sleep(SAMPLE);
+                // The timer is gone
+                let Some(found) = found.upgrade() else {
+                    return;
+                };
+                let now = wallclock::now_unix();
+                if in_focus.load(Ordering::Relaxed)
+                    && !apps.is_empty()
+                    && let Some(app) = active_window().and_then(|title| matching(&apps, &title))
+                {
+                    let mut found = found.lock().unwrap_or_else(|e| e.into_inner());
+                    found.apps.push((now, app.to_string()));
+                }
+                if on_break.load(Ordering::Relaxed)
+                    && track_rest
+                    && let Some(idle) = idle_time()
+                {
+                    let mut found = found.lock().unwrap_or_else(|e| e.into_inner());
+                    found.input.push((now, idle < SAMPLE));
+                }
+            }
+        });
+        Some(Self {
+            seen,
+            focus,
+            resting,
+        })
+    }
+
+    /// Subscriber that looks at apps during focus and at input during breaks.
+    pub fn follower(&self) -> ActivityPhases {
+        ActivityPhases {
+            focus: self.focus.clone(),
+            resting: self.resting.clone(),
+        }
+    }
+
+    /// Seconds in each app from `start` to `end`, leaving out the `stopped`
+    /// intervals, most first.
+    pub fn tally(&self, start: u64, end: u64, stopped: &[(u64, u64)]) -> Vec<(String, u64)> {
+        let seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        tally(&seen.apps, start, end, stopped)
+    }
+
+    /// Whether the break from `start` to `end` was rested, leaving out the
+    /// `stopped` intervals; `None` if input was never looked at.
+    pub fn rested(&self, start: u64, end: u64, stopped: &[(u64, u64)]) -> Option<bool> {
+        let seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        rested(&seen.input, start, end, stopped)
+    }
+
+    /// Drops what was seen up to `end`, once a session has kept it.
+    pub fn forget(&self, end: u64) {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.apps.retain(|(at, _)| *at > end);
+        seen.input.retain(|(at, _)| *at > end);
+    }
+}
+
+/// Looks at apps during focus sessions, at input during breaks, and at
+/// nothing during other phases.
+#[derive(Debug)]
+pub struct ActivityPhases {
+    focus: Arc<AtomicBool>,
+    resting: Arc<AtomicBool>,
+}
+
+impl Subscriber for ActivityPhases {
+    fn on_event(&mut self, event: &PhaseEvent) {
+        if let Some(kind) = event.phase() {
+            self.focus
+                .store(kind == PhaseKind::Focus, Ordering::Relaxed);
+            let resting = matches!(kind, PhaseKind::ShortBreak | PhaseKind::LongBreak);
+            self.resting.store(resting, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Whether `at` is from `start` to `end` and outside the `stopped` intervals.
+fn counts(at: u64, start: u64, end: u64, stopped: &[(u64, u64)]) -> bool {
+    (start..=end).contains(&at) && !stopped.iter().any(|(s, e)| (*s..*e).contains(&at))
+}
+
+fn tally(
+    samples: &[(u64, String)],
+    start: u64,
+    end: u64,
+    stopped: &[(u64, u64)],
+) -> Vec<(String, u64)> {
+    let mut seconds: Vec<(String, u64)> = Vec::new();
+    let counted = samples
+        .iter()
+        .filter(|(at, _)| counts(*at, start, end, stopped));
+    for (_, app) in counted {
+        match seconds.iter_mut().find(|(name, _)| name == app) {
+            Some((_, secs)) => *secs += SAMPLE.as_secs(),
+            None => seconds.push((app.clone(), SAMPLE.as_secs())),
+        }
+    }
+    seconds.sort_by_key(|(_, secs)| std::cmp::Reverse(*secs));
+    seconds
+}
+
+/// A break is worked through when there was input in half of the looks at it
+/// or more; a key or two to check the time still leaves it rested.
+fn rested(samples: &[(u64, bool)], start: u64, end: u64, stopped: &[(u64, u64)]) -> Option<bool> {
+    let looks: Vec<bool> = samples
+        .iter()
+        .filter(|(at, _)| counts(*at, start, end, stopped))
+        .map(|(_, input)| *input)
+        .collect();
+    let busy = looks.iter().filter(|input| **input).count();
+    (!looks.is_empty()).then_some(busy * 2 < looks.len())
+}
+
+/// The first of `apps` whose name is in `title`, ignoring case.
+fn matching<'a>(apps: &'a [String], title: &str) -> Option<&'a str> {
+    let title = title.to_lowercase();
+    apps.iter()
+        .find(|app| title.contains(&app.to_lowercase()))
+        .map(String::as_str)
+}
+
+/// The title of the active window on X11, through `xdotool`.
+#[cfg(all(feature = "activity", unix, not(target_os = "macos")))]
+fn active_window() -> Option<String> {
+    run("xdotool", &["getactivewindow", "getwindowname"])
+}
+
+/// The name of the frontmost app, through `osascript`.
+#[cfg(all(feature = "activity", target_os = "macos"))]
+fn active_window() -> Option<String> {
+    let script = r#"tell application "System Events" to get name of first application process whose frontmost is true"#;
+    run("osascript", &["-e", script])
+}
+
+/// The title of the foreground window, through PowerShell.
+#[cfg(all(feature = "activity", windows))]
+fn active_window() -> Option<String> {
+    let script = r#"
+        Add-Type -Name W -Namespace U -MemberDefinition '[DllImport("user32.dll")] public static extern IntPtr GetForegroundWindow();'
+        $h = [U.W]::GetForegroundWindow()
+        (Get-Process | Where-Object { $_.MainWindowHandle -eq $h }).MainWindowTitle
+    "#;
+    run(
+        "powershell",
+        &["-NoProfile", "-NonInteractive", "-Command", script],
+    )
+}
+
+#[cfg(not(feature = "activity"))]
+fn active_window() -> Option<String> {
+    None
+}
+
+/// Time since the last key or mouse input on X11, through `xprintidle`.
+#[cfg(all(feature = "activity", unix, not(target_os = "macos")))]
+fn idle_time() -> Option<Duration> {
+    let millis = run("xprintidle", &[])?.parse().ok()?;
+    Some(Duration::from_millis(millis))
+}
+
+/// Time since the last key or mouse input, from the HID system's idle time.
+#[cfg(all(feature = "activity", target_os = "macos"))]
+fn idle_time() -> Option<Duration> {
+    let registry = run("ioreg", &["-c", "IOHIDSystem", "-d", "4"])?;
+    let line = registry
+        .lines()
+        .find(|line| line.contains("\"HIDIdleTime\""))?;
+    let nanos = line.rsplit('=').next()?.trim().parse().ok()?;
+    Some(Duration::from_nanos(nanos))
+}
+
+/// Time since the last key or mouse input, through PowerShell.
+#[cfg(all(feature = "activity", windows))]
+fn idle_time() -> Option<Duration> {
+    let script = r#"
+        Add-Type -Name I -Namespace U -MemberDefinition '
+            [StructLayout(LayoutKind.Sequential)] public struct Info { public uint Size; public uint Time; }
+            [DllImport("user32.dll")] public static extern bool GetLastInputInfo(ref Info info);'
+        $info = New-Object U.I+Info
+        $info.Size = 8
+        [void][U.I]::GetLastInputInfo([ref]$info)
+        [uint32][Environment]::TickCount - $info.Time
+    "#;
+    let millis = run(
+        "powershell",
+        &["-NoProfile", "-NonInteractive", "-Command", script],
+    )?
+    .parse()
+    .ok()?;
+    Some(Duration::from_millis(millis))
+}
+
+#[cfg(not(feature = "activity"))]
+fn idle_time() -> Option<Duration> {
+    None
+}
+
+#[cfg(feature = "activity")]
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    let title = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !title.is_empty()).then_some(title)
+}
+
+/// Whether the active window and input can be read, for `doctor`.
+pub fn probe(args: &Args) -> Probe {
+    let apps = &args.distraction_apps;
+    if apps.is_empty() && !args.track_rest {
+        return Probe::Off("no apps in [distractions] and no --track-rest".to_string());
+    }
+    if !cfg!(feature = "activity") {
+        return Probe::Fail("built without the activity feature".to_string());
+    }
+    if !apps.is_empty() && active_window().is_none() {
+        let problem = if cfg!(target_os = "macos") {
+            "cannot read the frontmost app; allow the terminal to control System Events"
+        } else if cfg!(windows) {
+            "cannot read the foreground window"
+        } else {
+            "cannot read the active window; install xdotool (X11 only)"
+        };
+        return Probe::Fail(problem.to_string());
+    }
+    if args.track_rest && idle_time().is_none() {
+        let problem = if cfg!(target_os = "macos") || cfg!(windows) {
+            "cannot read the time since the last input"
+        } else {
+            "cannot read the time since the last input; install xprintidle (X11 only)"
+        };
+        return Probe::Fail(problem.to_string());
+    }
+    let mut watching = Vec::new();
+    if !apps.is_empty() {
+        watching.push(apps.join(", "));
+    }
+    if args.track_rest {
+        watching.push("input during breaks".to_string());
+    }
+    Probe::Ok(format!("watching for {}", watching.join(" and ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_time_in_distracting_apps() {
+        let apps = ["Slack".to_string(), "YouTube".to_string()];
+        assert_eq!(matching(&apps, "general | ACME - slack"), Some("Slack"));
+        assert_eq!(matching(&apps, "main.rs - editor"), None);
+
+        let sample = |at: u64, app: &str| (at, app.to_string());
+        let samples = [
+            sample(95, "Slack"),
+            sample(100, "Slack"),
+            sample(105, "YouTube"),
+            sample(110, "Slack"),
+            sample(115, "Slack"),
+            sample(120, "Slack"),
+        ];
+        // 115 falls in a pause, and 120 after the session
+        assert_eq!(
+            tally(&samples, 100, 119, &[(113, 118)]),
+            [("Slack".to_string(), 10), ("YouTube".to_string(), 5)]
+        );
+        assert!(tally(&samples, 200, 300, &[]).is_empty());
+    }
+
+    #[test]
+    fn tells_rested_breaks_from_ones_worked_through() {
+        let looks = |inputs: &[bool]| -> Vec<(u64, bool)> {
+            inputs
+                .iter()
+                .enumerate()
+                .map(|(i, input)| (100 + 5 * i as u64, *input))
+                .collect()
+        };
+        // One look with input, to check the time
+        let checked = looks(&[false, true, false, false]);
+        assert_eq!(rested(&checked, 100, 120, &[]), Some(true));
+        let typing = looks(&[true, true, false, false]);
+        assert_eq!(rested(&typing, 100, 120, &[]), Some(false));
+        // The typing at 100 and 105 was while paused
+        assert_eq!(rested(&typing, 100, 120, &[(100, 110)]), Some(true));
+        assert_eq!(rested(&typing, 200, 300, &[]), None);
+    }
+}
@@ -0,0 +1,322 @@
+//! Plain-text announcements for `--accessible`: whole sentences printed as new
+//! lines, which screen readers pick up, instead of a redrawn screen.
+
+use std::time::{Duration, Instant};
+
+use crate::i18n::{self, t, tf};
+use crate::state::{AppState, PhaseKind};
+use crate::timefmt::spoken;
+use crate::ui::task_progress;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Seen {
+    kind: PhaseKind,
+    session_index: u64,
+    started_at: Instant,
+    paused: bool,
+    snoozed_until: Option<Instant>,
+    skip_pending: bool,
+    finished: bool,
+    rating_prompt: bool,
+    cycle_prompt: bool,
+    extension_offer: bool,
+    meeting_warned: Option<u64>,
+    off_hours: bool,
+    message: Option<String>,
+    /// Remaining time in whole announcement intervals, rounded up.
+    interval: u64,
+}
+
+/// Turns state changes into sentences: phase starts, pauses, skip prompts and the
+/// remaining time every `every`.
+pub struct Announcer {
+    every: Duration,
+    bell: bool,
+    last: Option<Seen>,
+}
+
+impl Announcer {
+    pub fn new(every: Duration, bell: bool) -> Self {
+        Self {
+            every: every.max(Duration::from_secs(1)),
+            bell,
+            last: None,
+        }
+    }
+
+    /// Sentences for what changed since the previous call.
+    pub fn lines(&mut self, app: &AppState, now: Instant) -> Vec<String> {
+        let remaining = remaining(app, now);
+        let seen = Seen {
+            kind: app.current_phase.kind,
+            session_index: app.session_index,
+            started_at: app.phase_started_at,
+            paused: app.paused,
+            snoozed_until: app.snoozed_until,
+            skip_pending: app.skip_pending(now),
+            finished: app.finished,
+            rating_prompt: app.rating_prompt.is_some(),
+            cycle_prompt: app.cycle_prompt,
+            extension_offer: app.extension_offer.is_some(),
+            meeting_warned: app.meeting_warned,
+            off_hours: app.off_hours(),
+            message: app.message.clone(),
+            interval: remaining.as_secs().div_ceil(self.every.as_secs()),
+        };
+        let mut lines = Vec::new();
+        let phase_changed = !seen.finished
+            && self.last.as_ref().is_none_or(|last| {
+                (last.kind, last.session_index) != (seen.kind, seen.session_index)
+                    || (!seen.paused && last.started_at != seen.started_at && !last.paused)
+            });
+        if phase_changed {
+            let bell = match seen.kind {
+                PhaseKind::WarmUp | PhaseKind::Focus if self.bell => "\x07",
+                PhaseKind::ShortBreak
+                | PhaseKind::LongBreak
+                | PhaseKind::CycleReview
+                | PhaseKind::Lunch
+                | PhaseKind::Custom(_)
+                    if self.bell =>
+                {
+                    "\x07\x07"
+                }
+                _ => "",
+            };
+            lines.push(format!("{bell}{}", phase_started(app, remaining)));
+        } else if let Some(last) = &self.last {
+            if let Some(left) = app.snoozed_for(now)
+                && last.snoozed_until != seen.snoozed_until
+            {
+                // Whole seconds, rounded up, so a fresh snooze reads as the full length
+                let left = Duration::from_secs(left.as_secs_f64().ceil() as u64);
+                lines.push(tf("announce.snoozed", &[("duration", &spoken(left))]));
+            } else if last.paused != seen.paused {
+                let key = if seen.paused {
+                    "announce.paused"
+                } else {
+                    "announce.resumed"
+                };
+                lines.push(tf(key, &[("duration", &spoken(remaining))]));
+            }
+            if seen.skip_pending && !last.skip_pending {
+                lines.push(t("announce.skip").to_string());
+            }
+            if seen.finished && !last.finished && app.args.cycles.is_some() {
+                lines.push(i18n::done(app.completed_cycles, app.completed_focus));
+            }
+            if !seen.paused
+                && !seen.finished
+                && seen.interval < last.interval
+                && !remaining.is_zero()
+            {
+                let left = Duration::from_secs(seen.interval * self.every.as_secs());
+                lines.push(tf(
+                    "announce.left",
+                    &[
+                        ("duration", &spoken(left)),
+                        ("phase", i18n::phase_inline(seen.kind)),
+                    ],
+                ));
+            }
+        }
+        if let Some(start) = seen.meeting_warned
+            && self
+                .last
+                .as_ref()
+                .is_none_or(|last| last.meeting_warned != seen.meeting_warned)
+        {
+            lines.push(meeting_soon(app, start, now));
+        }
+        if let Some(hours) = &app.args.work_hours
+            && seen.off_hours
+            && !self.last.as_ref().is_some_and(|last| last.off_hours)
+        {
+            lines.push(tf("announce.off_hours", &[("hours", &hours.span())]));
+        }
+        if let Some(message) = &seen.message
+            && self
+                .last
+                .as_ref()
+                .is_none_or(|last| last.message != seen.message)
+        {
+            lines.push(message.clone());
+        }
+        if seen.rating_prompt && !self.last.as_ref().is_some_and(|last| last.rating_prompt) {
+            lines.push(t("announce.rate").to_string());
+        }
+        if let (true, Some(more)) = (seen.extension_offer, app.args.offer_extension)
+            && !self.last.as_ref().is_some_and(|last| last.extension_offer)
+        {
+            lines.push(tf("announce.extend", &[("duration", &spoken(more))]));
+        }
+        if seen.cycle_prompt && !self.last.as_ref().is_some_and(|last| last.cycle_prompt) {
+            lines.push(t("announce.review").to_string());
+        }
+        self.last = Some(seen);
+        lines
+    }
+}
+
+fn phase_started(app: &AppState, remaining: Duration) -> String {
+    let kind = app.current_phase.kind;
+    let mut line = tf(
+        "announce.started",
+        &[
+            ("phase", &sentence_start(i18n::phase_inline(kind))),
+            ("duration", &spoken(remaining)),
+        ],
+    );
+    if kind == PhaseKind::Focus {
+        let session = (app.session_index + 1).to_string();
+        line.push(' ');
+        line.push_str(&tf("announce.session", &[("n", &session)]));
+    }
+    if let Some(quote) = app.break_quote() {
+        line.push(' ');
+        line.push_str(quote);
+    }
+    line
+}
+
+fn meeting_soon(app: &AppState, start: u64, now: Instant) -> String {
+    let summary = app
+        .meetings
+        .iter()
+        .find(|m| m.start == start)
+        .map_or("", |m| m.summary.as_str());
+    let left = Duration::from_secs(start.saturating_sub(app.clock.unix()).div_ceil(60) * 60);
+    let mut line = tf(
+        "announce.meeting",
+        &[
+            ("summary", &sentence_start(i18n::meeting_name(summary))),
+            ("duration", &spoken(left)),
+        ],
+    );
+    if app.meeting_clash(now).is_some() {
+        line.push(' ');
+        line.push_str(t("announce.fit"));
+    }
+    line
+}
+
+/// Everything the TUI shows, as one sentence.
+pub fn status(app: &AppState, now: Instant) -> String {
+    let key = if app.paused {
+        "announce.status_paused"
+    } else {
+        "announce.status"
+    };
+    let mut parts = vec![tf(
+        key,
+        &[
+            (
+                "phase",
+                &sentence_start(i18n::phase_inline(app.current_phase.kind)),
+            ),
+            ("duration", &spoken(remaining(app, now))),
+        ],
+    )];
+    parts.push(tf(
+        "announce.completed",
+        &[("n", &app.completed_focus.to_string())],
+    ));
+    if let Some(project) = &app.args.project {
+        parts.push(tf("announce.project", &[("project", project)]));
+    }
+    if let Some(task) = &app.args.task {
+        let progress = task_progress(app.task_sessions(), app.args.estimate);
+        parts.push(tf(
+            "announce.task",
+            &[
+                ("task", task),
+                ("progress", progress.trim_end_matches(" 🍅")),
+            ],
+        ));
+    }
+    parts.join(" ")
+}
+
+/// Remaining time rounded up to whole seconds, so a fresh phase reads "25 minutes".
+fn remaining(app: &AppState, now: Instant) -> Duration {
+    Duration::from_secs(app.time_remaining(now).as_secs_f64().ceil() as u64)
+}
+
+/// Upper-cases the first letter, for phase names that open a sentence.
+fn sentence_start(s: &str) -> String {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map(|c| c.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::test_app;
+
+    #[test]
+    fn announces_changes_once() {
+        let mut app = test_app();
+        let mut announcer = Announcer::new(Duration::from_secs(20), false);
+        let now = app.now();
+        assert_eq!(
+            announcer.lines(&app, now),
+            ["Focus started, 1 minute. Session 1."]
+        );
+        assert!(announcer.lines(&app, now).is_empty());
+        assert_eq!(
+            announcer.lines(&app, now + Duration::from_secs(21)),
+            ["40 seconds left in focus."]
+        );
+        app.toggle_pause();
+        let lines = announcer.lines(&app, app.now());
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("Paused, "), "{lines:?}");
+        app.toggle_pause();
+        app.skip();
+        let lines = announcer.lines(&app, app.now());
+        assert_eq!(
+            lines,
+            ["Short break started, 1 minute. Rest is part of the work."]
+        );
+    }
+
+    #[test]
+    fn announces_the_end_of_the_last_cycle() {
+        let mut app = test_app();
+        app.args.cycles = Some(1);
+        app.skip();
+        app.skip();
+        app.skip();
+        let mut announcer = Announcer::new(Duration::from_secs(20), false);
+        announcer.lines(&app, app.now());
+        app.skip();
+        assert_eq!(
+            announcer.lines(&app, app.now()),
+            ["1 cycle and 0 focus sessions completed."]
+        );
+    }
+
+    #[test]
+    fn rings_distinct_bells_per_phase() {
+        let mut app = test_app();
+        let mut announcer = Announcer::new(Duration::from_secs(60), true);
+        assert!(announcer.lines(&app, app.now())[0].starts_with("\x07F"));
+        app.skip();
+        assert!(announcer.lines(&app, app.now())[0].starts_with("\x07\x07S"));
+    }
+
+    #[test]
+    fn describes_full_status() {
+        let mut app = test_app();
+        app.args.task = Some("Docs".to_string());
+        app.args.estimate = Some(3);
+        let line = status(&app, app.phase_started_at);
+        assert_eq!(
+            line,
+            "Focus, 1 minute left. 0 focus sessions completed. Task Docs, 0/3 pomodoros."
+        );
+    }
+}
@@ -1,6 +1,30 @@
-use clap::Parser;
+use std::path::PathBuf;
+use std::time::Duration;
 
+use clap::{Parser, Subcommand};
+
+use crate::bell::BellMode;
+use crate::clock::{Simulation, parse_simulation};
+use crate::completions::Shell;
+use crate::config::PushConfig;
+use crate::export::{ExportFormat, parse_day};
+use crate::history::StoreKind;
+use crate::i18n::Lang;
+use crate::import::ImportFormat;
+use crate::integrations::media::MusicMode;
+use crate::integrations::telegram::Bot;
+use crate::mascot::Mascot;
+use crate::notifications::{Channel, NotifierKind, Route, Style};
+use crate::paths;
+use crate::phases::CustomPhase;
+use crate::report::ReportFormat;
+use crate::score::Weights;
 use crate::theme::Theme;
+use crate::timefmt::TimeFormat;
+use crate::ui::LayoutPreset;
+use crate::validate::{parse_long_every, parse_phase_length};
+use crate::wallclock::{self, Zone};
+use crate::work_hours::{Lunch, WorkHours};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -8,72 +32,936 @@ use crate::theme::Theme;
     about = "Minimalist, visually pleasing Pomodoro CLI",
     version
 )]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Timer settings given without a subcommand, as in `rusty_pomo --focus 50`.
+    #[command(flatten)]
+    pub args: Args,
+}
+
+/// Settings for a timer run. `rusty_pomo run` takes them, and so does plain
+/// `rusty_pomo`, as before subcommands existed.
+#[derive(clap::Args, Debug)]
 pub struct Args {
-    /// Focus minutes
-    #[arg(short = 'f', long, default_value_t = 25)]
-    pub focus: u64,
-    /// Short break minutes
-    #[arg(short = 's', long, default_value_t = 5)]
-    pub short: u64,
-    /// Long break minutes
-    #[arg(short = 'l', long, default_value_t = 15)]
-    pub long: u64,
-    /// Number of focus sessions before long break
-    #[arg(short = 'n', long, default_value_t = 4)]
+    /// Focus length, e.g. 25m, 90s or 1h30m (a bare number is minutes)
+    #[arg(short = 'f', long, default_value = "25m", value_parser = parse_phase_length, value_name = "DURATION")]
+    pub focus: Duration,
+    /// Short break length
+    #[arg(short = 's', long, default_value = "5m", value_parser = parse_phase_length, value_name = "DURATION")]
+    pub short: Duration,
+    /// Long break length
+    #[arg(short = 'l', long, default_value = "15m", value_parser = parse_phase_length, value_name = "DURATION")]
+    pub long: Duration,
+    /// Number of focus sessions before long break; 0 for no long breaks
+    #[arg(short = 'n', long, default_value_t = 4, value_parser = parse_long_every)]
     pub long_every: u64,
+    /// A warm-up before the first focus session of the day, e.g. 5m to plan tasks
+    #[arg(long, value_parser = parse_phase_length, value_name = "DURATION")]
+    pub warm_up: Option<Duration>,
+    /// A review after each long break, to rate the cycle and look over your tasks, e.g. 5m
+    #[arg(long, value_parser = parse_phase_length, value_name = "DURATION")]
+    pub cycle_review: Option<Duration>,
+    /// Count focus sessions ended early past this share of their length as completed,
+    /// and ones short of it as void, e.g. 80
+    #[arg(long, value_name = "PERCENT", value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub partial_credit: Option<u8>,
+    /// Focus sessions to finish each day; shows when today's pace gets there
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub daily_goal: Option<u64>,
+    /// When focus runs out, offer this much more for 10 seconds before the break, e.g. 5m
+    #[arg(long, value_parser = parse_phase_length, value_name = "DURATION")]
+    pub offer_extension: Option<Duration>,
     /// Theme
     #[arg(long, value_enum, default_value_t = Theme::Dracula)]
     pub theme: Theme,
+    /// ASCII art that grows during focus and cheers when it's done
+    #[arg(long, value_enum, default_value_t = Mascot::Off)]
+    pub mascot: Mascot,
+    /// How the screen is arranged: wide for big monitors, vertical for narrow panes,
+    /// minimal for tiny windows
+    #[arg(long, value_enum, default_value_t = LayoutPreset::Default)]
+    pub layout: LayoutPreset,
+    /// Shown with the number of focus sessions finished today, e.g. "🍅 x3"; empty hides the count
+    #[arg(long, value_name = "TEXT", default_value = "🍅")]
+    pub counter_icon: String,
     /// Enable desktop notifications
     #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
     pub notifications: bool,
     /// Notification sound name (platform-dependent). Example macOS: Ping, Submarine. Linux: message-new-instant
     #[arg(long)]
     pub notification_sound: Option<String>,
+    /// How notifications are delivered; stdout needs --background or --accessible
+    #[arg(long, value_enum, default_value_t = NotifierKind::Desktop)]
+    pub notifier: NotifierKind,
     /// Notification duration in seconds (if supported by OS; macOS ignores)
     #[arg(long, default_value_t = 10)]
     pub notification_seconds: u64,
     /// macOS only: bundle identifier to use for notifications (controls icon). Requires the app to be installed with this bundle id and icon.
     #[arg(long)]
     pub macos_bundle_id: Option<String>,
+    /// Windows only: AppUserModelID to attribute toasts to (default: one registered for Rusty Pomo)
+    #[arg(long, value_name = "ID")]
+    pub windows_app_id: Option<String>,
+    /// Active project; picks up per-project durations from the config file
+    #[arg(short = 'p', long)]
+    pub project: Option<String>,
+    /// Use this profile's durations from the config, instead of the one `[schedule]` picks for today
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+    /// Task being worked on, shown below the timer
+    #[arg(short = 't', long)]
+    pub task: Option<String>,
+    /// Estimated number of pomodoros for the task
+    #[arg(short = 'e', long, requires = "task")]
+    pub estimate: Option<u64>,
+    /// Require pressing n twice to skip a focus session
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    pub confirm_skip: bool,
+    /// Record sessions to the history file
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    pub history: bool,
+    /// Where the history is kept
+    #[arg(long, value_enum, default_value_t = StoreKind::Jsonl)]
+    pub history_backend: StoreKind,
+    /// Directory for the JSON Lines history (default: the platform data dir)
+    #[arg(long)]
+    pub history_dir: Option<PathBuf>,
+    /// Keep all files (config, history, achievements, logs) in this one directory
+    #[arg(long, value_name = "DIR", conflicts_with = "context")]
+    pub data_dir: Option<PathBuf>,
+    /// Use a separate config and history, e.g. work or personal (default: `RUSTY_POMO_CONTEXT`, then the one picked with `context use`)
+    #[arg(long, value_name = "NAME", value_parser = paths::parse_context)]
+    pub context: Option<String>,
+    /// Time zone for today, days and hours: UTC, +HH:MM or a name like Europe/Madrid (default: the system's)
+    #[arg(long, value_name = "ZONE", value_parser = wallclock::parse_zone)]
+    pub tz: Option<Zone>,
+    /// Serve the HTTP API on this address, e.g. 127.0.0.1:7878
+    #[arg(long, value_name = "ADDR")]
+    pub serve: Option<String>,
+    /// Bearer token required by the API's control endpoints
+    #[arg(long, requires = "serve")]
+    pub serve_token: Option<String>,
+    /// MQTT broker to publish state to, with Home Assistant discovery
+    #[arg(long, value_name = "HOST:PORT")]
+    pub mqtt: Option<String>,
+    /// Control music playback at phase transitions
+    #[arg(long, value_enum, default_value_t = MusicMode::Off)]
+    pub music: MusicMode,
+    /// Run the executables in the config directory's plugins folder on each event
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    pub plugins: bool,
+    /// Sound to loop during focus: brown, white or an audio file
+    #[arg(long, value_name = "TRACK")]
+    pub ambient: Option<String>,
+    /// Sound to loop during breaks: brown, white or an audio file
+    #[arg(long, value_name = "TRACK")]
+    pub ambient_break: Option<String>,
+    /// Ambient sound volume in percent
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub ambient_volume: u8,
+    /// Screen-reader friendly mode: plain announcements instead of the full-screen UI
+    #[arg(long)]
+    pub accessible: bool,
+    /// Minutes between remaining-time announcements in accessible mode
+    #[arg(long, default_value_t = 5, value_name = "MIN", value_parser = clap::value_parser!(u64).range(1..))]
+    pub announce_every: u64,
+    /// Ring the terminal bell and flash the screen at phase changes; `auto` only while notifications are off or failing. In accessible mode it rings once for focus and twice for breaks
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = BellMode::Auto, num_args = 0..=1, default_missing_value = "on")]
+    pub bell: BellMode,
+    /// Notify with terminal escapes and bells, use 256 colors and play no sound, as suits SSH (default: on over SSH)
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true", action = clap::ArgAction::Set)]
+    pub remote_mode: Option<bool>,
+    /// Language for the timer screen, notifications and announcements (default: from LANG)
+    #[arg(long, value_enum)]
+    pub lang: Option<Lang>,
+    /// How the remaining time is shown
+    #[arg(long, value_enum, default_value_t = TimeFormat::Auto)]
+    pub time_format: TimeFormat,
+    /// Stop after this many full cycles (focus sessions up to and including a long break)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub cycles: Option<u64>,
+    /// Quit when the last cycle ends instead of showing the completion screen
+    #[arg(long, requires = "cycles")]
+    pub exit_on_complete: bool,
+    /// Run a single focus session and exit: status 0 if it ran to the end, 3 if not
+    #[arg(long, conflicts_with = "cycles")]
+    pub once: bool,
+    /// Print the phases this would run, with the time each would start, and exit
+    #[arg(long)]
+    pub plan: bool,
+    /// No screen at all: only notifications at phase changes; stop with Ctrl-C or SIGTERM
+    #[arg(long, conflicts_with = "accessible")]
+    pub background: bool,
+    /// With --background, leave the terminal and print the timer's PID (Unix)
+    #[arg(long, requires = "background")]
+    pub detach: bool,
+    /// How long `z` (or a notification's Snooze button) holds the phase, in minutes
+    #[arg(long, default_value_t = 5, value_name = "MIN", value_parser = clap::value_parser!(u64).range(1..=120))]
+    pub snooze_minutes: u64,
+    /// Ask how each finished focus session went, 1 to 5, and save it to the history
+    #[arg(long)]
+    pub rate_sessions: bool,
+    /// Show a line from this file during breaks, one per line, instead of the built-in ones
+    #[arg(long, value_name = "PATH")]
+    pub quotes_file: Option<PathBuf>,
+    /// Show the icon and a progress ring in terminals with kitty or iTerm2 graphics
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    pub graphics: bool,
+    /// Show a breathing exercise during short breaks
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
+    pub breathing: bool,
+    /// Notice typing and mouse use during breaks, and save whether each break was rested
+    #[arg(long)]
+    pub track_rest: bool,
+    /// Break lines from `[breaks] quotes` in the config; there's no flag for them.
+    #[arg(skip)]
+    pub quotes: Vec<String>,
+    /// Break suggestions from `[breaks] suggestions`, on top of the built-in ones.
+    #[arg(skip)]
+    pub suggestions: Vec<String>,
+    /// Meetings to plan focus sessions around: an ICS file, or an http:// ICS or CalDAV URL
+    #[arg(long, value_name = "PATH|URL")]
+    pub calendar: Option<String>,
+    /// Warn this many minutes before a meeting starts
+    #[arg(long, default_value_t = 10, value_name = "MIN", value_parser = clap::value_parser!(u64).range(1..=120))]
+    pub meeting_warning: u64,
+    /// Shorten focus sessions that would run into a meeting, so they end as it starts
+    #[arg(long)]
+    pub fit_to_meetings: bool,
+    /// The working day from `[work_hours]`; there's no flag for it.
+    #[arg(skip)]
+    pub work_hours: Option<WorkHours>,
+    /// The daily pause from `[lunch]`; there's no flag for it.
+    #[arg(skip)]
+    pub lunch: Option<Lunch>,
+    /// Phases of your own from `[phases.NAME]`; there are no flags for them.
+    #[arg(skip)]
+    pub phases: Vec<CustomPhase>,
+    /// Apps to count time in during focus, from `[distractions]`; there's no flag for them.
+    #[arg(skip)]
+    pub distraction_apps: Vec<String>,
+    /// How the focus score weighs its parts, from `[score]`; there are no flags for them.
+    #[arg(skip)]
+    pub score_weights: Weights,
+    /// Events sent elsewhere than `--notifier`, from `[notifications]`; there's no flag for them.
+    #[arg(skip)]
+    pub notification_routes: Vec<(Route, Vec<Channel>)>,
+    /// Urgency and hints per event, from `[notifications.style]`; there are no flags for them.
+    #[arg(skip)]
+    pub notification_styles: Vec<(Route, Style)>,
+    /// Where the `ntfy` and `email` channels send to, from `[push]`; there are no flags for it.
+    #[arg(skip)]
+    pub push: PushConfig,
+    /// The bot from `[telegram]`; there are no flags for it.
+    #[arg(skip)]
+    pub telegram: Option<Bot>,
+
+    /// Redraw every 15 seconds while paused or before the final minute, to save battery
+    #[arg(long)]
+    pub low_power: bool,
+    /// Render N frames off-screen, print the timing and exit (for measuring the UI)
+    #[arg(long, hide = true, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub bench_render: Option<u64>,
+    /// Demo mode: speed time up so a phase lasts this long, e.g. focus=2s; nothing is saved to the history
+    #[arg(long, value_name = "PHASE=DURATION", value_parser = parse_simulation)]
+    pub simulate: Option<Simulation>,
+    /// For screencasts and trying out transitions: run SPEED times faster (default 60) with notifications, the bell and the history off
+    #[arg(long, value_name = "SPEED", num_args = 0..=1, require_equals = true, default_missing_value = "60", conflicts_with = "simulate", value_parser = clap::value_parser!(u32).range(2..=3600))]
+    pub demo: Option<u32>,
+    /// Write diagnostics to this file; with -v and no file, rusty_pomo.log in the state directory
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+    /// Log more: -v adds phase changes, -vv every key and command, -vvv everything
+    #[arg(short = 'v', long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run the timer (the default when no subcommand is given)
+    Run(Box<Args>),
+    /// Say what the running timer is doing: its phase and the time left
+    Status,
+    /// Summarize focus time from the history
+    Stats {
+        /// How many days back to summarize, including today
+        #[arg(long, default_value_t = 7, value_parser = clap::value_parser!(u64).range(1..))]
+        days: u64,
+        /// Show patterns instead of totals: best hours, weekdays, skips, pauses and the weekly trend
+        #[arg(long)]
+        insights: bool,
+        /// Write the period's totals to FILE for a friends' leaderboard instead
+        #[arg(long, value_name = "FILE", conflicts_with = "insights")]
+        leaderboard_export: Option<PathBuf>,
+        /// Name in the leaderboard file (default: $USER)
+        #[arg(long, requires = "leaderboard_export")]
+        name: Option<String>,
+        /// Browse today, the week, the month, projects and tasks full screen
+        #[arg(long, conflicts_with_all = ["insights", "leaderboard_export"])]
+        tui: bool,
+        /// Finished focus sessions a day that tick it in the --tui calendar
+        #[arg(long, value_name = "N", default_value_t = 4, requires = "tui", value_parser = clap::value_parser!(u64).range(1..))]
+        daily_goal: u64,
+        /// Only sessions of this project
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// Write the history to standard output
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// First local day to include
+        #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_day)]
+        since: Option<i64>,
+        /// Last local day to include
+        #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_day)]
+        until: Option<i64>,
+        /// Only sessions of this project
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// Write a review of the last week or month as Markdown or HTML to standard output
+    Report {
+        /// The last 7 days, today included (the default)
+        #[arg(long, conflicts_with = "month")]
+        week: bool,
+        /// The last 30 days, today included
+        #[arg(long)]
+        month: bool,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Md)]
+        format: ReportFormat,
+        /// Only sessions of this project
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// List contexts, or pick the one to use when `--context` isn't given
+    Context {
+        #[command(subcommand)]
+        action: ContextAction,
+    },
+    /// Inspect the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Share durations, theme and sounds as a file, or take them over from one
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// Merge sessions tracked elsewhere into the history file
+    Import {
+        /// Format of the file to import
+        #[arg(long, value_enum, default_value_t = ImportFormat::Csv)]
+        from: ImportFormat,
+        /// File to import
+        file: PathBuf,
+    },
+    /// Look after the history files
+    History {
+        #[command(subcommand)]
+        action: HistoryAction,
+    },
+    /// Carry the config, plugins, history and achievements to another machine
+    Backup {
+        #[command(subcommand)]
+        action: BackupAction,
+    },
+    /// Keep the history consistent across machines
+    Sync {
+        #[command(subcommand)]
+        action: SyncAction,
+    },
+    /// Rank leaderboard files from `stats --leaderboard-export`
+    Leaderboard {
+        #[command(subcommand)]
+        action: LeaderboardAction,
+    },
+    /// Drive Philips Hue lights at phase transitions
+    Hue {
+        #[command(subcommand)]
+        action: HueAction,
+    },
+    /// Print a shell completion script to standard output
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print the man page (roff) to standard output
+    Manpage,
+    /// Check notifications, sound, the config, file locations and integrations
+    Doctor,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print where the config file is read from
+    Path,
+    /// Write a fully commented config file to start from
+    Init {
+        /// Replace an existing config file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Open the config file in $VISUAL or $EDITOR and check it before saving
+    Edit,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ContextAction {
+    /// List the contexts, marking the one in use
+    List,
+    /// Use this context from now on; `default` goes back to the usual files
+    Use {
+        #[arg(value_parser = paths::parse_context)]
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplateAction {
+    /// Write the config's durations, theme, sounds, profiles and schedule as a template
+    Export {
+        /// File to write; standard output when omitted
+        file: Option<PathBuf>,
+        /// Name recorded in the template
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Merge a template into the config file, replacing the settings it has
+    Import {
+        /// Template file to merge in
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LeaderboardAction {
+    /// Rank every leaderboard file in a folder by focus time
+    Show {
+        /// Folder of leaderboard files
+        dir: PathBuf,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SyncAction {
+    /// Fold sync-conflict copies and other history directories into the local history
+    Merge {
+        /// Other history directories to merge in (e.g. a copy from another machine)
+        dirs: Vec<PathBuf>,
+    },
+    /// Upload the sessions still queued for the `[remote]` server
+    Push,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryAction {
+    /// Roll sessions older than the retention period into one line per day, kind, project and task
+    Compact {
+        /// Days of sessions to keep as they are, today included (default: `keep_days` in `[history]`, or 365)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        keep_days: Option<u64>,
+        /// Only say what would be rolled up
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Look for corrupt lines, impossible times and overlapping sessions
+    Check {
+        /// Repair what can be repaired and move the rest to `quarantine/`
+        #[arg(long)]
+        fix: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BackupAction {
+    /// Write everything rusty_pomo keeps to a tar file
+    Create { archive: PathBuf },
+    /// Put a backup's files in place, merging its history with this one
+    Restore {
+        archive: PathBuf,
+        /// Replace files that exist and differ from the backup's
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HueAction {
+    /// Register with a bridge (press its link button) and save the credentials to the config
+    Pair {
+        /// Bridge address; discovered on the local network when omitted
+        #[arg(long)]
+        bridge: Option<String>,
+    },
+    /// List the paired bridge's scenes, for `focus_scene` and `break_scene`
+    Scenes,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::wallclock;
+    use clap::error::ErrorKind;
 
     #[test]
     fn parses_defaults() {
-        let args = Args::parse_from(["rusty_pomo"]);
-        assert_eq!(args.focus, 25);
-        assert_eq!(args.short, 5);
-        assert_eq!(args.long, 15);
+        let cli = Cli::parse_from(["rusty_pomo"]);
+        assert!(cli.command.is_none());
+        let args = cli.args;
+        assert_eq!(args.focus, Duration::from_secs(25 * 60));
+        assert_eq!(args.short, Duration::from_secs(5 * 60));
+        assert_eq!(args.long, Duration::from_secs(15 * 60));
         assert_eq!(args.long_every, 4);
+        assert_eq!(args.warm_up, None);
+        assert_eq!(args.cycle_review, None);
+        assert_eq!(args.partial_credit, None);
+        assert_eq!(args.offer_extension, None);
         assert!(args.notifications);
         assert_eq!(args.notification_seconds, 10);
+        assert_eq!(args.project, None);
+        assert_eq!(args.profile, None);
+        assert_eq!(args.task, None);
+        assert_eq!(args.estimate, None);
+        assert!(args.confirm_skip);
+        assert!(args.history);
+        assert_eq!(args.history_backend, StoreKind::Jsonl);
+        assert_eq!(args.history_dir, None);
+        assert_eq!(args.data_dir, None);
+        assert_eq!(args.context, None);
+        assert_eq!(args.serve, None);
+        assert_eq!(args.mqtt, None);
+        assert_eq!(args.music, MusicMode::Off);
+        assert!(args.plugins);
+        assert_eq!(args.ambient, None);
+        assert_eq!(args.ambient_break, None);
+        assert_eq!(args.ambient_volume, 50);
+        assert!(!args.accessible);
+        assert_eq!(args.announce_every, 5);
+        assert_eq!(args.bell, BellMode::Auto);
+        assert_eq!(args.remote_mode, None);
+        assert_eq!(args.lang, None);
+        assert_eq!(args.time_format, TimeFormat::Auto);
+        assert_eq!(args.mascot, Mascot::Off);
+        assert_eq!(args.layout, LayoutPreset::Default);
+        assert_eq!(args.counter_icon, "🍅");
+        assert_eq!(args.daily_goal, None);
+        assert_eq!(args.cycles, None);
+        assert!(!args.plan);
+        assert!(!args.exit_on_complete);
+        assert!(!args.once);
+        assert!(!args.background);
+        assert!(!args.detach);
+        assert!(!args.low_power);
+        assert_eq!(args.bench_render, None);
+        assert_eq!(args.simulate, None);
+        assert_eq!(args.demo, None);
+        assert_eq!(args.log_file, None);
+        assert_eq!(args.verbose, 0);
+        assert_eq!(args.notifier, NotifierKind::Desktop);
+        assert_eq!(args.snooze_minutes, 5);
+        assert!(!args.rate_sessions);
+        assert_eq!(args.quotes_file, None);
+        assert!(args.breathing);
+        assert!(args.graphics);
+        assert!(!args.track_rest);
+        assert_eq!(args.calendar, None);
+        assert_eq!(args.meeting_warning, 10);
+        assert!(!args.fit_to_meetings);
     }
 
     #[test]
     fn parses_overrides() {
-        let args = Args::parse_from([
+        let args = Cli::parse_from([
             "rusty_pomo",
             "--focus",
             "50",
             "--short",
-            "10",
+            "2m30s",
             "--long",
-            "20",
+            "20m",
             "--long-every",
             "3",
             "--notifications=false",
             "--notification-seconds",
             "15",
-        ]);
-        assert_eq!(args.focus, 50);
-        assert_eq!(args.short, 10);
-        assert_eq!(args.long, 20);
+            "--project",
+            "thesis",
+            "--profile",
+            "deep",
+            "--task",
+            "Write intro",
+            "--estimate",
+            "5",
+            "--confirm-skip=false",
+            "--history=false",
+            "--history-backend",
+            "memory",
+            "--history-dir",
+            "/tmp/pomo",
+            "--serve",
+            "127.0.0.1:7878",
+            "--serve-token",
+            "s3cret",
+            "--mqtt",
+            "broker.lan:1883",
+            "--music",
+            "pause-on-focus",
+            "--plugins=false",
+            "--ambient",
+            "brown",
+            "--ambient-break",
+            "rain.ogg",
+            "--ambient-volume",
+            "30",
+            "--accessible",
+            "--announce-every",
+            "10",
+            "--bell",
+            "--remote-mode",
+            "--lang",
+            "ja",
+            "--time-format",
+            "human",
+            "--mascot",
+            "cat",
+            "--layout",
+            "vertical",
+            "--cycles",
+            "2",
+            "--exit-on-complete",
+            "--low-power",
+            "--simulate",
+            "focus=2s",
+            "--notifier",
+            "stdout",
+            "--snooze-minutes",
+            "10",
+            "--rate-sessions",
+            "--quotes-file",
+            "quotes.txt",
+            "--breathing=false",
+            "--graphics=false",
+            "--track-rest",
+            "--calendar",
+            "http://dav.lan/me/work/",
+            "--meeting-warning",
+            "5",
+            "--fit-to-meetings",
+            "--warm-up",
+            "5m",
+            "--cycle-review",
+            "3m",
+            "--partial-credit",
+            "80",
+            "--offer-extension",
+            "5m",
+            "--log-file",
+            "/tmp/pomo.log",
+            "-vv",
+        ])
+        .args;
+        assert_eq!(args.focus, Duration::from_secs(50 * 60));
+        assert_eq!(args.short, Duration::from_secs(150));
+        assert_eq!(args.long, Duration::from_secs(20 * 60));
         assert_eq!(args.long_every, 3);
+        assert_eq!(args.warm_up, Some(Duration::from_secs(5 * 60)));
+        assert_eq!(args.cycle_review, Some(Duration::from_secs(3 * 60)));
+        assert_eq!(args.partial_credit, Some(80));
+        assert_eq!(args.offer_extension, Some(Duration::from_secs(5 * 60)));
         assert!(!args.notifications);
         assert_eq!(args.notification_seconds, 15);
+        assert_eq!(args.project.as_deref(), Some("thesis"));
+        assert_eq!(args.profile.as_deref(), Some("deep"));
+        assert_eq!(args.task.as_deref(), Some("Write intro"));
+        assert_eq!(args.estimate, Some(5));
+        assert!(!args.confirm_skip);
+        assert!(!args.history);
+        assert_eq!(args.history_backend, StoreKind::Memory);
+        assert_eq!(args.history_dir, Some(PathBuf::from("/tmp/pomo")));
+        assert_eq!(args.serve.as_deref(), Some("127.0.0.1:7878"));
+        assert_eq!(args.serve_token.as_deref(), Some("s3cret"));
+        assert_eq!(args.mqtt.as_deref(), Some("broker.lan:1883"));
+        assert_eq!(args.music, MusicMode::PauseOnFocus);
+        assert!(!args.plugins);
+        assert_eq!(args.ambient.as_deref(), Some("brown"));
+        assert_eq!(args.ambient_break.as_deref(), Some("rain.ogg"));
+        assert_eq!(args.ambient_volume, 30);
+        assert!(args.accessible);
+        assert_eq!(args.announce_every, 10);
+        assert_eq!(args.bell, BellMode::On);
+        assert_eq!(args.remote_mode, Some(true));
+        assert_eq!(args.lang, Some(Lang::Ja));
+        assert_eq!(args.time_format, TimeFormat::Human);
+        assert_eq!(args.mascot, Mascot::Cat);
+        assert_eq!(args.layout, LayoutPreset::Vertical);
+        assert_eq!(args.cycles, Some(2));
+        assert!(args.exit_on_complete);
+        assert!(args.low_power);
+        assert_eq!(args.simulate.map(|s| s.lasts), Some(Duration::from_secs(2)));
+        assert_eq!(args.log_file, Some(PathBuf::from("/tmp/pomo.log")));
+        assert_eq!(args.verbose, 2);
+        assert_eq!(args.snooze_minutes, 10);
+        assert!(args.rate_sessions);
+        assert_eq!(args.quotes_file, Some(PathBuf::from("quotes.txt")));
+        assert!(!args.breathing);
+        assert!(!args.graphics);
+        assert!(args.track_rest);
+        assert_eq!(args.calendar.as_deref(), Some("http://dav.lan/me/work/"));
+        assert_eq!(args.meeting_warning, 5);
+        assert!(args.fit_to_meetings);
+    }
+
+    #[test]
+    fn parses_import_subcommand() {
+        let cli = Cli::parse_from(["rusty_pomo", "import", "--from", "csv", "old.csv"]);
+        match cli.command {
+            Some(Command::Import { from, file }) => {
+                assert_eq!(from, ImportFormat::Csv);
+                assert_eq!(file, PathBuf::from("old.csv"));
+            }
+            other => panic!("unexpected command {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_sync_merge_subcommand() {
+        let cli = Cli::parse_from(["rusty_pomo", "sync", "merge", "/mnt/laptop"]);
+        match cli.command {
+            Some(Command::Sync {
+                action: SyncAction::Merge { dirs },
+            }) => assert_eq!(dirs, vec![PathBuf::from("/mnt/laptop")]),
+            other => panic!("unexpected command {other:?}"),
+        }
+        let cli = Cli::parse_from(["rusty_pomo", "history", "compact", "--keep-days", "90"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::History {
+                action: HistoryAction::Compact {
+                    keep_days: Some(90),
+                    dry_run: false
+                }
+            })
+        ));
+        let cli = Cli::parse_from(["rusty_pomo", "history", "check", "--fix"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::History {
+                action: HistoryAction::Check { fix: true }
+            })
+        ));
+        let cli = Cli::parse_from(["rusty_pomo", "backup", "restore", "pomo.tar", "--force"]);
+        match cli.command {
+            Some(Command::Backup {
+                action: BackupAction::Restore { archive, force },
+            }) => assert_eq!((archive, force), (PathBuf::from("pomo.tar"), true)),
+            other => panic!("unexpected command {other:?}"),
+        }
+        let cli = Cli::parse_from(["rusty_pomo", "sync", "push"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Sync {
+                action: SyncAction::Push
+            })
+        ));
+    }
+
+    #[test]
+    fn parses_hue_pair_subcommand() {
+        let cli = Cli::parse_from(["rusty_pomo", "hue", "pair", "--bridge", "10.0.0.2"]);
+        match cli.command {
+            Some(Command::Hue {
+                action: HueAction::Pair { bridge },
+            }) => assert_eq!(bridge.as_deref(), Some("10.0.0.2")),
+            other => panic!("unexpected command {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parses_completions_subcommand() {
+        let cli = Cli::parse_from(["rusty_pomo", "completions", "zsh"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Completions { shell: Shell::Zsh })
+        ));
+        assert!(Cli::try_parse_from(["rusty_pomo", "completions", "tcsh"]).is_err());
+    }
+
+    #[test]
+    fn run_subcommand_takes_the_timer_flags() {
+        let cli = Cli::parse_from([
+            "rusty_pomo",
+            "run",
+            "--focus",
+            "50",
+            "--theme",
+            "gruvbox-dark",
+        ]);
+        match cli.command {
+            Some(Command::Run(args)) => assert_eq!(args.focus, Duration::from_secs(50 * 60)),
+            other => panic!("unexpected command {other:?}"),
+        }
+        assert_eq!(cli.args.focus, Duration::from_secs(25 * 60));
+    }
+
+    #[test]
+    fn parses_stats_export_and_config_subcommands() {
+        let cli = Cli::parse_from(["rusty_pomo", "stats", "--days", "30"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Stats {
+                days: 30,
+                insights: false,
+                leaderboard_export: None,
+                name: None,
+                tui: false,
+                daily_goal: 4,
+                project: None,
+            })
+        ));
+        let cli = Cli::parse_from(["rusty_pomo", "stats", "--insights"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Stats {
+                days: 7,
+                insights: true,
+                ..
+            })
+        ));
+        let cli = Cli::parse_from([
+            "rusty_pomo",
+            "stats",
+            "--leaderboard-export",
+            "week.json",
+            "--name",
+            "ana",
+        ]);
+        match cli.command {
+            Some(Command::Stats {
+                leaderboard_export: Some(file),
+                name: Some(name),
+                ..
+            }) => assert_eq!((file.to_str(), name.as_str()), (Some("week.json"), "ana")),
+            other => panic!("unexpected command {other:?}"),
+        }
+        assert!(
+            Cli::try_parse_from([
+                "rusty_pomo",
+                "stats",
+                "--insights",
+                "--leaderboard-export",
+                "a"
+            ])
+            .is_err()
+        );
+        let cli = Cli::parse_from([
+            "rusty_pomo",
+            "export",
+            "--format",
+            "jsonl",
+            "--since",
+            "2024-05-01",
+            "--project",
+            "thesis",
+        ]);
+        match cli.command {
+            Some(Command::Export {
+                format,
+                since,
+                until,
+                project,
+            }) => {
+                assert_eq!(format, ExportFormat::Jsonl);
+                assert_eq!(since, Some(wallclock::days_from_civil(2024, 5, 1)));
+                assert_eq!(until, None);
+                assert_eq!(project.as_deref(), Some("thesis"));
+            }
+            other => panic!("unexpected command {other:?}"),
+        }
+        assert!(Cli::try_parse_from(["rusty_pomo", "export", "--since", "May 1"]).is_err());
+        let cli = Cli::parse_from(["rusty_pomo", "report", "--month", "--format", "html"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Report {
+                week: false,
+                month: true,
+                format: ReportFormat::Html,
+                project: None,
+            })
+        ));
+        assert!(Cli::try_parse_from(["rusty_pomo", "report", "--week", "--month"]).is_err());
+        let cli = Cli::parse_from(["rusty_pomo", "status"]);
+        assert!(matches!(cli.command, Some(Command::Status)));
+        let cli = Cli::parse_from(["rusty_pomo", "config", "path"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Config {
+                action: ConfigAction::Path
+            })
+        ));
+        let cli = Cli::parse_from(["rusty_pomo", "--context", "work", "context", "list"]);
+        assert_eq!(cli.args.context.as_deref(), Some("work"));
+        assert!(matches!(
+            cli.command,
+            Some(Command::Context {
+                action: ContextAction::List
+            })
+        ));
+        assert!(Cli::try_parse_from(["rusty_pomo", "context", "use", "a/b"]).is_err());
+        assert!(
+            Cli::try_parse_from(["rusty_pomo", "--context", "work", "--data-dir", "/tmp/p"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn rejects_ambient_volume_over_100() {
+        assert!(Cli::try_parse_from(["rusty_pomo", "--ambient-volume", "101"]).is_err());
+    }
+
+    #[test]
+    fn once_and_cycles_do_not_mix() {
+        assert!(Cli::parse_from(["rusty_pomo", "--once"]).args.once);
+        let args = Cli::parse_from(["rusty_pomo", "--background", "--detach"]).args;
+        assert!(args.background && args.detach);
+        assert!(Cli::try_parse_from(["rusty_pomo", "--detach"]).is_err());
+        let err = Cli::try_parse_from(["rusty_pomo", "--once", "--cycles", "2"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn demo_runs_sixty_times_faster_unless_told() {
+        assert_eq!(
+            Cli::parse_from(["rusty_pomo", "--demo"]).args.demo,
+            Some(60)
+        );
+        assert_eq!(
+            Cli::parse_from(["rusty_pomo", "--demo=300"]).args.demo,
+            Some(300)
+        );
+        assert!(Cli::try_parse_from(["rusty_pomo", "--demo=1"]).is_err());
+        let err =
+            Cli::try_parse_from(["rusty_pomo", "--demo", "--simulate", "focus=2s"]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn rejects_nonsensical_timer_values() {
+        for argv in [
+            ["rusty_pomo", "--focus", "0"],
+            ["rusty_pomo", "--short", "25h"],
+            ["rusty_pomo", "--long-every", "101"],
+            ["rusty_pomo", "--announce-every", "0"],
+        ] {
+            let err = Cli::try_parse_from(argv).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ValueValidation, "{argv:?}");
+        }
+    }
+
+    #[test]
+    fn estimate_requires_task() {
+        assert!(Cli::try_parse_from(["rusty_pomo", "--estimate", "3"]).is_err());
     }
 }
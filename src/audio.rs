@@ -0,0 +1,403 @@
+//! Ambient sound during phases: generated brown/white noise or any audio file,
+//! looped through the platform's command-line player. Also the chime for
+//! notifications routed to sound alone.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::args::Args;
+use crate::doctor::{self, Probe};
+use crate::events::{PhaseEvent, Subscriber};
+use crate::ssh;
+use crate::state::PhaseKind;
+
+const SAMPLE_RATE: u32 = 22_050;
+const NOISE_SECONDS: u32 = 30;
+const FADE_SECONDS: f64 = 0.05;
+const LOOP_CHECK: Duration = Duration::from_millis(200);
+pub const VOLUME_STEP: i16 = 10;
+/// Command-line players to try, in order.
+const PLAYERS: &[&str] = if cfg!(target_os = "macos") {
+    &["afplay"]
+} else {
+    &["paplay", "aplay"]
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Track {
+    BrownNoise,
+    WhiteNoise,
+    File(PathBuf),
+}
+
+impl Track {
+    /// `brown`/`white` select generated noise; anything else is a file path.
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "brown" => Track::BrownNoise,
+            "white" => Track::WhiteNoise,
+            path => Track::File(PathBuf::from(path)),
+        }
+    }
+
+    fn path(&self) -> io::Result<PathBuf> {
+        let (name, brown) = match self {
+            Track::File(path) => return Ok(path.clone()),
+            Track::BrownNoise => ("brown", true),
+            Track::WhiteNoise => ("white", false),
+        };
+        let dir = crate::paths::cache_dir().unwrap_or_else(std::env::temp_dir);
+        let path = dir.join(format!("rusty_pomo-{name}-noise.wav"));
+        if !path.exists() {
+            fs::create_dir_all(&dir)?;
+            fs::write(&path, noise_wav(brown, NOISE_SECONDS, 0x9E37_79B9))?;
+        }
+        Ok(path)
+    }
+}
+
+/// What to play in each phase; `None` is silence.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub focus: Option<Track>,
+    pub rest: Option<Track>,
+}
+
+impl Profile {
+    /// Silent in remote mode, where it would play on the other machine.
+    pub fn from_args(args: &Args) -> Self {
+        if ssh::active(args) {
+            if args.ambient.is_some() || args.ambient_break.is_some() {
+                tracing::info!("remote mode: ambient sound is off");
+            }
+            return Self::default();
+        }
+        Self {
+            focus: args.ambient.as_deref().map(Track::parse),
+            rest: args.ambient_break.as_deref().map(Track::parse),
+        }
+    }
+
+    fn track_for(&self, kind: PhaseKind) -> Option<&Track> {
+        match kind {
+            PhaseKind::Focus => self.focus.as_ref(),
+            PhaseKind::ShortBreak | PhaseKind::LongBreak => self.rest.as_ref(),
+            PhaseKind::WarmUp
+            | PhaseKind::CycleReview
+            | PhaseKind::Lunch
+            | PhaseKind::Custom(_) => None,
+        }
+    }
+}
+
+enum Message {
+    Phase(PhaseKind),
+    Volume(u8),
+    Stop,
+}
+
+/// Handle to the player thread; dropping it stops playback.
+#[derive(Debug)]
+pub struct Ambient {
+    messages: Option<Sender<Message>>,
+    worker: Option<JoinHandle<()>>,
+    volume: u8,
+}
+
+impl Ambient {
+    /// `None` when the profile is silent everywhere or the platform has no player.
+    pub fn spawn(profile: Profile, volume: u8) -> Option<Self> {
+        if !cfg!(unix) || (profile.focus.is_none() && profile.rest.is_none()) {
+            return None;
+        }
+        let (tx, rx) = mpsc::channel();
+        let worker = thread::spawn(move || {
+            let mut volume = volume;
+            let mut track: Option<Track> = None;
+            let mut child: Option<Child> = None;
+            loop {
+                match rx.recv_timeout(LOOP_CHECK) {
+                    Ok(Message::Phase(kind)) => {
+                        let next = profile.track_for(kind).cloned();
+                        if next == track {
+                            continue;
+                        }
+                        stop(&mut child);
+                        track = next;
+                    }
+                    Ok(Message::Volume(v)) => {
+                        volume = v;
+                        stop(&mut child);
+                    }
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => {
+                        stop(&mut child);
+                        return;
+                    }
+                }
+                let finished = match &mut child {
+                    Some(c) => !matches!(c.try_wait(), Ok(None)),
+                    None => true,
+                };
+                if finished && let Some(track) = &track {
+                    child = match track.path() {
+                        Ok(path) => play(&path, volume),
+                        Err(e) => {
+                            tracing::warn!(error = %e, "cannot prepare the ambient track");
+                            None
+                        }
+                    };
+                }
+            }
+        });
+        Some(Self {
+            messages: Some(tx),
+            worker: Some(worker),
+            volume,
+        })
+    }
+
+    /// Follows phase events for the player, apart from this handle, which keeps
+    /// the volume and stops playback when dropped.
+    pub fn follower(&self) -> Option<AmbientPhases> {
+        self.messages.clone().map(AmbientPhases)
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    pub fn change_volume(&mut self, delta: i16) {
+        self.volume = adjust_volume(self.volume, delta);
+        self.send(Message::Volume(self.volume));
+    }
+
+    fn send(&self, message: Message) {
+        if let Some(messages) = &self.messages {
+            let _ = messages.send(message);
+        }
+    }
+}
+
+impl Drop for Ambient {
+    fn drop(&mut self) {
+        // Followers may still hold senders, so disconnecting alone can't stop it
+        self.send(Message::Stop);
+        self.messages.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AmbientPhases(Sender<Message>);
+
+impl Subscriber for AmbientPhases {
+    fn on_event(&mut self, event: &PhaseEvent) {
+        if let Some(kind) = event.phase() {
+            let _ = self.0.send(Message::Phase(kind));
+        }
+    }
+}
+
+fn adjust_volume(volume: u8, delta: i16) -> u8 {
+    (volume as i16 + delta).clamp(0, 100) as u8
+}
+
+fn stop(child: &mut Option<Child>) {
+    if let Some(mut c) = child.take() {
+        let _ = c.kill();
+        let _ = c.wait();
+    }
+}
+
+/// Whether `--ambient` has a player to go through.
+pub fn probe(args: &Args) -> Probe {
+    let wanted = args.ambient.is_some() || args.ambient_break.is_some();
+    if ssh::active(args) {
+        return Probe::Off("remote mode: nothing plays on this machine".to_string());
+    }
+    if !cfg!(unix) {
+        let why = "ambient sound only plays on macOS and Linux".to_string();
+        return if wanted {
+            Probe::Fail(why)
+        } else {
+            Probe::Off(why)
+        };
+    }
+    match PLAYERS.iter().find(|player| doctor::on_path(player)) {
+        Some(player) => Probe::Ok(format!("ambient sound plays through {player}")),
+        None => {
+            let advice = format!(
+                "no audio player found; install {} (from pulseaudio-utils or alsa-utils)",
+                PLAYERS.join(" or ")
+            );
+            if wanted {
+                Probe::Fail(advice)
+            } else {
+                Probe::Warn(advice)
+            }
+        }
+    }
+}
+
+/// Plays `file`, or a generated chime without one, in the background.
+pub fn chime(file: Option<&std::path::Path>) {
+    let path = match file {
+        Some(file) => file.to_path_buf(),
+        None => {
+            let dir = crate::paths::cache_dir().unwrap_or_else(std::env::temp_dir);
+            let path = dir.join("rusty_pomo-chime.wav");
+            if !path.exists()
+                && let Err(e) =
+                    fs::create_dir_all(&dir).and_then(|()| fs::write(&path, chime_wav()))
+            {
+                tracing::warn!(error = %e, "cannot write the chime");
+                return;
+            }
+            path
+        }
+    };
+    if let Some(mut child) = play(&path, 100) {
+        // Reaped off the UI thread once it's done
+        thread::spawn(move || child.wait());
+    }
+}
+
+/// Starts one pass of the file with the first player available.
+fn play(path: &std::path::Path, volume: u8) -> Option<Child> {
+    let child = PLAYERS.iter().find_map(|player| {
+        let mut command = Command::new(player);
+        match *player {
+            "afplay" => command
+                .arg("-v")
+                .arg(format!("{:.2}", volume as f64 / 100.0)),
+            "paplay" => command.arg(format!("--volume={}", volume as u32 * 65_536 / 100)),
+            _ => command.arg("-q"),
+        };
+        command
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()
+    });
+    if child.is_none() {
+        tracing::warn!(path = %path.display(), "no audio player could play the sound");
+    }
+    child
+}
+
+/// Two rising notes, each faded in and out.
+fn chime_wav() -> Vec<u8> {
+    let note = SAMPLE_RATE * 3 / 20;
+    let fade = (SAMPLE_RATE as f64 * FADE_SECONDS) as u32;
+    let mut wav = wav_header(note * 2);
+    for (pitch, i) in [880.0, 1320.0]
+        .into_iter()
+        .flat_map(|pitch| (0..note).map(move |i| (pitch, i)))
+    {
+        let t = i as f64 / SAMPLE_RATE as f64;
+        let edge = i.min(note - 1 - i);
+        let gain = (edge as f64 / fade as f64).min(1.0) * 0.4;
+        let value = (t * pitch * std::f64::consts::TAU).sin() * gain * i16::MAX as f64;
+        wav.extend_from_slice(&(value as i16).to_le_bytes());
+    }
+    wav
+}
+
+/// The header of a 16-bit mono WAV with room for `samples`.
+fn wav_header(samples: u32) -> Vec<u8> {
+    let mut wav = Vec::with_capacity(44 + samples as usize * 2);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + samples * 2).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(samples * 2).to_le_bytes());
+    wav
+}
+
+/// A 16-bit mono WAV of noise, faded at both ends so it loops without clicks.
+fn noise_wav(brown: bool, seconds: u32, seed: u32) -> Vec<u8> {
+    let samples = SAMPLE_RATE * seconds;
+    let fade = (SAMPLE_RATE as f64 * FADE_SECONDS) as u32;
+    let mut state = seed.max(1);
+    let mut last = 0.0;
+    let mut wav = wav_header(samples);
+    for i in 0..samples {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        let white = state as f64 / u32::MAX as f64 * 2.0 - 1.0;
+        let sample = if brown {
+            last = (last + 0.02 * white) / 1.02;
+            last * 3.5
+        } else {
+            white * 0.5
+        };
+        let edge = i.min(samples - 1 - i);
+        let gain = (edge as f64 / fade as f64).min(1.0);
+        let value = (sample * gain).clamp(-1.0, 1.0) * i16::MAX as f64;
+        wav.extend_from_slice(&(value as i16).to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tracks_and_picks_per_phase() {
+        assert_eq!(Track::parse("brown"), Track::BrownNoise);
+        assert_eq!(
+            Track::parse("~/rain.ogg"),
+            Track::File(PathBuf::from("~/rain.ogg"))
+        );
+        let profile = Profile {
+            focus: Some(Track::WhiteNoise),
+            rest: None,
+        };
+        assert_eq!(
+            profile.track_for(PhaseKind::Focus),
+            Some(&Track::WhiteNoise)
+        );
+        assert_eq!(profile.track_for(PhaseKind::LongBreak), None);
+    }
+
+    #[test]
+    fn clamps_volume() {
+        assert_eq!(adjust_volume(95, VOLUME_STEP), 100);
+        assert_eq!(adjust_volume(5, -VOLUME_STEP), 0);
+        assert_eq!(adjust_volume(50, VOLUME_STEP), 60);
+    }
+
+    #[test]
+    fn generates_looping_noise() {
+        let wav = noise_wav(true, 1, 7);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(wav.len(), 44 + SAMPLE_RATE as usize * 2);
+        let sample = |i: usize| i16::from_le_bytes([wav[44 + i * 2], wav[45 + i * 2]]);
+        assert_eq!(sample(0), 0);
+        assert_eq!(sample(SAMPLE_RATE as usize - 1), 0);
+        assert!((0..SAMPLE_RATE as usize).any(|i| sample(i).abs() > 1000));
+        let chime = chime_wav();
+        assert_eq!(&chime[..4], b"RIFF");
+        // Two notes of 0.15 s
+        assert_eq!(chime.len(), 44 + SAMPLE_RATE as usize * 3 / 20 * 2 * 2);
+    }
+}
@@ -0,0 +1,286 @@
+//! `backup create` and `backup restore`: the config, plugins, history and
+//! achievements in one tar file, to carry to another machine.
+
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::history;
+use crate::{paths, tar};
+
+/// The folder everything in a backup sits under.
+const ROOT: &str = "rusty_pomo";
+
+/// Where each part of a backup lives on this machine.
+pub struct Places {
+    /// The config file and plugins.
+    pub config: Option<PathBuf>,
+    pub history: PathBuf,
+    pub achievements: Option<PathBuf>,
+}
+
+impl Places {
+    pub fn here(history: PathBuf) -> Self {
+        Self {
+            config: paths::config_dir(),
+            history,
+            achievements: paths::achievements_file(),
+        }
+    }
+
+    /// Each part's name in the archive, with its place here.
+    fn parts(&self) -> Vec<(&'static str, PathBuf)> {
+        [
+            ("config", self.config.clone()),
+            ("history", Some(self.history.clone())),
+            ("achievements.json", self.achievements.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(name, path)| Some((name, path?)))
+        .collect()
+    }
+}
+
+/// Writes every file in `places` to `archive`; returns how many there were.
+pub fn create(places: &Places, archive: &Path) -> Result<usize, String> {
+    let mut entries = Vec::new();
+    for (name, place) in places.parts() {
+        for path in files(&place).map_err(|e| format!("{}: {e}", place.display()))? {
+            let relative = path.strip_prefix(&place).unwrap_or(&path);
+            let mut inside = vec![ROOT.to_string(), name.to_string()];
+            inside.extend(
+                relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+            );
+            let read = |path: &Path| -> io::Result<(Vec<u8>, fs::Metadata)> {
+                Ok((fs::read(path)?, fs::metadata(path)?))
+            };
+            let (data, metadata) = read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+            entries.push(tar::Entry {
+                path: inside.join("/"),
+                mode: mode(&metadata),
+                mtime: metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |d| d.as_secs()),
+                data,
+            });
+        }
+    }
+    let bytes = tar::write(&entries)?;
+    fs::write(archive, bytes).map_err(|e| format!("{}: {e}", archive.display()))?;
+    Ok(entries.len())
+}
+
+/// Files under `place`, or `place` itself if it's a file; none if it's missing.
+fn files(place: &Path) -> io::Result<Vec<PathBuf>> {
+    if place.is_file() {
+        return Ok(vec![place.to_path_buf()]);
+    }
+    let Ok(entries) = fs::read_dir(place) else {
+        return Ok(Vec::new());
+    };
+    let mut found = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            found.extend(files(&path)?);
+        } else {
+            found.push(path);
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Restored {
+    /// Written outside the history.
+    pub files: usize,
+    /// In the backup's history.
+    pub sessions: usize,
+    /// Of those, the ones this history didn't have.
+    pub added: usize,
+}
+
+/// Puts a backup's files in their places here. The history is merged with the
+/// one already here; other files that exist and differ are only replaced with
+/// `force`, and otherwise nothing is written.
+pub fn restore(places: &Places, archive: &Path, force: bool) -> Result<Restored, String> {
+    let bytes = fs::read(archive).map_err(|e| format!("{}: {e}", archive.display()))?;
+    let entries = tar::read(&bytes).map_err(|e| format!("{}: {e}", archive.display()))?;
+    let parts = places.parts();
+    let mut records = history::Lines::default();
+    let mut writes: Vec<(PathBuf, tar::Entry)> = Vec::new();
+    for entry in entries {
+        let mut components = entry.path.split('/');
+        let (Some(ROOT), Some(name)) = (components.next(), components.next()) else {
+            return Err(format!("{}: not a rusty_pomo backup", archive.display()));
+        };
+        let rest: Vec<&str> = components.collect();
+        if rest
+            .iter()
+            .any(|c| !matches!(Path::new(c).components().next(), Some(Component::Normal(_))))
+        {
+            return Err(format!("{}: unsafe path {}", archive.display(), entry.path));
+        }
+        let Some((_, place)) = parts.iter().find(|(part, _)| *part == name) else {
+            // A part with no place here, such as achievements without a data directory
+            continue;
+        };
+        if name == "history" && rest.len() == 1 && rest[0].ends_with(".jsonl") {
+            records.extend(history::Lines::parse(&String::from_utf8_lossy(&entry.data)));
+        } else {
+            writes.push((
+                rest.iter().fold(place.clone(), |path, c| path.join(c)),
+                entry,
+            ));
+        }
+    }
+
+    let differ: Vec<_> = writes
+        .iter()
+        .filter(|(path, entry)| fs::read(path).is_ok_and(|data| data != entry.data))
+        .map(|(path, _)| path.display().to_string())
+        .collect();
+    if !differ.is_empty() && !force {
+        return Err(format!(
+            "would replace {} files that differ ({}); use --force to replace them",
+            differ.len(),
+            differ.join(", ")
+        ));
+    }
+    for (path, entry) in &writes {
+        let write = || -> io::Result<()> {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, &entry.data)?;
+            set_mode(path, entry.mode)
+        };
+        write().map_err(|e| format!("{}: {e}", path.display()))?;
+    }
+    let sessions = records.sessions();
+    let added = history::add_records(&places.history, records)
+        .map_err(|e| format!("{}: {e}", places.history.display()))?;
+    Ok(Restored {
+        files: writes.len(),
+        sessions,
+        added,
+    })
+}
+
+#[cfg(unix)]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn mode(_metadata: &fs::Metadata) -> u32 {
+    0o644
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn places(root: &Path) -> Places {
+        Places {
+            config: Some(root.join("config")),
+            history: root.join("history"),
+            achievements: Some(root.join("data/achievements.json")),
+        }
+    }
+
+    #[test]
+    fn restores_a_backup_on_another_machine() {
+        let base = std::env::temp_dir().join(format!("rusty_pomo_backup_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let (old, new) = (places(&base.join("old")), places(&base.join("new")));
+        let start = 1_700_000_000;
+        let session = |start| {
+            format!(
+                r#"{{"kind":"focus","start":{start},"end":{},"planned":1500,"completed":true}}"#,
+                start + 1500
+            )
+        };
+        let config = old.config.as_ref().unwrap();
+        fs::create_dir_all(config.join("plugins")).unwrap();
+        fs::write(config.join("config.toml"), "[timer]\nfocus = 50\n").unwrap();
+        fs::write(config.join("plugins/log.sh"), "#!/bin/sh\n").unwrap();
+        set_mode(&config.join("plugins/log.sh"), 0o755).unwrap();
+        fs::create_dir_all(&old.history).unwrap();
+        fs::write(
+            old.history.join("2023-11-14.jsonl"),
+            format!("{}\n{}\n", session(start), session(start + 2000)),
+        )
+        .unwrap();
+        let archive = base.join("pomo.tar");
+        assert_eq!(create(&old, &archive).unwrap(), 3);
+
+        // The new machine has one of the sessions already, and its own config
+        fs::create_dir_all(&new.history).unwrap();
+        fs::write(new.history.join("2023-11-14.jsonl"), session(start) + "\n").unwrap();
+        let new_config = new.config.as_ref().unwrap();
+        fs::create_dir_all(new_config).unwrap();
+        fs::write(new_config.join("config.toml"), "").unwrap();
+        let refused = restore(&new, &archive, false).unwrap_err();
+        assert!(refused.contains("--force"), "{refused}");
+        assert!(!new_config.join("plugins").exists());
+
+        let restored = restore(&new, &archive, true).unwrap();
+        assert_eq!(
+            restored,
+            Restored {
+                files: 2,
+                sessions: 2,
+                added: 1
+            }
+        );
+        assert_eq!(
+            fs::read_to_string(new_config.join("config.toml")).unwrap(),
+            "[timer]\nfocus = 50\n"
+        );
+        assert_eq!(history::History::open(new.history.clone()).records.len(), 2);
+        let plugin = fs::metadata(new_config.join("plugins/log.sh")).unwrap();
+        assert_eq!(
+            mode(&plugin) & 0o777,
+            if cfg!(unix) { 0o755 } else { 0o644 }
+        );
+        // Restoring again changes nothing
+        assert_eq!(restore(&new, &archive, false).unwrap().added, 0);
+
+        fs::write(
+            &archive,
+            tar::write(&[tar::Entry {
+                path: "rusty_pomo/config/../../escape".to_string(),
+                mode: 0o644,
+                mtime: 0,
+                data: Vec::new(),
+            }])
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(
+            restore(&new, &archive, true)
+                .unwrap_err()
+                .contains("unsafe")
+        );
+        let _ = fs::remove_dir_all(&base);
+    }
+}
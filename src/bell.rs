@@ -0,0 +1,132 @@
+//! The terminal bell, with a flash of the screen, for when desktop
+//! notifications can't be seen: over SSH, without a notification server, or
+//! with them turned off.
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+use crate::args::Args;
+use crate::doctor::Probe;
+use crate::events::{PhaseEvent, Subscriber};
+use crate::notifications;
+use crate::ssh;
+
+/// How long the screen stays inverted after the bell.
+const FLASH: Duration = Duration::from_millis(400);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum BellMode {
+    /// Only while notifications are off or failing, or in remote mode
+    Auto,
+    On,
+    Off,
+}
+
+/// Rings at each new phase and when the cycles are done. Clones share what
+/// they know, so the screen can flash when the one on the bus rings.
+#[derive(Clone, Debug)]
+pub struct Bell {
+    mode: BellMode,
+    /// Whether to ring here; `--accessible` rings its own bells.
+    audible: bool,
+    /// Whether notifications were off or had no server at the last look, or
+    /// this is remote mode.
+    unavailable: Arc<AtomicBool>,
+    rang_at: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Bell {
+    pub fn from_args(args: &Args) -> Self {
+        let bell = Self {
+            mode: args.bell,
+            audible: !args.accessible,
+            unavailable: Arc::default(),
+            rang_at: Arc::default(),
+        };
+        bell.look(args);
+        bell
+    }
+
+    /// Checks for a notification server only in `auto`, where it matters.
+    fn look(&self, args: &Args) {
+        let unavailable = self.mode == BellMode::Auto
+            && (!args.notifications
+                || ssh::active(args)
+                || matches!(notifications::probe(args), Probe::Fail(_)));
+        self.unavailable.store(unavailable, Ordering::Relaxed);
+    }
+
+    pub fn rings(&self) -> bool {
+        match self.mode {
+            BellMode::On => true,
+            BellMode::Off => false,
+            BellMode::Auto => {
+                self.unavailable.load(Ordering::Relaxed) || notifications::delivery_failed()
+            }
+        }
+    }
+
+    /// Whether the bell rang just now, for the screen to flash.
+    pub fn flashing(&self) -> bool {
+        self.rang_at
+            .lock()
+            .ok()
+            .and_then(|rang_at| *rang_at)
+            .is_some_and(|at| at.elapsed() < FLASH)
+    }
+}
+
+impl Subscriber for Bell {
+    fn on_event(&mut self, event: &PhaseEvent) {
+        if !matches!(event, PhaseEvent::Started { .. } | PhaseEvent::Done { .. }) || !self.rings() {
+            return;
+        }
+        if let Ok(mut rang_at) = self.rang_at.lock() {
+            *rang_at = Some(Instant::now());
+        }
+        if self.audible {
+            let mut out = io::stdout();
+            let _ = out.write_all(b"\x07").and_then(|()| out.flush());
+        }
+    }
+
+    fn reload(&mut self, settings: &Args) {
+        self.look(settings);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PhaseKind;
+    use clap::Parser;
+
+    #[test]
+    fn rings_in_auto_only_without_notifications() {
+        let parse = |flags: &[&str]| {
+            let mut argv = vec!["rusty_pomo", "--accessible"];
+            argv.extend(flags);
+            crate::args::Cli::parse_from(argv).args
+        };
+        assert_eq!(parse(&[]).bell, BellMode::Auto);
+        assert_eq!(parse(&["--bell"]).bell, BellMode::On);
+        assert!(Bell::from_args(&parse(&["--bell=on"])).rings());
+        assert!(!Bell::from_args(&parse(&["--bell=off", "--notifications=false"])).rings());
+
+        let mut bell = Bell::from_args(&parse(&["--notifications=false"]));
+        assert!(bell.rings());
+        let screen = bell.clone();
+        assert!(!screen.flashing());
+        bell.on_event(&PhaseEvent::Entered(PhaseKind::Focus));
+        assert!(!screen.flashing());
+        bell.on_event(&PhaseEvent::Started {
+            kind: PhaseKind::ShortBreak,
+            quote: None,
+        });
+        assert!(screen.flashing());
+    }
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::Span;
+use ratatui::widgets::Widget;
+
+use crate::i18n::t;
+
+/// One breath: in for 4 seconds, hold for 4, out for 4.
+const INHALE: f64 = 4.0;
+const HOLD: f64 = 4.0;
+const EXHALE: f64 = 4.0;
+/// How small the circle gets with empty lungs, so it never vanishes.
+const SMALLEST: f64 = 0.3;
+
+/// Rows the widget needs: the circle and the step's name under it.
+pub const HEIGHT: u16 = 7;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    Inhale,
+    Hold,
+    Exhale,
+}
+
+impl Step {
+    pub fn label(self) -> &'static str {
+        match self {
+            Step::Inhale => t("ui.inhale"),
+            Step::Hold => t("ui.hold"),
+            Step::Exhale => t("ui.exhale"),
+        }
+    }
+}
+
+/// The step `elapsed` into the exercise, and how full the lungs are, 0 to 1.
+pub fn breath(elapsed: Duration) -> (Step, f64) {
+    let at = elapsed.as_secs_f64() % (INHALE + HOLD + EXHALE);
+    if at < INHALE {
+        (Step::Inhale, at / INHALE)
+    } else if at < INHALE + HOLD {
+        (Step::Hold, 1.0)
+    } else {
+        (Step::Exhale, 1.0 - (at - INHALE - HOLD) / EXHALE)
+    }
+}
+
+/// A circle that grows on the in-breath and shrinks on the out-breath, with
+/// the step's name underneath.
+pub struct Breathing {
+    /// Time since the break started, paused along with it.
+    pub elapsed: Duration,
+    pub color: Color,
+}
+
+impl Widget for Breathing {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 2 || area.width == 0 {
+            return;
+        }
+        let (step, fullness) = breath(self.elapsed);
+        let rows = area.height - 1;
+        // Cells are about twice as tall as wide, so the circle is twice as wide in cells
+        let radius = f64::from(rows) / 2.0 * (SMALLEST + (1.0 - SMALLEST) * fullness);
+        let (cx, cy) = (f64::from(area.width) / 2.0, f64::from(rows) / 2.0);
+        for y in 0..rows {
+            for x in 0..area.width {
+                let dx = (f64::from(x) + 0.5 - cx) / (2.0 * radius);
+                let dy = (f64::from(y) + 0.5 - cy) / radius;
+                if dx * dx + dy * dy <= 1.0 {
+                    buf.get_mut(area.x + x, area.y + y)
+                        .set_symbol("█")
+                        .set_style(Style::default().fg(self.color));
+                }
+            }
+        }
+        let label = Span::styled(step.label(), Style::default().fg(Color::Gray));
+        let width = label.width() as u16;
+        buf.set_span(
+            area.x + area.width.saturating_sub(width) / 2,
+            area.y + rows,
+            &label,
+            area.width,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breathes_in_holds_and_breathes_out() {
+        let at = |secs: f64| breath(Duration::from_secs_f64(secs));
+        assert_eq!(at(0.0), (Step::Inhale, 0.0));
+        assert_eq!(at(2.0), (Step::Inhale, 0.5));
+        assert_eq!(at(5.0), (Step::Hold, 1.0));
+        assert_eq!(at(9.0), (Step::Exhale, 0.75));
+        assert_eq!(at(13.0), (Step::Inhale, 0.25));
+    }
+}
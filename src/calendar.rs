@@ -0,0 +1,587 @@
+//! Meetings from an ICS file or a calendar server, so a focus session can end
+//! as a meeting starts instead of running into it.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use crate::args::Args;
+use crate::doctor::Probe;
+use crate::integrations::http_send;
+use crate::wallclock::{self, days_from_civil};
+use crate::websocket::base64;
+
+const DAY: i64 = 86_400;
+/// How far ahead meetings are read; the calendar is read again long before that runs out.
+const LOOKAHEAD: u64 = 2 * DAY as u64;
+/// How often the calendar is read again: a local file is cheap, a server less so.
+const FILE_REFRESH: Duration = Duration::from_secs(30);
+const SERVER_REFRESH: Duration = Duration::from_secs(5 * 60);
+/// Stops runaway repeat rules; a daily meeting reaches it after a century.
+const MAX_REPEATS: i64 = 36_600;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Meeting {
+    pub start: u64,
+    pub end: u64,
+    pub summary: String,
+}
+
+/// Where meetings are read from: a file, or `http://[user:password@]host[:port]/path`.
+/// Paths ending in `.ics` are fetched whole; anything else is asked as a CalDAV
+/// collection, which expands repeating events itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Source {
+    File(PathBuf),
+    Server {
+        host: String,
+        path: String,
+        auth: Option<String>,
+    },
+}
+
+impl Source {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        if spec.starts_with("https://") || spec.starts_with("webcal://") {
+            return Err("https is not supported; use an http:// URL or a file".to_string());
+        }
+        let Some(rest) = spec.strip_prefix("http://") else {
+            return Ok(Source::File(PathBuf::from(spec)));
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        };
+        let (auth, host) = match authority.rsplit_once('@') {
+            Some((login, host)) => (Some(format!("Basic {}", base64(login.as_bytes()))), host),
+            None => (None, authority),
+        };
+        Ok(Source::Server {
+            host: host.to_string(),
+            path: path.to_string(),
+            auth,
+        })
+    }
+
+    /// Meetings overlapping `from..to`, earliest first.
+    pub fn fetch(&self, from: u64, to: u64) -> Result<Vec<Meeting>, String> {
+        let contents = match self {
+            Source::File(path) => fs::read_to_string(path)
+                .map_err(|e| format!("cannot read {}: {e}", path.display()))?,
+            Source::Server { host, path, auth } => {
+                let caldav = !path.ends_with(".ics");
+                let mut headers = Vec::new();
+                if let Some(auth) = auth {
+                    headers.push(("Authorization", auth.as_str()));
+                }
+                let (method, body) = if caldav {
+                    headers.push(("Depth", "1"));
+                    headers.push(("Content-Type", "application/xml; charset=utf-8"));
+                    ("REPORT", calendar_query(from, to))
+                } else {
+                    ("GET", String::new())
+                };
+                match http_send(host, method, path, &headers, &body) {
+                    Ok((200..=299, body)) if caldav => calendar_data(&body),
+                    Ok((200..=299, body)) => body,
+                    Ok((status, _)) => return Err(format!("{host} answered {status}")),
+                    Err(e) => return Err(format!("cannot reach {host}: {e}")),
+                }
+            }
+        };
+        Ok(meetings(&contents, from, to))
+    }
+
+    fn refresh(&self) -> Duration {
+        match self {
+            Source::File(_) => FILE_REFRESH,
+            Source::Server { .. } => SERVER_REFRESH,
+        }
+    }
+}
+
+/// Reads the calendar now and again every little while, sending the meetings
+/// of the next two days, or why they couldn't be read.
+pub fn watch(source: Source) -> Receiver<Result<Vec<Meeting>, String>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        loop {
+            let now = wallclock::now_unix();
+            if tx.send(source.fetch(now, now + LOOKAHEAD)).is_err() {
+                break;
+            }
+            thread::sleep(source.refresh());
+        }
+    });
+    rx
+}
+
+/// Whether `--calendar` can be read, for `doctor`.
+pub fn probe(args: &Args) -> Probe {
+    let Some(spec) = &args.calendar else {
+        return Probe::Off("not set up (--calendar or [calendar] source)".to_string());
+    };
+    let source = match Source::parse(spec) {
+        Ok(source) => source,
+        Err(e) => return Probe::Fail(format!("{spec}: {e}")),
+    };
+    let now = wallclock::now_unix();
+    match source.fetch(now, now + LOOKAHEAD) {
+        Ok(meetings) if meetings.len() == 1 => {
+            Probe::Ok("1 meeting in the next two days".to_string())
+        }
+        Ok(meetings) => Probe::Ok(format!("{} meetings in the next two days", meetings.len())),
+        Err(e) => Probe::Fail(e),
+    }
+}
+
+/// A CalDAV query for the events in `from..to`, repeats expanded by the server.
+fn calendar_query(from: u64, to: u64) -> String {
+    let (start, end) = (utc_stamp(from), utc_stamp(to));
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop>
+    <C:calendar-data><C:expand start="{start}" end="{end}"/></C:calendar-data>
+  </D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT"><C:time-range start="{start}" end="{end}"/></C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>
+"#
+    )
+}
+
+/// The calendars in a CalDAV multistatus answer, one after another.
+fn calendar_data(xml: &str) -> String {
+    let text = xml
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&amp;", "&");
+    let mut calendars = String::new();
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find("BEGIN:VCALENDAR") {
+        let end = rest[start..]
+            .find("END:VCALENDAR")
+            .map_or(rest.len(), |i| start + i + "END:VCALENDAR".len());
+        calendars.push_str(&rest[start..end]);
+        calendars.push('\n');
+        rest = &rest[end..];
+    }
+    calendars
+}
+
+/// `YYYYMMDDTHHMMSSZ`, as CalDAV time ranges want.
+fn utc_stamp(unix: u64) -> String {
+    let secs = unix as i64;
+    let (year, month, day) = wallclock::civil_from_days(secs.div_euclid(DAY));
+    let time = secs.rem_euclid(DAY);
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// A moment as the calendar wrote it: in UTC, or in local time, which also
+/// stands in for named time zones.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Stamp {
+    Utc(i64),
+    Local(i64),
+}
+
+impl Stamp {
+    /// `YYYYMMDDTHHMMSS[Z]`; `None` for dates alone, as all-day events have.
+    fn parse(value: &str) -> Option<Self> {
+        let (date, time) = value.split_once('T')?;
+        let (time, utc) = match time.strip_suffix('Z') {
+            Some(time) => (time, true),
+            None => (time, false),
+        };
+        if date.len() != 8 || time.len() != 6 {
+            return None;
+        }
+        let num = |s: &str| s.parse::<i64>().ok();
+        let days = days_from_civil(
+            num(&date[..4])?,
+            num(&date[4..6])? as u32,
+            num(&date[6..])? as u32,
+        );
+        let secs = num(&time[..2])? * 3600 + num(&time[2..4])? * 60 + num(&time[4..])?;
+        let naive = days * DAY + secs;
+        Some(if utc {
+            Stamp::Utc(naive)
+        } else {
+            Stamp::Local(naive)
+        })
+    }
+
+    fn unix(self) -> i64 {
+        match self {
+            Stamp::Utc(secs) => secs,
+            Stamp::Local(secs) => secs - wallclock::local_offset(secs.max(0) as u64),
+        }
+    }
+
+    /// The same time of day `days` later, across daylight saving changes.
+    fn plus_days(self, days: i64) -> Self {
+        match self {
+            Stamp::Utc(secs) => Stamp::Utc(secs + days * DAY),
+            Stamp::Local(secs) => Stamp::Local(secs + days * DAY),
+        }
+    }
+
+    /// Monday is 0.
+    fn weekday(self) -> i64 {
+        let (Stamp::Utc(secs) | Stamp::Local(secs)) = self;
+        (secs.div_euclid(DAY) + 3).rem_euclid(7)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Event {
+    uid: String,
+    start: Option<Stamp>,
+    end: Option<Stamp>,
+    length: Option<i64>,
+    summary: String,
+    rule: Option<String>,
+    except: Vec<i64>,
+    replaces: Option<i64>,
+    /// All-day, free or cancelled: nothing to plan around.
+    ignored: bool,
+}
+
+/// Meetings overlapping `from..to` in an iCalendar file, earliest first. Events
+/// that repeat daily or weekly are expanded; all-day, free and cancelled ones
+/// are left out.
+pub fn meetings(ics: &str, from: u64, to: u64) -> Vec<Meeting> {
+    let events = events(ics);
+    // Occurrences moved or changed one at a time, which stand in for the repeat
+    let moved: HashSet<(&str, i64)> = events
+        .iter()
+        .filter_map(|e| Some((e.uid.as_str(), e.replaces?)))
+        .collect();
+    let (from, to) = (from as i64, to as i64);
+    let mut meetings = Vec::new();
+    for event in events.iter().filter(|e| !e.ignored) {
+        let Some(start) = event.start else {
+            continue;
+        };
+        let length = match (event.end, event.length) {
+            (Some(end), _) => (end.unix() - start.unix()).max(0),
+            (None, Some(length)) => length,
+            (None, None) => 0,
+        };
+        for occurrence in occurrences(event, start, to) {
+            let begins = occurrence.unix();
+            let ends = begins + length;
+            let skipped = event.except.contains(&begins)
+                || (event.replaces.is_none() && moved.contains(&(event.uid.as_str(), begins)));
+            if !skipped && ends.max(begins + 1) > from && begins < to && begins >= 0 {
+                meetings.push(Meeting {
+                    start: begins as u64,
+                    end: ends as u64,
+                    summary: event.summary.clone(),
+                });
+            }
+        }
+    }
+    meetings.sort_by_key(|m| m.start);
+    meetings.dedup();
+    meetings
+}
+
+fn events(ics: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<Event> = None;
+    // Alarms and the like inside an event have properties of their own
+    let mut nested = 0;
+    for line in unfold(ics) {
+        let (name, params, value) = split_property(&line);
+        let name = name.to_ascii_uppercase();
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                current = Some(Event::default());
+            }
+            ("BEGIN", Some(_)) => nested += 1,
+            ("END", Some(_)) if nested > 0 => nested -= 1,
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                events.extend(current.take());
+            }
+            (_, Some(_)) if nested > 0 => {}
+            ("UID", Some(event)) => event.uid = value.to_string(),
+            ("SUMMARY", Some(event)) => event.summary = unescape(value),
+            ("DTSTART", Some(event)) => {
+                event.start = Stamp::parse(value);
+                event.ignored |= event.start.is_none() || is_date(params);
+            }
+            ("DTEND", Some(event)) => event.end = Stamp::parse(value),
+            ("DURATION", Some(event)) => event.length = parse_length(value),
+            ("RRULE", Some(event)) => event.rule = Some(value.to_ascii_uppercase()),
+            ("EXDATE", Some(event)) => event
+                .except
+                .extend(value.split(',').filter_map(Stamp::parse).map(Stamp::unix)),
+            ("RECURRENCE-ID", Some(event)) => event.replaces = Stamp::parse(value).map(Stamp::unix),
+            ("TRANSP", Some(event)) => event.ignored |= value.eq_ignore_ascii_case("TRANSPARENT"),
+            ("STATUS", Some(event)) => event.ignored |= value.eq_ignore_ascii_case("CANCELLED"),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Joins lines folded onto the next with a leading space or tab.
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// `NAME;PARAM=x:value` into its parts; colons inside quoted parameters don't count.
+fn split_property(line: &str) -> (&str, &str, &str) {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| {
+        match c {
+            '"' => quoted = !quoted,
+            ':' if !quoted => return Some(i),
+            _ => {}
+        }
+        None
+    });
+    let (head, value) = match colon {
+        Some(i) => (&line[..i], &line[i + 1..]),
+        None => (line, ""),
+    };
+    let (name, params) = head.split_once(';').unwrap_or((head, ""));
+    (name, params, value.trim())
+}
+
+fn is_date(params: &str) -> bool {
+    params
+        .split(';')
+        .any(|p| p.eq_ignore_ascii_case("VALUE=DATE"))
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("\\n", " ")
+        .replace("\\N", " ")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+}
+
+/// `PT1H30M`, `P1D` and the like, in seconds.
+fn parse_length(value: &str) -> Option<i64> {
+    let value = value.strip_prefix('+').unwrap_or(value);
+    let mut rest = value.strip_prefix('P')?;
+    let mut secs = 0;
+    while !rest.is_empty() {
+        rest = rest.strip_prefix('T').unwrap_or(rest);
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let n: i64 = rest[..digits].parse().ok()?;
+        secs += n * match rest[digits..].chars().next()? {
+            'W' => 7 * DAY,
+            'D' => DAY,
+            'H' => 3600,
+            'M' => 60,
+            'S' => 1,
+            _ => return None,
+        };
+        rest = &rest[digits + 1..];
+    }
+    Some(secs)
+}
+
+/// Starts of `event` up to `to`: the first alone, or every repeat of a daily or
+/// weekly rule. Other rules only keep their first occurrence.
+fn occurrences(event: &Event, start: Stamp, to: i64) -> Vec<Stamp> {
+    let Some(rule) = &event.rule else {
+        return vec![start];
+    };
+    let part = |key: &str| {
+        rule.split(';')
+            .find_map(|p| p.strip_prefix(key)?.strip_prefix('='))
+    };
+    let interval = part("INTERVAL")
+        .and_then(|n| n.parse::<i64>().ok())
+        .unwrap_or(1)
+        .max(1);
+    let count = part("COUNT").and_then(|n| n.parse::<i64>().ok());
+    let until = part("UNTIL").map(|until| match Stamp::parse(until) {
+        Some(stamp) => stamp.unix(),
+        // A date alone: through the end of that day
+        None => Stamp::parse(&format!("{until}T235959")).map_or(i64::MAX, Stamp::unix),
+    });
+    const WEEKDAYS: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+    let days: Vec<i64> = match part("BYDAY") {
+        Some(days) => days
+            .split(',')
+            .filter_map(|d| WEEKDAYS.iter().position(|w| *w == d))
+            .map(|d| d as i64)
+            .collect(),
+        None => vec![start.weekday()],
+    };
+    let repeats = |offset: i64| match part("FREQ") {
+        Some("DAILY") => offset % interval == 0,
+        Some("WEEKLY") => {
+            let week = (start.weekday() + offset).div_euclid(7);
+            week % interval == 0 && days.contains(&start.plus_days(offset).weekday())
+        }
+        _ => offset == 0,
+    };
+    let mut starts = Vec::new();
+    let mut seen = 0;
+    for offset in 0..MAX_REPEATS {
+        let stamp = start.plus_days(offset);
+        let at = stamp.unix();
+        if at > to || until.is_some_and(|until| at > until) || count.is_some_and(|n| seen >= n) {
+            break;
+        }
+        if offset == 0 || repeats(offset) {
+            starts.push(stamp);
+            seen += 1;
+        }
+    }
+    starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-10-12 is a Monday.
+    fn utc(day: u32, hour: i64, minute: i64) -> u64 {
+        (days_from_civil(2026, 10, day) * DAY + hour * 3600 + minute * 60) as u64
+    }
+
+    const ICS: &str = "BEGIN:VCALENDAR\r\n\
+BEGIN:VEVENT\r\n\
+UID:standup\r\n\
+SUMMARY:Stand\r\n  up\r\n\
+DTSTART:20261012T090000Z\r\n\
+DTEND:20261012T091500Z\r\n\
+RRULE:FREQ=WEEKLY;BYDAY=MO,WE,FR\r\n\
+EXDATE:20261016T090000Z\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:standup\r\n\
+RECURRENCE-ID:20261014T090000Z\r\n\
+SUMMARY:Stand up (moved)\r\n\
+DTSTART:20261014T100000Z\r\n\
+DURATION:PT30M\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+UID:review\r\n\
+SUMMARY:Design review\\, round 2\r\n\
+DTSTART;TZID=\"Europe/Madrid\":20261013T140000\r\n\
+DTEND;TZID=\"Europe/Madrid\":20261013T150000\r\n\
+BEGIN:VALARM\r\n\
+TRIGGER:-PT10M\r\nSUMMARY:Reminder\r\n\
+END:VALARM\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+SUMMARY:Holiday\r\n\
+DTSTART;VALUE=DATE:20261013\r\n\
+END:VEVENT\r\n\
+BEGIN:VEVENT\r\n\
+SUMMARY:Focus block\r\n\
+DTSTART:20261013T160000Z\r\n\
+TRANSP:TRANSPARENT\r\n\
+END:VEVENT\r\n\
+END:VCALENDAR\r\n";
+
+    #[test]
+    fn reads_events_and_weekly_repeats() {
+        let found = meetings(ICS, utc(12, 0, 0), utc(19, 23, 0));
+        let summaries: Vec<_> = found.iter().map(|m| m.summary.as_str()).collect();
+        assert_eq!(
+            summaries,
+            [
+                "Stand up",
+                "Design review, round 2",
+                "Stand up (moved)",
+                "Stand up"
+            ]
+        );
+        assert_eq!(
+            (found[0].start, found[0].end),
+            (utc(12, 9, 0), utc(12, 9, 15))
+        );
+        assert_eq!(
+            (found[2].start, found[2].end),
+            (utc(14, 10, 0), utc(14, 10, 30))
+        );
+        assert_eq!(found[3].start, utc(19, 9, 0));
+        // Named zones are read as local time
+        let review = Stamp::Local((days_from_civil(2026, 10, 13) * DAY) + 14 * 3600).unix();
+        assert_eq!(found[1].start, review as u64);
+        assert_eq!(found[1].end - found[1].start, 3600);
+        // Only what overlaps the window
+        assert_eq!(meetings(ICS, utc(12, 9, 10), utc(12, 12, 0)).len(), 1);
+    }
+
+    #[test]
+    fn daily_repeats_stop_at_count_or_until() {
+        let ics = |rule: &str| {
+            format!(
+                "BEGIN:VEVENT\nSUMMARY:Sync\nDTSTART:20261012T080000Z\nRRULE:{rule}\nEND:VEVENT\n"
+            )
+        };
+        let starts = |rule| -> Vec<u64> {
+            meetings(&ics(rule), utc(12, 0, 0), utc(31, 0, 0))
+                .iter()
+                .map(|m| m.start)
+                .collect()
+        };
+        assert_eq!(starts("FREQ=DAILY;COUNT=2"), [utc(12, 8, 0), utc(13, 8, 0)]);
+        assert_eq!(
+            starts("FREQ=DAILY;INTERVAL=3;UNTIL=20261018"),
+            [utc(12, 8, 0), utc(15, 8, 0), utc(18, 8, 0)]
+        );
+        assert_eq!(starts("FREQ=MONTHLY"), [utc(12, 8, 0)]);
+    }
+
+    #[test]
+    fn parses_sources_and_caldav_answers() {
+        assert_eq!(
+            Source::parse("~/cal.ics").unwrap(),
+            Source::File(PathBuf::from("~/cal.ics"))
+        );
+        assert_eq!(
+            Source::parse("http://me:pw@dav.lan:5232/me/work/").unwrap(),
+            Source::Server {
+                host: "dav.lan:5232".to_string(),
+                path: "/me/work/".to_string(),
+                auth: Some("Basic bWU6cHc=".to_string()),
+            }
+        );
+        assert!(Source::parse("https://cal.example.com/x.ics").is_err());
+        let xml = "<d:multistatus><d:response><c:calendar-data>BEGIN:VCALENDAR&#13;\n\
+                   BEGIN:VEVENT&#13;\nSUMMARY:R&amp;D&#13;\nDTSTART:20261012T090000Z&#13;\n\
+                   END:VEVENT&#13;\nEND:VCALENDAR</c:calendar-data></d:response></d:multistatus>";
+        let found = meetings(&calendar_data(xml), utc(12, 0, 0), utc(13, 0, 0));
+        assert_eq!(found[0].summary, "R&D");
+        assert!(
+            calendar_query(utc(12, 0, 0), utc(14, 0, 0))
+                .contains(r#"<C:time-range start="20261012T000000Z" end="20261014T000000Z"/>"#)
+        );
+    }
+}
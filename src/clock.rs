@@ -0,0 +1,172 @@
+//! Where the timer gets the time from: the system clock normally, a sped-up one
+//! for `--simulate` and `--demo`, and one that only moves when told to in tests.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::args::Args;
+use crate::wallclock;
+
+pub trait Clock: fmt::Debug {
+    fn now(&self) -> Instant;
+    /// Seconds since the Unix epoch, for history records.
+    fn unix(&self) -> u64;
+    /// How long `clock` of this clock's time takes in real time, for sleeping.
+    fn real(&self, clock: Duration) -> Duration {
+        clock
+    }
+}
+
+#[derive(Debug)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn unix(&self) -> u64 {
+        wallclock::now_unix()
+    }
+}
+
+/// `--simulate focus=2s`: a phase of that kind lasts the given real time.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Simulation {
+    pub phase: SimulatedPhase,
+    pub lasts: Duration,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SimulatedPhase {
+    Focus,
+    Short,
+    Long,
+}
+
+impl Simulation {
+    /// How many times faster than real time the timer runs.
+    pub fn speed(&self, args: &Args) -> f64 {
+        let length = match self.phase {
+            SimulatedPhase::Focus => args.focus,
+            SimulatedPhase::Short => args.short,
+            SimulatedPhase::Long => args.long,
+        };
+        (length.as_secs_f64() / self.lasts.as_secs_f64()).max(1.0)
+    }
+}
+
+pub fn parse_simulation(value: &str) -> Result<Simulation, String> {
+    let Some((phase, lasts)) = value.split_once('=') else {
+        return Err(format!("`{value}` is not PHASE=DURATION, e.g. focus=2s"));
+    };
+    let phase = match phase.trim() {
+        "focus" => SimulatedPhase::Focus,
+        "short" => SimulatedPhase::Short,
+        "long" => SimulatedPhase::Long,
+        other => return Err(format!("`{other}` is not one of focus, short or long")),
+    };
+    let lasts = crate::timefmt::parse_duration(lasts)?;
+    if lasts.is_zero() {
+        return Err("must last at least a moment".to_string());
+    }
+    Ok(Simulation { phase, lasts })
+}
+
+/// Runs `speed` times faster than the system clock from the moment it's made.
+#[derive(Debug)]
+pub struct ScaledClock {
+    started: Instant,
+    started_unix: u64,
+    speed: f64,
+}
+
+impl ScaledClock {
+    pub fn new(speed: f64) -> Self {
+        Self {
+            started: Instant::now(),
+            started_unix: wallclock::now_unix(),
+            speed,
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.started.elapsed().mul_f64(self.speed)
+    }
+}
+
+impl Clock for ScaledClock {
+    fn now(&self) -> Instant {
+        self.started + self.elapsed()
+    }
+
+    fn unix(&self) -> u64 {
+        self.started_unix + self.elapsed().as_secs()
+    }
+
+    fn real(&self, clock: Duration) -> Duration {
+        clock.div_f64(self.speed)
+    }
+}
+
+/// Stands still until advanced; clones share the same time.
+#[cfg(test)]
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    started: Instant,
+    offset: std::rc::Rc<std::cell::Cell<Duration>>,
+}
+
+#[cfg(test)]
+impl MockClock {
+    /// 2023-11-14 22:13:20 UTC, so history records made in tests are stable.
+    pub const STARTED_UNIX: u64 = 1_700_000_000;
+
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            offset: Default::default(),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.offset.set(self.offset.get() + by);
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.started + self.offset.get()
+    }
+
+    fn unix(&self) -> u64 {
+        Self::STARTED_UNIX + self.offset.get().as_secs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_simulations() {
+        assert_eq!(
+            parse_simulation("focus=2s"),
+            Ok(Simulation {
+                phase: SimulatedPhase::Focus,
+                lasts: Duration::from_secs(2),
+            })
+        );
+        assert!(parse_simulation("focus").is_err());
+        assert!(parse_simulation("lunch=2s").is_err());
+        assert!(parse_simulation("short=0s").is_err());
+    }
+
+    #[test]
+    fn scaled_clock_sleeps_proportionally() {
+        let clock = ScaledClock::new(750.0);
+        assert_eq!(clock.real(Duration::from_secs(3)), Duration::from_millis(4));
+        assert!(clock.now() >= clock.started);
+    }
+}
@@ -0,0 +1,358 @@
+//! Shell completion scripts, generated from the clap definition so they never
+//! drift from the real flags.
+
+use clap::ValueEnum;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+/// One flag as the completion scripts need it.
+struct Flag {
+    long: Option<String>,
+    short: Option<char>,
+    help: String,
+    takes_value: bool,
+    values: Vec<String>,
+}
+
+/// A command or subcommand, with the names leading to it (`["rusty_pomo", "sync", "merge"]`).
+struct Node {
+    path: Vec<String>,
+    flags: Vec<Flag>,
+    subcommands: Vec<(String, String)>,
+    /// Choices for a positional argument, such as the shell name here.
+    values: Vec<String>,
+}
+
+impl Node {
+    fn id(&self) -> String {
+        self.path.join("__")
+    }
+}
+
+fn nodes(cmd: &clap::Command, parent: &[String], out: &mut Vec<Node>) {
+    let mut path = parent.to_vec();
+    path.push(cmd.get_name().to_string());
+    let flags = cmd
+        .get_arguments()
+        .filter(|arg| !arg.is_positional() && !arg.is_hide_set())
+        .map(|arg| Flag {
+            long: arg.get_long().map(str::to_string),
+            short: arg.get_short(),
+            help: first_line(arg.get_help().map(ToString::to_string)),
+            takes_value: arg.get_action().takes_values(),
+            values: arg
+                .get_possible_values()
+                .iter()
+                .filter(|v| !v.is_hide_set())
+                .map(|v| v.get_name().to_string())
+                .collect(),
+        })
+        .collect();
+    let values = cmd
+        .get_positionals()
+        .flat_map(|arg| arg.get_possible_values())
+        .filter(|v| !v.is_hide_set())
+        .map(|v| v.get_name().to_string())
+        .collect();
+    let visible: Vec<_> = cmd.get_subcommands().filter(|s| !s.is_hide_set()).collect();
+    out.push(Node {
+        path: path.clone(),
+        flags,
+        subcommands: visible
+            .iter()
+            .map(|s| {
+                let about = first_line(s.get_about().map(ToString::to_string));
+                (s.get_name().to_string(), about)
+            })
+            .collect(),
+        values,
+    });
+    for sub in visible {
+        nodes(sub, &path, out);
+    }
+}
+
+fn first_line(text: Option<String>) -> String {
+    text.unwrap_or_default()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+pub fn generate(shell: Shell, mut cmd: clap::Command) -> String {
+    cmd.build();
+    let mut all = Vec::new();
+    nodes(&cmd, &[], &mut all);
+    let name = cmd.get_name();
+    match shell {
+        Shell::Bash => bash(name, &all),
+        Shell::Zsh => zsh(name, &all),
+        Shell::Fish => fish(name, &all),
+        Shell::Powershell => powershell(name, &all),
+    }
+}
+
+fn flag_words(flag: &Flag) -> Vec<String> {
+    let mut words = Vec::new();
+    if let Some(short) = flag.short {
+        words.push(format!("-{short}"));
+    }
+    if let Some(long) = &flag.long {
+        words.push(format!("--{long}"));
+    }
+    words
+}
+
+fn bash(name: &str, all: &[Node]) -> String {
+    let func = format!("_{name}");
+    let mut out = format!(
+        "{func}() {{\n    local cur prev path i\n    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"\n    path=\"{name}\"\n    for ((i = 1; i < COMP_CWORD; i++)); do\n        case \"${{path}},${{COMP_WORDS[i]}}\" in\n"
+    );
+    for node in all {
+        for (sub, _) in &node.subcommands {
+            out.push_str(&format!(
+                "            {},{sub}) path=\"{}__{sub}\" ;;\n",
+                node.id(),
+                node.id()
+            ));
+        }
+    }
+    out.push_str("        esac\n    done\n    case \"${path}\" in\n");
+    for node in all {
+        out.push_str(&format!("        {})\n", node.id()));
+        out.push_str("            case \"${prev}\" in\n");
+        for flag in node.flags.iter().filter(|f| f.takes_value) {
+            let words = flag_words(flag).join("|");
+            let reply = if flag.values.is_empty() {
+                "compgen -f -- \"${cur}\"".to_string()
+            } else {
+                format!("compgen -W \"{}\" -- \"${{cur}}\"", flag.values.join(" "))
+            };
+            out.push_str(&format!(
+                "                {words}) COMPREPLY=($({reply})); return ;;\n"
+            ));
+        }
+        out.push_str("            esac\n");
+        let mut words: Vec<String> = node.flags.iter().flat_map(flag_words).collect();
+        words.extend(node.subcommands.iter().map(|(sub, _)| sub.clone()));
+        words.extend(node.values.iter().cloned());
+        out.push_str(&format!(
+            "            COMPREPLY=($(compgen -W \"{}\" -- \"${{cur}}\"))\n            ;;\n",
+            words.join(" ")
+        ));
+    }
+    out.push_str(&format!(
+        "    esac\n}}\n\ncomplete -o default -F {func} {name}\n"
+    ));
+    out
+}
+
+fn zsh_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\'', "'\\''")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+        .replace(':', "\\:")
+}
+
+fn zsh(name: &str, all: &[Node]) -> String {
+    let mut out = format!("#compdef {name}\n\n");
+    for node in all.iter().rev() {
+        out.push_str(&format!("_{}() {{\n", node.id()));
+        out.push_str("    local curcontext=\"$curcontext\" state line\n");
+        out.push_str("    _arguments -C \\\n");
+        for flag in &node.flags {
+            let help = zsh_escape(&flag.help);
+            let value = match (flag.takes_value, flag.values.is_empty()) {
+                (false, _) => String::new(),
+                (true, true) => ":value:_files".to_string(),
+                (true, false) => format!(":value:({})", flag.values.join(" ")),
+            };
+            for word in flag_words(flag) {
+                out.push_str(&format!("        '{word}[{help}]{value}' \\\n"));
+            }
+        }
+        if node.subcommands.is_empty() {
+            if node.values.is_empty() {
+                out.push_str("        '*::file:_files'\n}\n\n");
+            } else {
+                out.push_str(&format!(
+                    "        '1:value:({})'\n}}\n\n",
+                    node.values.join(" ")
+                ));
+            }
+            continue;
+        }
+        out.push_str("        '1: :->command' \\\n        '*:: :->args'\n");
+        out.push_str("    case $state in\n        command)\n            local -a commands\n            commands=(\n");
+        for (sub, about) in &node.subcommands {
+            out.push_str(&format!(
+                "                '{sub}:{}'\n",
+                about.replace('\'', "'\\''")
+            ));
+        }
+        out.push_str("            )\n            _describe 'command' commands\n            ;;\n");
+        out.push_str("        args)\n            case $line[1] in\n");
+        for (sub, _) in &node.subcommands {
+            out.push_str(&format!(
+                "                {sub}) _{}__{sub} ;;\n",
+                node.id()
+            ));
+        }
+        out.push_str("            esac\n            ;;\n    esac\n}\n\n");
+    }
+    out.push_str(&format!("_{name} \"$@\"\n"));
+    out
+}
+
+fn fish_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn fish(name: &str, all: &[Node]) -> String {
+    let mut out = String::new();
+    for node in all {
+        // Which subcommands have been typed decides where we are; depth 1 is the root.
+        let condition = match node.path.len() {
+            1 => "__fish_use_subcommand".to_string(),
+            _ => {
+                let here = node.path.last().cloned().unwrap_or_default();
+                let children: Vec<_> = node.subcommands.iter().map(|(s, _)| s.as_str()).collect();
+                if children.is_empty() {
+                    format!("__fish_seen_subcommand_from {here}")
+                } else {
+                    format!(
+                        "__fish_seen_subcommand_from {here}; and not __fish_seen_subcommand_from {}",
+                        children.join(" ")
+                    )
+                }
+            }
+        };
+        for flag in &node.flags {
+            let mut line = format!("complete -c {name} -n '{condition}'");
+            if let Some(short) = flag.short {
+                line.push_str(&format!(" -s {short}"));
+            }
+            if let Some(long) = &flag.long {
+                line.push_str(&format!(" -l {long}"));
+            }
+            if flag.takes_value {
+                line.push_str(" -r");
+                if !flag.values.is_empty() {
+                    line.push_str(&format!(" -f -a '{}'", flag.values.join(" ")));
+                }
+            }
+            if !flag.help.is_empty() {
+                line.push_str(&format!(" -d '{}'", fish_escape(&flag.help)));
+            }
+            out.push_str(&line);
+            out.push('\n');
+        }
+        for (sub, about) in &node.subcommands {
+            out.push_str(&format!(
+                "complete -c {name} -n '{condition}' -f -a {sub} -d '{}'\n",
+                fish_escape(about)
+            ));
+        }
+        if !node.values.is_empty() {
+            out.push_str(&format!(
+                "complete -c {name} -n '{condition}' -f -a '{}'\n",
+                node.values.join(" ")
+            ));
+        }
+    }
+    out
+}
+
+fn powershell(name: &str, all: &[Node]) -> String {
+    let mut out = format!(
+        "Register-ArgumentCompleter -Native -CommandName '{name}' -ScriptBlock {{\n    param($wordToComplete, $commandAst, $cursorPosition)\n    $path = '{name}'\n    foreach ($element in $commandAst.CommandElements | Select-Object -Skip 1) {{\n        $word = $element.ToString()\n        if ($word -eq $wordToComplete) {{ break }}\n        switch (\"$path,$word\") {{\n"
+    );
+    for node in all {
+        for (sub, _) in &node.subcommands {
+            out.push_str(&format!(
+                "            '{},{sub}' {{ $path = '{}__{sub}' }}\n",
+                node.id(),
+                node.id()
+            ));
+        }
+    }
+    out.push_str("        }\n    }\n    $candidates = switch ($path) {\n");
+    for node in all {
+        out.push_str(&format!("        '{}' {{\n", node.id()));
+        for flag in &node.flags {
+            let help = flag.help.replace('\'', "''");
+            for word in flag_words(flag) {
+                out.push_str(&format!(
+                    "            [CompletionResult]::new('{word}', '{word}', [CompletionResultType]::ParameterName, '{}')\n",
+                    if help.is_empty() { &word } else { &help }
+                ));
+            }
+        }
+        for (sub, about) in &node.subcommands {
+            let about = about.replace('\'', "''");
+            out.push_str(&format!(
+                "            [CompletionResult]::new('{sub}', '{sub}', [CompletionResultType]::ParameterValue, '{}')\n",
+                if about.is_empty() { sub } else { &about }
+            ));
+        }
+        for value in &node.values {
+            out.push_str(&format!(
+                "            [CompletionResult]::new('{value}', '{value}', [CompletionResultType]::ParameterValue, '{value}')\n"
+            ));
+        }
+        out.push_str("        }\n");
+    }
+    out.push_str(
+        "    }\n    $candidates | Where-Object { $_.CompletionText -like \"$wordToComplete*\" }\n}\n",
+    );
+    format!("using namespace System.Management.Automation\n\n{out}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::Cli;
+    use clap::CommandFactory;
+
+    #[test]
+    fn bash_completes_flags_values_and_nested_subcommands() {
+        let script = generate(Shell::Bash, Cli::command());
+        assert!(script.contains("complete -o default -F _rusty_pomo rusty_pomo"));
+        assert!(script.contains("--focus"));
+        assert!(script.contains("compgen -W \"dracula solarized-dark gruvbox-dark\""));
+        assert!(script.contains("rusty_pomo__sync,merge) path=\"rusty_pomo__sync__merge\""));
+        assert!(script.contains("\"-h --help bash zsh fish powershell\""));
+    }
+
+    #[test]
+    fn every_shell_mentions_every_subcommand() {
+        for shell in Shell::value_variants() {
+            let script = generate(*shell, Cli::command());
+            for sub in [
+                "import",
+                "sync",
+                "merge",
+                "hue",
+                "pair",
+                "scenes",
+                "completions",
+            ] {
+                assert!(script.contains(sub), "{shell:?} misses {sub}");
+            }
+        }
+    }
+
+    #[test]
+    fn escapes_quotes_in_help() {
+        assert_eq!(zsh_escape("it's [a:b]"), "it'\\''s \\[a\\:b\\]");
+        assert_eq!(fish_escape("today's"), "today\\'s");
+    }
+}
@@ -0,0 +1,1574 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use toml_edit::{ImDocument, Item, TableLike};
+
+use crate::args::Args;
+use crate::calendar;
+use crate::doctor::Probe;
+use crate::history::StoreKind;
+use crate::integrations::telegram::Bot;
+use crate::mascot::Mascot;
+use crate::notifications::{Channel, Route, Style, Urgency};
+use crate::paths;
+use crate::phases::{self, CustomPhase};
+use crate::score::Weights;
+use crate::settings;
+use crate::template;
+use crate::theme::Theme;
+use crate::timefmt::parse_duration;
+use crate::ui::LayoutPreset;
+use crate::validate;
+use crate::wallclock::{self, Zone};
+use crate::work_hours::{self, Lunch, WorkHours};
+
+/// The fully commented starting point written by `config init`.
+pub const TEMPLATE: &str = include_str!("../assets/config.toml");
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProjectConfig {
+    pub focus: Option<Duration>,
+    pub short: Option<Duration>,
+    pub long: Option<Duration>,
+    pub long_every: Option<u64>,
+}
+
+/// `[timer]`: defaults for every run, below any profile's or project's own durations.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TimerConfig {
+    pub durations: ProjectConfig,
+    pub warm_up: Option<Duration>,
+    pub cycle_review: Option<Duration>,
+    pub partial_credit: Option<u8>,
+    pub daily_goal: Option<u64>,
+    pub offer_extension: Option<Duration>,
+    pub theme: Option<Theme>,
+    pub mascot: Option<Mascot>,
+    pub layout: Option<LayoutPreset>,
+    pub counter_icon: Option<String>,
+    pub notifications: Option<bool>,
+    pub notification_sound: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HistoryConfig {
+    pub backend: Option<StoreKind>,
+    pub dir: Option<PathBuf>,
+    pub tz: Option<Zone>,
+    /// Days of sessions `history compact` keeps as they are.
+    pub keep_days: Option<u64>,
+}
+
+/// Philips Hue bridge credentials (written by `hue pair`) and the scenes to recall.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HueConfig {
+    pub bridge: Option<String>,
+    pub username: Option<String>,
+    pub group: Option<String>,
+    pub focus_scene: Option<String>,
+    pub break_scene: Option<String>,
+}
+
+/// A server that finished sessions are uploaded to; see `docs/remote-api.md`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RemoteConfig {
+    pub url: Option<String>,
+    pub token: Option<String>,
+}
+
+/// `[push]`: where the `ntfy` and `email` notification channels send to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PushConfig {
+    pub ntfy: Option<String>,
+    pub ntfy_token: Option<String>,
+    pub email: Option<String>,
+    pub email_from: Option<String>,
+    /// `host:port` of the relay.
+    pub smtp: Option<String>,
+}
+
+/// `[calendar]`: meetings to plan focus sessions around.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CalendarConfig {
+    pub source: Option<String>,
+    pub warning: Option<u64>,
+    pub fit: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AmbientConfig {
+    pub focus: Option<String>,
+    pub rest: Option<String>,
+    pub volume: Option<u8>,
+}
+
+/// `[breaks]`: the lines shown during breaks, listed inline or in a file, more
+/// suggestions, and the breathing exercise.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BreaksConfig {
+    pub breathing: Option<bool>,
+    pub track_rest: Option<bool>,
+    pub quotes: Option<Vec<String>>,
+    pub quotes_file: Option<PathBuf>,
+    pub suggestions: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub timer: TimerConfig,
+    pub projects: BTreeMap<String, ProjectConfig>,
+    /// Durations by profile name, from `[profiles.NAME]`.
+    pub profiles: BTreeMap<String, ProjectConfig>,
+    /// `[schedule]` rules in file order: the weekdays each applies to (Monday
+    /// first) and the profile for them. The first rule for a day wins.
+    pub schedule: Vec<([bool; 7], String)>,
+    pub history: HistoryConfig,
+    pub hue: HueConfig,
+    pub ambient: AmbientConfig,
+    pub breaks: BreaksConfig,
+    pub remote: RemoteConfig,
+    pub push: PushConfig,
+    pub telegram: Option<Bot>,
+    pub calendar: CalendarConfig,
+    pub work_hours: Option<WorkHours>,
+    pub lunch: Option<Lunch>,
+    /// `[phases.NAME]` in file order.
+    pub phases: Vec<CustomPhase>,
+    /// `[distractions] apps`: the apps to count time in during focus.
+    pub distraction_apps: Vec<String>,
+    /// `[score]`: how the focus score weighs its parts.
+    pub score: Weights,
+    /// `[notifications]`: events sent elsewhere than `--notifier`.
+    pub notification_routes: Vec<(Route, Vec<Channel>)>,
+    /// `[notifications.style]`: urgency and hints per event.
+    pub notification_styles: Vec<(Route, Style)>,
+    /// Settings from `RUSTY_POMO_*` variables, which win over the file's; see
+    /// `settings`.
+    pub env: Option<Box<Config>>,
+}
+
+impl Config {
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = paths::config_file() else {
+            return Self::default().with_env();
+        };
+        let config = match fs::read_to_string(&path) {
+            Ok(contents) => {
+                Self::parse(&contents).map_err(|e| format!("{}: {e}", path.display()))?
+            }
+            Err(_) => Self::default(),
+        };
+        config.with_env()
+    }
+
+    /// This config with the `RUSTY_POMO_*` variables layered on top.
+    pub fn with_env(self) -> Result<Self, String> {
+        Ok(Self {
+            env: settings::from_env()?.map(Box::new),
+            ..self
+        })
+    }
+
+    /// Parses and validates a config file; errors name the key and, where known, its line.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let doc = ImDocument::parse(contents).map_err(|e| format!("{e}"))?;
+        let read = Reader { source: contents };
+        let mut config = Self::default();
+        if let Some(item) = doc.as_table().get("timer") {
+            let table = read.table(item, "timer")?;
+            config.timer.durations = read.durations(table, "timer")?;
+            config.timer.warm_up = read.phase_length(table, "timer", "warm_up")?;
+            config.timer.cycle_review = read.phase_length(table, "timer", "cycle_review")?;
+            config.timer.offer_extension = read.phase_length(table, "timer", "offer_extension")?;
+            config.timer.partial_credit = match read.u64(table, "timer", "partial_credit")? {
+                Some(v @ 1..=100) => Some(v as u8),
+                Some(_) => {
+                    let message = "must be a percentage between 1 and 100";
+                    return Err(read.invalid(table, "partial_credit", "timer", message));
+                }
+                None => None,
+            };
+            config.timer.daily_goal = match read.u64(table, "timer", "daily_goal")? {
+                Some(0) => {
+                    let message = "must be at least 1";
+                    return Err(read.invalid(table, "daily_goal", "timer", message));
+                }
+                goal => goal,
+            };
+            if let Some(theme) = read.str(table, "timer", "theme")? {
+                config.timer.theme = Some(Theme::from_str(theme, false).map_err(|_| {
+                    let names: Vec<_> = Theme::value_variants()
+                        .iter()
+                        .filter_map(|t| t.to_possible_value())
+                        .map(|v| format!("\"{}\"", v.get_name()))
+                        .collect();
+                    let message = format!("must be one of {}", names.join(", "));
+                    read.invalid(table, "theme", "timer", &message)
+                })?);
+            }
+            if let Some(mascot) = read.str(table, "timer", "mascot")? {
+                config.timer.mascot = Some(Mascot::from_str(mascot, false).map_err(|_| {
+                    let message = "must be \"off\", \"tomato\" or \"cat\"";
+                    read.invalid(table, "mascot", "timer", message)
+                })?);
+            }
+            if let Some(layout) = read.str(table, "timer", "layout")? {
+                config.timer.layout =
+                    Some(LayoutPreset::from_str(layout, false).map_err(|_| {
+                        let message = "must be \"default\", \"wide\", \"vertical\" or \"minimal\"";
+                        read.invalid(table, "layout", "timer", message)
+                    })?);
+            }
+            config.timer.counter_icon = read
+                .str(table, "timer", "counter_icon")?
+                .map(str::to_string);
+            config.timer.notifications = read.bool(table, "timer", "notifications")?;
+            config.timer.notification_sound = read
+                .str(table, "timer", "notification_sound")?
+                .map(str::to_string);
+        }
+        if let Some(item) = doc.as_table().get("projects") {
+            let projects = read.table(item, "projects")?;
+            for (name, item) in projects.iter() {
+                let prefix = format!("projects.{name}");
+                let table = read.table(item, &prefix)?;
+                config
+                    .projects
+                    .insert(name.to_string(), read.durations(table, &prefix)?);
+            }
+        }
+        if let Some(item) = doc.as_table().get("profiles") {
+            let profiles = read.table(item, "profiles")?;
+            for (name, item) in profiles.iter() {
+                let prefix = format!("profiles.{name}");
+                let table = read.table(item, &prefix)?;
+                config
+                    .profiles
+                    .insert(name.to_string(), read.durations(table, &prefix)?);
+            }
+        }
+        if let Some(item) = doc.as_table().get("schedule") {
+            let table = read.table(item, "schedule")?;
+            for (days, _) in table.iter() {
+                let Some(profile) = read.str(table, "schedule", days)? else {
+                    continue;
+                };
+                if !config.profiles.contains_key(profile) {
+                    let problem = format!("names no [profiles.{profile}] table");
+                    return Err(read.invalid(table, days, "schedule", &problem));
+                }
+                let names: Vec<String> = days.split(',').map(str::to_string).collect();
+                let days = work_hours::parse_days(&names)
+                    .map_err(|e| read.invalid(table, days, "schedule", &e))?;
+                config.schedule.push((days, profile.to_string()));
+            }
+        }
+        if let Some(item) = doc.as_table().get("history") {
+            let table = read.table(item, "history")?;
+            if let Some(backend) = read.str(table, "history", "backend")? {
+                config.history.backend = Some(StoreKind::from_key(backend).ok_or_else(|| {
+                    let message = format!("must be \"jsonl\" or \"memory\", got \"{backend}\"");
+                    read.invalid(table, "backend", "history", &message)
+                })?);
+            }
+            config.history.dir = read.str(table, "history", "dir")?.map(expand_home);
+            config.history.keep_days = match read.u64(table, "history", "keep_days")? {
+                Some(0) => {
+                    return Err(read.invalid(table, "keep_days", "history", "must be at least 1"));
+                }
+                days => days,
+            };
+            if let Some(tz) = read.str(table, "history", "tz")? {
+                config.history.tz = Some(
+                    wallclock::parse_zone(tz)
+                        .map_err(|e| read.invalid(table, "tz", "history", &e))?,
+                );
+            }
+        }
+        if let Some(item) = doc.as_table().get("hue") {
+            let table = read.table(item, "hue")?;
+            let get = |key| read.str(table, "hue", key).map(|v| v.map(str::to_string));
+            config.hue = HueConfig {
+                bridge: get("bridge")?,
+                username: get("username")?,
+                group: get("group")?,
+                focus_scene: get("focus_scene")?,
+                break_scene: get("break_scene")?,
+            };
+        }
+        if let Some(item) = doc.as_table().get("ambient") {
+            let table = read.table(item, "ambient")?;
+            let track = |key| read.str(table, "ambient", key).map(|v| v.map(expand_track));
+            config.ambient.focus = track("focus")?;
+            config.ambient.rest = track("break")?;
+            config.ambient.volume = match read.u64(table, "ambient", "volume")? {
+                Some(v @ 0..=100) => Some(v as u8),
+                Some(_) => {
+                    return Err(read.invalid(
+                        table,
+                        "volume",
+                        "ambient",
+                        "must be between 0 and 100",
+                    ));
+                }
+                None => None,
+            };
+        }
+        if let Some(item) = doc.as_table().get("breaks") {
+            let table = read.table(item, "breaks")?;
+            config.breaks.breathing = read.bool(table, "breaks", "breathing")?;
+            config.breaks.track_rest = read.bool(table, "breaks", "track_rest")?;
+            config.breaks.quotes = read.strings(table, "breaks", "quotes")?;
+            config.breaks.quotes_file = read.str(table, "breaks", "quotes_file")?.map(expand_home);
+            config.breaks.suggestions = read.strings(table, "breaks", "suggestions")?;
+        }
+        if let Some(item) = doc.as_table().get("distractions") {
+            let table = read.table(item, "distractions")?;
+            config.distraction_apps = read
+                .strings(table, "distractions", "apps")?
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|app| !app.trim().is_empty())
+                .collect();
+        }
+        if let Some(item) = doc.as_table().get("notifications") {
+            let table = read.table(item, "notifications")?;
+            let route_named = |table: &dyn TableLike, key: &str, prefix: &str| {
+                Route::ALL
+                    .into_iter()
+                    .find(|r| r.name() == key)
+                    .ok_or_else(|| {
+                        let names: Vec<_> = Route::ALL.iter().map(|r| r.name()).collect();
+                        let message = format!("is not an event; use {}", names.join(", "));
+                        read.invalid(table, key, prefix, &message)
+                    })
+            };
+            for (key, item) in table.iter() {
+                if key == "style" {
+                    let styles = read.table(item, "notifications.style")?;
+                    for (key, item) in styles.iter() {
+                        let route = route_named(styles, key, "notifications.style")?;
+                        let prefix = format!("notifications.style.{key}");
+                        let style = read.table(item, &prefix)?;
+                        let urgency = read
+                            .str(style, &prefix, "urgency")?
+                            .map(|name| {
+                                Urgency::parse(name).ok_or_else(|| {
+                                    read.invalid(
+                                        style,
+                                        "urgency",
+                                        &prefix,
+                                        "must be \"low\", \"normal\" or \"critical\"",
+                                    )
+                                })
+                            })
+                            .transpose()?;
+                        let parsed = Style {
+                            urgency,
+                            resident: read.bool(style, &prefix, "resident")?,
+                            transient: read.bool(style, &prefix, "transient")?,
+                        };
+                        config.notification_styles.push((route, parsed));
+                    }
+                    continue;
+                }
+                let route = route_named(table, key, "notifications")?;
+                let names = read
+                    .strings(table, "notifications", key)?
+                    .unwrap_or_default();
+                let channels = names
+                    .iter()
+                    .map(|name| {
+                        Channel::parse(name).ok_or_else(|| {
+                            let message = format!("has an unknown channel \"{name}\"");
+                            read.invalid(table, key, "notifications", &message)
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+                config.notification_routes.push((route, channels));
+            }
+        }
+        if let Some(item) = doc.as_table().get("score") {
+            let table = read.table(item, "score")?;
+            let weights = &mut config.score;
+            for (key, field) in [
+                ("sessions", &mut weights.sessions),
+                ("pauses", &mut weights.pauses),
+                ("skips", &mut weights.skips),
+                ("rest", &mut weights.rest),
+                ("target", &mut weights.target),
+            ] {
+                if let Some(n) = read.u64(table, "score", key)? {
+                    *field = n;
+                }
+            }
+            if weights.target == 0 {
+                return Err(read.invalid(table, "target", "score", "must be at least 1"));
+            }
+        }
+        if let Some(item) = doc.as_table().get("remote") {
+            let table = read.table(item, "remote")?;
+            if let Some(url) = read.str(table, "remote", "url")? {
+                if !url.starts_with("http://") {
+                    let message = "must start with http:// (https is not supported)";
+                    return Err(read.invalid(table, "url", "remote", message));
+                }
+                config.remote.url = Some(url.to_string());
+            }
+            config.remote.token = read.str(table, "remote", "token")?.map(str::to_string);
+        }
+        if let Some(item) = doc.as_table().get("push") {
+            let table = read.table(item, "push")?;
+            if let Some(url) = read.str(table, "push", "ntfy")? {
+                if !url.starts_with("http://") || !url["http://".len()..].contains('/') {
+                    let message = "must be an http:// URL with a topic (https is not supported)";
+                    return Err(read.invalid(table, "ntfy", "push", message));
+                }
+                config.push.ntfy = Some(url.to_string());
+            }
+            for (key, field) in [
+                ("ntfy_token", &mut config.push.ntfy_token),
+                ("email", &mut config.push.email),
+                ("email_from", &mut config.push.email_from),
+                ("smtp", &mut config.push.smtp),
+            ] {
+                *field = read.str(table, "push", key)?.map(str::to_string);
+            }
+        }
+        if let Some(item) = doc.as_table().get("telegram") {
+            let table = read.table(item, "telegram")?;
+            let required = |key: &str| {
+                read.str(table, "telegram", key)?
+                    .ok_or_else(|| read.at(item, format!("`telegram` needs `{key}`")))
+            };
+            let api = required("api")?;
+            let Some(host) = api.strip_prefix("http://") else {
+                let message = "must start with http:// (https is not supported)";
+                return Err(read.invalid(table, "api", "telegram", message));
+            };
+            // A number, negative for groups, or a "@channel" name
+            let chat = match table.get("chat").and_then(Item::as_integer) {
+                Some(id) => id.to_string(),
+                None => required("chat")?.to_string(),
+            };
+            config.telegram = Some(Bot {
+                api: host.trim_end_matches('/').to_string(),
+                token: required("token")?.to_string(),
+                user: read.u64(table, "telegram", "user")?,
+                chat,
+            });
+        }
+        if let Some(item) = doc.as_table().get("calendar") {
+            let table = read.table(item, "calendar")?;
+            if let Some(source) = read.str(table, "calendar", "source")? {
+                if calendar::Source::parse(source).is_err() {
+                    let message = "must be a file or start with http:// (https is not supported)";
+                    return Err(read.invalid(table, "source", "calendar", message));
+                }
+                config.calendar.source = Some(if source.starts_with("http://") {
+                    source.to_string()
+                } else {
+                    expand_home(source).to_string_lossy().into_owned()
+                });
+            }
+            config.calendar.warning = match read.u64(table, "calendar", "warning")? {
+                Some(v @ 1..=120) => Some(v),
+                Some(_) => {
+                    let message = "must be between 1 and 120 minutes";
+                    return Err(read.invalid(table, "warning", "calendar", message));
+                }
+                None => None,
+            };
+            config.calendar.fit = read.bool(table, "calendar", "fit")?;
+        }
+        if let Some(item) = doc.as_table().get("work_hours") {
+            let table = read.table(item, "work_hours")?;
+            let mut hours = WorkHours::default();
+            for (key, field) in [("start", &mut hours.start), ("end", &mut hours.end)] {
+                if let Some(text) = read.str(table, "work_hours", key)? {
+                    *field = work_hours::parse_time(text).ok_or_else(|| {
+                        read.invalid(table, key, "work_hours", "must be a time like \"09:00\"")
+                    })?;
+                }
+            }
+            if hours.start == hours.end {
+                return Err(read.invalid(table, "end", "work_hours", "must differ from start"));
+            }
+            if let Some(days) = read.strings(table, "work_hours", "days")? {
+                hours.days = work_hours::parse_days(&days)
+                    .map_err(|e| read.invalid(table, "days", "work_hours", &e))?;
+            }
+            if let Some(on) = read.bool(table, "work_hours", "notifications")? {
+                hours.notifications = on;
+            }
+            config.work_hours = Some(hours);
+        }
+        if let Some(item) = doc.as_table().get("lunch") {
+            let table = read.table(item, "lunch")?;
+            let mut lunch = Lunch::default();
+            for (key, field) in [("start", &mut lunch.start), ("end", &mut lunch.end)] {
+                if let Some(text) = read.str(table, "lunch", key)? {
+                    *field = work_hours::parse_time(text).ok_or_else(|| {
+                        read.invalid(table, key, "lunch", "must be a time like \"13:00\"")
+                    })?;
+                }
+            }
+            if lunch.end <= lunch.start {
+                return Err(read.invalid(table, "end", "lunch", "must be after start"));
+            }
+            config.lunch = Some(lunch);
+        }
+        if let Some(item) = doc.as_table().get("phases") {
+            let table = read.table(item, "phases")?;
+            let names: Vec<&str> = table.iter().map(|(name, _)| name).collect();
+            for (name, item) in table.iter() {
+                phases::check_name(name).map_err(|e| read.invalid(table, name, "phases", &e))?;
+                let prefix = format!("phases.{name}");
+                let phase = read.table(item, &prefix)?;
+                let required = |key| read.invalid(phase, key, &prefix, "is required");
+                let duration = read
+                    .phase_length(phase, &prefix, "duration")?
+                    .ok_or_else(|| required("duration"))?;
+                let after = read
+                    .str(phase, &prefix, "after")?
+                    .ok_or_else(|| required("after"))?;
+                let after = phases::check_after(after, &names)
+                    .map_err(|e| read.invalid(phase, "after", &prefix, &e))?;
+                if let Some(earlier) = phases::following(&config.phases, after) {
+                    let problem = format!(
+                        "is taken: [phases.{}] already comes after {}",
+                        earlier.kind.key(),
+                        after.key()
+                    );
+                    return Err(read.invalid(phase, "after", &prefix, &problem));
+                }
+                let color = read
+                    .str(phase, &prefix, "color")?
+                    .map(phases::parse_color)
+                    .transpose()
+                    .map_err(|e| read.invalid(phase, "color", &prefix, &e))?;
+                config.phases.push(CustomPhase {
+                    kind: phases::kind(name),
+                    duration,
+                    color,
+                    message: read.str(phase, &prefix, "message")?.map(str::to_string),
+                    after,
+                });
+            }
+            if let Some(phase) = phases::find_loop(&config.phases) {
+                let name = phase.kind.key();
+                let inner = table
+                    .get(name)
+                    .and_then(Item::as_table_like)
+                    .unwrap_or(table);
+                let problem = "goes round in a loop that never gets back to focus or a break";
+                return Err(read.invalid(inner, "after", &format!("phases.{name}"), problem));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Fills in config values, including the active project's durations, for every flag
+    /// not given on the command line.
+    pub fn apply(&self, args: &mut Args, matches: &ArgMatches) {
+        let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        self.apply_settings(args, &from_cli);
+        args.work_hours = self.work_hours.clone();
+        args.lunch = self.lunch;
+        args.phases = self.phases.clone();
+        args.distraction_apps = self.distraction_apps.clone();
+        args.score_weights = self.score;
+        args.notification_routes = self.notification_routes.clone();
+        args.notification_styles = self.notification_styles.clone();
+        args.push = self.push.clone();
+        args.telegram = self.telegram.clone();
+        if !from_cli("profile") {
+            let today = wallclock::weekday(wallclock::local_day(wallclock::now_unix()));
+            args.profile = self.scheduled_profile(today).map(str::to_string);
+        }
+        let profile = args.profile.as_ref().and_then(|p| self.profiles.get(p));
+        let project = args.project.as_ref().and_then(|p| self.projects.get(p));
+        let layers = [Some(&self.timer.durations), profile, project];
+        for durations in layers.into_iter().flatten() {
+            durations.apply(args, &from_cli);
+        }
+        if let Some(env) = &self.env {
+            env.apply_settings(args, &from_cli);
+            env.timer.durations.apply(args, &from_cli);
+        }
+    }
+
+    /// The settings that have a flag, for those not on the command line.
+    fn apply_settings(&self, args: &mut Args, from_cli: &impl Fn(&str) -> bool) {
+        if let (Some(length), false) = (self.timer.warm_up, from_cli("warm_up")) {
+            args.warm_up = Some(length);
+        }
+        if let (Some(length), false) = (self.timer.cycle_review, from_cli("cycle_review")) {
+            args.cycle_review = Some(length);
+        }
+        if let (Some(percent), false) = (self.timer.partial_credit, from_cli("partial_credit")) {
+            args.partial_credit = Some(percent);
+        }
+        if let (Some(goal), false) = (self.timer.daily_goal, from_cli("daily_goal")) {
+            args.daily_goal = Some(goal);
+        }
+        if let (Some(length), false) = (self.timer.offer_extension, from_cli("offer_extension")) {
+            args.offer_extension = Some(length);
+        }
+        if let (Some(theme), false) = (self.timer.theme, from_cli("theme")) {
+            args.theme = theme;
+        }
+        if let (Some(mascot), false) = (self.timer.mascot, from_cli("mascot")) {
+            args.mascot = mascot;
+        }
+        if let (Some(layout), false) = (self.timer.layout, from_cli("layout")) {
+            args.layout = layout;
+        }
+        if let (Some(icon), false) = (&self.timer.counter_icon, from_cli("counter_icon")) {
+            args.counter_icon = icon.clone();
+        }
+        if let (Some(on), false) = (self.timer.notifications, from_cli("notifications")) {
+            args.notifications = on;
+        }
+        if let (Some(sound), false) = (
+            &self.timer.notification_sound,
+            from_cli("notification_sound"),
+        ) {
+            args.notification_sound = Some(sound.clone());
+        }
+        if let (Some(backend), false) = (self.history.backend, from_cli("history_backend")) {
+            args.history_backend = backend;
+        }
+        if let (Some(dir), false) = (&self.history.dir, from_cli("history_dir")) {
+            args.history_dir = Some(dir.clone());
+        }
+        if let (Some(tz), false) = (&self.history.tz, from_cli("tz")) {
+            args.tz = Some(tz.clone());
+        }
+        if let (Some(track), false) = (&self.ambient.focus, from_cli("ambient")) {
+            args.ambient = Some(track.clone());
+        }
+        if let (Some(track), false) = (&self.ambient.rest, from_cli("ambient_break")) {
+            args.ambient_break = Some(track.clone());
+        }
+        if let (Some(volume), false) = (self.ambient.volume, from_cli("ambient_volume")) {
+            args.ambient_volume = volume;
+        }
+        if let (Some(on), false) = (self.breaks.breathing, from_cli("breathing")) {
+            args.breathing = on;
+        }
+        if let (Some(on), false) = (self.breaks.track_rest, from_cli("track_rest")) {
+            args.track_rest = on;
+        }
+        if let Some(quotes) = &self.breaks.quotes {
+            args.quotes = quotes.clone();
+        }
+        if let (Some(path), false) = (&self.breaks.quotes_file, from_cli("quotes_file")) {
+            args.quotes_file = Some(path.clone());
+        }
+        if let Some(suggestions) = &self.breaks.suggestions {
+            args.suggestions = suggestions.clone();
+        }
+        if let (Some(source), false) = (&self.calendar.source, from_cli("calendar")) {
+            args.calendar = Some(source.clone());
+        }
+        if let (Some(minutes), false) = (self.calendar.warning, from_cli("meeting_warning")) {
+            args.meeting_warning = minutes;
+        }
+        if let (Some(on), false) = (self.calendar.fit, from_cli("fit_to_meetings")) {
+            args.fit_to_meetings = on;
+        }
+    }
+
+    /// The profile `[schedule]` picks for a weekday, Monday being 0.
+    pub fn scheduled_profile(&self, weekday: usize) -> Option<&str> {
+        self.schedule
+            .iter()
+            .find(|(days, _)| days[weekday])
+            .map(|(_, profile)| profile.as_str())
+    }
+}
+
+impl ProjectConfig {
+    fn apply(&self, args: &mut Args, from_cli: &impl Fn(&str) -> bool) {
+        if let (Some(v), false) = (self.focus, from_cli("focus")) {
+            args.focus = v;
+        }
+        if let (Some(v), false) = (self.short, from_cli("short")) {
+            args.short = v;
+        }
+        if let (Some(v), false) = (self.long, from_cli("long")) {
+            args.long = v;
+        }
+        if let (Some(v), false) = (self.long_every, from_cli("long_every")) {
+            args.long_every = v;
+        }
+    }
+}
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Checks the config file for changes once a second and sends it, parsed, after
+/// each one. A removed file reads as an empty config.
+pub fn watch() -> Option<Receiver<Result<Config, String>>> {
+    let path = paths::config_file()?;
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut seen = stamp(&path);
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            let now = stamp(&path);
+            if now == seen {
+                continue;
+            }
+            seen = now;
+            let config = match fs::read_to_string(&path) {
+                Ok(contents) => Config::parse(&contents),
+                Err(_) => Ok(Config::default()),
+            }
+            .and_then(Config::with_env);
+            if tx.send(config).is_err() {
+                break;
+            }
+        }
+    });
+    Some(rx)
+}
+
+/// Modification time and size; the size catches rewrites within the clock's resolution.
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Sets string keys in `[table]` of the config file, leaving everything else as written.
+pub fn update(table: &str, values: &[(&str, &str)]) -> Result<PathBuf, String> {
+    let path = config_path_or_err()?;
+    let contents = fs::read_to_string(&path).unwrap_or_default();
+    let updated = update_contents(&contents, table, values)?;
+    write(&path, &updated)?;
+    Ok(path)
+}
+
+/// The shared settings of the config file as a template; see `template::export`.
+pub fn export_template(name: Option<&str>) -> Result<String, String> {
+    let path = config_path_or_err()?;
+    let contents = fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    template::export(&contents, name)
+}
+
+/// Merges the template in `file` into the config file, which is only written
+/// when something changes. Returns its path and the keys that changed.
+pub fn import_template(file: &Path) -> Result<(PathBuf, Vec<String>), String> {
+    let template = fs::read_to_string(file).map_err(|e| format!("{}: {e}", file.display()))?;
+    let path = config_path_or_err()?;
+    let contents = fs::read_to_string(&path).unwrap_or_default();
+    let (merged, changed) = template::import(&contents, &template)?;
+    if !changed.is_empty() {
+        write(&path, &merged)?;
+    }
+    Ok((path, changed))
+}
+
+/// Writes the commented template, refusing to replace an existing file unless `force`.
+pub fn init(force: bool) -> Result<PathBuf, String> {
+    let path = config_path_or_err()?;
+    if path.exists() && !force {
+        return Err(format!(
+            "{} already exists; pass --force to overwrite it",
+            path.display()
+        ));
+    }
+    write(&path, TEMPLATE)?;
+    Ok(path)
+}
+
+/// Opens a copy of the config file (or the template) in the user's editor and
+/// saves it back only once it parses. After a bad edit, `again` is asked whether to
+/// reopen the copy; declining discards it. Returns the path when saved.
+pub fn edit(again: impl Fn(&str) -> bool) -> Result<PathBuf, String> {
+    let path = config_path_or_err()?;
+    let original = fs::read_to_string(&path).unwrap_or_else(|_| TEMPLATE.to_string());
+    let draft = path.with_extension("toml.edit");
+    write(&draft, &original)?;
+    let result = loop {
+        if let Err(e) = open_in_editor(&draft) {
+            break Err(e);
+        }
+        let contents = match fs::read_to_string(&draft) {
+            Ok(contents) => contents,
+            Err(e) => break Err(format!("{}: {e}", draft.display())),
+        };
+        match Config::parse(&contents) {
+            Ok(_) => break write(&path, &contents).map(|()| path.clone()),
+            Err(e) if again(&e) => continue,
+            Err(_) => break Err("changes discarded".to_string()),
+        }
+    };
+    let _ = fs::remove_file(&draft);
+    result
+}
+
+fn open_in_editor(path: &Path) -> Result<(), String> {
+    let command = editor_command(env::var("VISUAL").ok(), env::var("EDITOR").ok());
+    let (program, args) = command.split_first().ok_or("no editor configured")?;
+    let status = process::Command::new(program)
+        .args(args)
+        .arg(path)
+        .status()
+        .map_err(|e| format!("cannot start `{program}`: {e}"))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("`{program}` exited with {status}"))
+    }
+}
+
+/// `$VISUAL`, then `$EDITOR`, split on whitespace so `code --wait` works.
+fn editor_command(visual: Option<String>, editor: Option<String>) -> Vec<String> {
+    let fallback = if cfg!(windows) { "notepad" } else { "vi" };
+    [visual, editor]
+        .into_iter()
+        .flatten()
+        .find(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| fallback.to_string())
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    }
+    fs::write(path, contents).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// How `Config::load` went, for `doctor`.
+pub fn probe(loaded: &Result<Config, String>) -> Probe {
+    match (loaded, paths::config_file()) {
+        (Err(e), _) => Probe::Fail(format!("{e}; fix it with `rusty_pomo config edit`")),
+        (Ok(_), Some(path)) if path.exists() => Probe::Ok(path.display().to_string()),
+        (Ok(_), _) => Probe::Ok("no config file, using defaults".to_string()),
+    }
+}
+
+fn config_path_or_err() -> Result<PathBuf, String> {
+    paths::config_file().ok_or_else(|| "cannot locate the config directory".to_string())
+}
+
+fn update_contents(contents: &str, table: &str, values: &[(&str, &str)]) -> Result<String, String> {
+    let quoted: Vec<_> = values.iter().map(|(key, v)| (*key, quote(v))).collect();
+    let updated = set_values(contents, table, &quoted);
+    Config::parse(&updated)?;
+    Ok(updated)
+}
+
+/// A TOML basic string.
+pub fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// `contents` with each key in `[table]` set to a value as written, such as
+/// `50` or `"dracula"`. Keys not there yet go at the end of the table, and a
+/// missing table at the end of the file.
+pub fn set_values(contents: &str, table: &str, values: &[(&str, String)]) -> String {
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let header = format!("[{table}]");
+    let start = match lines.iter().position(|l| l.trim() == header) {
+        Some(i) => i + 1,
+        None => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(header);
+            lines.len()
+        }
+    };
+    for (key, value) in values {
+        let end = lines[start..]
+            .iter()
+            .position(|l| l.trim_start().starts_with('['))
+            .map_or(lines.len(), |i| start + i);
+        let line = format!("{key} = {value}");
+        let existing = lines[start..end]
+            .iter()
+            .position(|l| l.split_once('=').is_some_and(|(k, _)| k.trim() == *key));
+        match existing {
+            Some(i) => lines[start + i] = line,
+            None => {
+                let mut at = end;
+                while at > start && lines[at - 1].trim().is_empty() {
+                    at -= 1;
+                }
+                lines.insert(at, line);
+            }
+        }
+    }
+    let mut updated = lines.join("\n");
+    updated.push('\n');
+    updated
+}
+
+/// Typed lookups into a parsed document that report bad values with their line.
+struct Reader<'a> {
+    source: &'a str,
+}
+
+impl Reader<'_> {
+    fn table<'t>(&self, item: &'t Item, name: &str) -> Result<&'t dyn TableLike, String> {
+        item.as_table_like()
+            .ok_or_else(|| self.at(item, format!("`{name}` must be a table")))
+    }
+
+    /// Phase lengths and `long_every`, as in `[timer]`, `[profiles.NAME]` and
+    /// `[projects.NAME]`.
+    fn durations(&self, table: &dyn TableLike, prefix: &str) -> Result<ProjectConfig, String> {
+        Ok(ProjectConfig {
+            focus: self.phase_length(table, prefix, "focus")?,
+            short: self.phase_length(table, prefix, "short")?,
+            long: self.phase_length(table, prefix, "long")?,
+            long_every: self
+                .u64(table, prefix, "long_every")?
+                .map(validate::long_every)
+                .transpose()
+                .map_err(|e| self.invalid(table, "long_every", prefix, &e))?,
+        })
+    }
+
+    fn bool(&self, table: &dyn TableLike, prefix: &str, key: &str) -> Result<Option<bool>, String> {
+        let Some(item) = table.get(key) else {
+            return Ok(None);
+        };
+        item.as_bool()
+            .map(Some)
+            .ok_or_else(|| self.invalid(table, key, prefix, "must be true or false"))
+    }
+
+    fn u64(&self, table: &dyn TableLike, prefix: &str, key: &str) -> Result<Option<u64>, String> {
+        let Some(item) = table.get(key) else {
+            return Ok(None);
+        };
+        item.as_integer()
+            .and_then(|v| u64::try_from(v).ok())
+            .map(Some)
+            .ok_or_else(|| self.invalid(table, key, prefix, "must be a non-negative integer"))
+    }
+
+    /// Integer minutes, or a string such as `"90s"` or `"1h30m"`.
+    fn phase_length(
+        &self,
+        table: &dyn TableLike,
+        prefix: &str,
+        key: &str,
+    ) -> Result<Option<Duration>, String> {
+        let Some(item) = table.get(key) else {
+            return Ok(None);
+        };
+        let length = match (item.as_str(), item.as_integer()) {
+            (Some(text), _) => parse_duration(text),
+            (_, Some(minutes)) => u64::try_from(minutes)
+                .ok()
+                .and_then(|m| m.checked_mul(60))
+                .map(Duration::from_secs)
+                .ok_or_else(|| "must not be negative".to_string()),
+            _ => Err("must be minutes or a duration like \"90s\"".to_string()),
+        };
+        length
+            .and_then(validate::phase_length)
+            .map(Some)
+            .map_err(|e| self.invalid(table, key, prefix, &e))
+    }
+
+    fn str<'t>(
+        &self,
+        table: &'t dyn TableLike,
+        prefix: &str,
+        key: &str,
+    ) -> Result<Option<&'t str>, String> {
+        let Some(item) = table.get(key) else {
+            return Ok(None);
+        };
+        item.as_str()
+            .map(Some)
+            .ok_or_else(|| self.invalid(table, key, prefix, "must be a string"))
+    }
+
+    fn strings(
+        &self,
+        table: &dyn TableLike,
+        prefix: &str,
+        key: &str,
+    ) -> Result<Option<Vec<String>>, String> {
+        let Some(item) = table.get(key) else {
+            return Ok(None);
+        };
+        let strings = item.as_array().and_then(|array| {
+            array
+                .iter()
+                .map(|value| value.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+        });
+        strings
+            .map(Some)
+            .ok_or_else(|| self.invalid(table, key, prefix, "must be a list of strings"))
+    }
+
+    /// "line 4: `projects.a.focus` must be at least 1 second".
+    fn invalid(&self, table: &dyn TableLike, key: &str, prefix: &str, problem: &str) -> String {
+        let message = format!("`{prefix}.{key}` {problem}");
+        match table.get(key) {
+            Some(item) => self.at(item, message),
+            None => message,
+        }
+    }
+
+    fn at(&self, item: &Item, message: String) -> String {
+        match item.span() {
+            Some(span) => {
+                let line = self.source[..span.start].matches('\n').count() + 1;
+                format!("line {line}: {message}")
+            }
+            None => message,
+        }
+    }
+}
+
+/// Expands `~/` in file tracks; `brown`/`white` pass through unchanged.
+fn expand_track(track: &str) -> String {
+    expand_home(track).to_string_lossy().into_owned()
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::Cli;
+    use crate::state::PhaseKind;
+    use clap::{CommandFactory, FromArgMatches};
+
+    const SAMPLE: &str = r#"
+[projects.thesis]
+focus = 50
+short = 10
+
+[projects.website]
+short = "2m30s"
+long_every = 2
+
+[history]
+backend = "memory"
+dir = "/srv/pomo"
+
+[hue]
+bridge = "192.168.1.20"
+username = "abc"
+focus_scene = "Xr1kZ3vQw9pLmNo"
+
+[ambient]
+focus = "brown"
+volume = 35
+"#;
+
+    fn minutes(n: u64) -> Duration {
+        Duration::from_secs(n * 60)
+    }
+
+    fn resolve(argv: &[&str]) -> Args {
+        let matches = Cli::command().get_matches_from(argv);
+        let mut args = Cli::from_arg_matches(&matches).unwrap().args;
+        Config::parse(SAMPLE).unwrap().apply(&mut args, &matches);
+        args
+    }
+
+    #[test]
+    fn parses_projects() {
+        let config = Config::parse(SAMPLE).unwrap();
+        assert_eq!(config.projects.len(), 2);
+        assert_eq!(config.projects["thesis"].focus, Some(minutes(50)));
+        assert_eq!(config.projects["website"].long_every, Some(2));
+        assert_eq!(
+            config.projects["website"].short,
+            Some(Duration::from_secs(150))
+        );
+        assert_eq!(config.projects["website"].focus, None);
+    }
+
+    #[test]
+    fn schedules_profiles_by_weekday() {
+        let config = Config::parse(
+            r#"
+[profiles.deep]
+focus = 50
+
+[profiles.light]
+focus = 15
+short = 10
+
+[schedule]
+mon-wed = "deep"
+"fri,sat" = "light"
+wed-thu = "light"
+
+[projects.thesis]
+short = 7
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.scheduled_profile(0), Some("deep"));
+        // Wednesday is in two rules; the first one wins
+        assert_eq!(config.scheduled_profile(2), Some("deep"));
+        assert_eq!(config.scheduled_profile(3), Some("light"));
+        assert_eq!(config.scheduled_profile(5), Some("light"));
+        assert_eq!(config.scheduled_profile(6), None);
+        // The flag beats the schedule, and a project's durations still go on top
+        let argv = ["rusty_pomo", "--profile", "light", "--project", "thesis"];
+        let matches = Cli::command().get_matches_from(argv);
+        let mut args = Cli::from_arg_matches(&matches).unwrap().args;
+        config.apply(&mut args, &matches);
+        assert_eq!(args.focus, minutes(15));
+        assert_eq!(args.short, minutes(7));
+
+        let err = Config::parse("[schedule]\nmon = \"deep\"\n").unwrap_err();
+        assert_eq!(err, "line 2: `schedule.mon` names no [profiles.deep] table");
+        let err = Config::parse("[profiles.a]\n[schedule]\nmonday-funday = \"a\"\n").unwrap_err();
+        assert_eq!(
+            err,
+            "line 3: `schedule.monday-funday` has an unknown day \"funday\""
+        );
+    }
+
+    #[test]
+    fn parses_history_table() {
+        let config = Config::parse(SAMPLE).unwrap();
+        assert_eq!(config.history.backend, Some(StoreKind::Memory));
+        assert_eq!(config.history.dir, Some(PathBuf::from("/srv/pomo")));
+        let err = Config::parse("[history]\nbackend = \"sqlite\"\n").unwrap_err();
+        assert!(err.contains("history.backend"), "{err}");
+        let config = Config::parse("[history]\ntz = \"+09:00\"\n").unwrap();
+        assert_eq!(config.history.tz, Some(Zone::Fixed(9 * 3600)));
+        let err = Config::parse("[history]\ntz = \"Atlantis\"\n").unwrap_err();
+        assert!(err.contains("history.tz"), "{err}");
+    }
+
+    #[test]
+    fn parses_hue_table() {
+        let config = Config::parse(SAMPLE).unwrap();
+        assert_eq!(config.hue.bridge.as_deref(), Some("192.168.1.20"));
+        assert_eq!(config.hue.focus_scene.as_deref(), Some("Xr1kZ3vQw9pLmNo"));
+        assert_eq!(config.hue.break_scene, None);
+    }
+
+    #[test]
+    fn parses_remote_table() {
+        let config =
+            Config::parse("[remote]\nurl = \"http://pomo.lan/api\"\ntoken = \"t\"\n").unwrap();
+        assert_eq!(config.remote.url.as_deref(), Some("http://pomo.lan/api"));
+        assert_eq!(config.remote.token.as_deref(), Some("t"));
+        let err = Config::parse("[remote]\nurl = \"https://pomo.lan\"\n").unwrap_err();
+        assert!(
+            err.starts_with("line 2: `remote.url` must start with http://"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn parses_calendar_table() {
+        let config = Config::parse(
+            "[calendar]\nsource = \"http://dav.lan/me/work/\"\nwarning = 5\nfit = true\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.calendar,
+            CalendarConfig {
+                source: Some("http://dav.lan/me/work/".to_string()),
+                warning: Some(5),
+                fit: Some(true),
+            }
+        );
+        let err = Config::parse("[calendar]\nsource = \"https://dav.lan/x.ics\"\n").unwrap_err();
+        assert!(
+            err.starts_with("line 2: `calendar.source` must be a file"),
+            "{err}"
+        );
+        assert!(Config::parse("[calendar]\nwarning = 0\n").is_err());
+    }
+
+    #[test]
+    fn parses_work_hours_table() {
+        let config =
+            Config::parse("[work_hours]\nend = \"17:30\"\ndays = [\"mon-thu\"]\n").unwrap();
+        assert_eq!(
+            config.work_hours,
+            Some(WorkHours {
+                end: 17 * 3600 + 30 * 60,
+                days: [true, true, true, true, false, false, false],
+                ..WorkHours::default()
+            })
+        );
+        let err = Config::parse("[work_hours]\nstart = \"9am\"\n").unwrap_err();
+        assert_eq!(
+            err,
+            "line 2: `work_hours.start` must be a time like \"09:00\""
+        );
+        let err = Config::parse("[work_hours]\ndays = [\"mon\", \"fun\"]\n").unwrap_err();
+        assert_eq!(err, "line 2: `work_hours.days` has an unknown day \"fun\"");
+        assert!(Config::parse("[work_hours]\nstart = \"18:00\"\n").is_err());
+    }
+
+    #[test]
+    fn parses_lunch_table() {
+        let config = Config::parse("[lunch]\nstart = \"12:30\"\nend = \"13:15\"\n").unwrap();
+        assert_eq!(
+            config.lunch,
+            Some(Lunch {
+                start: 12 * 3600 + 30 * 60,
+                end: 13 * 3600 + 15 * 60,
+            })
+        );
+        assert_eq!(
+            Config::parse("[lunch]\n").unwrap().lunch,
+            Some(Lunch::default())
+        );
+        let err = Config::parse("[lunch]\nstart = \"14:30\"\nend = \"14:00\"\n").unwrap_err();
+        assert_eq!(err, "line 3: `lunch.end` must be after start");
+    }
+
+    #[test]
+    fn parses_custom_phases() {
+        let config = Config::parse(
+            r##"[phases.Review]
+duration = 10
+after = "focus"
+color = "#ff79c6"
+message = "Look back over what you did"
+
+[phases."Inbox zero"]
+duration = "5m"
+after = "Review"
+"##,
+        )
+        .unwrap();
+        let kinds: Vec<_> = config.phases.iter().map(|p| p.kind.key()).collect();
+        assert_eq!(kinds, ["Review", "Inbox zero"]);
+        let review = &config.phases[0];
+        assert_eq!(review.duration, Duration::from_secs(600));
+        assert_eq!(review.after, PhaseKind::Focus);
+        assert_eq!(
+            review.color,
+            Some(ratatui::style::Color::Rgb(255, 121, 198))
+        );
+        assert_eq!(config.phases[1].after, phases::kind("Review"));
+        assert_eq!(config.phases[1].color, None);
+
+        let err = Config::parse("[phases.lunch]\nduration = 5\nafter = \"focus\"\n").unwrap_err();
+        assert_eq!(
+            err,
+            "line 1: `phases.lunch` is taken by the built-in lunch phase"
+        );
+        let err = Config::parse("[phases.Review]\nafter = \"focus\"\n").unwrap_err();
+        assert_eq!(err, "`phases.Review.duration` is required");
+        let err = Config::parse(
+            "[phases.A]\nduration = 5\nafter = \"B\"\n[phases.B]\nduration = 5\nafter = \"A\"\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            "line 3: `phases.A.after` goes round in a loop that never gets back to focus or a break"
+        );
+        let err = Config::parse("[phases.A]\nduration = 5\nafter = \"focus\"\n[phases.B]\nduration = 5\nafter = \"focus\"\n")
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "line 6: `phases.B.after` is taken: [phases.A] already comes after focus"
+        );
+    }
+
+    #[test]
+    fn parses_notification_routes() {
+        let config = Config::parse(
+            "[notifications]\nphase_end = [\"desktop\", \"sound\"]\nwarn_before = [\"sound\"]\nachievement = []\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.notification_routes,
+            [
+                (Route::PhaseEnd, vec![Channel::Desktop, Channel::Sound]),
+                (Route::WarnBefore, vec![Channel::Sound]),
+                (Route::Achievement, Vec::new()),
+            ]
+        );
+        let err = Config::parse("[notifications]\ndone = [\"pager\"]\n").unwrap_err();
+        assert_eq!(
+            err,
+            "line 2: `notifications.done` has an unknown channel \"pager\""
+        );
+        let err = Config::parse("[notifications]\nlunch = []\n").unwrap_err();
+        assert!(
+            err.starts_with("line 2: `notifications.lunch` is not an event"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn parses_notification_styles() {
+        let config = Config::parse(
+            "[notifications]\nbreak_end = [\"desktop\"]\n\n[notifications.style]\nbreak_end = { urgency = \"critical\", resident = true }\nphase_end = { transient = true }\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.notification_routes,
+            [(Route::BreakEnd, vec![Channel::Desktop])]
+        );
+        assert_eq!(
+            config.notification_styles,
+            [
+                (
+                    Route::BreakEnd,
+                    Style {
+                        urgency: Some(Urgency::Critical),
+                        resident: Some(true),
+                        transient: None,
+                    }
+                ),
+                (
+                    Route::PhaseEnd,
+                    Style {
+                        transient: Some(true),
+                        ..Style::default()
+                    }
+                ),
+            ]
+        );
+        let err =
+            Config::parse("[notifications.style]\ndone = { urgency = \"urgent\" }\n").unwrap_err();
+        assert!(
+            err.starts_with("line 2: `notifications.style.done.urgency` must be"),
+            "{err}"
+        );
+        let err = Config::parse("[notifications.style]\nlunch = {}\n").unwrap_err();
+        assert!(
+            err.starts_with("line 2: `notifications.style.lunch` is not an event"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn parses_push_table() {
+        let config = Config::parse(
+            "[push]\nntfy = \"http://ntfy.sh/pomo\"\nemail = \"me@example.com\"\nsmtp = \"mail:2525\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.push.ntfy.as_deref(), Some("http://ntfy.sh/pomo"));
+        assert_eq!(config.push.email.as_deref(), Some("me@example.com"));
+        assert_eq!(config.push.smtp.as_deref(), Some("mail:2525"));
+        let err = Config::parse("[push]\nntfy = \"https://ntfy.sh/pomo\"\n").unwrap_err();
+        assert_eq!(
+            err,
+            "line 2: `push.ntfy` must be an http:// URL with a topic (https is not supported)"
+        );
+    }
+
+    #[test]
+    fn parses_telegram_table() {
+        let config = Config::parse(
+            "[telegram]\napi = \"http://localhost:8081/\"\ntoken = \"123:abc\"\nchat = -1001\nuser = 42\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.telegram,
+            Some(Bot {
+                api: "localhost:8081".to_string(),
+                token: "123:abc".to_string(),
+                chat: "-1001".to_string(),
+                user: Some(42),
+            })
+        );
+        let err =
+            Config::parse("[telegram]\napi = \"http://localhost:8081\"\nchat = 1\n").unwrap_err();
+        assert_eq!(err, "line 1: `telegram` needs `token`");
+    }
+
+    #[test]
+    fn parses_score_table() {
+        let config = Config::parse("[score]\nrest = 0\ntarget = 4\n").unwrap();
+        assert_eq!(
+            config.score,
+            Weights {
+                rest: 0,
+                target: 4,
+                ..Weights::default()
+            }
+        );
+        let err = Config::parse("[score]\ntarget = 0\n").unwrap_err();
+        assert_eq!(err, "line 2: `score.target` must be at least 1");
+    }
+
+    #[test]
+    fn parses_breaks_table() {
+        let config = Config::parse(
+            "[breaks]\nbreathing = false\nquotes = [\"Stretch.\", \"Water.\"]\nsuggestions = []\n",
+        )
+        .unwrap();
+        assert_eq!(config.breaks.breathing, Some(false));
+        assert_eq!(config.breaks.suggestions, Some(Vec::new()));
+        assert_eq!(
+            config.breaks.quotes,
+            Some(vec!["Stretch.".to_string(), "Water.".to_string()])
+        );
+        let err = Config::parse("[breaks]\nquotes = \"Stretch.\"\n").unwrap_err();
+        assert_eq!(err, "line 2: `breaks.quotes` must be a list of strings");
+    }
+
+    #[test]
+    fn ambient_settings_yield_to_flags() {
+        let args = resolve(&["rusty_pomo"]);
+        assert_eq!(args.ambient.as_deref(), Some("brown"));
+        assert_eq!(args.ambient_volume, 35);
+        let args = resolve(&["rusty_pomo", "--ambient", "white", "--ambient-volume", "80"]);
+        assert_eq!(args.ambient.as_deref(), Some("white"));
+        assert_eq!(args.ambient_volume, 80);
+        assert!(Config::parse("[ambient]\nvolume = 120\n").is_err());
+    }
+
+    #[test]
+    fn updates_table_in_place() {
+        let updated =
+            update_contents(SAMPLE, "hue", &[("username", "x\"y"), ("group", "2")]).unwrap();
+        let config = Config::parse(&updated).unwrap();
+        assert_eq!(config.hue.username.as_deref(), Some("x\"y"));
+        assert_eq!(config.hue.group.as_deref(), Some("2"));
+        assert_eq!(config.projects["thesis"].focus, Some(minutes(50)));
+        assert!(updated.contains("username = \"x\\\"y\"\n"), "{updated}");
+        assert!(updated.contains("group = \"2\"\n\n[ambient]"), "{updated}");
+        let fresh = update_contents("", "hue", &[("bridge", "10.0.0.2")]).unwrap();
+        assert_eq!(fresh, "[hue]\nbridge = \"10.0.0.2\"\n");
+    }
+
+    #[test]
+    fn template_is_valid_and_sets_nothing() {
+        let config = Config::parse(TEMPLATE).unwrap();
+        assert!(config.projects.is_empty());
+        assert_eq!(config.hue, HueConfig::default());
+        // Uncommenting the examples must give a valid file too.
+        let uncommented: String = TEMPLATE
+            .lines()
+            .map(|line| line.strip_prefix("# ").unwrap_or(line))
+            .filter(|line| line.starts_with('[') || line.contains(" = "))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        let config = Config::parse(&uncommented).unwrap();
+        assert_eq!(config.projects["thesis"].long_every, Some(3));
+        assert_eq!(config.ambient.volume, Some(50));
+    }
+
+    #[test]
+    fn picks_the_editor_from_the_environment() {
+        let editor = |visual: Option<&str>, editor: Option<&str>| {
+            editor_command(visual.map(str::to_string), editor.map(str::to_string))
+        };
+        assert_eq!(
+            editor(Some("code --wait"), Some("nano")),
+            ["code", "--wait"]
+        );
+        assert_eq!(editor(Some(" "), Some("nano")), ["nano"]);
+        assert_eq!(editor(None, None).len(), 1);
+    }
+
+    #[test]
+    fn history_settings_yield_to_flags() {
+        let args = resolve(&["rusty_pomo"]);
+        assert_eq!(args.history_backend, StoreKind::Memory);
+        assert_eq!(args.history_dir, Some(PathBuf::from("/srv/pomo")));
+        let args = resolve(&["rusty_pomo", "--history-backend", "jsonl"]);
+        assert_eq!(args.history_backend, StoreKind::Jsonl);
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        let err = Config::parse("[projects.a]\nfocus = \"long\"\n").unwrap_err();
+        assert!(err.contains("projects.a.focus"), "{err}");
+        let err = Config::parse("[projects.a]\nshort = -5\n").unwrap_err();
+        assert!(err.contains("projects.a.short"), "{err}");
+        assert!(Config::parse("projects = 3").is_err());
+    }
+
+    #[test]
+    fn reports_the_line_of_nonsensical_values() {
+        let err = Config::parse("[projects.a]\nshort = 5\nfocus = 0\n").unwrap_err();
+        assert_eq!(err, "line 3: `projects.a.focus` must be at least 1 second");
+        let err = Config::parse("\n[projects.b]\nlong_every = 101\n").unwrap_err();
+        assert_eq!(
+            err,
+            "line 3: `projects.b.long_every` must be between 0 and 100"
+        );
+        let err = Config::parse("[ambient]\nvolume = 300\n").unwrap_err();
+        assert!(err.starts_with("line 2: "), "{err}");
+    }
+
+    #[test]
+    fn project_durations_fill_unset_flags() {
+        let args = resolve(&["rusty_pomo", "--project", "thesis"]);
+        assert_eq!(args.focus, minutes(50));
+        assert_eq!(args.short, minutes(10));
+        assert_eq!(args.long, minutes(15));
+    }
+
+    #[test]
+    fn cli_flags_override_project_durations() {
+        let args = resolve(&["rusty_pomo", "--project", "thesis", "--focus", "30"]);
+        assert_eq!(args.focus, minutes(30));
+        assert_eq!(args.short, minutes(10));
+    }
+
+    #[test]
+    fn unknown_project_keeps_defaults() {
+        let args = resolve(&["rusty_pomo", "--project", "other"]);
+        assert_eq!(args.focus, minutes(25));
+        assert_eq!(args.project.as_deref(), Some("other"));
+    }
+
+    #[test]
+    fn timer_defaults_sit_below_projects_and_flags() {
+        let config = Config::parse(
+            "[timer]\nfocus = 40\nlong = \"20m\"\ntheme = \"gruvbox-dark\"\nmascot = \"tomato\"\nlayout = \"wide\"\nnotifications = false\n\n[projects.thesis]\nfocus = 50\n",
+        )
+        .unwrap();
+        let resolve = |argv: &[&str]| {
+            let matches = Cli::command().get_matches_from(argv);
+            let mut args = Cli::from_arg_matches(&matches).unwrap().args;
+            config.apply(&mut args, &matches);
+            args
+        };
+        let args = resolve(&["rusty_pomo"]);
+        assert_eq!((args.focus, args.long), (minutes(40), minutes(20)));
+        assert_eq!(args.theme, Theme::GruvboxDark);
+        assert_eq!(args.mascot, Mascot::Tomato);
+        assert_eq!(args.layout, LayoutPreset::Wide);
+        assert!(!args.notifications);
+        let args = resolve(&["rusty_pomo", "-p", "thesis", "--theme", "dracula"]);
+        assert_eq!((args.focus, args.long), (minutes(50), minutes(20)));
+        assert_eq!(args.theme, Theme::Dracula);
+
+        let err = Config::parse("[timer]\ntheme = \"neon\"\n").unwrap_err();
+        assert!(
+            err.starts_with("line 2: `timer.theme` must be one of \"dracula\""),
+            "{err}"
+        );
+        assert!(Config::parse("[timer]\nnotifications = \"no\"\n").is_err());
+    }
+}
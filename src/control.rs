@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use crate::state::AppState;
+
+/// Requests to change the timer from outside the keyboard (HTTP API, signals, ...).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Control {
+    Pause,
+    Resume,
+    TogglePause,
+    Skip,
+    Snooze,
+    /// End the focus session as the next meeting starts.
+    FitMeeting,
+    SetTask(Option<String>),
+    SetDurations(Durations),
+    /// A line to show below the timer until the next phase.
+    ShowMessage(String),
+    /// Lengthen the running phase by this many seconds, or shorten it.
+    AdjustTime(i64),
+}
+
+/// Phase lengths, applied from the next phase on; `None` keeps the current value.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Durations {
+    pub focus: Option<Duration>,
+    pub short: Option<Duration>,
+    pub long: Option<Duration>,
+    pub long_every: Option<u64>,
+}
+
+pub fn apply(app: &mut AppState, control: Control) {
+    tracing::debug!(?control, "control");
+    match control {
+        Control::Pause if !app.paused => app.toggle_pause(),
+        Control::Resume if app.paused => app.toggle_pause(),
+        Control::Pause | Control::Resume => {}
+        Control::TogglePause => app.toggle_pause(),
+        Control::Skip => app.skip(),
+        Control::Snooze => app.snooze(),
+        Control::FitMeeting => {
+            app.fit_to_meeting();
+        }
+        Control::SetTask(task) => app.args.task = task,
+        Control::SetDurations(durations) => {
+            let args = &mut app.args;
+            args.focus = durations.focus.unwrap_or(args.focus);
+            args.short = durations.short.unwrap_or(args.short);
+            args.long = durations.long.unwrap_or(args.long);
+            args.long_every = durations.long_every.unwrap_or(args.long_every);
+        }
+        Control::ShowMessage(text) => app.message = Some(text),
+        Control::AdjustTime(secs) => app.adjust_time(secs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::{make_args, mock_app, test_app};
+
+    #[test]
+    fn pause_and_resume_are_idempotent() {
+        let mut app = test_app();
+        apply(&mut app, Control::Pause);
+        apply(&mut app, Control::Pause);
+        assert!(app.paused);
+        apply(&mut app, Control::Resume);
+        apply(&mut app, Control::Resume);
+        assert!(!app.paused);
+    }
+
+    #[test]
+    fn updates_task_and_durations() {
+        let mut app = test_app();
+        apply(&mut app, Control::SetTask(Some("Docs".to_string())));
+        assert_eq!(app.args.task.as_deref(), Some("Docs"));
+        apply(
+            &mut app,
+            Control::SetDurations(Durations {
+                short: Some(Duration::from_secs(7 * 60)),
+                ..Durations::default()
+            }),
+        );
+        assert_eq!(app.args.short, Duration::from_secs(7 * 60));
+        assert_eq!(app.args.focus, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn adjusts_time_and_shows_messages() {
+        let (mut app, clock) = mock_app(make_args());
+        apply(&mut app, Control::AdjustTime(90));
+        assert_eq!(app.current_phase.duration, Duration::from_secs(150));
+        clock.advance(Duration::from_secs(40));
+        // Never below what has already run, so the phase ends now at the earliest
+        apply(&mut app, Control::AdjustTime(-600));
+        assert_eq!(app.current_phase.duration, Duration::from_secs(40));
+        apply(&mut app, Control::ShowMessage("Drink water".to_string()));
+        assert_eq!(app.message.as_deref(), Some("Drink water"));
+        app.skip();
+        assert_eq!(app.message, None);
+    }
+}
@@ -0,0 +1,43 @@
+//! `--detach`: moving a `--background` timer off the terminal.
+
+use std::io;
+
+/// Forks; the parent prints the child's PID and exits, the child carries on in
+/// a new session with its standard streams on /dev/null. Call it before any
+/// thread is spawned.
+#[cfg(unix)]
+pub fn detach() -> io::Result<()> {
+    use std::fs::OpenOptions;
+    use std::os::fd::AsRawFd;
+
+    // SAFETY: no other threads exist yet, so the child starts from a consistent state.
+    match unsafe { libc::fork() } {
+        -1 => return Err(io::Error::last_os_error()),
+        0 => {}
+        pid => {
+            println!("{pid}");
+            std::process::exit(0);
+        }
+    }
+    if unsafe { libc::setsid() } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let null = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/null")?;
+    for fd in [libc::STDIN_FILENO, libc::STDOUT_FILENO, libc::STDERR_FILENO] {
+        if unsafe { libc::dup2(null.as_raw_fd(), fd) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn detach() -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--detach is only available on Unix; start the timer with your system's service manager instead",
+    ))
+}
@@ -0,0 +1,630 @@
+//! `rusty_pomo stats --tui`: the history as a full-screen dashboard, apart
+//! from the timer. A tab each for today, the week, the month, the hours of the
+//! day, projects and tasks, with focus time as bars over a table of totals,
+//! and a calendar to look up any day's sessions.
+
+use std::io;
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Bar, BarChart, BarGroup, Block, Borders, Paragraph, Row, Table, TableState, Tabs,
+};
+use ratatui::{Frame, Terminal};
+
+use crate::history::SessionRecord;
+use crate::i18n;
+use crate::run::TerminalGuard;
+use crate::stats::{self, Totals};
+use crate::theme::Theme;
+use crate::ui::{HelpBar, Palette};
+use crate::wallclock;
+
+/// Days covered by the month, hours, projects and tasks tabs, today included.
+const MONTH: u64 = 30;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Tab {
+    Today,
+    Week,
+    Month,
+    Hours,
+    Projects,
+    Tasks,
+    Calendar,
+}
+
+impl Tab {
+    const ALL: [Tab; 7] = [
+        Tab::Today,
+        Tab::Week,
+        Tab::Month,
+        Tab::Hours,
+        Tab::Projects,
+        Tab::Tasks,
+        Tab::Calendar,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Tab::Today => "Today",
+            Tab::Week => "Week",
+            Tab::Month => "Month",
+            Tab::Hours => "Hours",
+            Tab::Projects => "Projects",
+            Tab::Tasks => "Tasks",
+            Tab::Calendar => "Calendar",
+        }
+    }
+
+    /// What the bars stand for, as the chart's title.
+    fn bars_title(self) -> &'static str {
+        match self {
+            Tab::Today => "Focus minutes by hour",
+            Tab::Week | Tab::Month => "Focus minutes by day",
+            Tab::Hours => "Focus minutes by hour of day, last 30 days",
+            Tab::Projects => "Focus minutes by project, last 30 days",
+            Tab::Tasks => "Focus minutes by task, last 30 days",
+            Tab::Calendar => "",
+        }
+    }
+}
+
+/// What a tab shows: focus minutes as labelled bars, and a row of totals for
+/// each project, task or hour, most focus first.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct View {
+    bars: Vec<(String, u64)>,
+    rows: Vec<(String, Totals)>,
+}
+
+fn view(tab: Tab, records: &[SessionRecord], now: u64) -> View {
+    let today = wallclock::local_day(now);
+    let since = |days: u64| today - days as i64 + 1;
+    let project = |record: &SessionRecord| record.project.clone();
+    let task = |record: &SessionRecord| record.task.clone();
+    match tab {
+        Tab::Today => View {
+            bars: hour_bars(&by_hour(records, today, today)),
+            rows: stats::totals_by(records, today, today, project),
+        },
+        Tab::Hours => {
+            let hours = by_hour(records, since(MONTH), today);
+            let mut rows: Vec<(String, Totals)> = hours
+                .iter()
+                .enumerate()
+                .filter(|(_, totals)| totals.focus_secs > 0)
+                .map(|(hour, totals)| (format!("{hour:02}:00–{:02}:00", hour + 1), *totals))
+                .collect();
+            rows.sort_by_key(|(_, totals)| std::cmp::Reverse(totals.focus_secs));
+            View {
+                bars: hour_bars(&hours),
+                rows,
+            }
+        }
+        Tab::Week | Tab::Month => {
+            let first = since(if tab == Tab::Week { 7 } else { MONTH });
+            let bars = (first..=today)
+                .map(|day| {
+                    let mut totals = Totals::default();
+                    stats::in_days(records, day, day).for_each(|record| totals.add(record));
+                    let label = if tab == Tab::Week {
+                        stats::WEEKDAYS[wallclock::weekday(day)].to_string()
+                    } else {
+                        format!("{:02}", wallclock::civil_from_days(day).2)
+                    };
+                    (label, totals.focus_secs / 60)
+                })
+                .collect();
+            View {
+                bars,
+                rows: stats::totals_by(records, first, today, project),
+            }
+        }
+        Tab::Projects | Tab::Tasks => {
+            let key = if tab == Tab::Projects { project } else { task };
+            let rows = stats::totals_by(records, since(MONTH), today, key);
+            View {
+                bars: rows
+                    .iter()
+                    .map(|(label, totals)| (label.clone(), totals.focus_secs / 60))
+                    .collect(),
+                rows,
+            }
+        }
+        // Drawn from the records, for whichever day is picked
+        Tab::Calendar => View::default(),
+    }
+}
+
+/// The month around a day: its first day and the totals of each of its days.
+#[derive(Debug, PartialEq, Eq)]
+struct Month {
+    first: i64,
+    days: Vec<Totals>,
+}
+
+impl Month {
+    fn around(records: &[SessionRecord], day: i64) -> Self {
+        let (year, month, _) = wallclock::civil_from_days(day);
+        let first = wallclock::days_from_civil(year, month, 1);
+        let next = if month == 12 {
+            wallclock::days_from_civil(year + 1, 1, 1)
+        } else {
+            wallclock::days_from_civil(year, month + 1, 1)
+        };
+        let mut days = vec![Totals::default(); (next - first) as usize];
+        for record in stats::in_days(records, first, next - 1) {
+            days[(record.day() - first) as usize].add(record);
+        }
+        Self { first, days }
+    }
+
+    /// The days as weeks from Monday, `None` before the first and after the
+    /// last.
+    fn weeks(&self) -> Vec<[Option<i64>; 7]> {
+        let lead = wallclock::weekday(self.first);
+        let last = self.first + self.days.len() as i64 - 1;
+        let mut weeks = Vec::new();
+        let mut monday = self.first - lead as i64;
+        while monday <= last {
+            let mut week = [None; 7];
+            for (i, slot) in week.iter_mut().enumerate() {
+                let day = monday + i as i64;
+                *slot = (self.first..=last).contains(&day).then_some(day);
+            }
+            weeks.push(week);
+            monday += 7;
+        }
+        weeks
+    }
+
+    fn totals(&self, day: i64) -> Totals {
+        self.days[(day - self.first) as usize]
+    }
+}
+
+/// Totals for each hour of the day a record started in, from day `first` to
+/// `last`.
+fn by_hour(records: &[SessionRecord], first: i64, last: i64) -> Vec<Totals> {
+    let mut hours = vec![Totals::default(); 24];
+    for record in stats::in_days(records, first, last) {
+        let hour = record.seconds_of_day() / 3600;
+        hours[hour as usize].add(record);
+    }
+    hours
+}
+
+fn hour_bars(hours: &[Totals]) -> Vec<(String, u64)> {
+    hours
+        .iter()
+        .enumerate()
+        .map(|(hour, totals)| (format!("{hour:02}"), totals.focus_secs / 60))
+        .collect()
+}
+
+/// Shows the dashboard until `q` or Esc.
+pub fn run(records: &[SessionRecord], theme: Theme, goal: u64) -> io::Result<()> {
+    let now = wallclock::now_unix();
+    let views: Vec<View> = Tab::ALL
+        .iter()
+        .map(|tab| view(*tab, records, now))
+        .collect();
+    let today = wallclock::local_day(now);
+    let mut picked = today;
+    let _guard = TerminalGuard::enter(true)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    terminal.hide_cursor()?;
+    let mut at = 0;
+    let mut table = TableState::default();
+    let palette = Palette::from(theme);
+    loop {
+        terminal.draw(|frame| {
+            let body = draw(frame, at, palette);
+            if Tab::ALL[at] == Tab::Calendar {
+                draw_calendar(frame, body, records, picked, goal, palette);
+            } else {
+                draw_view(frame, body, Tab::ALL[at], &views[at], &mut table, palette);
+            }
+        })?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let rows = views[at].rows.len();
+        let calendar = Tab::ALL[at] == Tab::Calendar;
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Left | KeyCode::Char('h') if calendar => picked -= 1,
+            KeyCode::Right | KeyCode::Char('l') if calendar => picked = (picked + 1).min(today),
+            KeyCode::Up | KeyCode::Char('k') if calendar => picked -= 7,
+            KeyCode::Down | KeyCode::Char('j') if calendar => picked = (picked + 7).min(today),
+            KeyCode::Right | KeyCode::Tab | KeyCode::Char('l') => at = (at + 1) % Tab::ALL.len(),
+            KeyCode::Left | KeyCode::BackTab | KeyCode::Char('h') => {
+                at = (at + Tab::ALL.len() - 1) % Tab::ALL.len()
+            }
+            KeyCode::Char(c @ '1'..='7') => at = c as usize - '1' as usize,
+            KeyCode::Down | KeyCode::Char('j') if rows > 0 => table.select(Some(
+                table.selected().map_or(0, |row| (row + 1).min(rows - 1)),
+            )),
+            KeyCode::Up | KeyCode::Char('k') if rows > 0 => table.select(Some(
+                table.selected().map_or(0, |row| row.saturating_sub(1)),
+            )),
+            _ => continue,
+        }
+        if !matches!(
+            key.code,
+            KeyCode::Down | KeyCode::Up | KeyCode::Char('j' | 'k')
+        ) {
+            table.select(None);
+        }
+    }
+}
+
+/// The tabs and the key help around the tab's own area, which is returned.
+fn draw(frame: &mut Frame, at: usize, palette: Palette) -> Rect {
+    let [tabs, body, help] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(8),
+        Constraint::Length(1),
+    ])
+    .areas(frame.size());
+
+    let titles = Tab::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, tab)| format!("{} {}", i + 1, tab.title()));
+    frame.render_widget(
+        Tabs::new(titles)
+            .select(at)
+            .highlight_style(
+                Style::default()
+                    .fg(palette.accent)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Rusty Pomo stats "),
+            ),
+        tabs,
+    );
+    let keys = HelpBar::new(palette).unframed();
+    let keys = if Tab::ALL[at] == Tab::Calendar {
+        keys.key("arrows", "pick a day")
+            .key("Tab 1-7", "switch tabs")
+    } else {
+        keys.key("←/→ 1-7", "switch tabs")
+            .key("↑/↓", "move in the table")
+    };
+    frame.render_widget(keys.key("q", "quit"), help);
+    body
+}
+
+fn draw_view(
+    frame: &mut Frame,
+    area: Rect,
+    tab: Tab,
+    view: &View,
+    table: &mut TableState,
+    palette: Palette,
+) {
+    let Palette {
+        accent, ok: second, ..
+    } = palette;
+    let [chart, rows] =
+        Layout::vertical([Constraint::Percentage(60), Constraint::Min(4)]).areas(area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!(" {} ", tab.bars_title()));
+    if view.bars.iter().all(|(_, minutes)| *minutes == 0) {
+        frame.render_widget(Paragraph::new("No focus time yet.").block(block), chart);
+    } else {
+        // The peak stands out, so the best hours are found at a glance
+        let peak = view.bars.iter().map(|(_, minutes)| *minutes).max();
+        let bars: Vec<Bar> = view
+            .bars
+            .iter()
+            .map(|(label, minutes)| {
+                let bar = Bar::default()
+                    .label(Line::from(label.as_str()))
+                    .value(*minutes);
+                if Some(*minutes) == peak {
+                    bar.style(Style::default().fg(second))
+                } else {
+                    bar
+                }
+            })
+            .collect();
+        frame.render_widget(
+            BarChart::default()
+                .block(block)
+                .data(BarGroup::default().bars(&bars))
+                .bar_width(bar_width(chart, bars.len()))
+                .bar_gap(1)
+                .bar_style(Style::default().fg(accent))
+                .value_style(Style::default().fg(second).add_modifier(Modifier::REVERSED)),
+            chart,
+        );
+    }
+
+    let name = match tab {
+        Tab::Tasks => "Task",
+        Tab::Hours => "Hour",
+        _ => "Project",
+    };
+    let header =
+        Row::new([name, "Sessions", "Focus"]).style(Style::default().add_modifier(Modifier::BOLD));
+    let body = view.rows.iter().map(|(label, totals)| {
+        Row::new([
+            label.clone(),
+            totals.completed.to_string(),
+            stats::hours_minutes(totals.focus_secs),
+        ])
+    });
+    frame.render_stateful_widget(
+        Table::new(
+            body,
+            [
+                Constraint::Min(16),
+                Constraint::Length(10),
+                Constraint::Length(10),
+            ],
+        )
+        .header(header)
+        .highlight_style(Style::default().fg(accent).add_modifier(Modifier::REVERSED))
+        .block(Block::default().borders(Borders::ALL)),
+        rows,
+        table,
+    );
+}
+
+/// The picked day's month as a grid, each day shaded by its focus time and
+/// ticked when it met the goal, over a table of the day's sessions.
+fn draw_calendar(
+    frame: &mut Frame,
+    area: Rect,
+    records: &[SessionRecord],
+    picked: i64,
+    goal: u64,
+    palette: Palette,
+) {
+    let Palette {
+        bg: background,
+        accent,
+        ok: second,
+        ..
+    } = palette;
+    let [grid, sessions] =
+        Layout::vertical([Constraint::Length(9), Constraint::Min(4)]).areas(area);
+    let month = Month::around(records, picked);
+    let (year, number, _) = wallclock::civil_from_days(month.first);
+    let most = month.days.iter().map(|t| t.focus_secs).max().unwrap_or(0);
+
+    let mut lines = vec![
+        Line::from(
+            stats::WEEKDAYS
+                .iter()
+                .map(|name| format!(" {name:<5}"))
+                .collect::<String>(),
+        )
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+    ];
+    for week in month.weeks() {
+        let cells = week.iter().map(|day| {
+            let Some(day) = *day else {
+                return Span::raw("     ");
+            };
+            let totals = month.totals(day);
+            let met = totals.completed >= goal;
+            let level = shade_level(totals.focus_secs, most);
+            let mut style = Style::default();
+            if level > 0 {
+                style = style.bg(shade(background, accent, level));
+            }
+            if level > 2 {
+                style = style.fg(background);
+            }
+            if met {
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            if day == picked {
+                style = style.fg(second).add_modifier(Modifier::REVERSED);
+            }
+            let mark = if met { '✓' } else { ' ' };
+            Span::styled(format!(" {:>2}{mark} ", day - month.first + 1), style)
+        });
+        let mut spans: Vec<Span> = Vec::new();
+        for cell in cells {
+            spans.push(cell);
+            spans.push(Span::raw(" "));
+        }
+        lines.push(Line::from(spans));
+    }
+    let month_name = format!(" {year}-{number:02} · ✓ {goal} or more focus sessions ");
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(month_name)),
+        grid,
+    );
+
+    let header = Row::new(["Time", "Phase", "Active", "", "Project · task"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let body = stats::in_days(records, picked, picked).map(|record| {
+        let active = stats::active_secs(record);
+        let about: Vec<&str> = [record.project.as_deref(), record.task.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        Row::new([
+            format!(
+                "{}–{}",
+                wallclock::format_hh_mm_at(record.start, record.offset()),
+                wallclock::format_hh_mm_at(record.end, record.offset())
+            ),
+            i18n::phase_title(record.kind).to_string(),
+            format!("{}:{:02}", active / 60, active % 60),
+            if record.completed { "done" } else { "stopped" }.to_string(),
+            about.join(" · "),
+        ])
+    });
+    frame.render_widget(
+        Table::new(
+            body,
+            [
+                Constraint::Length(12),
+                Constraint::Length(13),
+                Constraint::Length(7),
+                Constraint::Length(8),
+                Constraint::Min(10),
+            ],
+        )
+        .header(header)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" {} ", wallclock::format_date(picked))),
+        ),
+        sessions,
+    );
+}
+
+/// 0 for a day without focus, otherwise 1 to 4 by its share of the month's
+/// busiest day.
+fn shade_level(focus_secs: u64, most: u64) -> u8 {
+    match (focus_secs * 4).div_ceil(most.max(1)) {
+        0 => 0,
+        level => level.min(4) as u8,
+    }
+}
+
+/// A quarter of the way from `from` to `to` for each level; colors that
+/// aren't RGB can't be mixed, so they give `to`.
+fn shade(from: Color, to: Color, level: u8) -> Color {
+    let (Color::Rgb(r0, g0, b0), Color::Rgb(r1, g1, b1)) = (from, to) else {
+        return to;
+    };
+    let mix =
+        |a: u8, b: u8| (i32::from(a) + (i32::from(b) - i32::from(a)) * i32::from(level) / 4) as u8;
+    Color::Rgb(mix(r0, r1), mix(g0, g1), mix(b0, b1))
+}
+
+/// As wide as the bars can be and still all fit, between 2 and 12 columns.
+fn bar_width(area: Rect, bars: usize) -> u16 {
+    let inner = area.width.saturating_sub(2);
+    (inner / bars.max(1) as u16).saturating_sub(1).clamp(2, 12)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PhaseKind;
+
+    #[test]
+    fn aggregates_each_tab_from_the_history() {
+        let focus =
+            |start: u64, minutes: u64, project: Option<&str>, task: Option<&str>| SessionRecord {
+                kind: PhaseKind::Focus,
+                start,
+                end: start + minutes * 60,
+                planned_secs: 1500,
+                completed: true,
+                pauses: Vec::new(),
+                snoozes: Vec::new(),
+                project: project.map(str::to_string),
+                task: task.map(str::to_string),
+                estimate: None,
+                rating: None,
+                extended_secs: 0,
+                distractions: Vec::new(),
+                rested: None,
+                utc_offset: None,
+                rolled_up: false,
+            };
+        let noon = 1_700_000_000 - wallclock::seconds_of_local_day(1_700_000_000) + 12 * 3600;
+        let records = [
+            focus(noon - 3 * 3600, 25, Some("thesis"), Some("outline")),
+            focus(noon - 3600, 45, None, None),
+            focus(noon - 86_400, 25, Some("thesis"), Some("draft")),
+            focus(noon - 10 * 86_400, 30, Some("site"), Some("draft")),
+        ];
+
+        let today = view(Tab::Today, &records, noon);
+        assert_eq!(today.bars.len(), 24);
+        assert_eq!(today.bars[9], ("09".to_string(), 25));
+        assert_eq!(today.bars[11].1, 45);
+        let names: Vec<&str> = today.rows.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["(none)", "thesis"]);
+
+        let week = view(Tab::Week, &records, noon);
+        assert_eq!(week.bars.len(), 7);
+        let minutes: Vec<u64> = week.bars.iter().map(|(_, m)| *m).collect();
+        assert_eq!(minutes, [0, 0, 0, 0, 0, 25, 70]);
+        assert_eq!(view(Tab::Month, &records, noon).bars.len(), 30);
+
+        let hours = view(Tab::Hours, &records, noon);
+        assert_eq!(hours.bars[12], ("12".to_string(), 55));
+        let peaks: Vec<&str> = hours.rows.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(peaks, ["12:00–13:00", "11:00–12:00", "09:00–10:00"]);
+
+        let tasks = view(Tab::Tasks, &records, noon);
+        assert_eq!(tasks.bars[0], ("draft".to_string(), 55));
+        assert_eq!(tasks.rows[0].1.completed, 2);
+        let projects = view(Tab::Projects, &records, noon);
+        assert_eq!(projects.rows[0].0, "thesis");
+        assert_eq!(projects.rows[0].1.focus_secs, 50 * 60);
+    }
+
+    #[test]
+    fn lays_out_a_month_with_each_days_totals() {
+        // 2024-02: a leap month starting on a Thursday
+        let first = wallclock::days_from_civil(2024, 2, 1);
+        let at = |day: i64, hour: u64| {
+            let unix = day as u64 * 86_400 + hour * 3600;
+            unix - wallclock::seconds_of_local_day(unix) + hour * 3600
+        };
+        let session = |start: u64| SessionRecord {
+            kind: PhaseKind::Focus,
+            start,
+            end: start + 25 * 60,
+            planned_secs: 1500,
+            completed: true,
+            pauses: Vec::new(),
+            snoozes: Vec::new(),
+            project: None,
+            task: None,
+            estimate: None,
+            rating: None,
+            extended_secs: 0,
+            distractions: Vec::new(),
+            rested: None,
+            utc_offset: None,
+            rolled_up: false,
+        };
+        let records = [
+            session(at(first + 13, 9)),
+            session(at(first + 13, 10)),
+            session(at(first + 28, 9)),
+            session(at(first + 29, 9)),
+        ];
+        let month = Month::around(&records, first + 20);
+        assert_eq!(month.first, first);
+        assert_eq!(month.days.len(), 29);
+        assert_eq!(month.totals(first + 13).completed, 2);
+        assert_eq!(month.totals(first + 28).focus_secs, 25 * 60);
+
+        let weeks = month.weeks();
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(weeks[0][..4], [None, None, None, Some(first)]);
+        assert_eq!(weeks[4][3], Some(first + 28));
+        assert_eq!(weeks[4][4], None);
+
+        assert_eq!(shade_level(0, 3000), 0);
+        assert_eq!(shade_level(1, 3000), 1);
+        assert_eq!(shade_level(3000, 3000), 4);
+    }
+}
@@ -0,0 +1,152 @@
+//! `rusty_pomo doctor`: checks what would otherwise fail quietly during a run
+//! (notifications, sound, config, integrations) and says what to do about it.
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::args::Args;
+use crate::config::Config;
+use crate::integrations::{hue, push, remote, telegram};
+use crate::{
+    activity, audio, calendar, config, graphics, mqtt, notifications, paths, plugins, server, ssh,
+    theme,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Probe {
+    Ok(String),
+    /// Works, but not as well as it could; says how to improve it.
+    Warn(String),
+    /// Won't work; says how to fix it.
+    Fail(String),
+    /// Not set up, so there's nothing to check.
+    Off(String),
+}
+
+impl Probe {
+    fn mark(&self) -> char {
+        match self {
+            Probe::Ok(_) => '✓',
+            Probe::Warn(_) => '!',
+            Probe::Fail(_) => '✗',
+            Probe::Off(_) => '-',
+        }
+    }
+
+    fn detail(&self) -> &str {
+        match self {
+            Probe::Ok(s) | Probe::Warn(s) | Probe::Fail(s) | Probe::Off(s) => s,
+        }
+    }
+}
+
+/// Runs every check and prints the results; false if any failed.
+pub fn run(args: &Args, config: &Result<Config, String>) -> io::Result<bool> {
+    let default = Config::default();
+    let loaded = config.as_ref().unwrap_or(&default);
+    let checks = [
+        ("Config", config::probe(config)),
+        ("Notifications", notifications::probe(args)),
+        ("Sound", audio::probe(args)),
+        ("Files", probe_dirs(args)),
+        ("Colors", theme::probe()),
+        ("Graphics", graphics::probe(args)),
+        ("SSH", ssh::probe(args)),
+        ("HTTP API", server::probe(args)),
+        ("MQTT", mqtt::probe(args.mqtt.as_deref())),
+        ("Hue", hue::probe(&loaded.hue)),
+        ("Remote", remote::probe(&loaded.remote)),
+        ("Push", push::probe(&loaded.push)),
+        ("Telegram", telegram::probe(loaded.telegram.as_ref())),
+        ("Calendar", calendar::probe(args)),
+        ("Plugins", plugins::probe(args)),
+        ("Activity", activity::probe(args)),
+    ];
+    let mut out = io::stdout().lock();
+    render(&mut out, &checks)?;
+    Ok(!checks
+        .iter()
+        .any(|(_, probe)| matches!(probe, Probe::Fail(_))))
+}
+
+fn render(out: &mut impl Write, checks: &[(&str, Probe)]) -> io::Result<()> {
+    let width = checks.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, probe) in checks {
+        writeln!(out, "{} {name:<width$}  {}", probe.mark(), probe.detail())?;
+    }
+    Ok(())
+}
+
+/// Whether `program` can be started by name.
+pub fn on_path(program: &str) -> bool {
+    let Some(path) = env::var_os("PATH") else {
+        return false;
+    };
+    env::split_paths(&path).any(|dir| {
+        dir.join(program).is_file()
+            || (cfg!(target_os = "windows") && dir.join(format!("{program}.exe")).is_file())
+    })
+}
+
+fn probe_dirs(args: &Args) -> Probe {
+    let mut dirs = Vec::new();
+    if args.history {
+        let history = args.history_dir.clone().or_else(paths::history_dir);
+        dirs.push(("history", "RUSTY_POMO_DATA_DIR", history));
+    }
+    dirs.push(("cache", "RUSTY_POMO_CACHE_DIR", paths::cache_dir()));
+    let mut found = Vec::new();
+    for (what, var, dir) in dirs {
+        let Some(dir) = dir else {
+            return Probe::Fail(format!(
+                "cannot locate the {what} directory; set HOME or {var}"
+            ));
+        };
+        if let Err(e) = check_writable(&dir) {
+            return Probe::Fail(format!("cannot write to {}: {e}", dir.display()));
+        }
+        found.push(dir.display().to_string());
+    }
+    Probe::Ok(format!("writable: {}", found.join(", ")))
+}
+
+fn check_writable(dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let probe = dir.join(format!(".doctor.{}", std::process::id()));
+    fs::write(&probe, b"")?;
+    fs::remove_file(&probe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_aligned_results() {
+        let checks = [
+            ("Config", Probe::Ok("defaults".to_string())),
+            (
+                "Notifications",
+                Probe::Fail("no notification server".to_string()),
+            ),
+            ("Hue", Probe::Off("not paired".to_string())),
+        ];
+        let mut out = Vec::new();
+        render(&mut out, &checks).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "✓ Config         defaults\n✗ Notifications  no notification server\n- Hue            not paired\n"
+        );
+    }
+
+    #[test]
+    fn checks_directories_are_writable() {
+        let dir = env::temp_dir().join(format!("rusty_pomo_doctor_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        assert!(check_writable(&dir.join("history")).is_ok());
+        assert!(fs::read_dir(dir.join("history")).unwrap().next().is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
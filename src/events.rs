@@ -0,0 +1,153 @@
+//! Phase changes as events. `AppState` only sends them; notifications, lights,
+//! music and ambient sound subscribe and react when the bus is dispatched.
+
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::args::Args;
+use crate::control::Control;
+use crate::history::SessionRecord;
+use crate::state::PhaseKind;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PhaseEvent {
+    /// The timer moved on to a new phase; breaks come with a quote to show.
+    Started {
+        kind: PhaseKind,
+        quote: Option<String>,
+    },
+    /// A phase is running without having just started: the first one at launch,
+    /// or one brought back by undo.
+    Entered(PhaseKind),
+    /// The last of `--cycles` ended.
+    Done { cycles: u64, focus_sessions: u64 },
+    /// A phase was saved to the history, sent once a skip can no longer take
+    /// it back.
+    Recorded(SessionRecord),
+    /// The focus session saved with this start got a rating from the prompt.
+    Rated { start: u64, rating: u8 },
+    /// A meeting from `--calendar` starts in `minutes`; `clash` if the running
+    /// focus session would still be going then.
+    MeetingSoon {
+        summary: String,
+        minutes: u64,
+        clash: bool,
+    },
+    /// The history just earned the achievement with this key.
+    Unlocked(&'static str),
+}
+
+impl PhaseEvent {
+    /// The phase now running, for subscribers that only follow the phase.
+    pub fn phase(&self) -> Option<PhaseKind> {
+        match self {
+            PhaseEvent::Started { kind, .. } | PhaseEvent::Entered(kind) => Some(*kind),
+            PhaseEvent::Done { .. }
+            | PhaseEvent::Recorded(_)
+            | PhaseEvent::Rated { .. }
+            | PhaseEvent::MeetingSoon { .. }
+            | PhaseEvent::Unlocked(_) => None,
+        }
+    }
+}
+
+pub trait Subscriber: fmt::Debug {
+    fn on_event(&mut self, event: &PhaseEvent);
+    /// Takes over settings from a reloaded config.
+    fn reload(&mut self, _settings: &Args) {}
+    /// A way back to the timer, for subscribers with buttons to press.
+    fn connect(&mut self, _controls: &Sender<Control>) {}
+}
+
+/// Queues events until `dispatch`, so they reach subscribers after the state
+/// change that caused them is complete.
+#[derive(Debug)]
+pub struct Bus {
+    sender: Sender<PhaseEvent>,
+    events: Receiver<PhaseEvent>,
+    subscribers: Vec<Box<dyn Subscriber>>,
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        let (sender, events) = mpsc::channel();
+        Self {
+            sender,
+            events,
+            subscribers: Vec::new(),
+        }
+    }
+}
+
+impl Bus {
+    pub fn subscribe(&mut self, subscriber: Box<dyn Subscriber>) {
+        self.subscribers.push(subscriber);
+    }
+
+    pub fn send(&self, event: PhaseEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Hands every queued event to each subscriber, in the order they were sent.
+    pub fn dispatch(&mut self) {
+        while let Ok(event) = self.events.try_recv() {
+            for subscriber in &mut self.subscribers {
+                subscriber.on_event(&event);
+            }
+        }
+    }
+
+    pub fn reload(&mut self, settings: &Args) {
+        for subscriber in &mut self.subscribers {
+            subscriber.reload(settings);
+        }
+    }
+
+    pub fn connect(&mut self, controls: &Sender<Control>) {
+        for subscriber in &mut self.subscribers {
+            subscriber.connect(controls);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Debug)]
+    struct Log(Rc<RefCell<Vec<PhaseEvent>>>);
+
+    impl Subscriber for Log {
+        fn on_event(&mut self, event: &PhaseEvent) {
+            self.0.borrow_mut().push(event.clone());
+        }
+    }
+
+    #[test]
+    fn delivers_queued_events_in_order_on_dispatch() {
+        let mut bus = Bus::default();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        bus.subscribe(Box::new(Log(Rc::clone(&seen))));
+        bus.send(PhaseEvent::Entered(PhaseKind::Focus));
+        bus.send(PhaseEvent::Started {
+            kind: PhaseKind::ShortBreak,
+            quote: None,
+        });
+        assert!(seen.borrow().is_empty());
+        bus.dispatch();
+        assert_eq!(
+            *seen.borrow(),
+            [
+                PhaseEvent::Entered(PhaseKind::Focus),
+                PhaseEvent::Started {
+                    kind: PhaseKind::ShortBreak,
+                    quote: None,
+                }
+            ]
+        );
+        bus.dispatch();
+        assert_eq!(seen.borrow().len(), 2);
+    }
+}
@@ -0,0 +1,134 @@
+//! The garden behind `g`: every finished focus session in the history plants
+//! something, and every abandoned one wilts the newest plant still standing.
+//! It lives on in the history, so it needs nothing saved of its own.
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::widgets::Widget;
+
+use crate::history::SessionRecord;
+use crate::state::PhaseKind;
+
+/// Columns and rows for a plant: its flower, its stem and the soil under it.
+const PLANT_WIDTH: u16 = 4;
+const BED_HEIGHT: u16 = 3;
+
+const FLOWERS: [&str; 4] = [" @", " *", " &", " %"];
+const STEMS: [&str; 4] = ["\\|/", " |/", "\\|", " |"];
+const WILTED: [&str; 2] = [" .", "_|_"];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Plant {
+    /// Which flower grew, picked by when the session started.
+    pub kind: usize,
+    pub wilted: bool,
+}
+
+/// The garden grown by `records`, oldest plant first.
+pub fn grow(records: &[SessionRecord]) -> Vec<Plant> {
+    let mut plants: Vec<Plant> = Vec::new();
+    for record in records.iter().filter(|r| r.kind == PhaseKind::Focus) {
+        if record.completed {
+            plants.push(Plant {
+                kind: (record.start / 60) as usize % FLOWERS.len(),
+                wilted: false,
+            });
+        } else if let Some(plant) = plants.iter_mut().rev().find(|p| !p.wilted) {
+            plant.wilted = true;
+        }
+    }
+    plants
+}
+
+/// The newest plants that fit, in beds filled from the top left.
+pub struct Garden<'a> {
+    pub plants: &'a [Plant],
+    pub flower: Color,
+    pub stem: Color,
+}
+
+impl Widget for Garden<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let per_bed = usize::from(area.width / PLANT_WIDTH);
+        let beds = usize::from(area.height / BED_HEIGHT);
+        if per_bed == 0 || beds == 0 {
+            return;
+        }
+        let shown = &self.plants[self.plants.len().saturating_sub(per_bed * beds)..];
+        let wilted = Style::default().fg(Color::DarkGray);
+        for (i, plant) in shown.iter().enumerate() {
+            let x = area.x + (i % per_bed) as u16 * PLANT_WIDTH;
+            let y = area.y + (i / per_bed) as u16 * BED_HEIGHT;
+            let (flower, stem, style) = if plant.wilted {
+                (WILTED[0], WILTED[1], (wilted, wilted))
+            } else {
+                (
+                    FLOWERS[plant.kind],
+                    STEMS[plant.kind],
+                    (
+                        Style::default().fg(self.flower),
+                        Style::default().fg(self.stem),
+                    ),
+                )
+            };
+            buf.set_string(x, y, flower, style.0);
+            buf.set_string(x, y + 1, stem, style.1);
+            buf.set_string(x, y + 2, "~~~~", Style::default().fg(Color::DarkGray));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn focus(start: u64, completed: bool) -> SessionRecord {
+        SessionRecord {
+            completed,
+            ..SessionRecord::test_focus(start, 1500)
+        }
+    }
+
+    #[test]
+    fn plants_finished_sessions_and_wilts_for_abandoned_ones() {
+        let records = [
+            focus(0, true),
+            focus(60, true),
+            SessionRecord {
+                kind: PhaseKind::ShortBreak,
+                ..focus(120, false)
+            },
+            focus(180, false),
+            focus(240, true),
+            focus(300, false),
+            focus(360, false),
+            focus(420, false),
+        ];
+        let plants = grow(&records);
+        let wilted: Vec<_> = plants.iter().map(|p| p.wilted).collect();
+        // The second plant wilts, then the third, then the first; with nothing
+        // left standing, the last abandoned session changes nothing
+        assert_eq!(wilted, [true, true, true]);
+        assert_eq!(plants.iter().map(|p| p.kind).collect::<Vec<_>>(), [0, 1, 0]);
+        assert!(grow(&records[..2]).iter().all(|p| !p.wilted));
+
+        // Two beds of two: only the newest four fit
+        let plants = grow(&[0, 60, 120, 180, 240].map(|start| focus(start, true)));
+        let mut buf = Buffer::empty(Rect::new(0, 0, 9, 6));
+        let garden = Garden {
+            plants: &plants,
+            flower: Color::Red,
+            stem: Color::Green,
+        };
+        garden.render(buf.area, &mut buf);
+        let rows: Vec<String> = buf
+            .content()
+            .chunks(9)
+            .map(|row| row.iter().map(|c| c.symbol()).collect())
+            .collect();
+        assert_eq!(rows[0], " *   &   ");
+        assert_eq!(rows[2], "~~~~~~~~ ");
+        assert_eq!(rows[3], " %   @   ");
+    }
+}
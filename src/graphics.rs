@@ -0,0 +1,385 @@
+//! Pictures in the timer screen for terminals that show them: the app icon
+//! and a progress ring on either side of the gauge, through the kitty
+//! graphics protocol or iTerm2's inline images. Elsewhere the gauge has the
+//! row to itself, as before.
+
+use std::env;
+use std::io::{self, Write};
+
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+
+use crate::args::Args;
+use crate::doctor::Probe;
+use crate::websocket::base64;
+
+const ICON: &[u8] = include_bytes!("../assets/rusty_pomo.png");
+/// Width and height of the ring, in pixels.
+const RING: u32 = 96;
+/// Kitty takes payloads in chunks of at most this many bytes.
+const CHUNK: usize = 4096;
+/// Image ids, for kitty to replace and delete.
+const ICON_ID: u32 = 7001;
+const RING_ID: u32 = 7002;
+/// The ring is redrawn when the progress moves by this much.
+const STEPS: f64 = 200.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    /// kitty, Ghostty and Konsole.
+    Kitty,
+    /// iTerm2 and WezTerm.
+    Iterm2,
+}
+
+/// The protocol this terminal speaks, from the environment. None inside
+/// tmux or screen, which get in the way of both.
+pub fn detect() -> Option<Protocol> {
+    detect_with(|name| env::var(name).ok())
+}
+
+fn detect_with(var: impl Fn(&str) -> Option<String>) -> Option<Protocol> {
+    let term = var("TERM").unwrap_or_default();
+    let program = var("TERM_PROGRAM").unwrap_or_default();
+    if var("TMUX").is_some() || term.starts_with("screen") || term.starts_with("tmux") {
+        return None;
+    }
+    if term == "xterm-kitty"
+        || term == "xterm-ghostty"
+        || var("KITTY_WINDOW_ID").is_some()
+        || var("KONSOLE_VERSION").is_some()
+        || program == "ghostty"
+    {
+        return Some(Protocol::Kitty);
+    }
+    // iTerm2 sends LC_TERMINAL over SSH
+    if program == "iTerm.app"
+        || program == "WezTerm"
+        || var("LC_TERMINAL").as_deref() == Some("iTerm2")
+    {
+        return Some(Protocol::Iterm2);
+    }
+    None
+}
+
+/// Whether the timer screen will show pictures, for `doctor`.
+pub fn probe(args: &Args) -> Probe {
+    if !args.graphics {
+        return Probe::Off("turned off with --graphics false".to_string());
+    }
+    match detect() {
+        Some(Protocol::Kitty) => {
+            Probe::Ok("icon and progress ring through the kitty graphics protocol".to_string())
+        }
+        Some(Protocol::Iterm2) => {
+            Probe::Ok("icon and progress ring as iTerm2 inline images".to_string())
+        }
+        None => {
+            Probe::Off("the terminal shows no pictures, or runs inside tmux or screen".to_string())
+        }
+    }
+}
+
+/// What's on screen, so pictures are only sent again when they change.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Shown {
+    icon: Rect,
+    ring: Rect,
+    step: u32,
+    color: [u8; 3],
+}
+
+/// Draws the pictures after each frame.
+#[derive(Debug)]
+pub struct Graphics {
+    protocol: Protocol,
+    shown: Option<Shown>,
+}
+
+impl Graphics {
+    pub fn new(protocol: Protocol) -> Self {
+        Self {
+            protocol,
+            shown: None,
+        }
+    }
+
+    /// Shows the icon and a ring `progress` full in `areas`, or clears them
+    /// without areas.
+    pub fn draw(
+        &mut self,
+        out: &mut impl Write,
+        areas: Option<(Rect, Rect)>,
+        progress: f64,
+        color: Color,
+    ) -> io::Result<()> {
+        let Some((icon, ring)) = areas else {
+            if self.shown.take().is_some() {
+                self.clear(out)?;
+            }
+            return Ok(());
+        };
+        let shown = Shown {
+            icon,
+            ring,
+            step: (progress.clamp(0.0, 1.0) * STEPS).round() as u32,
+            color: rgb(color),
+        };
+        if self.shown == Some(shown) {
+            return Ok(());
+        }
+        if self.shown.is_none_or(|last| last.icon != icon) {
+            self.place(out, icon, ICON_ID, Picture::Png(ICON))?;
+        }
+        let pixels = ring_pixels(f64::from(shown.step) / STEPS, shown.color);
+        self.place(out, ring, RING_ID, Picture::Rgba(&pixels, RING, RING))?;
+        self.shown = Some(shown);
+        out.flush()
+    }
+
+    /// Sends everything again next time, after the terminal was cleared.
+    pub fn forget(&mut self) {
+        self.shown = None;
+    }
+
+    fn place(&self, out: &mut impl Write, area: Rect, id: u32, picture: Picture) -> io::Result<()> {
+        // Cursor positions count from 1
+        write!(out, "\x1b7\x1b[{};{}H", area.y + 1, area.x + 1)?;
+        match self.protocol {
+            Protocol::Kitty => {
+                let format = match picture {
+                    Picture::Png(_) => "f=100".to_string(),
+                    Picture::Rgba(_, width, height) => format!("f=32,s={width},v={height}"),
+                };
+                let data = base64(picture.bytes());
+                let mut chunks = data.as_bytes().chunks(CHUNK).peekable();
+                let mut first = true;
+                while let Some(chunk) = chunks.next() {
+                    let more = u8::from(chunks.peek().is_some());
+                    if first {
+                        // Sending an image again under its id replaces it
+                        write!(
+                            out,
+                            "\x1b_Ga=T,{format},i={id},p=1,c={},r={},C=1,q=2,m={more};",
+                            area.width, area.height
+                        )?;
+                        first = false;
+                    } else {
+                        write!(out, "\x1b_Gm={more};")?;
+                    }
+                    out.write_all(chunk)?;
+                    out.write_all(b"\x1b\\")?;
+                }
+            }
+            Protocol::Iterm2 => {
+                let png = picture.png();
+                write!(
+                    out,
+                    "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+                    png.len(),
+                    area.width,
+                    area.height,
+                    base64(&png)
+                )?;
+            }
+        }
+        out.write_all(b"\x1b8")
+    }
+
+    /// Takes the pictures off the screen. iTerm2's go with the cells under
+    /// them, which the next frame draws over.
+    fn clear(&self, out: &mut impl Write) -> io::Result<()> {
+        if self.protocol == Protocol::Kitty {
+            for id in [ICON_ID, RING_ID] {
+                write!(out, "\x1b_Ga=d,d=I,i={id},q=2\x1b\\")?;
+            }
+        }
+        out.flush()
+    }
+}
+
+impl Drop for Graphics {
+    fn drop(&mut self) {
+        if self.shown.is_some() {
+            let _ = self.clear(&mut io::stdout());
+        }
+    }
+}
+
+enum Picture<'a> {
+    Png(&'a [u8]),
+    Rgba(&'a [u8], u32, u32),
+}
+
+impl Picture<'_> {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Picture::Png(bytes) | Picture::Rgba(bytes, ..) => bytes,
+        }
+    }
+
+    fn png(&self) -> Vec<u8> {
+        match self {
+            Picture::Png(bytes) => bytes.to_vec(),
+            Picture::Rgba(pixels, width, height) => png(*width, *height, pixels),
+        }
+    }
+}
+
+fn rgb(color: Color) -> [u8; 3] {
+    match color {
+        Color::Rgb(r, g, b) => [r, g, b],
+        _ => [255, 255, 255],
+    }
+}
+
+/// A ring filled clockwise from the top to `progress` in `color`, with the
+/// rest faint, as RGBA pixels.
+fn ring_pixels(progress: f64, color: [u8; 3]) -> Vec<u8> {
+    let size = f64::from(RING);
+    let (center, outer, inner) = (size / 2.0, size / 2.0 - 2.0, size / 2.0 - 12.0);
+    let mut pixels = Vec::with_capacity((RING * RING * 4) as usize);
+    for y in 0..RING {
+        for x in 0..RING {
+            // Four samples a pixel smooth the edges
+            let (mut filled, mut track) = (0_u16, 0_u16);
+            for (dx, dy) in [(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)] {
+                let (px, py) = (f64::from(x) + dx - center, f64::from(y) + dy - center);
+                let distance = px.hypot(py);
+                if distance < inner || distance > outer {
+                    continue;
+                }
+                // Clockwise from 12 o'clock, from 0 to 1
+                let turn = (px.atan2(-py) / std::f64::consts::TAU).rem_euclid(1.0);
+                if turn < progress {
+                    filled += 1;
+                } else {
+                    track += 1;
+                }
+            }
+            let pixel = if filled >= track {
+                [color[0], color[1], color[2], (filled * 255 / 4) as u8]
+            } else {
+                [128, 128, 128, (track * 16) as u8]
+            };
+            pixels.extend_from_slice(&pixel);
+        }
+    }
+    pixels
+}
+
+/// An uncompressed PNG: stored deflate blocks are enough for pictures this
+/// small.
+fn png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(rgba.len() + height as usize);
+    for row in rgba.chunks((width * 4) as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xffff).peekable();
+    while let Some(block) = blocks.next() {
+        zlib.push(u8::from(blocks.peek().is_none()));
+        let len = block.len() as u16;
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    let (a, b) = raw.iter().fold((1_u32, 0_u32), |(a, b), byte| {
+        let a = (a + u32::from(*byte)) % 65_521;
+        (a, (b + a) % 65_521)
+    });
+    zlib.extend_from_slice(&((b << 16) | a).to_be_bytes());
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8-bit RGBA, no interlacing
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, data) in [(b"IHDR", header), (b"IDAT", zlib), (b"IEND", Vec::new())] {
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        let start = out.len();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(&data);
+        let crc = crc32(&out[start..]);
+        out.extend_from_slice(&crc.to_be_bytes());
+    }
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0_u32, |crc, byte| {
+        (0..8).fold(crc ^ u32::from(*byte), |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_a_ring_for_the_protocol_the_terminal_speaks() {
+        let detect = |vars: &[(&str, &str)]| {
+            detect_with(|name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        assert_eq!(detect(&[("TERM", "xterm-kitty")]), Some(Protocol::Kitty));
+        assert_eq!(
+            detect(&[("TERM_PROGRAM", "iTerm.app")]),
+            Some(Protocol::Iterm2)
+        );
+        assert_eq!(
+            detect(&[("TERM", "xterm-kitty"), ("TMUX", "/tmp/tmux")]),
+            None
+        );
+        assert_eq!(detect(&[("TERM", "xterm-256color")]), None);
+
+        // A quarter done: the top right is filled, the bottom left faint
+        let pixels = ring_pixels(0.25, [255, 0, 0]);
+        let at = |x: u32, y: u32| &pixels[((y * RING + x) * 4) as usize..][..4];
+        assert_eq!(at(60, 6), [255, 0, 0, 255]);
+        assert_eq!(at(36, 90)[..3], [128, 128, 128]);
+        assert_eq!(at(48, 48)[3], 0);
+
+        let image = png(RING, RING, &pixels);
+        assert!(image.starts_with(b"\x89PNG\r\n\x1a\n"));
+        // IEND's CRC never changes
+        assert!(image.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+
+        let mut graphics = Graphics::new(Protocol::Kitty);
+        let areas = Some((Rect::new(2, 4, 6, 3), Rect::new(70, 4, 6, 3)));
+        let mut out = Vec::new();
+        graphics
+            .draw(&mut out, areas, 0.25, Color::Rgb(255, 0, 0))
+            .unwrap();
+        let sent = String::from_utf8(out).unwrap();
+        assert!(
+            sent.starts_with("\x1b7\x1b[5;3H\x1b_Ga=T,f=100,i=7001,p=1,c=6,r=3"),
+            "{sent:.60}"
+        );
+        assert!(sent.contains("f=32,s=96,v=96,i=7002"));
+        // Nothing moved, so nothing is sent
+        let mut out = Vec::new();
+        graphics
+            .draw(&mut out, areas, 0.2501, Color::Rgb(255, 0, 0))
+            .unwrap();
+        assert!(out.is_empty());
+        graphics
+            .draw(&mut out, None, 0.3, Color::Rgb(255, 0, 0))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b_Ga=d,d=I,i=7001,q=2\x1b\\\x1b_Ga=d,d=I,i=7002,q=2\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,1453 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::args::Args;
+use crate::json::{self, Value};
+use crate::paths;
+use crate::phases;
+use crate::state::PhaseKind;
+use crate::{stats, wallclock};
+
+/// One finished (or abandoned) phase. Times are unix seconds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionRecord {
+    pub kind: PhaseKind,
+    pub start: u64,
+    pub end: u64,
+    pub planned_secs: u64,
+    pub completed: bool,
+    pub pauses: Vec<(u64, u64)>,
+    /// Time the phase was put off with snooze, kept apart from pauses.
+    pub snoozes: Vec<(u64, u64)>,
+    pub project: Option<String>,
+    pub task: Option<String>,
+    /// The focus sessions `--estimate` said the task would take.
+    pub estimate: Option<u64>,
+    /// How the session went, 1 to 5, from `--rate-sessions`.
+    pub rating: Option<u8>,
+    /// Of `planned_secs`, the time added by taking up `--offer-extension`.
+    pub extended_secs: u64,
+    /// Seconds spent in each app from `[distractions]`, most first.
+    pub distractions: Vec<(String, u64)>,
+    /// For a break with `--track-rest`, whether it went without typing or using
+    /// the mouse for most of it.
+    pub rested: Option<bool>,
+    /// Seconds local time was ahead of UTC when the session started, so it
+    /// stays on its day after a trip or a change of `--tz`.
+    pub utc_offset: Option<i64>,
+    /// Stands in for a session that `history compact` rolled into its day's
+    /// totals: the day, length and outcome are right, the times are not.
+    pub rolled_up: bool,
+}
+
+impl SessionRecord {
+    /// A finished focus session of `secs` from `start`, with nothing else set;
+    /// tests change what they need with struct update syntax.
+    #[cfg(test)]
+    pub(crate) fn test_focus(start: u64, secs: u64) -> Self {
+        Self {
+            kind: PhaseKind::Focus,
+            start,
+            end: start + secs,
+            planned_secs: secs,
+            completed: true,
+            pauses: Vec::new(),
+            snoozes: Vec::new(),
+            project: None,
+            task: None,
+            estimate: None,
+            rating: None,
+            extended_secs: 0,
+            distractions: Vec::new(),
+            rested: None,
+            utc_offset: None,
+            rolled_up: false,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let intervals = |list: &[(u64, u64)]| {
+            list.iter()
+                .map(|(s, e)| Value::Array(vec![(*s).into(), (*e).into()]))
+                .collect()
+        };
+        Value::Object(vec![
+            ("kind".to_string(), self.kind.key().into()),
+            ("start".to_string(), self.start.into()),
+            ("end".to_string(), self.end.into()),
+            ("planned".to_string(), self.planned_secs.into()),
+            ("completed".to_string(), self.completed.into()),
+            ("pauses".to_string(), Value::Array(intervals(&self.pauses))),
+            (
+                "snoozed".to_string(),
+                Value::Array(intervals(&self.snoozes)),
+            ),
+            ("project".to_string(), self.project.as_deref().into()),
+            ("task".to_string(), self.task.as_deref().into()),
+            ("estimate".to_string(), self.estimate.into()),
+            (
+                "rating".to_string(),
+                self.rating.map_or(Value::Null, |r| u64::from(r).into()),
+            ),
+            ("extended".to_string(), self.extended_secs.into()),
+            (
+                "distractions".to_string(),
+                Value::Object(
+                    self.distractions
+                        .iter()
+                        .map(|(app, secs)| (app.clone(), (*secs).into()))
+                        .collect(),
+                ),
+            ),
+            ("rested".to_string(), self.rested.into()),
+            ("utc_offset".to_string(), self.utc_offset.into()),
+        ])
+    }
+
+    /// The offset the session was recorded at, or for sessions saved before
+    /// offsets were, the local one.
+    pub fn offset(&self) -> i64 {
+        self.utc_offset
+            .unwrap_or_else(|| wallclock::local_offset(self.start))
+    }
+
+    /// The day the session started on, where it was recorded.
+    pub fn day(&self) -> i64 {
+        wallclock::day_at(self.start, self.offset())
+    }
+
+    /// Seconds into that day the session started at.
+    pub fn seconds_of_day(&self) -> u64 {
+        wallclock::seconds_of_day_at(self.start, self.offset())
+    }
+
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let text = |key| value.get(key).and_then(Value::as_str).map(str::to_string);
+        // Missing lists are empty: "snoozed" is newer than the first history files
+        let intervals = |key| {
+            value
+                .get(key)
+                .and_then(Value::as_array)
+                .unwrap_or_default()
+                .iter()
+                .map(|pair| match pair.as_array()? {
+                    [s, e] => Some((s.as_u64()?, e.as_u64()?)),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+        };
+        Some(Self {
+            kind: phases::kind(value.get("kind")?.as_str().filter(|k| !k.is_empty())?),
+            start: value.get("start")?.as_u64()?,
+            end: value.get("end")?.as_u64()?,
+            planned_secs: value.get("planned")?.as_u64()?,
+            completed: value.get("completed")?.as_bool()?,
+            pauses: intervals("pauses")?,
+            snoozes: intervals("snoozed")?,
+            project: text("project"),
+            task: text("task"),
+            // Missing from files older than `--estimate`
+            estimate: value.get("estimate").and_then(Value::as_u64),
+            rating: value
+                .get("rating")
+                .and_then(Value::as_u64)
+                .and_then(|r| u8::try_from(r).ok())
+                .filter(|r| (1..=5).contains(r)),
+            // Missing from files older than `--offer-extension`
+            extended_secs: value.get("extended").and_then(Value::as_u64).unwrap_or(0),
+            distractions: match value.get("distractions") {
+                Some(Value::Object(apps)) => apps
+                    .iter()
+                    .map(|(app, secs)| Some((app.clone(), secs.as_u64()?)))
+                    .collect::<Option<_>>()?,
+                _ => Vec::new(),
+            },
+            rested: value.get("rested").and_then(Value::as_bool),
+            // Missing from files older than `--tz`
+            utc_offset: value.get("utc_offset").and_then(Value::as_i64),
+            rolled_up: false,
+        })
+    }
+}
+
+/// Where sessions are persisted. `History` keeps the loaded records in memory and
+/// forwards every change to its store.
+pub trait HistoryStore: fmt::Debug {
+    fn load(&self) -> io::Result<Vec<SessionRecord>>;
+    fn append(&mut self, record: &SessionRecord) -> io::Result<()>;
+    /// Replaces the stored `old` with `new`, or removes it, leaving the rest of
+    /// its day as it is.
+    fn replace(&mut self, old: &SessionRecord, new: Option<&SessionRecord>) -> io::Result<()>;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum StoreKind {
+    /// Per-day JSON Lines files
+    Jsonl,
+    /// Keep sessions for this run only
+    Memory,
+}
+
+impl StoreKind {
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::from_str(key, true).ok()
+    }
+}
+
+/// Append-only JSON Lines shards, one file per local day (`YYYY-MM-DD.jsonl`),
+/// so copies edited on different machines merge line by line.
+#[derive(Debug)]
+pub struct JsonlStore {
+    dir: PathBuf,
+}
+
+impl JsonlStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+impl HistoryStore for JsonlStore {
+    /// Reads every day shard, skipping lines that can't be parsed.
+    fn load(&self) -> io::Result<Vec<SessionRecord>> {
+        Ok(shard_files(&self.dir)
+            .into_iter()
+            .filter(|(_, name)| is_shard_name(name))
+            .flat_map(|(path, _)| read_records(&path))
+            .collect())
+    }
+
+    fn append(&mut self, record: &SessionRecord) -> io::Result<()> {
+        let _lock = lock(&self.dir)?;
+        let day = record.day();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(shard_path(&self.dir, day))?;
+        writeln!(file, "{}", record.to_json())
+    }
+
+    /// Re-reads the shard under the lock, so sessions other timers appended
+    /// since this one loaded, and lines `history check` has yet to quarantine,
+    /// are written back untouched.
+    fn replace(&mut self, old: &SessionRecord, new: Option<&SessionRecord>) -> io::Result<()> {
+        let _lock = lock(&self.dir)?;
+        let path = shard_path(&self.dir, old.day());
+        let contents = match fs::read_to_string(&path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            contents => contents?,
+        };
+        let mut found = false;
+        let mut kept = String::new();
+        for line in contents.lines() {
+            let matches = !found
+                && json::parse(line)
+                    .ok()
+                    .and_then(|value| SessionRecord::from_json(&value))
+                    .is_some_and(|r| {
+                        r.kind == old.kind && r.start == old.start && r.end == old.end
+                    });
+            if !matches {
+                kept.push_str(&format!("{line}\n"));
+                continue;
+            }
+            found = true;
+            if let Some(new) = new {
+                kept.push_str(&format!("{}\n", new.to_json()));
+            }
+        }
+        write_file(&path, kept)
+    }
+}
+
+/// Takes the history directory's lock, making the directory if need be. It's
+/// held until the file is dropped, so a day being rewritten doesn't lose a
+/// line another timer appends meanwhile.
+fn lock(dir: &Path) -> io::Result<fs::File> {
+    fs::create_dir_all(dir)?;
+    lock_file(&dir.join(".lock"))
+}
+
+/// Takes an exclusive lock on `path`, creating it, until the file is dropped.
+pub(crate) fn lock_file(path: &Path) -> io::Result<fs::File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::fd::AsRawFd;
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(file)
+}
+
+/// Persists nothing; the records only live in `History` for the current run.
+#[derive(Debug, Default)]
+pub struct MemoryStore;
+
+impl HistoryStore for MemoryStore {
+    fn load(&self) -> io::Result<Vec<SessionRecord>> {
+        Ok(Vec::new())
+    }
+
+    fn append(&mut self, _record: &SessionRecord) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn replace(&mut self, _old: &SessionRecord, _new: Option<&SessionRecord>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct History {
+    store: Box<dyn HistoryStore>,
+    pub records: Vec<SessionRecord>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::with_store(Box::new(MemoryStore))
+    }
+}
+
+impl History {
+    /// Loads whatever the store holds; a store that can't be read starts empty.
+    pub fn with_store(store: Box<dyn HistoryStore>) -> Self {
+        let mut records = store.load().unwrap_or_default();
+        records.sort_by_key(|r| r.start);
+        Self { store, records }
+    }
+
+    pub fn open(dir: PathBuf) -> Self {
+        Self::with_store(Box::new(JsonlStore::new(dir)))
+    }
+
+    /// The store selected by `--history`, `--history-backend` and `--history-dir`.
+    pub fn from_args(args: &Args) -> Self {
+        if !args.history || args.history_backend == StoreKind::Memory {
+            return Self::default();
+        }
+        args.history_dir
+            .clone()
+            .or_else(paths::history_dir)
+            .map(Self::open)
+            .unwrap_or_default()
+    }
+
+    pub fn append(&mut self, record: SessionRecord) -> io::Result<()> {
+        self.store.append(&record)?;
+        self.records.push(record);
+        Ok(())
+    }
+
+    /// Rates the record at `index`, in the store as well.
+    pub fn rate(&mut self, index: usize, rating: u8) -> io::Result<()> {
+        let Some(record) = self.records.get_mut(index) else {
+            return Ok(());
+        };
+        let old = record.clone();
+        record.rating = Some(rating);
+        self.store.replace(&old, Some(record))
+    }
+
+    /// Drops every record after the first `len`, from the store as well.
+    pub fn truncate(&mut self, len: usize) -> io::Result<()> {
+        if len >= self.records.len() {
+            return Ok(());
+        }
+        for record in self.records.split_off(len) {
+            self.store.replace(&record, None)?;
+        }
+        Ok(())
+    }
+
+    /// Leaves out every record of another project, or of none, when `project`
+    /// is given; the store keeps them.
+    pub fn keep_project(&mut self, project: Option<&str>) {
+        if let Some(project) = project {
+            self.records
+                .retain(|r| r.project.as_deref() == Some(project));
+        }
+    }
+
+    /// Records that started on the same local day as `now`.
+    pub fn today(&self, now: u64) -> impl Iterator<Item = &SessionRecord> {
+        let day = wallclock::local_day(now);
+        self.records.iter().filter(move |r| r.day() == day)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    pub shards: usize,
+    pub added: usize,
+    pub conflict_copies: usize,
+}
+
+/// Reconciles the JSON Lines history in `dir` with the given other history
+/// directories and with any sync-conflict copies of its shards
+/// (`2024-05-01 (conflicted copy).jsonl`, `2024-05-01.sync-conflict-….jsonl`,
+/// git conflict markers). Every shard is rewritten as the sorted,
+/// de-duplicated union of all copies, so merging is order-independent and
+/// repeatable. Conflict copies inside `dir` are removed once folded in.
+pub fn sync_merge(dir: &Path, others: &[PathBuf]) -> io::Result<MergeSummary> {
+    let _lock = lock(dir)?;
+    let mut local = Lines::default();
+    let mut incoming = Lines::default();
+    let mut copies = Vec::new();
+    for (path, name) in shard_files(dir) {
+        if is_shard_name(&name) {
+            local.extend(Lines::read(&path));
+        } else if shard_day(&name).is_some() {
+            incoming.extend(Lines::read(&path));
+            copies.push(path);
+        }
+    }
+    for other in others {
+        for (path, name) in shard_files(other) {
+            if shard_day(&name).is_some() {
+                incoming.extend(Lines::read(&path));
+            }
+        }
+    }
+    let (shards, added) = write_union(dir, local, incoming)?;
+    for copy in &copies {
+        fs::remove_file(copy)?;
+    }
+    Ok(MergeSummary {
+        shards,
+        added,
+        conflict_copies: copies.len(),
+    })
+}
+
+/// Adds `lines` to the history in `dir`, leaving out sessions it already
+/// has; returns how many were new.
+pub fn add_records(dir: &Path, lines: Lines) -> io::Result<usize> {
+    let _lock = lock(dir)?;
+    let mut local = Lines::default();
+    for (path, name) in shard_files(dir) {
+        if is_shard_name(&name) {
+            local.extend(Lines::read(&path));
+        }
+    }
+    write_union(dir, local, lines).map(|(_, added)| added)
+}
+
+/// Rewrites each day file as the union of both sets; returns the number of
+/// day files and of sessions `local` didn't have. Lines of `local` that can't
+/// be read stay in their files for `history check`; those of `incoming`, such
+/// as conflict markers, are left out. Callers hold the lock from reading `local`.
+fn write_union(dir: &Path, local: Lines, mut incoming: Lines) -> io::Result<(usize, usize)> {
+    let before = local.clone().merged().sessions();
+    incoming.unreadable.clear();
+    let mut merged = local;
+    merged.extend(incoming);
+    let merged = merged.merged();
+    let added = merged.sessions().saturating_sub(before);
+    let days = merged.by_day();
+    for (day, lines) in &days {
+        write_day(&shard_path(dir, *day), lines)?;
+    }
+    Ok((days.len(), added))
+}
+
+/// Sorted union of records, treating equal kind/start/end as the same session
+/// and keeping a copy that was rated over one that wasn't.
+pub fn union(mut records: Vec<SessionRecord>) -> Vec<SessionRecord> {
+    records.sort_by(|a, b| {
+        (a.start, a.end, a.kind.key(), a.rating.is_none())
+            .cmp(&(b.start, b.end, b.kind.key(), b.rating.is_none()))
+            .then_with(|| a.to_json().to_string().cmp(&b.to_json().to_string()))
+    });
+    records.dedup_by(|b, a| a.kind == b.kind && a.start == b.start && a.end == b.end);
+    records
+}
+
+/// Every session in `contents`, with rollups expanded into stand-ins for them.
+pub fn parse_records(contents: &str) -> Vec<SessionRecord> {
+    Lines::parse(contents).records()
+}
+
+/// The lines of day files as they are stored, sessions apart from rollups, so
+/// merging, compacting and checking can rewrite them without expanding the
+/// rollups into stand-ins: those have made-up times, which can't be told apart
+/// or even be sure to fall on the right day.
+#[derive(Clone, Debug, Default)]
+pub struct Lines {
+    sessions: Vec<SessionRecord>,
+    rollups: Vec<Rollup>,
+    /// Lines of a day file that are neither, byte for byte, with its day.
+    unreadable: Vec<(i64, Vec<u8>)>,
+}
+
+impl Lines {
+    /// Sorts out the lines of `contents`, skipping those that are neither.
+    pub fn parse(contents: &str) -> Self {
+        Self::sort_out(contents.as_bytes(), None)
+    }
+
+    /// With `day`, keeps the lines that are neither as that day's.
+    fn sort_out(contents: &[u8], day: Option<i64>) -> Self {
+        let mut lines = Self::default();
+        for line in contents.split(|b| *b == b'\n') {
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            let value = std::str::from_utf8(line)
+                .ok()
+                .and_then(|line| json::parse(line).ok());
+            let read = match &value {
+                Some(value) if value.get("rollup").is_some() => Rollup::from_json(value)
+                    .map(|rollup| lines.rollups.push(rollup))
+                    .is_some(),
+                Some(value) => SessionRecord::from_json(value)
+                    .map(|record| lines.sessions.push(record))
+                    .is_some(),
+                None => false,
+            };
+            if let (false, Some(day)) = (read, day) {
+                lines.unreadable.push((day, line.to_vec()));
+            }
+        }
+        lines
+    }
+
+    fn read(path: &Path) -> Self {
+        let day = path
+            .file_name()
+            .and_then(|name| shard_day(&name.to_string_lossy()));
+        fs::read(path)
+            .map(|contents| Self::sort_out(&contents, day))
+            .unwrap_or_default()
+    }
+
+    /// Every session, with rollups expanded into stand-ins for them.
+    fn records(self) -> Vec<SessionRecord> {
+        let mut records = self.sessions;
+        records.extend(self.rollups.iter().flat_map(Rollup::expand));
+        records
+    }
+
+    pub fn extend(&mut self, other: Self) {
+        self.sessions.extend(other.sessions);
+        self.rollups.extend(other.rollups);
+        self.unreadable.extend(other.unreadable);
+    }
+
+    /// How many sessions they hold, rolled up or not.
+    pub fn sessions(&self) -> usize {
+        let rolled: u64 = self.rollups.iter().map(|r| r.sessions).sum();
+        self.sessions.len() + rolled as usize
+    }
+
+    /// One rollup for each day, kind, project and task, the fullest where
+    /// copies differ, in place of the raw sessions it covers; then the raw
+    /// sessions left, each once.
+    fn merged(self) -> Self {
+        let Lines {
+            sessions,
+            mut rollups,
+            unreadable,
+        } = self;
+        rollups.sort_by(|a, b| {
+            a.key()
+                .cmp(&b.key())
+                .then_with(|| b.totals().cmp(&a.totals()))
+        });
+        rollups.dedup_by(|b, a| a.key() == b.key());
+        let sessions = sessions
+            .into_iter()
+            .filter(|record| !rollups.iter().any(|rollup| rollup.covers(record)))
+            .collect();
+        Lines {
+            sessions: union(sessions),
+            rollups,
+            unreadable,
+        }
+    }
+
+    /// The lines of each day.
+    fn by_day(self) -> BTreeMap<i64, Lines> {
+        let mut days: BTreeMap<i64, Lines> = BTreeMap::new();
+        for record in self.sessions {
+            days.entry(record.day()).or_default().sessions.push(record);
+        }
+        for rollup in self.rollups {
+            days.entry(rollup.day).or_default().rollups.push(rollup);
+        }
+        for (day, line) in self.unreadable {
+            days.entry(day).or_default().unreadable.push((day, line));
+        }
+        days
+    }
+}
+
+/// The sessions of one kind, project and task on one day, as one line in place
+/// of a line each. Loading gives back a record per session, so totals,
+/// streaks and achievements come out the same.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Rollup {
+    day: i64,
+    kind: PhaseKind,
+    project: Option<String>,
+    task: Option<String>,
+    sessions: u64,
+    completed: u64,
+    active_secs: u64,
+}
+
+impl Rollup {
+    /// What two copies of the same rollup share.
+    fn key(&self) -> (i64, &'static str, Option<&str>, Option<&str>) {
+        (
+            self.day,
+            self.kind.key(),
+            self.project.as_deref(),
+            self.task.as_deref(),
+        )
+    }
+
+    fn totals(&self) -> (u64, u64, u64) {
+        (self.sessions, self.completed, self.active_secs)
+    }
+
+    /// Whether `record` is one of the sessions this rolled up.
+    fn covers(&self, record: &SessionRecord) -> bool {
+        self.key()
+            == (
+                record.day(),
+                record.kind.key(),
+                record.project.as_deref(),
+                record.task.as_deref(),
+            )
+    }
+
+    /// Adds in `other`, of the same day, kind, project and task.
+    fn add(&mut self, other: &Self) {
+        self.sessions += other.sessions;
+        self.completed += other.completed;
+        self.active_secs += other.active_secs;
+    }
+
+    /// One rollup for each kind, project and task among `records`.
+    fn of(records: &[&SessionRecord]) -> Vec<Self> {
+        let mut rollups: Vec<Self> = Vec::new();
+        for record in records {
+            let key = (record.day(), record.kind, &record.project, &record.task);
+            let rollup = match rollups
+                .iter_mut()
+                .find(|r| (r.day, r.kind, &r.project, &r.task) == key)
+            {
+                Some(rollup) => rollup,
+                None => {
+                    rollups.push(Self {
+                        day: key.0,
+                        kind: record.kind,
+                        project: record.project.clone(),
+                        task: record.task.clone(),
+                        sessions: 0,
+                        completed: 0,
+                        active_secs: 0,
+                    });
+                    rollups.last_mut().expect("just pushed")
+                }
+            };
+            rollup.sessions += 1;
+            rollup.completed += u64::from(record.completed);
+            rollup.active_secs += stats::active_secs(record);
+        }
+        rollups
+    }
+
+    fn to_json(&self) -> Value {
+        Value::Object(vec![
+            ("rollup".to_string(), true.into()),
+            (
+                "day".to_string(),
+                wallclock::format_date(self.day).as_str().into(),
+            ),
+            ("kind".to_string(), self.kind.key().into()),
+            ("project".to_string(), self.project.as_deref().into()),
+            ("task".to_string(), self.task.as_deref().into()),
+            ("sessions".to_string(), self.sessions.into()),
+            ("completed".to_string(), self.completed.into()),
+            ("active".to_string(), self.active_secs.into()),
+        ])
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let text = |key| value.get(key).and_then(Value::as_str).map(str::to_string);
+        let rollup = Self {
+            day: wallclock::parse_date(value.get("day")?.as_str()?)?,
+            kind: phases::kind(value.get("kind")?.as_str().filter(|k| !k.is_empty())?),
+            project: text("project"),
+            task: text("task"),
+            sessions: value.get("sessions")?.as_u64()?,
+            completed: value.get("completed")?.as_u64()?,
+            active_secs: value.get("active")?.as_u64()?,
+        };
+        (rollup.completed <= rollup.sessions).then_some(rollup)
+    }
+
+    /// A record per session, the finished ones first, back to back from noon
+    /// UTC on the day, sharing the time evenly.
+    fn expand(&self) -> Vec<SessionRecord> {
+        let noon = (self.day * 86_400 + 12 * 3600).max(0) as u64;
+        let length = self.active_secs.checked_div(self.sessions).unwrap_or(0);
+        let mut start = noon;
+        (0..self.sessions)
+            .map(|i| {
+                // The first takes what doesn't divide evenly
+                let length = if i == 0 {
+                    self.active_secs - length * (self.sessions - 1)
+                } else {
+                    length
+                };
+                let record = SessionRecord {
+                    kind: self.kind,
+                    start,
+                    end: start + length,
+                    planned_secs: length,
+                    completed: i < self.completed,
+                    pauses: Vec::new(),
+                    snoozes: Vec::new(),
+                    project: self.project.clone(),
+                    task: self.task.clone(),
+                    estimate: None,
+                    rating: None,
+                    extended_secs: 0,
+                    distractions: Vec::new(),
+                    rested: None,
+                    utc_offset: Some(0),
+                    rolled_up: true,
+                };
+                start += length;
+                record
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CompactSummary {
+    pub days: usize,
+    pub sessions: usize,
+    pub lines: usize,
+}
+
+/// Rolls the sessions of every day before `before` into one line for each
+/// kind, project and task, leaving later days as they are. With `dry_run`,
+/// only counts what would be rolled up.
+pub fn compact(dir: &Path, before: i64, dry_run: bool) -> io::Result<CompactSummary> {
+    let _lock = lock(dir)?;
+    let mut summary = CompactSummary::default();
+    for (path, name) in shard_files(dir) {
+        if !is_shard_name(&name) || shard_day(&name).is_none_or(|day| day >= before) {
+            continue;
+        }
+        let mut lines = Lines::read(&path);
+        if lines.sessions.is_empty() {
+            continue;
+        }
+        let sessions = std::mem::take(&mut lines.sessions);
+        // Added to the day's rollups from an earlier run, if any
+        for new in Rollup::of(&sessions.iter().collect::<Vec<_>>()) {
+            match lines.rollups.iter_mut().find(|r| r.key() == new.key()) {
+                Some(rollup) => rollup.add(&new),
+                None => lines.rollups.push(new),
+            }
+        }
+        summary.days += 1;
+        summary.sessions += sessions.len();
+        summary.lines += lines.rollups.len();
+        if !dry_run {
+            write_day(&path, &lines)?;
+        }
+    }
+    Ok(summary)
+}
+
+/// The sessions in a day file, skipping lines that can't be read, even those
+/// that aren't UTF-8.
+fn read_records(path: &Path) -> Vec<SessionRecord> {
+    Lines::read(path).records()
+}
+
+/// Something wrong with a line of the history.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// Not JSON, or not a session: quarantined.
+    Corrupt,
+    /// Ends before it starts: quarantined.
+    Backwards,
+    /// Starts more than a day from now, from a clock set wrong: quarantined.
+    Future,
+    /// Pauses or snoozes outside the session: cut to fit.
+    Pauses,
+    /// In another day's file: moved.
+    Misfiled,
+    /// The same session twice: dropped.
+    Duplicate,
+    /// Starts before the previous session ended: quarantined.
+    Overlap,
+}
+
+impl Problem {
+    /// What `--fix` does about it.
+    pub fn fix(self) -> &'static str {
+        match self {
+            Problem::Corrupt | Problem::Backwards | Problem::Future | Problem::Overlap => {
+                "quarantined"
+            }
+            Problem::Pauses => "repaired",
+            Problem::Misfiled => "moved",
+            Problem::Duplicate => "dropped",
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Problem::Corrupt => "not a session",
+            Problem::Backwards => "ends before it starts",
+            Problem::Future => "starts in the future",
+            Problem::Pauses => "pauses outside the session",
+            Problem::Misfiled => "in another day's file",
+            Problem::Duplicate => "duplicate session",
+            Problem::Overlap => "overlaps the session before",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Finding {
+    /// The day file's name.
+    pub file: String,
+    /// 1-based.
+    pub line: usize,
+    pub problem: Problem,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CheckReport {
+    pub sessions: usize,
+    pub files: usize,
+    pub findings: Vec<Finding>,
+}
+
+/// Where `history check --fix` puts the lines it takes out, by the file they
+/// came from; out of the way of loading, but still there to look at.
+pub fn quarantine_dir(dir: &Path) -> PathBuf {
+    dir.join("quarantine")
+}
+
+/// Looks through every day file in `dir` for lines that can't be read and
+/// sessions that can't be right, such as two written at once by the daemon
+/// and the timer. With `fix`, rewrites the files it found problems in.
+pub fn check(dir: &Path, now: u64, fix: bool) -> io::Result<CheckReport> {
+    // From the first read to the last write, so nothing appended between is lost
+    let _lock = if fix { Some(lock(dir)?) } else { None };
+    let mut report = CheckReport::default();
+    // Sessions that pass the checks on their own, with where they came from
+    let mut sessions: Vec<(String, usize, SessionRecord)> = Vec::new();
+    let mut rollups: Vec<Rollup> = Vec::new();
+    let mut quarantined: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut touched: Vec<i64> = Vec::new();
+    for (path, name) in shard_files(dir) {
+        let Some(day) = shard_day(&name).filter(|_| is_shard_name(&name)) else {
+            continue;
+        };
+        report.files += 1;
+        // As bytes, so a line that isn't UTF-8 is one more corrupt line
+        let contents = fs::read(&path)?;
+        for (i, line) in contents.split(|b| *b == b'\n').enumerate() {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            let mut problems = Vec::new();
+            let lines = Lines::sort_out(line, None);
+            if lines.sessions.is_empty() && lines.rollups.is_empty() {
+                problems.push(Problem::Corrupt);
+                quarantined.push((name.clone(), line.to_vec()));
+            }
+            report.sessions += lines.sessions();
+            for rollup in lines.rollups {
+                if rollup.day != day {
+                    problems.push(Problem::Misfiled);
+                    touched.push(rollup.day);
+                }
+                rollups.push(rollup);
+            }
+            for mut record in lines.sessions {
+                if record.end < record.start {
+                    problems.push(Problem::Backwards);
+                    quarantined.push((name.clone(), line.to_vec()));
+                    continue;
+                }
+                if record.start > now + 86_400 {
+                    problems.push(Problem::Future);
+                    quarantined.push((name.clone(), line.to_vec()));
+                    continue;
+                }
+                let (start, end) = (record.start, record.end);
+                let fit = |list: &mut Vec<(u64, u64)>| {
+                    let before = list.clone();
+                    list.retain_mut(|(s, e)| {
+                        (*s, *e) = ((*s).max(start), (*e).min(end));
+                        s < e
+                    });
+                    *list != before
+                };
+                if fit(&mut record.pauses) | fit(&mut record.snoozes) {
+                    problems.push(Problem::Pauses);
+                }
+                if record.day() != day {
+                    problems.push(Problem::Misfiled);
+                    touched.push(record.day());
+                }
+                sessions.push((name.clone(), i + 1, record));
+            }
+            if !problems.is_empty() {
+                touched.push(day);
+            }
+            report
+                .findings
+                .extend(problems.into_iter().map(|problem| Finding {
+                    file: name.clone(),
+                    line: i + 1,
+                    problem,
+                }));
+        }
+    }
+
+    sessions.sort_by_key(|(_, _, r)| (r.start, r.end));
+    let mut kept: Vec<SessionRecord> = Vec::new();
+    for (file, line, record) in sessions {
+        let problem = match kept.last() {
+            Some(p) if p.kind == record.kind && p.start == record.start && p.end == record.end => {
+                Some(Problem::Duplicate)
+            }
+            Some(p) if record.start < p.end => Some(Problem::Overlap),
+            _ => None,
+        };
+        match problem {
+            Some(problem) => {
+                if problem == Problem::Overlap {
+                    quarantined.push((file.clone(), record.to_json().to_string().into_bytes()));
+                }
+                touched.push(record.day());
+                report.findings.push(Finding {
+                    file,
+                    line,
+                    problem,
+                });
+            }
+            None => kept.push(record),
+        }
+    }
+    report
+        .findings
+        .sort_by(|a, b| (&a.file, a.line).cmp(&(&b.file, b.line)));
+
+    if fix {
+        touched.sort_unstable();
+        touched.dedup();
+        let mut days = Lines {
+            sessions: kept,
+            rollups,
+            unreadable: Vec::new(),
+        }
+        .by_day();
+        for day in touched {
+            let lines = days.remove(&day).unwrap_or_default();
+            write_day(&shard_path(dir, day), &lines)?;
+        }
+        if !quarantined.is_empty() {
+            fs::create_dir_all(quarantine_dir(dir))?;
+        }
+        for (file, line) in quarantined {
+            let mut out = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(quarantine_dir(dir).join(file))?;
+            out.write_all(&line)?;
+            out.write_all(b"\n")?;
+        }
+    }
+    Ok(report)
+}
+
+/// Writes a whole shard, its sessions, its rollups and then the lines that are
+/// neither, through a temporary file so a crash never leaves it half written.
+fn write_day(path: &Path, lines: &Lines) -> io::Result<()> {
+    let mut contents: String = lines
+        .sessions
+        .iter()
+        .map(|r| format!("{}\n", r.to_json()))
+        .collect();
+    for rollup in &lines.rollups {
+        contents.push_str(&format!("{}\n", rollup.to_json()));
+    }
+    let mut contents = contents.into_bytes();
+    for (_, line) in &lines.unreadable {
+        contents.extend_from_slice(line);
+        contents.push(b'\n');
+    }
+    write_file(path, contents)
+}
+
+/// Writes `contents` through a temporary file, or removes the file when there
+/// are none.
+fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let contents = contents.as_ref();
+    if contents.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let tmp = path.with_extension("jsonl.tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(tmp, path)
+}
+
+fn shard_path(dir: &Path, day: i64) -> PathBuf {
+    dir.join(format!("{}.jsonl", wallclock::format_date(day)))
+}
+
+/// `.jsonl` files in `dir` with their file names.
+fn shard_files(dir: &Path) -> Vec<(PathBuf, String)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.to_string();
+            Some((path, name))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Day of a shard or of a sync-conflict copy of one, from the leading date in its name.
+fn shard_day(name: &str) -> Option<i64> {
+    wallclock::parse_date(name.get(..10)?)
+}
+
+fn is_shard_name(name: &str) -> bool {
+    name.len() == "YYYY-MM-DD.jsonl".len() && shard_day(name).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(start: u64) -> SessionRecord {
+        SessionRecord {
+            kind: PhaseKind::Focus,
+            start,
+            end: start + 1500,
+            planned_secs: 1500,
+            completed: true,
+            pauses: vec![(start + 100, start + 160)],
+            snoozes: vec![(start, start + 300)],
+            project: Some("thesis".to_string()),
+            task: None,
+            estimate: None,
+            rating: Some(4),
+            extended_secs: 300,
+            distractions: vec![("Slack".to_string(), 120)],
+            rested: None,
+            utc_offset: None,
+            rolled_up: false,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rusty_pomo_history_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn record_round_trips_through_json() {
+        let r = record(1_700_000_000);
+        assert_eq!(SessionRecord::from_json(&r.to_json()), Some(r));
+        let r = SessionRecord {
+            utc_offset: Some(-5 * 3600),
+            ..record(1_700_000_000)
+        };
+        assert_eq!(SessionRecord::from_json(&r.to_json()), Some(r));
+    }
+
+    #[test]
+    fn records_keep_the_day_they_were_recorded_on() {
+        // 23:30 UTC: the next day in Tokyo, still the same one in New York
+        let late = 1_700_000_000 - 1_700_000_000 % 86_400 + 23 * 3600 + 1800;
+        let utc_day = late as i64 / 86_400;
+        let at = |offset| SessionRecord {
+            utc_offset: Some(offset),
+            ..record(late)
+        };
+        assert_eq!(at(9 * 3600).day(), utc_day + 1);
+        assert_eq!(at(9 * 3600).seconds_of_day(), 8 * 3600 + 1800);
+        assert_eq!(at(-5 * 3600).day(), utc_day);
+        assert_eq!(record(late).day(), wallclock::local_day(late));
+    }
+
+    #[test]
+    fn skips_corrupt_lines() {
+        let good = record(10).to_json().to_string();
+        let contents = format!("{good}\nnot json\n{{\"kind\":\"nap\"}}\n{good}\n");
+        assert_eq!(parse_records(&contents).len(), 2);
+    }
+
+    #[test]
+    fn appends_into_day_shards_and_truncates() {
+        let dir = temp_dir("append");
+        let day = 24 * 3600;
+        let mut history = History::open(dir.clone());
+        history.append(record(10 * day)).unwrap();
+        history.append(record(12 * day)).unwrap();
+        history.append(record(12 * day + 3600)).unwrap();
+        assert_eq!(shard_files(&dir).len(), 2);
+        assert_eq!(History::open(dir.clone()).records.len(), 3);
+        history.truncate(1).unwrap();
+        assert_eq!(History::open(dir.clone()).records, vec![record(10 * day)]);
+        assert_eq!(shard_files(&dir).len(), 1);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn truncating_keeps_what_another_writer_appended() {
+        let dir = temp_dir("second_writer");
+        let start = 1_700_000_000;
+        let mut history = History::open(dir.clone());
+        history.append(record(start)).unwrap();
+        History::open(dir.clone())
+            .append(record(start + 1800))
+            .unwrap();
+        let path = shard_path(&dir, record(start).day());
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "not json").unwrap();
+        history.append(record(start + 3600)).unwrap();
+        history.truncate(1).unwrap();
+        let starts: Vec<_> = History::open(dir.clone())
+            .records
+            .iter()
+            .map(|r| r.start)
+            .collect();
+        assert_eq!(starts, vec![start, start + 1800]);
+        assert!(fs::read_to_string(path).unwrap().contains("not json\n"));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn rating_rewrites_only_that_record() {
+        let dir = temp_dir("rate");
+        let mut history = History::open(dir.clone());
+        history.append(record(1_700_000_000)).unwrap();
+        history.append(record(1_700_001_800)).unwrap();
+        // Appended by another timer after this one loaded
+        History::open(dir.clone())
+            .append(record(1_700_003_600))
+            .unwrap();
+        history.rate(1, 2).unwrap();
+        let ratings: Vec<_> = History::open(dir.clone())
+            .records
+            .iter()
+            .map(|r| r.rating)
+            .collect();
+        assert_eq!(ratings, vec![Some(4), Some(2), Some(4)]);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn keeps_one_project() {
+        let mut history = History::default();
+        history.append(record(10)).unwrap();
+        history
+            .append(SessionRecord {
+                project: None,
+                ..record(20)
+            })
+            .unwrap();
+        history.keep_project(None);
+        assert_eq!(history.records.len(), 2);
+        history.keep_project(Some("thesis"));
+        assert_eq!(history.records, vec![record(10)]);
+    }
+
+    #[test]
+    fn memory_store_keeps_records_for_the_run_only() {
+        let mut history = History::default();
+        history.append(record(10)).unwrap();
+        history.append(record(20)).unwrap();
+        history.truncate(1).unwrap();
+        assert_eq!(history.records, vec![record(10)]);
+        assert!(History::default().records.is_empty());
+    }
+
+    #[test]
+    fn union_is_order_independent() {
+        let a = vec![record(100), record(5000)];
+        let b = vec![record(5000), record(9000), record(100)];
+        let ab = union([a.clone(), b.clone()].concat());
+        let ba = union([b, a].concat());
+        assert_eq!(ab, ba);
+        assert_eq!(ab.len(), 3);
+        assert_eq!(union(ab.clone()), ab);
+        let unrated = SessionRecord {
+            rating: None,
+            ..record(100)
+        };
+        assert_eq!(union(vec![unrated.clone(), record(100)]), vec![record(100)]);
+        assert_eq!(union(vec![record(100), unrated]), vec![record(100)]);
+    }
+
+    #[test]
+    fn compacts_old_days_without_changing_their_totals() {
+        let dir = temp_dir("compact");
+        let old = 1_700_000_000;
+        let recent = old + 400 * 86_400;
+        let mut history = History::open(dir.clone());
+        let stopped = SessionRecord {
+            completed: false,
+            ..record(old + 3000)
+        };
+        let other = SessionRecord {
+            task: Some("notes".to_string()),
+            ..record(old + 6000)
+        };
+        for r in [record(old), stopped, other, record(recent)] {
+            history.append(r).unwrap();
+        }
+        let totals = |records: &[SessionRecord]| {
+            let mut totals = stats::Totals::default();
+            records.iter().for_each(|r| totals.add(r));
+            totals
+        };
+        let before = totals(&history.records);
+
+        let cutoff = wallclock::local_day(recent);
+        let dry = compact(&dir, cutoff, true).unwrap();
+        assert_eq!(History::open(dir.clone()).records, history.records);
+        let done = compact(&dir, cutoff, false).unwrap();
+        assert_eq!(dry, done);
+        assert_eq!(
+            done,
+            CompactSummary {
+                days: 1,
+                sessions: 3,
+                lines: 2
+            }
+        );
+        let shard = fs::read_to_string(shard_path(&dir, wallclock::local_day(old))).unwrap();
+        assert_eq!(shard.lines().count(), 2);
+
+        let reloaded = History::open(dir.clone()).records;
+        assert_eq!(reloaded.len(), 4);
+        assert_eq!(totals(&reloaded), before);
+        assert_eq!(reloaded.iter().filter(|r| r.rolled_up).count(), 3);
+        assert!(
+            reloaded
+                .iter()
+                .filter(|r| r.rolled_up)
+                .all(|r| r.day() == wallclock::local_day(old))
+        );
+        assert_eq!(reloaded.last(), history.records.last());
+        assert_eq!(
+            compact(&dir, cutoff, false).unwrap(),
+            CompactSummary::default()
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn merges_compacted_days_without_losing_or_doubling_sessions() {
+        let dir = temp_dir("merge_compacted");
+        let raw = temp_dir("merge_compacted_raw");
+        let old = 1_700_000_000;
+        let day = wallclock::local_day(old);
+        let in_project = |start, project: &str| SessionRecord {
+            project: Some(project.to_string()),
+            ..record(start)
+        };
+        // The same length in two projects: their stand-ins would be identical
+        let sessions = [in_project(old, "a"), in_project(old + 3600, "b")];
+        for history in [
+            &mut History::open(dir.clone()),
+            &mut History::open(raw.clone()),
+        ] {
+            for r in &sessions {
+                history.append(r.clone()).unwrap();
+            }
+        }
+        // Over 12 hours of one kind, which would spill past midnight UTC
+        let long = Rollup {
+            day,
+            kind: PhaseKind::LongBreak,
+            project: None,
+            task: None,
+            sessions: 1,
+            completed: 1,
+            active_secs: 13 * 3600,
+        };
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(shard_path(&dir, day))
+            .unwrap();
+        writeln!(file, "{}", long.to_json()).unwrap();
+        compact(&dir, day + 1, false).unwrap();
+        let lines = || {
+            let mut lines: Vec<_> = History::open(dir.clone())
+                .records
+                .iter()
+                .map(|r| r.to_json().to_string())
+                .collect();
+            lines.sort();
+            lines
+        };
+        let compacted = lines();
+        assert_eq!(compacted.len(), 3);
+
+        assert_eq!(add_records(&dir, Lines::default()).unwrap(), 0);
+        // The other machine's copy of the day is still raw
+        assert_eq!(
+            sync_merge(&dir, std::slice::from_ref(&raw)).unwrap().added,
+            0
+        );
+        assert_eq!(lines(), compacted);
+        assert_eq!(shard_files(&dir).len(), 1);
+        let _ = fs::remove_dir_all(dir);
+        let _ = fs::remove_dir_all(raw);
+    }
+
+    #[test]
+    fn checks_and_fixes_a_damaged_history() {
+        let dir = temp_dir("check");
+        let start = 1_700_000_000;
+        let day = wallclock::local_day(start);
+        let backwards = SessionRecord {
+            end: start + 1000,
+            ..record(start + 2000)
+        };
+        let mut pauses = record(start + 3000);
+        pauses.pauses = vec![(start + 4400, start + 4700)];
+        let lines = [
+            record(start).to_json().to_string(),
+            "{\"kind\":\"focus\",\"start\":".to_string(),
+            record(start).to_json().to_string(),
+            record(start + 600).to_json().to_string(),
+            backwards.to_json().to_string(),
+            pauses.to_json().to_string(),
+            record(start + 86_400).to_json().to_string(),
+        ];
+        // And a line that isn't UTF-8
+        let contents = [(lines.join("\n") + "\n").as_bytes(), b"\xff\xfe\n"].concat();
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(shard_path(&dir, day), &contents).unwrap();
+
+        let now = start + 2 * 86_400;
+        let report = check(&dir, now, false).unwrap();
+        let found: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.line, f.problem))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (2, Problem::Corrupt),
+                (3, Problem::Duplicate),
+                (4, Problem::Overlap),
+                (5, Problem::Backwards),
+                (6, Problem::Pauses),
+                (7, Problem::Misfiled),
+                (8, Problem::Corrupt),
+            ]
+        );
+        assert_eq!(report.sessions, 6);
+        assert_eq!(fs::read(shard_path(&dir, day)).unwrap(), contents);
+
+        assert_eq!(check(&dir, now, true).unwrap(), report);
+        let records = History::open(dir.clone()).records;
+        let starts: Vec<_> = records.iter().map(|r| r.start).collect();
+        assert_eq!(starts, [start, start + 3000, start + 86_400]);
+        assert_eq!(records[1].pauses, [(start + 4400, start + 4500)]);
+        let quarantine =
+            fs::read(quarantine_dir(&dir).join(shard_path(&dir, day).file_name().unwrap()))
+                .unwrap();
+        assert_eq!(quarantine.split(|b| *b == b'\n').count(), 5);
+        assert!(quarantine.windows(3).any(|w| w == b"\xff\xfe\n"));
+        assert!(check(&dir, now, false).unwrap().findings.is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn sync_merge_folds_conflict_copies_and_other_dirs() {
+        let dir = temp_dir("merge_local");
+        let other = temp_dir("merge_other");
+        let mut local = History::open(dir.clone());
+        let start = 1_700_000_000;
+        local.append(record(start)).unwrap();
+        let mut remote = History::open(other.clone());
+        remote.append(record(start)).unwrap();
+        remote.append(record(start + 2000)).unwrap();
+
+        let name = format!(
+            "{}",
+            shard_path(&dir, wallclock::local_day(start)).display()
+        );
+        // Lines `history check` has yet to quarantine stay as they are
+        let mut file = OpenOptions::new().append(true).open(&name).unwrap();
+        file.write_all(b"not json\n\xff\xfe\n").unwrap();
+        let copy = name.replace(".jsonl", " (conflicted copy).jsonl");
+        let conflicted = format!(
+            "<<<<<<< HEAD\n{}\n=======\n{}\n>>>>>>> theirs\n",
+            record(start + 4000).to_json(),
+            record(start + 6000).to_json()
+        );
+        fs::write(&copy, conflicted).unwrap();
+
+        let summary = sync_merge(&dir, std::slice::from_ref(&other)).unwrap();
+        assert_eq!(summary.added, 3);
+        assert_eq!(summary.conflict_copies, 1);
+        let merged = History::open(dir.clone()).records;
+        let starts: Vec<_> = merged.iter().map(|r| r.start).collect();
+        assert_eq!(
+            starts,
+            vec![start, start + 2000, start + 4000, start + 6000]
+        );
+        let shard = fs::read(&name).unwrap();
+        assert!(shard.ends_with(b"not json\n\xff\xfe\n"));
+        assert!(!String::from_utf8_lossy(&shard).contains("<<<<<<<"));
+
+        assert_eq!(
+            sync_merge(&dir, std::slice::from_ref(&other))
+                .unwrap()
+                .added,
+            0
+        );
+        let _ = fs::remove_dir_all(dir);
+        let _ = fs::remove_dir_all(other);
+    }
+}
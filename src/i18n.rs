@@ -0,0 +1,225 @@
+//! User-facing strings, looked up by key in per-locale TOML bundles that are
+//! embedded in the binary. Keys missing from a bundle fall back to English.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+use toml_edit::{DocumentMut, Item};
+
+use crate::state::PhaseKind;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    #[default]
+    En,
+    Es,
+    De,
+    Fr,
+    Ja,
+}
+
+impl Lang {
+    fn bundle(self) -> &'static str {
+        match self {
+            Lang::En => include_str!("../locales/en.toml"),
+            Lang::Es => include_str!("../locales/es.toml"),
+            Lang::De => include_str!("../locales/de.toml"),
+            Lang::Fr => include_str!("../locales/fr.toml"),
+            Lang::Ja => include_str!("../locales/ja.toml"),
+        }
+    }
+
+    /// The language of a POSIX locale name such as `es_ES.UTF-8`.
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let code = locale.split(['_', '.', '@', '-']).next()?;
+        Lang::from_str(code, true).ok()
+    }
+
+    /// The first of `LC_ALL`, `LC_MESSAGES` and `LANG` that is set, like gettext.
+    pub fn from_env() -> Option<Self> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::from_locale(&value))
+    }
+
+    fn is_singular(self, n: u64) -> bool {
+        match self {
+            Lang::Fr => n <= 1,
+            _ => n == 1,
+        }
+    }
+}
+
+pub struct Catalog {
+    lang: Lang,
+    messages: HashMap<String, String>,
+}
+
+impl Catalog {
+    pub fn new(lang: Lang) -> Self {
+        let mut messages = parse_bundle(Lang::En.bundle());
+        if lang != Lang::En {
+            messages.extend(parse_bundle(lang.bundle()));
+        }
+        Self { lang, messages }
+    }
+
+    /// The message for `key`, or the key itself when no bundle has it.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.messages.get(key).map_or(key, String::as_str)
+    }
+
+    /// The message with each `{name}` placeholder replaced.
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        args.iter()
+            .fold(self.get(key).to_string(), |message, (name, value)| {
+                message.replace(&format!("{{{name}}}"), value)
+            })
+    }
+
+    /// `key_one` or `key_other` depending on `n`, with `{n}` filled in.
+    pub fn plural(&self, key: &str, n: u64) -> String {
+        let form = if self.lang.is_singular(n) {
+            "one"
+        } else {
+            "other"
+        };
+        self.format(&format!("{key}_{form}"), &[("n", &n.to_string())])
+    }
+}
+
+/// Flattens `[section] key = "..."` into `section.key` entries.
+fn parse_bundle(source: &str) -> HashMap<String, String> {
+    let doc: DocumentMut = source.parse().expect("locale bundles are valid TOML");
+    let mut messages = HashMap::new();
+    for (section, item) in doc.iter() {
+        let Item::Table(table) = item else {
+            continue;
+        };
+        for (key, value) in table.iter() {
+            if let Some(text) = value.as_str() {
+                messages.insert(format!("{section}.{key}"), text.to_string());
+            }
+        }
+    }
+    messages
+}
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// Selects the language for the rest of the run; only the first call has an effect.
+pub fn init(lang: Lang) {
+    let _ = CATALOG.set(Catalog::new(lang));
+}
+
+pub fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(|| Catalog::new(Lang::En))
+}
+
+pub fn t(key: &'static str) -> &'static str {
+    catalog().get(key)
+}
+
+pub fn tf(key: &str, args: &[(&str, &str)]) -> String {
+    catalog().format(key, args)
+}
+
+/// Title-case phase name, for headers and notifications.
+pub fn phase_title(kind: PhaseKind) -> &'static str {
+    match kind {
+        PhaseKind::WarmUp => t("phase.warm_up"),
+        PhaseKind::Focus => t("phase.focus"),
+        PhaseKind::ShortBreak => t("phase.short_break"),
+        PhaseKind::LongBreak => t("phase.long_break"),
+        PhaseKind::CycleReview => t("phase.cycle_review"),
+        PhaseKind::Lunch => t("phase.lunch"),
+        PhaseKind::Custom(name) => name,
+    }
+}
+
+/// Phase name as it reads inside a sentence.
+pub fn phase_inline(kind: PhaseKind) -> &'static str {
+    match kind {
+        PhaseKind::WarmUp => t("phase_inline.warm_up"),
+        PhaseKind::Focus => t("phase_inline.focus"),
+        PhaseKind::ShortBreak => t("phase_inline.short_break"),
+        PhaseKind::LongBreak => t("phase_inline.long_break"),
+        PhaseKind::CycleReview => t("phase_inline.cycle_review"),
+        PhaseKind::Lunch => t("phase_inline.lunch"),
+        PhaseKind::Custom(name) => name,
+    }
+}
+
+/// A meeting's title, or "Busy" for one without.
+pub fn meeting_name(summary: &str) -> &str {
+    if summary.trim().is_empty() {
+        t("ui.busy")
+    } else {
+        summary
+    }
+}
+
+/// "2 cycles and 8 focus sessions completed."
+pub fn done(cycles: u64, focus_sessions: u64) -> String {
+    let catalog = catalog();
+    tf(
+        "done.summary",
+        &[
+            ("cycles", &catalog.plural("done.cycles", cycles)),
+            ("sessions", &catalog.plural("done.sessions", focus_sessions)),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_bundle_defines_every_english_key() {
+        let english = parse_bundle(Lang::En.bundle());
+        for lang in Lang::value_variants() {
+            let bundle = parse_bundle(lang.bundle());
+            let mut missing: Vec<_> = english
+                .keys()
+                .filter(|k| !bundle.contains_key(*k))
+                .collect();
+            missing.sort();
+            assert!(missing.is_empty(), "{lang:?} is missing {missing:?}");
+            for (key, text) in &english {
+                for placeholder in ["{n}", "{phase}", "{duration}", "{volume}", "{task}"] {
+                    assert_eq!(
+                        text.contains(placeholder),
+                        bundle[key].contains(placeholder),
+                        "{lang:?} {key} and English disagree on {placeholder}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn formats_and_pluralizes() {
+        let es = Catalog::new(Lang::Es);
+        assert_eq!(es.get("ui.quit"), "salir");
+        assert_eq!(es.format("ui.volume", &[("volume", "40")]), "volumen 40%");
+        assert_eq!(es.plural("duration.minutes", 1), "1 minuto");
+        assert_eq!(es.plural("duration.minutes", 0), "0 minutos");
+        let fr = Catalog::new(Lang::Fr);
+        assert_eq!(fr.plural("duration.seconds", 0), "0 seconde");
+        assert_eq!(fr.get("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn reads_language_from_locale_names() {
+        assert_eq!(Lang::from_locale("es_ES.UTF-8"), Some(Lang::Es));
+        assert_eq!(Lang::from_locale("de"), Some(Lang::De));
+        assert_eq!(Lang::from_locale("ja_JP"), Some(Lang::Ja));
+        assert_eq!(Lang::from_locale("C.UTF-8"), None);
+        assert_eq!(Lang::from_locale("pt_BR"), None);
+    }
+}
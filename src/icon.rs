@@ -0,0 +1,49 @@
+//! The app icon, built into the binary so notifications have one wherever it
+//! runs from. Notification servers want a file, so it's written to the cache
+//! directory the first time it's needed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::paths;
+
+const PNG: &[u8] = include_bytes!("../assets/rusty_pomo.png");
+
+/// The icon file, or `None` if there's nowhere to write it.
+pub fn path() -> Option<&'static Path> {
+    static PATH: OnceLock<Option<PathBuf>> = OnceLock::new();
+    PATH.get_or_init(|| write_into(&paths::cache_dir()?))
+        .as_deref()
+}
+
+/// Writes the icon unless an identical copy is already there, e.g. from an
+/// earlier run. A copy left by another version is replaced.
+fn write_into(dir: &Path) -> Option<PathBuf> {
+    let path = dir.join("rusty_pomo.png");
+    if fs::read(&path).ok().as_deref() != Some(PNG) {
+        fs::create_dir_all(dir).ok()?;
+        // Renamed into place so another timer starting up never reads half an icon
+        let partial = dir.join(format!("rusty_pomo.png.{}", std::process::id()));
+        fs::write(&partial, PNG).ok()?;
+        fs::rename(&partial, &path).ok()?;
+    }
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_the_icon_and_replaces_stale_copies() {
+        let dir = std::env::temp_dir().join(format!("rusty_pomo_icon_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = write_into(&dir).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), PNG);
+        fs::write(&path, b"old").unwrap();
+        assert_eq!(write_into(&dir), Some(path.clone()));
+        assert_eq!(fs::read(&path).unwrap(), PNG);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
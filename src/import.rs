@@ -0,0 +1,283 @@
+use std::fs;
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::history::{History, SessionRecord};
+use crate::state::PhaseKind;
+use crate::wallclock;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    Csv,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub duplicates: usize,
+    /// CSV line numbers of rows that overlap an existing session without matching it.
+    pub conflicts: Vec<usize>,
+}
+
+pub fn run(
+    format: ImportFormat,
+    file: &Path,
+    history: &mut History,
+    project: Option<&str>,
+) -> Result<ImportSummary, String> {
+    let contents =
+        fs::read_to_string(file).map_err(|e| format!("cannot read {}: {e}", file.display()))?;
+    let rows = match format {
+        ImportFormat::Csv => parse_sessions(&contents, project)?,
+    };
+    merge(history, rows).map_err(|e| format!("cannot write history: {e}"))
+}
+
+/// Appends rows that don't collide with existing sessions. Exact matches on
+/// kind/start/end are duplicates; any other overlap is a conflict and skipped.
+pub fn merge(
+    history: &mut History,
+    rows: Vec<(usize, SessionRecord)>,
+) -> std::io::Result<ImportSummary> {
+    let mut summary = ImportSummary::default();
+    for (line, record) in rows {
+        let duplicate = history
+            .records
+            .iter()
+            .any(|r| r.kind == record.kind && r.start == record.start && r.end == record.end);
+        if duplicate {
+            summary.duplicates += 1;
+        } else if history
+            .records
+            .iter()
+            .any(|r| r.start < record.end && record.start < r.end)
+        {
+            summary.conflicts.push(line);
+        } else {
+            history.append(record)?;
+            summary.imported += 1;
+        }
+    }
+    Ok(summary)
+}
+
+/// Parses a CSV export with a header row. Recognised columns: `start` (required),
+/// `end` or `minutes` (one required), `kind`, `completed`, `project`, `task`.
+pub fn parse_sessions(
+    contents: &str,
+    default_project: Option<&str>,
+) -> Result<Vec<(usize, SessionRecord)>, String> {
+    let mut rows = parse_csv(contents)?.into_iter();
+    let Some((_, header)) = rows.next() else {
+        return Ok(Vec::new());
+    };
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let start_col = column("start").ok_or("missing `start` column")?;
+    let end_col = column("end");
+    let minutes_col = column("minutes");
+    if end_col.is_none() && minutes_col.is_none() {
+        return Err("missing `end` or `minutes` column".to_string());
+    }
+    let (kind_col, completed_col) = (column("kind"), column("completed"));
+    let (project_col, task_col) = (column("project"), column("task"));
+
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    for (line, fields) in rows {
+        if fields.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+        let field = |col: Option<usize>| {
+            col.and_then(|c| fields.get(c))
+                .map(|f| f.trim())
+                .filter(|f| !f.is_empty())
+        };
+        let parsed = (|| {
+            let start_text = field(Some(start_col)).ok_or("missing start")?;
+            let start = wallclock::parse_datetime(start_text)
+                .ok_or(format!("invalid start `{start_text}`"))?;
+            let end = match (field(end_col), field(minutes_col)) {
+                (Some(end), _) => {
+                    wallclock::parse_datetime(end).ok_or(format!("invalid end `{end}`"))?
+                }
+                (None, Some(minutes)) => {
+                    let minutes: f64 = minutes
+                        .parse()
+                        .map_err(|_| format!("invalid minutes `{minutes}`"))?;
+                    start + (minutes * 60.0).round() as u64
+                }
+                (None, None) => return Err("missing end".to_string()),
+            };
+            if end <= start {
+                return Err("end is not after start".to_string());
+            }
+            let kind = match field(kind_col) {
+                Some(kind) => parse_kind(kind).ok_or(format!("unknown kind `{kind}`"))?,
+                None => PhaseKind::Focus,
+            };
+            let completed = match field(completed_col) {
+                Some(value) => parse_bool(value).ok_or(format!("invalid completed `{value}`"))?,
+                None => true,
+            };
+            Ok(SessionRecord {
+                kind,
+                start,
+                end,
+                planned_secs: end - start,
+                completed,
+                pauses: Vec::new(),
+                snoozes: Vec::new(),
+                project: field(project_col).or(default_project).map(str::to_string),
+                task: field(task_col).map(str::to_string),
+                estimate: None,
+                rating: None,
+                extended_secs: 0,
+                distractions: Vec::new(),
+                rested: None,
+                utc_offset: None,
+                rolled_up: false,
+            })
+        })();
+        match parsed {
+            Ok(record) => records.push((line, record)),
+            Err(e) => errors.push(format!("line {line}: {e}")),
+        }
+    }
+    if errors.is_empty() {
+        Ok(records)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+fn parse_kind(value: &str) -> Option<PhaseKind> {
+    match value.to_lowercase().replace([' ', '-'], "_").as_str() {
+        "focus" | "work" | "pomodoro" => Some(PhaseKind::Focus),
+        "short_break" | "break" | "short" => Some(PhaseKind::ShortBreak),
+        "long_break" | "long" => Some(PhaseKind::LongBreak),
+        _ => None,
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Splits CSV text into records (RFC 4180 quoting), each tagged with the line it starts on.
+pub fn parse_csv(contents: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let mut rows = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = contents
+        .strip_prefix('\u{feff}')
+        .unwrap_or(contents)
+        .chars()
+        .peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) => {
+                fields.push(std::mem::take(&mut field));
+                rows.push((row_line, std::mem::take(&mut fields)));
+                line += 1;
+                row_line = line;
+            }
+            ('\n', true) => {
+                field.push(c);
+                line += 1;
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(format!("line {row_line}: unterminated quoted field"));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        rows.push((row_line, fields));
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quoted_csv() {
+        let rows = parse_csv("a,b\r\n\"x, y\",\"say \"\"hi\"\"\"\n\"multi\nline\",z").unwrap();
+        assert_eq!(rows[0], (1, vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(rows[1].1, vec!["x, y", "say \"hi\""]);
+        assert_eq!(
+            rows[2],
+            (3, vec!["multi\nline".to_string(), "z".to_string()])
+        );
+        assert!(parse_csv("\"open").is_err());
+    }
+
+    #[test]
+    fn parses_sessions_with_defaults() {
+        let csv = "Start,Minutes,Kind,Task\n\
+                   2023-11-14T22:13:20Z,25,Work,Write intro\n\
+                   \n\
+                   1700001800,5,short break,\n";
+        let rows = parse_sessions(csv, Some("thesis")).unwrap();
+        assert_eq!(rows.len(), 2);
+        let (line, first) = &rows[0];
+        assert_eq!(*line, 2);
+        assert_eq!(first.start, 1_700_000_000);
+        assert_eq!(first.end, 1_700_001_500);
+        assert_eq!(first.kind, PhaseKind::Focus);
+        assert_eq!(first.project.as_deref(), Some("thesis"));
+        assert_eq!(first.task.as_deref(), Some("Write intro"));
+        assert_eq!(rows[1].1.kind, PhaseKind::ShortBreak);
+        assert_eq!(rows[1].1.task, None);
+    }
+
+    #[test]
+    fn reports_bad_rows_with_line_numbers() {
+        assert!(parse_sessions("kind\nfocus\n", None).is_err());
+        let err = parse_sessions("start,end\nsoon,1700000100\n1700000200,1700000100\n", None)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "line 2: invalid start `soon`\nline 3: end is not after start"
+        );
+    }
+
+    #[test]
+    fn merge_skips_duplicates_and_conflicts() {
+        let mut history = History::default();
+        let rows = parse_sessions(
+            "start,end\n1000,2500\n1000,2500\n2000,2600\n3000,3300\n",
+            None,
+        )
+        .unwrap();
+        let summary = merge(&mut history, rows).unwrap();
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 2,
+                duplicates: 1,
+                conflicts: vec![4],
+            }
+        );
+        assert_eq!(history.records.len(), 2);
+    }
+}
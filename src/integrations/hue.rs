@@ -0,0 +1,263 @@
+//! Philips Hue via the bridge's local v1 API: recall a scene when a phase starts.
+
+use std::collections::BTreeSet;
+use std::io;
+use std::net::UdpSocket;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::http_request;
+use crate::config::HueConfig;
+use crate::doctor::Probe;
+use crate::events::{PhaseEvent, Subscriber};
+use crate::json::{self, Value};
+use crate::state::PhaseKind;
+
+const SSDP_ADDR: &str = "239.255.255.250:1900";
+const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
+const PAIR_TIMEOUT: Duration = Duration::from_secs(30);
+const PAIR_RETRY: Duration = Duration::from_secs(2);
+/// Error type the bridge returns until its link button has been pressed.
+const LINK_BUTTON_NOT_PRESSED: u64 = 101;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hue {
+    bridge: String,
+    username: String,
+    group: String,
+    focus_scene: Option<String>,
+    break_scene: Option<String>,
+}
+
+impl Hue {
+    /// `None` until the bridge is paired and at least one scene is configured.
+    pub fn from_config(config: &HueConfig) -> Option<Self> {
+        if config.focus_scene.is_none() && config.break_scene.is_none() {
+            return None;
+        }
+        Some(Self {
+            bridge: config.bridge.clone()?,
+            username: config.username.clone()?,
+            group: config.group.clone().unwrap_or_else(|| "0".to_string()),
+            focus_scene: config.focus_scene.clone(),
+            break_scene: config.break_scene.clone(),
+        })
+    }
+
+    fn scene_for(&self, kind: PhaseKind) -> Option<&str> {
+        match kind {
+            PhaseKind::WarmUp | PhaseKind::Focus => self.focus_scene.as_deref(),
+            PhaseKind::ShortBreak
+            | PhaseKind::LongBreak
+            | PhaseKind::CycleReview
+            | PhaseKind::Lunch
+            | PhaseKind::Custom(_) => self.break_scene.as_deref(),
+        }
+    }
+
+    /// Recalls the phase's scene on a background thread; failures are ignored
+    /// like notification errors.
+    pub fn set_phase(&self, kind: PhaseKind) {
+        let Some(scene) = self.scene_for(kind) else {
+            return;
+        };
+        let bridge = self.bridge.clone();
+        let path = format!("/api/{}/groups/{}/action", self.username, self.group);
+        let body = Value::Object(vec![("scene".to_string(), scene.into())]).to_string();
+        thread::spawn(move || {
+            if let Err(e) = http_request(&bridge, "PUT", &path, &body) {
+                tracing::warn!(error = %e, bridge, "Hue scene not recalled");
+            }
+        });
+    }
+}
+
+impl Subscriber for Hue {
+    fn on_event(&mut self, event: &PhaseEvent) {
+        if let Some(kind) = event.phase() {
+            self.set_phase(kind);
+        }
+    }
+}
+
+/// Whether the saved credentials still work and the configured scenes exist, for `doctor`.
+pub fn probe(config: &HueConfig) -> Probe {
+    let (Some(bridge), Some(username)) = (&config.bridge, &config.username) else {
+        return Probe::Off("not paired (rusty_pomo hue pair)".to_string());
+    };
+    let scenes = match scenes(bridge, username) {
+        Ok(scenes) => scenes,
+        Err(e) => return Probe::Fail(format!("{e}; pair again with `rusty_pomo hue pair`")),
+    };
+    let missing: Vec<_> = [&config.focus_scene, &config.break_scene]
+        .into_iter()
+        .flatten()
+        .filter(|id| !scenes.iter().any(|(known, _)| known == *id))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return Probe::Warn(format!(
+            "scenes {} are not on the bridge; list them with `rusty_pomo hue scenes`",
+            missing.join(", ")
+        ));
+    }
+    if config.focus_scene.is_none() && config.break_scene.is_none() {
+        return Probe::Warn(format!(
+            "paired with {bridge}, but no focus_scene or break_scene is set"
+        ));
+    }
+    Probe::Ok(format!("paired with {bridge}"))
+}
+
+/// Finds bridges on the local network with an SSDP search.
+pub fn discover() -> io::Result<Vec<String>> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_read_timeout(Some(DISCOVERY_TIMEOUT))?;
+    let search = "M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: ssdp:all\r\n\r\n";
+    socket.send_to(search.as_bytes(), SSDP_ADDR)?;
+    let mut bridges = BTreeSet::new();
+    let mut buf = [0; 2048];
+    while let Ok((len, _)) = socket.recv_from(&mut buf) {
+        if let Some(bridge) = bridge_from_ssdp(&String::from_utf8_lossy(&buf[..len])) {
+            bridges.insert(bridge);
+        }
+    }
+    Ok(bridges.into_iter().collect())
+}
+
+/// The bridge address advertised in an SSDP response, if it came from a Hue bridge.
+fn bridge_from_ssdp(response: &str) -> Option<String> {
+    let mut is_hue = false;
+    let mut location = None;
+    for line in response.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "hue-bridgeid" => is_hue = true,
+            "server" if value.contains("IpBridge") => is_hue = true,
+            "location" => location = Some(value.trim()),
+            _ => {}
+        }
+    }
+    let rest = location?.strip_prefix("http://")?;
+    let host = rest.split('/').next()?;
+    is_hue.then(|| host.strip_suffix(":80").unwrap_or(host).to_string())
+}
+
+/// Registers with the bridge, retrying while the user presses its link button,
+/// and returns the username (API token) to store.
+pub fn pair(bridge: &str) -> Result<String, String> {
+    let body = Value::Object(vec![("devicetype".to_string(), "rusty_pomo#cli".into())]).to_string();
+    let deadline = Instant::now() + PAIR_TIMEOUT;
+    loop {
+        let (_, response) = http_request(bridge, "POST", "/api", &body)
+            .map_err(|e| format!("cannot reach the bridge at {bridge}: {e}"))?;
+        if let Some(username) = parse_pair_response(&response)? {
+            return Ok(username);
+        }
+        if Instant::now() >= deadline {
+            return Err("timed out waiting for the link button".to_string());
+        }
+        thread::sleep(PAIR_RETRY);
+    }
+}
+
+/// `Ok(None)` while the link button hasn't been pressed yet.
+fn parse_pair_response(response: &str) -> Result<Option<String>, String> {
+    let value = json::parse(response).map_err(|e| format!("unexpected bridge response: {e}"))?;
+    let first = value
+        .as_array()
+        .and_then(|items| items.first())
+        .ok_or("unexpected bridge response")?;
+    if let Some(username) = first
+        .get("success")
+        .and_then(|s| s.get("username"))
+        .and_then(Value::as_str)
+    {
+        return Ok(Some(username.to_string()));
+    }
+    match first
+        .get("error")
+        .and_then(|e| e.get("type"))
+        .and_then(Value::as_u64)
+    {
+        Some(LINK_BUTTON_NOT_PRESSED) => Ok(None),
+        _ => Err(bridge_error(&value)),
+    }
+}
+
+/// The description of the first error in a bridge response.
+fn bridge_error(value: &Value) -> String {
+    value
+        .as_array()
+        .and_then(|items| items.first())
+        .and_then(|item| item.get("error"))
+        .and_then(|e| e.get("description"))
+        .and_then(Value::as_str)
+        .unwrap_or("unexpected bridge response")
+        .to_string()
+}
+
+/// Scene ids and names known to the bridge, for filling in `focus_scene`/`break_scene`.
+pub fn scenes(bridge: &str, username: &str) -> Result<Vec<(String, String)>, String> {
+    let (_, response) = http_request(bridge, "GET", &format!("/api/{username}/scenes"), "")
+        .map_err(|e| format!("cannot reach the bridge at {bridge}: {e}"))?;
+    match json::parse(&response).map_err(|e| format!("unexpected bridge response: {e}"))? {
+        Value::Object(scenes) => Ok(scenes
+            .into_iter()
+            .map(|(id, scene)| {
+                let name = scene.get("name").and_then(Value::as_str).unwrap_or("");
+                (id, name.to_string())
+            })
+            .collect()),
+        other => Err(bridge_error(&other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needs_credentials_and_a_scene() {
+        let mut config = HueConfig {
+            bridge: Some("10.0.0.2".to_string()),
+            username: Some("u".to_string()),
+            ..HueConfig::default()
+        };
+        assert_eq!(Hue::from_config(&config), None);
+        config.focus_scene = Some("abc".to_string());
+        let hue = Hue::from_config(&config).unwrap();
+        assert_eq!(hue.group, "0");
+        assert_eq!(hue.scene_for(PhaseKind::Focus), Some("abc"));
+        assert_eq!(hue.scene_for(PhaseKind::LongBreak), None);
+    }
+
+    #[test]
+    fn finds_bridges_in_ssdp_responses() {
+        let bridge = "HTTP/1.1 200 OK\r\nLOCATION: http://192.168.1.20:80/description.xml\r\nSERVER: Hue/1.0 UPnP/1.0 IpBridge/1.60.0\r\nhue-bridgeid: 001788FFFE000000\r\n\r\n";
+        assert_eq!(bridge_from_ssdp(bridge), Some("192.168.1.20".to_string()));
+        let tv = "HTTP/1.1 200 OK\r\nLOCATION: http://192.168.1.30:8008/dd.xml\r\nSERVER: Linux UPnP/1.0\r\n\r\n";
+        assert_eq!(bridge_from_ssdp(tv), None);
+    }
+
+    #[test]
+    fn parses_pairing_responses() {
+        assert_eq!(
+            parse_pair_response(r#"[{"success":{"username":"s3cret"}}]"#),
+            Ok(Some("s3cret".to_string()))
+        );
+        assert_eq!(
+            parse_pair_response(
+                r#"[{"error":{"type":101,"address":"","description":"link button not pressed"}}]"#
+            ),
+            Ok(None)
+        );
+        assert_eq!(
+            parse_pair_response(r#"[{"error":{"type":7,"description":"invalid value"}}]"#),
+            Err("invalid value".to_string())
+        );
+        assert_eq!(bridge_error(&Value::Null), "unexpected bridge response");
+    }
+}
@@ -0,0 +1,292 @@
+//! Music playback control at phase transitions, through whatever media API the
+//! platform offers: MPRIS on Linux (via `dbus-send`), Spotify/Music on macOS.
+
+use std::process::Command;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use clap::ValueEnum;
+
+use crate::events::{PhaseEvent, Subscriber};
+use crate::state::PhaseKind;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MusicMode {
+    /// Pause music while focusing and resume it for breaks
+    PauseOnFocus,
+    /// Play music while focusing and pause it for breaks
+    PlayOnFocus,
+    /// Leave music alone
+    Off,
+}
+
+/// A platform media API.
+pub trait MediaControl: Send {
+    /// Pauses whatever is playing; `false` when nothing was.
+    fn pause(&mut self) -> bool;
+    /// Resumes what `pause` stopped, or starts the default player.
+    fn play(&mut self);
+}
+
+/// Applies the music mode to phase transitions on a worker thread, so slow
+/// player IPC never blocks the UI.
+#[derive(Debug)]
+pub struct Music {
+    phases: Sender<PhaseKind>,
+}
+
+impl Music {
+    /// `None` when the mode is off or the platform has no supported player API.
+    pub fn spawn(mode: MusicMode) -> Option<Self> {
+        if mode == MusicMode::Off {
+            return None;
+        }
+        let Some(mut player) = platform_player() else {
+            tracing::warn!("no supported music player on this platform");
+            return None;
+        };
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut policy = Policy::new(mode);
+            for kind in rx {
+                policy.transition(kind, player.as_mut());
+            }
+        });
+        Some(Self { phases: tx })
+    }
+}
+
+impl Subscriber for Music {
+    fn on_event(&mut self, event: &PhaseEvent) {
+        if let Some(kind) = event.phase() {
+            let _ = self.phases.send(kind);
+        }
+    }
+}
+
+struct Policy {
+    mode: MusicMode,
+    /// Whether music is paused because of us, so breaks only resume what focus stopped.
+    paused_by_us: bool,
+}
+
+impl Policy {
+    fn new(mode: MusicMode) -> Self {
+        Self {
+            mode,
+            paused_by_us: false,
+        }
+    }
+
+    fn transition(&mut self, kind: PhaseKind, player: &mut dyn MediaControl) {
+        let focus = kind == PhaseKind::Focus;
+        match self.mode {
+            MusicMode::PauseOnFocus if focus => self.paused_by_us |= player.pause(),
+            MusicMode::PauseOnFocus if self.paused_by_us => {
+                player.play();
+                self.paused_by_us = false;
+            }
+            MusicMode::PlayOnFocus if focus => player.play(),
+            MusicMode::PlayOnFocus => {
+                player.pause();
+            }
+            MusicMode::PauseOnFocus | MusicMode::Off => {}
+        }
+    }
+}
+
+fn platform_player() -> Option<Box<dyn MediaControl>> {
+    if cfg!(target_os = "macos") {
+        Some(Box::new(AppleScript))
+    } else if cfg!(all(unix, not(target_os = "macos"))) {
+        Some(Box::new(Mpris::default()))
+    } else {
+        None
+    }
+}
+
+/// MPRIS players on the session bus, driven through `dbus-send`.
+#[derive(Default)]
+struct Mpris {
+    paused: Vec<String>,
+}
+
+impl Mpris {
+    fn dbus(args: &[&str]) -> Option<String> {
+        let output = Command::new("dbus-send")
+            .arg("--session")
+            .arg("--print-reply")
+            .args(args)
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    fn players() -> Vec<String> {
+        Self::dbus(&[
+            "--dest=org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus.ListNames",
+        ])
+        .map(|reply| mpris_players(&reply))
+        .unwrap_or_default()
+    }
+
+    fn call(player: &str, method: &str) {
+        Self::dbus(&[
+            &format!("--dest={player}"),
+            "/org/mpris/MediaPlayer2",
+            &format!("org.mpris.MediaPlayer2.Player.{method}"),
+        ]);
+    }
+
+    fn is_playing(player: &str) -> bool {
+        Self::dbus(&[
+            &format!("--dest={player}"),
+            "/org/mpris/MediaPlayer2",
+            "org.freedesktop.DBus.Properties.Get",
+            "string:org.mpris.MediaPlayer2.Player",
+            "string:PlaybackStatus",
+        ])
+        .is_some_and(|reply| reply.contains("\"Playing\""))
+    }
+}
+
+impl MediaControl for Mpris {
+    fn pause(&mut self) -> bool {
+        self.paused = Self::players()
+            .into_iter()
+            .filter(|p| Self::is_playing(p))
+            .collect();
+        for player in &self.paused {
+            Self::call(player, "Pause");
+        }
+        !self.paused.is_empty()
+    }
+
+    fn play(&mut self) {
+        let players = match std::mem::take(&mut self.paused) {
+            paused if !paused.is_empty() => paused,
+            _ => Self::players().into_iter().take(1).collect(),
+        };
+        for player in players {
+            Self::call(&player, "Play");
+        }
+    }
+}
+
+/// MPRIS bus names in a `ListNames` reply.
+fn mpris_players(reply: &str) -> Vec<String> {
+    reply
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("string \""))
+        .filter_map(|name| name.strip_suffix('"'))
+        .filter(|name| name.starts_with("org.mpris.MediaPlayer2."))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Spotify, or else Apple Music, through `osascript`.
+struct AppleScript;
+
+impl AppleScript {
+    fn run(script: &str) -> Option<String> {
+        let output = Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .output()
+            .ok()?;
+        Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+impl MediaControl for AppleScript {
+    fn pause(&mut self) -> bool {
+        let script = r#"
+            set didPause to false
+            if application "Spotify" is running then
+                tell application "Spotify"
+                    if player state is playing then
+                        pause
+                        set didPause to true
+                    end if
+                end tell
+            end if
+            if application "Music" is running then
+                tell application "Music"
+                    if player state is playing then
+                        pause
+                        set didPause to true
+                    end if
+                end tell
+            end if
+            return didPause"#;
+        Self::run(script).as_deref() == Some("true")
+    }
+
+    fn play(&mut self) {
+        let script = r#"
+            if application "Spotify" is running then
+                tell application "Spotify" to play
+            else if application "Music" is running then
+                tell application "Music" to play
+            end if"#;
+        Self::run(script);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct FakePlayer {
+        playing: bool,
+        calls: Vec<&'static str>,
+    }
+
+    impl MediaControl for FakePlayer {
+        fn pause(&mut self) -> bool {
+            self.calls.push("pause");
+            std::mem::take(&mut self.playing)
+        }
+
+        fn play(&mut self) {
+            self.calls.push("play");
+            self.playing = true;
+        }
+    }
+
+    #[test]
+    fn pause_on_focus_only_resumes_what_it_paused() {
+        let mut policy = Policy::new(MusicMode::PauseOnFocus);
+        let mut player = FakePlayer::default();
+        policy.transition(PhaseKind::Focus, &mut player);
+        policy.transition(PhaseKind::ShortBreak, &mut player);
+        assert_eq!(player.calls, ["pause"]);
+        player.playing = true;
+        policy.transition(PhaseKind::Focus, &mut player);
+        policy.transition(PhaseKind::LongBreak, &mut player);
+        assert_eq!(player.calls, ["pause", "pause", "play"]);
+        assert!(player.playing);
+    }
+
+    #[test]
+    fn play_on_focus_follows_phases() {
+        let mut policy = Policy::new(MusicMode::PlayOnFocus);
+        let mut player = FakePlayer::default();
+        policy.transition(PhaseKind::Focus, &mut player);
+        assert!(player.playing);
+        policy.transition(PhaseKind::ShortBreak, &mut player);
+        assert!(!player.playing);
+    }
+
+    #[test]
+    fn lists_mpris_players() {
+        let reply = "method return time=1 sender=org.freedesktop.DBus\n   array [\n      string \"org.freedesktop.DBus\"\n      string \"org.mpris.MediaPlayer2.spotify\"\n      string \":1.42\"\n   ]\n";
+        assert_eq!(mpris_players(reply), ["org.mpris.MediaPlayer2.spotify"]);
+    }
+}
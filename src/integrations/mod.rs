@@ -0,0 +1,116 @@
+//! Third-party devices and services the timer drives at phase transitions.
+
+pub mod hue;
+pub mod media;
+pub mod push;
+pub mod remote;
+pub mod telegram;
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A plain-HTTP request to a device on the local network, returning the status
+/// code and body. The body is sent as JSON.
+pub fn http_request(host: &str, method: &str, path: &str, body: &str) -> io::Result<(u16, String)> {
+    http_send(host, method, path, &[], body)
+}
+
+/// `http_request` with extra headers, such as `Authorization`, or a
+/// `Content-Type` other than JSON.
+pub fn http_send(
+    host: &str,
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    body: &str,
+) -> io::Result<(u16, String)> {
+    let addr = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{host}:80")
+    };
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(HTTP_TIMEOUT))?;
+    stream.set_write_timeout(Some(HTTP_TIMEOUT))?;
+    let mut extra: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}: {value}\r\n"))
+        .collect();
+    if !headers
+        .iter()
+        .any(|(name, _)| name.eq_ignore_ascii_case("Content-Type"))
+    {
+        extra.push_str("Content-Type: application/json\r\n");
+    }
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: {host}\r\nContent-Length: {}\r\n{extra}Connection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    parse_response(&response)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed HTTP response"))
+}
+
+fn parse_response(response: &[u8]) -> Option<(u16, String)> {
+    let split = response.windows(4).position(|w| w == b"\r\n\r\n")?;
+    let head = std::str::from_utf8(&response[..split]).ok()?;
+    let body = &response[split + 4..];
+    let status = head.split_whitespace().nth(1)?.parse().ok()?;
+    let chunked = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .any(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("Transfer-Encoding")
+                && value.trim().eq_ignore_ascii_case("chunked")
+        });
+    let body = if chunked {
+        dechunk(body)?
+    } else {
+        body.to_vec()
+    };
+    Some((status, String::from_utf8_lossy(&body).into_owned()))
+}
+
+/// Joins the pieces of a `Transfer-Encoding: chunked` body, as calendar servers send.
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&body[..line_end]).ok()?;
+        let size = size.split(';').next()?.trim();
+        let size = usize::from_str_radix(size, 16).ok()?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Some(out);
+        }
+        out.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_http_response() {
+        let response =
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n[{\"success\":{}}]";
+        assert_eq!(
+            parse_response(response.as_bytes()),
+            Some((200, "[{\"success\":{}}]".to_string()))
+        );
+        assert_eq!(parse_response(b"garbage"), None);
+        let chunked = b"HTTP/1.1 207 Multi-Status\r\ntransfer-encoding: chunked\r\n\r\n\
+                        4\r\nBEGI\r\n8;x=y\r\nN:VCAL\xc3\xa9\r\n0\r\n\r\n";
+        assert_eq!(
+            parse_response(chunked),
+            Some((207, "BEGIN:VCALé".to_string()))
+        );
+    }
+}
@@ -0,0 +1,334 @@
+//! Notifications that reach a phone: an ntfy topic or an email, for the
+//! events `[notifications]` routes to `ntfy` or `email`. They go out from a
+//! background thread and are tried again a few times, so a slow server or a
+//! dropped connection never holds up the timer.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+use super::http_send;
+use crate::config::PushConfig;
+use crate::doctor::Probe;
+use crate::json::Value;
+use crate::notifications::Notifier;
+
+/// Waits between tries; a message is dropped after the last.
+const RETRIES: [Duration; 3] = [
+    Duration::from_secs(10),
+    Duration::from_secs(60),
+    Duration::from_secs(5 * 60),
+];
+const SMTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An ntfy server and topic, from an `http://host[:port]/topic` URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ntfy {
+    host: String,
+    topic: String,
+    token: Option<String>,
+}
+
+impl Ntfy {
+    /// `None` without a `[push] ntfy` URL with a topic.
+    pub fn from_config(config: &PushConfig) -> Option<Self> {
+        let rest = config.ntfy.as_deref()?.strip_prefix("http://")?;
+        let (host, topic) = rest.split_once('/')?;
+        let topic = topic.trim_end_matches('/');
+        if topic.is_empty() {
+            return None;
+        }
+        Some(Self {
+            host: host.to_string(),
+            topic: topic.to_string(),
+            token: config.ntfy_token.clone(),
+        })
+    }
+
+    fn payload(&self, title: &str, body: &str) -> Value {
+        Value::Object(vec![
+            ("topic".into(), self.topic.as_str().into()),
+            (
+                "title".into(),
+                format!("Rusty Pomo · {title}").as_str().into(),
+            ),
+            ("message".into(), body.into()),
+            ("tags".into(), Value::Array(vec!["tomato".into()])),
+        ])
+    }
+
+    fn send(&self, title: &str, body: &str) -> Result<(), String> {
+        let auth = self.token.as_ref().map(|token| format!("Bearer {token}"));
+        let headers: Vec<_> = auth.iter().map(|v| ("Authorization", v.as_str())).collect();
+        let payload = self.payload(title, body).to_string();
+        match http_send(&self.host, "POST", "/", &headers, &payload) {
+            Ok((200..=299, _)) => Ok(()),
+            Ok((status, body)) => Err(format!("{} answered {status}: {}", self.host, body.trim())),
+            Err(e) => Err(format!("cannot reach {}: {e}", self.host)),
+        }
+    }
+}
+
+/// An address to mail, through an SMTP relay that takes mail without TLS or
+/// a login, such as a local Postfix.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Email {
+    to: String,
+    from: String,
+    server: String,
+}
+
+impl Email {
+    /// `None` without a `[push] email` address.
+    pub fn from_config(config: &PushConfig) -> Option<Self> {
+        Some(Self {
+            to: config.email.clone()?,
+            from: config
+                .email_from
+                .clone()
+                .unwrap_or_else(|| "rusty_pomo@localhost".to_string()),
+            server: config
+                .smtp
+                .clone()
+                .unwrap_or_else(|| "localhost:25".to_string()),
+        })
+    }
+
+    fn send(&self, title: &str, body: &str) -> Result<(), String> {
+        let stream = TcpStream::connect(&self.server)
+            .map_err(|e| format!("cannot reach {}: {e}", self.server))?;
+        self.converse(stream)
+            .and_then(|mut session| session.mail(self, title, body))
+            .map_err(|e| format!("{}: {e}", self.server))
+    }
+
+    fn converse(&self, stream: TcpStream) -> io::Result<Smtp> {
+        stream.set_read_timeout(Some(SMTP_TIMEOUT))?;
+        stream.set_write_timeout(Some(SMTP_TIMEOUT))?;
+        let mut session = Smtp {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        };
+        session.expect(220)?;
+        session.command("HELO localhost", 250)?;
+        Ok(session)
+    }
+}
+
+/// One SMTP connection, a command and its reply at a time.
+struct Smtp {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Smtp {
+    fn mail(&mut self, email: &Email, title: &str, body: &str) -> io::Result<()> {
+        self.command(&format!("MAIL FROM:<{}>", email.from), 250)?;
+        self.command(&format!("RCPT TO:<{}>", email.to), 250)?;
+        self.command("DATA", 354)?;
+        let mut message = format!(
+            "From: Rusty Pomo <{}>\r\nTo: {}\r\nSubject: Rusty Pomo · {title}\r\n\
+             MIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\n\
+             Content-Transfer-Encoding: 8bit\r\n\r\n",
+            email.from, email.to
+        );
+        for line in body.lines() {
+            // A line of just "." would end the message early
+            if line.starts_with('.') {
+                message.push('.');
+            }
+            message.push_str(line);
+            message.push_str("\r\n");
+        }
+        message.push_str(".\r\n");
+        self.writer.write_all(message.as_bytes())?;
+        self.expect(250)?;
+        self.command("QUIT", 221)
+    }
+
+    fn command(&mut self, line: &str, code: u16) -> io::Result<()> {
+        write!(self.writer, "{line}\r\n")?;
+        self.expect(code)
+    }
+
+    /// Reads a reply, all its lines, and checks its code.
+    fn expect(&mut self, code: u16) -> io::Result<()> {
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "the server hung up",
+                ));
+            }
+            if line.as_bytes().get(3) == Some(&b'-') {
+                continue;
+            }
+            return match line.get(..3).and_then(|c| c.parse::<u16>().ok()) {
+                Some(got) if got == code => Ok(()),
+                _ => Err(io::Error::other(format!(
+                    "unexpected reply: {}",
+                    line.trim()
+                ))),
+            };
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    Ntfy,
+    Email,
+}
+
+/// The thread that sends, shared by every route to `ntfy` or `email`.
+#[derive(Clone, Debug)]
+pub struct Push(Sender<(Target, String, String)>);
+
+impl Push {
+    /// `None` with neither an ntfy topic nor an email address.
+    pub fn spawn(config: &PushConfig) -> Option<Self> {
+        let ntfy = Ntfy::from_config(config);
+        let email = Email::from_config(config);
+        if ntfy.is_none() && email.is_none() {
+            return None;
+        }
+        let (tx, rx) = mpsc::channel::<(Target, String, String)>();
+        thread::spawn(move || {
+            for (target, title, body) in rx {
+                let send = || match target {
+                    Target::Ntfy => ntfy.as_ref().map(|n| n.send(&title, &body)),
+                    Target::Email => email.as_ref().map(|e| e.send(&title, &body)),
+                };
+                let mut waits = RETRIES.iter();
+                while let Some(Err(e)) = send() {
+                    let Some(wait) = waits.next() else {
+                        tracing::warn!(error = %e, ?target, "push notification dropped");
+                        break;
+                    };
+                    tracing::warn!(error = %e, ?target, "push notification failed; will retry");
+                    thread::sleep(*wait);
+                }
+            }
+        });
+        Some(Self(tx))
+    }
+
+    /// A notifier that sends to `target`.
+    pub fn to(&self, target: Target) -> Pushed {
+        Pushed {
+            target,
+            push: self.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Pushed {
+    target: Target,
+    push: Push,
+}
+
+impl Notifier for Pushed {
+    fn notify(&self, title: &str, body: &str) {
+        let _ = self
+            .push
+            .0
+            .send((self.target, title.to_string(), body.to_string()));
+    }
+}
+
+/// What `[push]` is set up to send, for `doctor`. Sends nothing.
+pub fn probe(config: &PushConfig) -> Probe {
+    let mut targets = Vec::new();
+    if let Some(ntfy) = Ntfy::from_config(config) {
+        targets.push(format!("ntfy topic {} on {}", ntfy.topic, ntfy.host));
+    }
+    if let Some(email) = Email::from_config(config) {
+        targets.push(format!("email to {} via {}", email.to, email.server));
+    }
+    if targets.is_empty() {
+        return Probe::Off("not set up ([push] ntfy or email)".to_string());
+    }
+    Probe::Ok(targets.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn sends_to_ntfy_topics_and_over_smtp() {
+        let config = PushConfig {
+            ntfy: Some("http://ntfy.sh/pomo-42/".to_string()),
+            ..PushConfig::default()
+        };
+        let ntfy = Ntfy::from_config(&config).unwrap();
+        assert_eq!(
+            (ntfy.host.as_str(), ntfy.topic.as_str()),
+            ("ntfy.sh", "pomo-42")
+        );
+        assert_eq!(
+            ntfy.payload("Long Break", "Enjoy.").to_string(),
+            r#"{"topic":"pomo-42","title":"Rusty Pomo · Long Break","message":"Enjoy.","tags":["tomato"]}"#
+        );
+        let no_topic = PushConfig {
+            ntfy: Some("http://ntfy.sh".to_string()),
+            ..PushConfig::default()
+        };
+        assert_eq!(Ntfy::from_config(&no_topic), None);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = listener.local_addr().unwrap().to_string();
+        let relay = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let replies = [
+                "220 relay ready\r\n",
+                "250 hello\r\n",
+                "250 ok\r\n",
+                "250 ok\r\n",
+                "354 go ahead\r\n",
+                "250 queued\r\n",
+                "221 bye\r\n",
+            ];
+            let mut received = String::new();
+            let mut buf = [0; 1024];
+            for reply in replies {
+                stream.write_all(reply.as_bytes()).unwrap();
+                if reply.starts_with("221") {
+                    break;
+                }
+                // Read up to the end of the next command, or the message
+                let end = if reply.starts_with("354") {
+                    "\r\n.\r\n"
+                } else {
+                    "\r\n"
+                };
+                let start = received.len();
+                while !received[start..].ends_with(end) {
+                    let n = stream.read(&mut buf).unwrap();
+                    received.push_str(std::str::from_utf8(&buf[..n]).unwrap());
+                }
+            }
+            received
+        });
+        let email = Email::from_config(&PushConfig {
+            email: Some("me@example.com".to_string()),
+            smtp: Some(server),
+            ..PushConfig::default()
+        })
+        .unwrap();
+        email.send("Long Break", "Stretch.\n.hidden").unwrap();
+        let received = relay.join().unwrap();
+        assert!(received.starts_with("HELO localhost\r\nMAIL FROM:<rusty_pomo@localhost>\r\nRCPT TO:<me@example.com>\r\nDATA\r\n"), "{received}");
+        assert!(received.contains("Subject: Rusty Pomo · Long Break\r\n"));
+        assert!(
+            received.ends_with("\r\n\r\nStretch.\r\n..hidden\r\n.\r\nQUIT\r\n"),
+            "{received}"
+        );
+    }
+}
@@ -0,0 +1,367 @@
+//! Uploads finished sessions to a server that implements the small REST schema
+//! in `docs/remote-api.md`, for dashboards and self-hosted sync. Sessions wait
+//! in a queue file until the server takes them, so nothing is lost offline.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use super::http_send;
+use crate::config::RemoteConfig;
+use crate::doctor::Probe;
+use crate::events::{PhaseEvent, Subscriber};
+use crate::history::{self, SessionRecord};
+use crate::json::Value;
+use crate::paths;
+
+/// How long queued sessions wait before another try while the server is unreachable.
+const RETRY: Duration = Duration::from_secs(5 * 60);
+/// Sessions sent per request; a long offline stretch goes up in several.
+const BATCH: usize = 100;
+
+/// Where sessions are sent: `host[:port]` and the path under it, from a
+/// `http://host[:port]/base` URL.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Endpoint {
+    host: String,
+    base: String,
+    token: Option<String>,
+}
+
+impl Endpoint {
+    /// `None` without a `[remote] url`.
+    pub fn from_config(config: &RemoteConfig) -> Option<Self> {
+        let rest = config.url.as_deref()?.strip_prefix("http://")?;
+        let (host, base) = match rest.split_once('/') {
+            Some((host, base)) => (host, format!("/{}", base.trim_end_matches('/'))),
+            None => (rest, String::new()),
+        };
+        Some(Self {
+            host: host.to_string(),
+            base: base.trim_end_matches('/').to_string(),
+            token: config.token.clone(),
+        })
+    }
+
+    /// Sends `records`; the server has them once this returns `Ok`.
+    pub fn upload(&self, records: &[SessionRecord]) -> Result<(), String> {
+        let body = payload(records).to_string();
+        let auth = self.token.as_ref().map(|token| format!("Bearer {token}"));
+        let headers: Vec<_> = auth.iter().map(|v| ("Authorization", v.as_str())).collect();
+        let path = format!("{}/sessions", self.base);
+        match http_send(&self.host, "POST", &path, &headers, &body) {
+            Ok((200..=299, _)) => Ok(()),
+            Ok((status, body)) => Err(format!("{} answered {status}: {}", self.host, body.trim())),
+            Err(e) => Err(format!("cannot reach {}: {e}", self.host)),
+        }
+    }
+}
+
+/// Sessions are identified by when they started and what they were, so a
+/// retried upload, or a later rating, replaces the server's copy.
+pub fn session_id(record: &SessionRecord) -> String {
+    format!("{}-{}", record.start, record.kind.key())
+}
+
+fn payload(records: &[SessionRecord]) -> Value {
+    let sessions = records
+        .iter()
+        .map(|record| {
+            let Value::Object(mut fields) = record.to_json() else {
+                unreachable!("records serialize to objects");
+            };
+            fields.insert(0, ("id".to_string(), session_id(record).as_str().into()));
+            Value::Object(fields)
+        })
+        .collect();
+    Value::Object(vec![
+        (
+            "client".to_string(),
+            concat!("rusty_pomo/", env!("CARGO_PKG_VERSION")).into(),
+        ),
+        ("sessions".to_string(), Value::Array(sessions)),
+    ])
+}
+
+/// Sessions not yet accepted by the server, one JSON line each, in the same
+/// format as the history.
+#[derive(Clone, Debug)]
+pub struct Queue {
+    path: PathBuf,
+}
+
+impl Queue {
+    pub fn open() -> Option<Self> {
+        paths::upload_queue().map(|path| Self { path })
+    }
+
+    /// Queues `record`, in place of a queued copy of the same session, so a
+    /// rating sent before the upload doesn't make it go up twice.
+    pub fn push(&self, record: &SessionRecord) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let _lock = self.lock()?;
+        let mut queued = read(&self.path);
+        if let Some(copy) = queued
+            .iter_mut()
+            .find(|queued| session_id(queued) == session_id(record))
+        {
+            *copy = record.clone();
+            return self.write(&queued);
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", record.to_json())
+    }
+
+    /// Held while the queue changes, so a session pushed by another timer
+    /// isn't lost while `drop_first` rewrites the file.
+    fn lock(&self) -> io::Result<fs::File> {
+        history::lock_file(&self.path.with_extension("lock"))
+    }
+
+    pub fn pending(&self) -> Vec<SessionRecord> {
+        read(&self.path)
+    }
+
+    /// Uploads in batches, dropping each from the queue once it's accepted;
+    /// returns how many went up.
+    pub fn flush(&self, endpoint: &Endpoint) -> Result<usize, String> {
+        let mut sent = 0;
+        loop {
+            let pending = self.pending();
+            if pending.is_empty() {
+                return Ok(sent);
+            }
+            let batch = &pending[..pending.len().min(BATCH)];
+            endpoint.upload(batch)?;
+            self.drop_first(batch.len())
+                .map_err(|e| format!("cannot update {}: {e}", self.path.display()))?;
+            sent += batch.len();
+        }
+    }
+
+    /// Removes the first `n` sessions, keeping any queued since they were read.
+    fn drop_first(&self, n: usize) -> io::Result<()> {
+        let _lock = self.lock()?;
+        let mut rest = read(&self.path);
+        let rest = rest.split_off(n.min(rest.len()));
+        if rest.is_empty() {
+            return fs::remove_file(&self.path);
+        }
+        self.write(&rest)
+    }
+
+    fn write(&self, records: &[SessionRecord]) -> io::Result<()> {
+        let contents: String = records
+            .iter()
+            .map(|r| format!("{}\n", r.to_json()))
+            .collect();
+        let tmp = self.path.with_extension("jsonl.tmp");
+        fs::write(&tmp, contents)?;
+        fs::rename(tmp, &self.path)
+    }
+}
+
+fn read(path: &Path) -> Vec<SessionRecord> {
+    fs::read_to_string(path)
+        .map(|contents| history::parse_records(&contents))
+        .unwrap_or_default()
+}
+
+/// Queues every recorded session right away, then uploads from a background
+/// thread, so a slow or missing server never holds up the timer.
+#[derive(Debug)]
+pub struct RemoteSync {
+    queue: Queue,
+    wake: Sender<()>,
+    /// The last session queued, for the rating that may follow it.
+    last: Option<SessionRecord>,
+}
+
+impl RemoteSync {
+    pub fn spawn(config: &RemoteConfig) -> Option<Self> {
+        let endpoint = Endpoint::from_config(config)?;
+        let queue = Queue::open()?;
+        let (wake, woken) = mpsc::channel();
+        let uploads = queue.clone();
+        thread::spawn(move || {
+            // Starts with whatever an earlier run couldn't send
+            let mut wait = Duration::ZERO;
+            loop {
+                match woken.recv_timeout(wait) {
+                    Ok(()) | Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+                wait = match uploads.flush(&endpoint) {
+                    Ok(0) => Duration::MAX,
+                    Ok(sent) => {
+                        tracing::info!(sent, "sessions uploaded");
+                        Duration::MAX
+                    }
+                    Err(e) => {
+                        tracing::warn!(error = %e, "session upload failed; will retry");
+                        RETRY
+                    }
+                };
+            }
+        });
+        Some(Self {
+            queue,
+            wake,
+            last: None,
+        })
+    }
+}
+
+impl Subscriber for RemoteSync {
+    fn on_event(&mut self, event: &PhaseEvent) {
+        let record = match event {
+            PhaseEvent::Recorded(record) => record.clone(),
+            PhaseEvent::Rated { start, rating } => match &self.last {
+                Some(last) if last.start == *start => SessionRecord {
+                    rating: Some(*rating),
+                    ..last.clone()
+                },
+                _ => return,
+            },
+            _ => return,
+        };
+        if let Err(e) = self.queue.push(&record) {
+            tracing::warn!(error = %e, "cannot queue session for upload");
+        }
+        self.last = Some(record);
+        let _ = self.wake.send(());
+    }
+}
+
+/// Whether the server is set up and takes uploads, for `doctor`. Sends an empty batch.
+pub fn probe(config: &RemoteConfig) -> Probe {
+    let Some(endpoint) = Endpoint::from_config(config) else {
+        return Probe::Off("not set up ([remote] url)".to_string());
+    };
+    let queued = Queue::open().map_or(0, |queue| queue.pending().len());
+    match endpoint.upload(&[]) {
+        Ok(()) if queued > 0 => Probe::Warn(format!(
+            "{} takes uploads; {queued} sessions still queued, send them with `rusty_pomo sync push`",
+            endpoint.host
+        )),
+        Ok(()) => Probe::Ok(format!("{} takes uploads", endpoint.host)),
+        Err(e) => Probe::Fail(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PhaseKind;
+
+    fn record(start: u64) -> SessionRecord {
+        SessionRecord {
+            kind: PhaseKind::Focus,
+            start,
+            end: start + 1500,
+            planned_secs: 1500,
+            completed: true,
+            pauses: Vec::new(),
+            snoozes: Vec::new(),
+            project: None,
+            task: None,
+            estimate: None,
+            rating: Some(4),
+            extended_secs: 0,
+            distractions: Vec::new(),
+            rested: None,
+            utc_offset: None,
+            rolled_up: false,
+        }
+    }
+
+    #[test]
+    fn splits_urls_into_host_and_base() {
+        let config = |url: &str| RemoteConfig {
+            url: Some(url.to_string()),
+            token: None,
+        };
+        let endpoint = Endpoint::from_config(&config("http://pomo.lan:8080/api/v1/")).unwrap();
+        assert_eq!(endpoint.host, "pomo.lan:8080");
+        assert_eq!(endpoint.base, "/api/v1");
+        let endpoint = Endpoint::from_config(&config("http://pomo.lan")).unwrap();
+        assert_eq!(
+            (endpoint.host.as_str(), endpoint.base.as_str()),
+            ("pomo.lan", "")
+        );
+        assert_eq!(Endpoint::from_config(&RemoteConfig::default()), None);
+    }
+
+    #[test]
+    fn sends_sessions_with_stable_ids() {
+        let body = payload(&[record(1_700_000_000)]);
+        let session = &body.get("sessions").unwrap().as_array().unwrap()[0];
+        assert_eq!(
+            session.get("id").and_then(Value::as_str),
+            Some("1700000000-focus")
+        );
+        assert_eq!(session.get("rating").and_then(Value::as_u64), Some(4));
+        assert!(
+            body.get("client")
+                .and_then(Value::as_str)
+                .unwrap()
+                .starts_with("rusty_pomo/")
+        );
+    }
+
+    #[test]
+    fn queue_keeps_sessions_added_during_an_upload() {
+        let dir = std::env::temp_dir().join(format!("rusty_pomo_queue_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let queue = Queue {
+            path: dir.join("upload-queue.jsonl"),
+        };
+        assert!(queue.pending().is_empty());
+        queue.push(&record(1)).unwrap();
+        queue.push(&record(2)).unwrap();
+        queue.push(&record(3)).unwrap();
+        queue.drop_first(2).unwrap();
+        assert_eq!(queue.pending(), [record(3)]);
+        queue.drop_first(5).unwrap();
+        assert!(!queue.path.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rating_updates_the_queued_session() {
+        let dir = std::env::temp_dir().join(format!("rusty_pomo_rated_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let queue = Queue {
+            path: dir.join("upload-queue.jsonl"),
+        };
+        let (wake, _woken) = mpsc::channel();
+        let mut sync = RemoteSync {
+            queue: queue.clone(),
+            wake,
+            last: None,
+        };
+        let unrated = SessionRecord {
+            rating: None,
+            ..record(1)
+        };
+        sync.on_event(&PhaseEvent::Recorded(unrated));
+        sync.on_event(&PhaseEvent::Rated {
+            start: 1,
+            rating: 4,
+        });
+        sync.on_event(&PhaseEvent::Rated {
+            start: 2,
+            rating: 1,
+        });
+        assert_eq!(queue.pending(), [record(1)]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
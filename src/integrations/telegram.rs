@@ -0,0 +1,260 @@
+//! A Telegram bot that posts each new phase to a chat and takes `/pause`,
+//! `/resume`, `/skip` and `/status` from one user. It talks to a local Bot API
+//! server (`telegram-bot-api`) over plain HTTP, since api.telegram.org needs
+//! TLS.
+
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::http_send;
+use crate::control::Control;
+use crate::doctor::Probe;
+use crate::i18n::{self, tf};
+use crate::json::{self, Value};
+use crate::server::Remote;
+use crate::state::PhaseKind;
+
+/// How long each `getUpdates` waits for a message; under the HTTP timeout.
+const POLL_SECS: u64 = 4;
+/// Wait after a failed request before the next.
+const RETRY: Duration = Duration::from_secs(30);
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `[telegram]`: the bot, the chat it posts to and who may command it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bot {
+    /// `host[:port]` of the Bot API server.
+    pub api: String,
+    pub token: String,
+    pub chat: String,
+    /// The only user whose commands are followed; none are without one.
+    pub user: Option<u64>,
+}
+
+impl Bot {
+    fn call(&self, method: &str, body: &Value) -> Result<Value, String> {
+        let path = format!("/bot{}/{method}", self.token);
+        let (status, text) = http_send(&self.api, "POST", &path, &[], &body.to_string())
+            .map_err(|e| format!("cannot reach {}: {e}", self.api))?;
+        let value = json::parse(&text).map_err(|e| format!("{method}: {e}"))?;
+        match value.get("ok").and_then(Value::as_bool) {
+            Some(true) => Ok(value.get("result").cloned().unwrap_or(Value::Null)),
+            _ => Err(format!(
+                "{method} failed ({status}): {}",
+                value
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .unwrap_or("no description")
+            )),
+        }
+    }
+
+    fn say(&self, text: &str) {
+        let body = Value::Object(vec![
+            ("chat_id".to_string(), self.chat.as_str().into()),
+            ("text".to_string(), text.into()),
+        ]);
+        if let Err(e) = self.call("sendMessage", &body) {
+            tracing::warn!(error = %e, "cannot post to Telegram");
+        }
+    }
+}
+
+/// Starts the bot on background threads; commands come back through the remote.
+pub fn spawn(bot: &Bot) -> Remote {
+    let (remote, commands, status) = Remote::new();
+    let poller = bot.clone();
+    let polled = Arc::clone(&status);
+    thread::spawn(move || poll(&poller, &polled, &commands));
+    let watcher = bot.clone();
+    thread::spawn(move || watch(&watcher, &status));
+    remote
+}
+
+fn poll(bot: &Bot, status: &Mutex<Value>, commands: &Sender<Control>) {
+    let mut offset = 0;
+    loop {
+        let body = Value::Object(vec![
+            ("offset".to_string(), offset.into()),
+            ("timeout".to_string(), POLL_SECS.into()),
+        ]);
+        let updates = match bot.call("getUpdates", &body) {
+            Ok(updates) => updates,
+            Err(e) => {
+                tracing::warn!(error = %e, "Telegram polling failed; will retry");
+                thread::sleep(RETRY);
+                continue;
+            }
+        };
+        let (next, texts) = from_user(&updates, bot.user);
+        offset = next.max(offset);
+        for text in texts {
+            let (control, reply) = command(&text);
+            if let Some(control) = control
+                && commands.send(control).is_err()
+            {
+                return;
+            }
+            let reply = reply.unwrap_or_else(|| {
+                let now = status.lock().map(|s| s.clone()).unwrap_or(Value::Null);
+                describe(&now)
+            });
+            bot.say(&reply);
+        }
+    }
+}
+
+/// Posts each new phase, but not the one the timer starts in.
+fn watch(bot: &Bot, status: &Mutex<Value>) {
+    let mut last: Option<(String, u64)> = None;
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+        let now = status.lock().map(|s| s.clone()).unwrap_or(Value::Null);
+        let (Some(phase), Some(session)) = (
+            now.get("phase").and_then(Value::as_str),
+            now.get("session").and_then(Value::as_u64),
+        ) else {
+            continue;
+        };
+        let current = (phase.to_string(), session);
+        if last.as_ref().is_some_and(|last| *last != current) {
+            bot.say(&announce(phase));
+        }
+        last = Some(current);
+    }
+}
+
+/// The offset for the next `getUpdates` and the texts `user` sent, in order.
+fn from_user(updates: &Value, user: Option<u64>) -> (u64, Vec<String>) {
+    let mut next = 0;
+    let mut texts = Vec::new();
+    for update in updates.as_array().unwrap_or_default() {
+        if let Some(id) = update.get("update_id").and_then(Value::as_u64) {
+            next = next.max(id + 1);
+        }
+        let Some(message) = update.get("message") else {
+            continue;
+        };
+        let from = message
+            .get("from")
+            .and_then(|from| from.get("id"))
+            .and_then(Value::as_u64);
+        match (from, message.get("text").and_then(Value::as_str)) {
+            (Some(from), Some(text)) if Some(from) == user => texts.push(text.to_string()),
+            (from, _) => tracing::debug!(?from, "Telegram message from someone else ignored"),
+        }
+    }
+    (next, texts)
+}
+
+/// What a message asks for, and the reply; `None` replies with the status.
+fn command(text: &str) -> (Option<Control>, Option<String>) {
+    // "/skip@pomo_bot" in group chats
+    let name = text.split_whitespace().next().unwrap_or("");
+    let name = name.split('@').next().unwrap_or(name);
+    match name {
+        "/pause" => (Some(Control::Pause), Some("Paused".to_string())),
+        "/resume" => (Some(Control::Resume), Some("Resumed".to_string())),
+        "/skip" => (Some(Control::Skip), Some("Skipped".to_string())),
+        "/status" => (None, None),
+        _ => (
+            None,
+            Some("Commands: /status, /pause, /resume, /skip".to_string()),
+        ),
+    }
+}
+
+fn phase_title(key: &str) -> &str {
+    match PhaseKind::from_key(key) {
+        Some(kind) => i18n::phase_title(kind),
+        None => key,
+    }
+}
+
+/// The message for a new phase, as its desktop notification says it.
+fn announce(key: &str) -> String {
+    let greeting = match PhaseKind::from_key(key) {
+        Some(_) => tf(&format!("notify.{key}"), &[]),
+        None => tf("notify.custom", &[("phase", key)]),
+    };
+    format!("{}: {greeting}", phase_title(key))
+}
+
+/// The reply to `/status`.
+fn describe(status: &Value) -> String {
+    let (Some(phase), Some(left)) = (
+        status.get("phase").and_then(Value::as_str),
+        status.get("remaining_secs").and_then(Value::as_u64),
+    ) else {
+        return "The timer isn't running".to_string();
+    };
+    let paused = if status.get("paused").and_then(Value::as_bool) == Some(true) {
+        ", paused"
+    } else {
+        ""
+    };
+    format!(
+        "{} · {:02}:{:02} left{paused}",
+        phase_title(phase),
+        left / 60,
+        left % 60
+    )
+}
+
+/// Whether the Bot API server knows the token, for `doctor`.
+pub fn probe(bot: Option<&Bot>) -> Probe {
+    let Some(bot) = bot else {
+        return Probe::Off("not set up ([telegram])".to_string());
+    };
+    match bot.call("getMe", &Value::Object(Vec::new())) {
+        Ok(me) if bot.user.is_none() => Probe::Warn(format!(
+            "posting as @{}, but no `user` may send it commands",
+            me.get("username").and_then(Value::as_str).unwrap_or("?")
+        )),
+        Ok(me) => Probe::Ok(format!(
+            "@{} on {}",
+            me.get("username").and_then(Value::as_str).unwrap_or("?"),
+            bot.api
+        )),
+        Err(e) => Probe::Fail(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_commands_from_the_user_only() {
+        let updates = json::parse(
+            r#"[
+                {"update_id": 7, "message": {"from": {"id": 42}, "text": "/pause"}},
+                {"update_id": 8, "message": {"from": {"id": 666}, "text": "/skip"}},
+                {"update_id": 9, "edited_message": {"from": {"id": 42}, "text": "/skip"}},
+                {"update_id": 10, "message": {"from": {"id": 42}, "text": "/skip@pomo_bot now"}}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            from_user(&updates, Some(42)),
+            (
+                11,
+                vec!["/pause".to_string(), "/skip@pomo_bot now".to_string()]
+            )
+        );
+        assert_eq!(from_user(&updates, None).1, Vec::<String>::new());
+        assert_eq!(command("/skip@pomo_bot now").0, Some(Control::Skip));
+        assert_eq!(command("/status"), (None, None));
+        assert_eq!(command("hello").0, None);
+
+        let status = json::parse(
+            r#"{"phase": "short_break", "remaining_secs": 299, "paused": true, "session": 2}"#,
+        )
+        .unwrap();
+        assert_eq!(describe(&status), "Short Break · 04:59 left, paused");
+        assert_eq!(describe(&Value::Null), "The timer isn't running");
+        assert_eq!(announce("long_break"), "Long Break: Enjoy a longer rest.");
+    }
+}
@@ -0,0 +1,237 @@
+//! Leaderboard files: `stats --leaderboard-export` sums up a period of the
+//! history in a small file to swap with friends, and `leaderboard show` ranks
+//! a folder of them. Only the totals go in, never projects, tasks or times.
+//!
+//! Each file carries a checksum of its totals, so one edited by hand stands
+//! out. It's not a signature: anyone who reads this can write a valid one.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::history::SessionRecord;
+use crate::json::{self, Value};
+use crate::state::PhaseKind;
+use crate::stats;
+use crate::wallclock;
+
+const VERSION: u64 = 1;
+
+/// One person's totals over a period.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    /// First and last local day of the period.
+    pub from: i64,
+    pub until: i64,
+    pub sessions: u64,
+    pub focus_secs: u64,
+    /// Days with at least one finished focus session.
+    pub active_days: u64,
+}
+
+impl Entry {
+    /// Totals over the `days` local days ending today, as in `rusty_pomo stats`.
+    pub fn summarize(name: &str, records: &[SessionRecord], now: u64, days: u64) -> Self {
+        let until = wallclock::local_day(now);
+        let from = until - days.saturating_sub(1) as i64;
+        let period = stats::summarize(records, now, days).period;
+        let mut active: Vec<i64> = records
+            .iter()
+            .filter(|r| r.kind == PhaseKind::Focus && r.completed)
+            .map(|r| r.day())
+            .filter(|day| (from..=until).contains(day))
+            .collect();
+        active.sort_unstable();
+        active.dedup();
+        Self {
+            name: name.to_string(),
+            from,
+            until,
+            sessions: period.completed,
+            focus_secs: period.focus_secs,
+            active_days: active.len() as u64,
+        }
+    }
+
+    fn fields(&self) -> Vec<(String, Value)> {
+        vec![
+            ("version".into(), VERSION.into()),
+            ("name".into(), self.name.as_str().into()),
+            (
+                "from".into(),
+                wallclock::format_date(self.from).as_str().into(),
+            ),
+            (
+                "until".into(),
+                wallclock::format_date(self.until).as_str().into(),
+            ),
+            ("sessions".into(), self.sessions.into()),
+            ("focus_secs".into(), self.focus_secs.into()),
+            ("active_days".into(), self.active_days.into()),
+        ]
+    }
+
+    /// FNV-1a over the fields, in hex.
+    fn checksum(&self) -> String {
+        let text = Value::Object(self.fields()).to_string();
+        let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        format!("{hash:016x}")
+    }
+
+    /// The file's contents, one line of JSON.
+    pub fn to_json(&self) -> String {
+        let mut fields = self.fields();
+        fields.push(("checksum".into(), self.checksum().as_str().into()));
+        format!("{}\n", Value::Object(fields))
+    }
+
+    /// The entry in `text`, and whether its checksum still matches.
+    pub fn parse(text: &str) -> Result<(Self, bool), String> {
+        let value = json::parse(text)?;
+        match value.get("version").and_then(Value::as_u64) {
+            Some(VERSION) => {}
+            Some(version) => return Err(format!("version {version} is newer than this one")),
+            None => return Err("not a leaderboard file".to_string()),
+        }
+        let text_field = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .ok_or(format!("missing {key}"))
+        };
+        let number = |key: &str| {
+            value
+                .get(key)
+                .and_then(Value::as_u64)
+                .ok_or(format!("missing {key}"))
+        };
+        let day = |key: &str| {
+            wallclock::parse_date(text_field(key)?).ok_or(format!("{key} is not a YYYY-MM-DD date"))
+        };
+        let entry = Self {
+            name: text_field("name")?.to_string(),
+            from: day("from")?,
+            until: day("until")?,
+            sessions: number("sessions")?,
+            focus_secs: number("focus_secs")?,
+            active_days: number("active_days")?,
+        };
+        let intact = value.get("checksum").and_then(Value::as_str) == Some(&entry.checksum());
+        Ok((entry, intact))
+    }
+}
+
+/// The name put in exported files: `$USER`, or `$USERNAME` on Windows.
+pub fn default_name() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "anonymous".to_string())
+}
+
+/// A folder of leaderboard files, read.
+pub struct Board {
+    /// Each entry with whether its checksum matches.
+    pub entries: Vec<(Entry, bool)>,
+    /// `.json` files that aren't leaderboard files, with why.
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+pub fn read_dir(dir: &Path) -> io::Result<Board> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    let (mut entries, mut skipped) = (Vec::new(), Vec::new());
+    for path in paths {
+        match fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Entry::parse(&text))
+        {
+            Ok(entry) => entries.push(entry),
+            Err(e) => skipped.push((path, e)),
+        }
+    }
+    Ok(Board { entries, skipped })
+}
+
+/// Ranks the entries by focus time, then by sessions.
+pub fn render(entries: &mut [(Entry, bool)]) -> String {
+    entries.sort_by(|(a, _), (b, _)| {
+        (b.focus_secs, b.sessions)
+            .cmp(&(a.focus_secs, a.sessions))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    let mut out = format!(
+        "{:<4}{:<16}{:>8}  {:>8}  {:>4}  Period\n",
+        "", "Name", "Sessions", "Focus", "Days"
+    );
+    for (rank, (entry, intact)) in entries.iter().enumerate() {
+        out.push_str(&format!(
+            "{:<4}{:<16}{:>8}  {:>8}  {:>4}  {} to {}{}\n",
+            format!("{}.", rank + 1),
+            entry.name,
+            entry.sessions,
+            stats::hours_minutes(entry.focus_secs),
+            entry.active_days,
+            wallclock::format_date(entry.from),
+            wallclock::format_date(entry.until),
+            if *intact { "" } else { "  (edited)" },
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn focus(start: u64, completed: bool) -> SessionRecord {
+        SessionRecord {
+            completed,
+            project: Some("secret".to_string()),
+            ..SessionRecord::test_focus(start, 1500)
+        }
+    }
+
+    #[test]
+    fn exports_totals_that_round_trip_and_ranks_them() {
+        let now = 1_700_000_000;
+        let midnight = now - wallclock::seconds_of_local_day(now);
+        let records = [
+            // Before the period
+            focus(midnight - 7 * 86_400, true),
+            focus(midnight - 86_400, true),
+            focus(midnight, true),
+            focus(midnight + 1800, false),
+        ];
+        let alice = Entry::summarize("alice", &records, now, 7);
+        assert_eq!(alice.sessions, 2);
+        assert_eq!(alice.focus_secs, 3 * 1500);
+        assert_eq!(alice.active_days, 2);
+        let json = alice.to_json();
+        assert!(!json.contains("secret"));
+        assert_eq!(Entry::parse(&json), Ok((alice.clone(), true)));
+
+        let edited = json.replace("\"sessions\":2", "\"sessions\":20");
+        let (bob, intact) = Entry::parse(&edited).unwrap();
+        assert_eq!(bob.sessions, 20);
+        assert!(!intact);
+        assert!(Entry::parse(&json.replace("\"version\":1", "\"version\":2")).is_err());
+
+        let bob = Entry {
+            name: "bob".to_string(),
+            focus_secs: 7200,
+            ..alice.clone()
+        };
+        let board = render(&mut [(alice, true), (bob, false)]);
+        let lines: Vec<_> = board.lines().collect();
+        assert!(lines[1].starts_with("1.  bob"), "{board}");
+        assert!(lines[1].ends_with("(edited)"), "{board}");
+        assert!(lines[2].starts_with("2.  alice"), "{board}");
+        assert!(lines[2].contains("1h 15m"), "{board}");
+    }
+}
@@ -0,0 +1,167 @@
+//! Diagnostics for when something quietly didn't happen: a notification that
+//! never showed, a bridge that didn't answer. Off unless `--log-file` or `-v`
+//! is given, and only ever written to a file, since the screen belongs to the UI.
+
+use std::fmt::{self, Write as _};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+use crate::args::Args;
+use crate::{paths, wallclock};
+
+/// The log is moved to `<name>.1` once it grows past this, replacing the last one.
+const MAX_BYTES: u64 = 1024 * 1024;
+
+/// Starts logging as `--log-file` and `-v` ask. Fails only when the file can't
+/// be opened.
+pub fn init(args: &Args) -> io::Result<()> {
+    let path = match (&args.log_file, args.verbose) {
+        (Some(path), _) => path.clone(),
+        (None, 0) => return Ok(()),
+        (None, _) => paths::log_file()
+            .ok_or_else(|| io::Error::other("cannot locate the state directory"))?,
+    };
+    let log = FileLog {
+        level: level(args.verbose),
+        file: Mutex::new(LogFile::open(path)?),
+    };
+    let _ = tracing::subscriber::set_global_default(log);
+    Ok(())
+}
+
+fn level(verbose: u8) -> Level {
+    match verbose {
+        0 => Level::WARN,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > MAX_BYTES {
+            fs::rename(&self.path, rotated(&self.path))?;
+            *self = Self::open(self.path.clone())?;
+        }
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+fn rotated(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+/// Writes events as lines; spans aren't used, so they're accepted and ignored.
+struct FileLog {
+    level: Level,
+    file: Mutex<LogFile>,
+}
+
+impl Subscriber for FileLog {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        // Dependencies that log through tracing too are left out
+        *metadata.level() <= self.level && metadata.target().starts_with("rusty_pomo")
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let metadata = event.metadata();
+        let line = format!(
+            "{} {:<5} {}: {}{}\n",
+            wallclock::format_datetime(wallclock::now_unix()),
+            metadata.level(),
+            metadata.target(),
+            fields.message,
+            fields.rest
+        );
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_line(&line);
+        }
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    rest: String,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.rest, " {}={value:?}", field.name());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_filtered_lines_and_rotates() {
+        let dir = std::env::temp_dir().join(format!("rusty_pomo_log_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("rusty_pomo.log");
+        let log = FileLog {
+            level: level(1),
+            file: Mutex::new(LogFile::open(path.clone()).unwrap()),
+        };
+        tracing::subscriber::with_default(log, || {
+            tracing::info!(phase = "focus", "phase started");
+            tracing::debug!("too detailed for -v");
+        });
+        let written = fs::read_to_string(&path).unwrap();
+        assert_eq!(written.lines().count(), 1);
+        assert!(
+            written.ends_with(" INFO  rusty_pomo::logging::tests: phase started phase=\"focus\"\n")
+        );
+
+        let mut file = LogFile::open(path.clone()).unwrap();
+        file.write_line(&"x".repeat(MAX_BYTES as usize)).unwrap();
+        assert_eq!(fs::read_to_string(rotated(&path)).unwrap(), written);
+        assert_eq!(fs::metadata(&path).unwrap().len(), MAX_BYTES);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
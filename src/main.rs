@@ -1,21 +1,629 @@
+mod achievements;
+mod activity;
+mod announce;
 mod args;
+mod audio;
+mod backup;
+mod bell;
+mod breathing;
+mod calendar;
+mod clock;
+mod completions;
+mod config;
+mod control;
+mod daemon;
+mod dashboard;
+mod doctor;
+mod events;
+mod export;
+mod garden;
+mod graphics;
+mod history;
+mod i18n;
+mod icon;
+mod import;
+mod integrations;
+mod json;
+mod leaderboard;
+mod logging;
+mod manpage;
+mod mascot;
+mod mqtt;
 mod notifications;
+mod osc;
+mod paths;
+mod pdf;
+mod phases;
+mod plugins;
+mod quotes;
+mod report;
 mod run;
+mod score;
+mod server;
+mod settings;
+mod signals;
+mod ssh;
 mod state;
+mod stats;
+mod suggestions;
+mod tar;
+mod template;
 mod theme;
+mod timefmt;
+mod timeline;
 mod ui;
+mod validate;
+mod wallclock;
+mod websocket;
+mod work_hours;
 
-use clap::Parser;
-use std::io;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, FromArgMatches};
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
 
-use crate::args::Args;
-use crate::notifications::maybe_init_macos_bundle;
+use crate::args::{
+    Args, BackupAction, Cli, Command, ConfigAction, ContextAction, HistoryAction, HueAction,
+    LeaderboardAction, SyncAction, TemplateAction,
+};
+use crate::bell::BellMode;
+use crate::config::Config;
+use crate::history::{History, StoreKind};
+use crate::i18n::Lang;
+use crate::integrations::hue::{self, Hue};
+use crate::integrations::remote::{Endpoint, Queue, RemoteSync};
+use crate::notifications::{NotifierKind, maybe_init_macos_bundle, maybe_register_windows_app_id};
+use crate::quotes::Quotes;
 use crate::run::run;
-use crate::state::AppState;
+use crate::state::{AppState, PhaseKind};
 
 fn main() -> io::Result<()> {
-    let args = Args::parse();
+    let matches = Cli::command().get_matches();
+    let Cli { command, args } = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // `rusty_pomo run ...` and plain `rusty_pomo ...` start the same timer.
+    let (mut args, args_matches, command) = match command {
+        Some(Command::Run(run)) => {
+            let run_matches = matches.subcommand_matches("run").unwrap_or(&matches);
+            (*run, run_matches, None)
+        }
+        other => (args, &matches, other),
+    };
+    match paths::scope(args.data_dir.clone(), args.context.clone()) {
+        Ok(Some(scope)) => paths::use_scope(scope),
+        Ok(None) => {}
+        Err(e) => exit_with_error(e),
+    }
+    match &command {
+        Some(Command::Completions { shell }) => {
+            print!("{}", completions::generate(*shell, Cli::command()));
+            return Ok(());
+        }
+        Some(Command::Manpage) => {
+            print!("{}", manpage::render(Cli::command()));
+            return Ok(());
+        }
+        Some(Command::Context { action }) => {
+            context_command(action);
+            return Ok(());
+        }
+        // Before loading the config, so a broken file can still be found and fixed.
+        Some(Command::Config { action }) => {
+            config_command(action);
+            return Ok(());
+        }
+        Some(Command::Template { action }) => {
+            template_command(action);
+            return Ok(());
+        }
+        Some(Command::Leaderboard {
+            action: LeaderboardAction::Show { dir },
+        }) => {
+            let mut board = leaderboard::read_dir(dir)
+                .unwrap_or_else(|e| exit_with_error(format!("{}: {e}", dir.display())));
+            if board.entries.is_empty() {
+                println!("No leaderboard files in {}", dir.display());
+            } else {
+                print!("{}", leaderboard::render(&mut board.entries));
+            }
+            for (path, e) in &board.skipped {
+                println!("  {}: {e}, skipped", path.display());
+            }
+            return Ok(());
+        }
+        Some(Command::Doctor) => {
+            let config = Config::load();
+            if let Ok(config) = &config {
+                config.apply(&mut args, args_matches);
+            }
+            if !doctor::run(&args, &config)? {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+    let config =
+        Config::load().unwrap_or_else(|e| Cli::command().error(ErrorKind::InvalidValue, e).exit());
+    config.apply(&mut args, args_matches);
+    if let Some(zone) = &args.tz {
+        wallclock::use_zone(zone);
+    }
+    i18n::init(args.lang.or_else(Lang::from_env).unwrap_or_default());
+    if let Err(e) = logging::init(&args) {
+        exit_with_error(format!("cannot open the log file: {e}"));
+    }
+    match &command {
+        Some(Command::Status) => {
+            status_command();
+            return Ok(());
+        }
+        Some(Command::Import { from, file }) => {
+            if !args.history || args.history_backend == StoreKind::Memory {
+                exit_with_error("import needs a persistent --history-backend");
+            }
+            let mut history = History::from_args(&args);
+            match import::run(*from, file, &mut history, args.project.as_deref()) {
+                Ok(summary) => {
+                    println!(
+                        "Imported {} sessions ({} duplicates skipped)",
+                        summary.imported, summary.duplicates
+                    );
+                    for line in &summary.conflicts {
+                        println!("  line {line}: overlaps an existing session, skipped");
+                    }
+                }
+                Err(e) => exit_with_error(e),
+            }
+            return Ok(());
+        }
+        Some(Command::History {
+            action: HistoryAction::Compact { keep_days, dry_run },
+        }) => {
+            let keep = keep_days.or(config.history.keep_days).unwrap_or(365);
+            let before = wallclock::local_day(wallclock::now_unix()) - keep as i64 + 1;
+            match history::compact(&data_dir_or_exit(&args), before, *dry_run) {
+                Ok(summary) if summary.days == 0 => {
+                    println!(
+                        "Nothing to roll up before {}",
+                        wallclock::format_date(before)
+                    );
+                }
+                Ok(summary) => println!(
+                    "{} {} sessions from {} days before {} into {} lines",
+                    if *dry_run { "Would roll" } else { "Rolled" },
+                    summary.sessions,
+                    summary.days,
+                    wallclock::format_date(before),
+                    summary.lines
+                ),
+                Err(e) => exit_with_error(format!("compaction failed: {e}")),
+            }
+            return Ok(());
+        }
+        Some(Command::History {
+            action: HistoryAction::Check { fix },
+        }) => {
+            let dir = data_dir_or_exit(&args);
+            let report = match history::check(&dir, wallclock::now_unix(), *fix) {
+                Ok(report) => report,
+                Err(e) => exit_with_error(format!("check failed: {e}")),
+            };
+            for finding in &report.findings {
+                let action = if *fix {
+                    format!(" ({})", finding.problem.fix())
+                } else {
+                    String::new()
+                };
+                println!(
+                    "{}:{}: {}{action}",
+                    finding.file, finding.line, finding.problem
+                );
+            }
+            let count = report.findings.len();
+            if count == 0 {
+                println!(
+                    "Checked {} sessions in {} files: no problems",
+                    report.sessions, report.files
+                );
+            } else if *fix {
+                println!(
+                    "Fixed {count} problems in {} sessions; anything taken out is in {}",
+                    report.sessions,
+                    history::quarantine_dir(&dir).display()
+                );
+            } else {
+                println!(
+                    "Found {count} problems in {} sessions; run with --fix to repair them",
+                    report.sessions
+                );
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::Backup {
+            action: BackupAction::Create { archive },
+        }) => {
+            let places = backup::Places::here(data_dir_or_exit(&args));
+            match backup::create(&places, archive) {
+                Ok(files) => println!("Backed up {files} files to {}", archive.display()),
+                Err(e) => exit_with_error(format!("backup failed: {e}")),
+            }
+            return Ok(());
+        }
+        Some(Command::Backup {
+            action: BackupAction::Restore { archive, force },
+        }) => {
+            let places = backup::Places::here(data_dir_or_exit(&args));
+            match backup::restore(&places, archive, *force) {
+                Ok(restored) => println!(
+                    "Restored {} files and {} sessions, {} of them new",
+                    restored.files, restored.sessions, restored.added
+                ),
+                Err(e) => exit_with_error(format!("restore failed: {e}")),
+            }
+            return Ok(());
+        }
+        Some(Command::Sync {
+            action: SyncAction::Merge { dirs },
+        }) => {
+            match history::sync_merge(&data_dir_or_exit(&args), dirs) {
+                Ok(summary) => println!(
+                    "Merged {} day shards: {} new sessions, {} conflict copies folded in",
+                    summary.shards, summary.added, summary.conflict_copies
+                ),
+                Err(e) => exit_with_error(format!("sync failed: {e}")),
+            }
+            return Ok(());
+        }
+        Some(Command::Sync {
+            action: SyncAction::Push,
+        }) => {
+            sync_push(&config);
+            return Ok(());
+        }
+        Some(Command::Hue { action }) => {
+            hue_command(action, &config);
+            return Ok(());
+        }
+        Some(Command::Stats {
+            days,
+            insights,
+            leaderboard_export,
+            name,
+            tui,
+            daily_goal,
+            project,
+        }) => {
+            let mut history = History::from_args(&args);
+            history.keep_project(project.as_deref());
+            let now = wallclock::now_unix();
+            if *tui {
+                dashboard::run(&history.records, args.theme, *daily_goal)?;
+            } else if let Some(file) = leaderboard_export {
+                let name = name.clone().unwrap_or_else(leaderboard::default_name);
+                let entry = leaderboard::Entry::summarize(&name, &history.records, now, *days);
+                match fs::write(file, entry.to_json()) {
+                    Ok(()) => println!("Wrote {}", file.display()),
+                    Err(e) => exit_with_error(format!("{}: {e}", file.display())),
+                }
+            } else if *insights {
+                let insights = stats::insights(&history.records, now, *days);
+                print!("{}", stats::render_insights(&insights, *days));
+            } else {
+                let summary = stats::summarize(&history.records, now, *days);
+                print!("{}", stats::render(&summary, *days));
+                let scores = score::trend(&history.records, now, *days, &args.score_weights);
+                print!("{}", stats::render_scores(&scores));
+            }
+            return Ok(());
+        }
+        Some(Command::Export {
+            format,
+            since,
+            until,
+            project,
+        }) => {
+            let mut history = History::from_args(&args);
+            history.keep_project(project.as_deref());
+            let mut out = io::stdout().lock();
+            export::write(&mut out, &history.records, *format, *since, *until)?;
+            return Ok(());
+        }
+        Some(Command::Report {
+            week: _,
+            month,
+            format,
+            project,
+        }) => {
+            let mut history = History::from_args(&args);
+            history.keep_project(project.as_deref());
+            let days = if *month { 30 } else { 7 };
+            let report = report::Report::build(&history.records, wallclock::now_unix(), days);
+            io::stdout().write_all(&report::render(&report, *format))?;
+            return Ok(());
+        }
+        Some(
+            Command::Run(_)
+            | Command::Context { .. }
+            | Command::Config { .. }
+            | Command::Template { .. }
+            | Command::Leaderboard { .. }
+            | Command::Completions { .. }
+            | Command::Manpage
+            | Command::Doctor,
+        )
+        | None => {}
+    }
+    // Lines printed over the full-screen UI would only garble it
+    if args.notifier == NotifierKind::Stdout && !args.background && !args.accessible {
+        exit_with_error("--notifier stdout needs --background or --accessible");
+    }
+    if let Some(path) = &args.quotes_file
+        && let Err(e) = Quotes::read(path)
+    {
+        exit_with_error(format!("cannot read {}: {e}", path.display()));
+    }
+    if let Some(name) = &args.profile
+        && !config.profiles.contains_key(name)
+    {
+        exit_with_error(format!(
+            "--profile {name}: no [profiles.{name}] in the config"
+        ));
+    }
+    if let Some(spec) = &args.calendar
+        && let Err(e) = calendar::Source::parse(spec)
+    {
+        exit_with_error(format!("--calendar {spec}: {e}"));
+    }
+    if args.cycles.is_some() && args.long_every == 0 {
+        exit_with_error("--cycles counts long breaks, and --long-every 0 turns them off");
+    }
+    if args.plan {
+        print_plan(&args);
+        return Ok(());
+    }
+    if args.detach
+        && let Err(e) = daemon::detach()
+    {
+        exit_with_error(format!("cannot detach: {e}"));
+    }
+    if let Some(frames) = args.bench_render {
+        let mut app = AppState::new(args);
+        let took = ui::bench(&mut app, frames)?;
+        println!(
+            "{frames} frames in {:.1} ms ({:.1} µs/frame)",
+            took.as_secs_f64() * 1e3,
+            took.as_secs_f64() * 1e6 / frames as f64
+        );
+        return Ok(());
+    }
+    if args.simulate.is_some() || args.demo.is_some() {
+        // Sped-up sessions would only skew the stats
+        args.history = false;
+    }
+    if args.demo.is_some() {
+        // Nothing should pop up or ring over a recording
+        args.notifications = false;
+        args.bell = BellMode::Off;
+    }
     maybe_init_macos_bundle(&args);
-    let app = AppState::new(args);
-    run(app)
+    maybe_register_windows_app_id(&args);
+    let mut app = AppState::new(args);
+    if let Some(hue) = Hue::from_config(&config.hue) {
+        app.bus.subscribe(Box::new(hue));
+    }
+    if let Some(sync) = RemoteSync::spawn(&config.remote) {
+        app.bus.subscribe(Box::new(sync));
+    }
+    if !run(app, args_matches)? {
+        std::process::exit(run::EXIT_ABANDONED);
+    }
+    Ok(())
+}
+
+fn config_command(action: &ConfigAction) {
+    match action {
+        ConfigAction::Path => match paths::config_file() {
+            Some(path) => println!("{}", path.display()),
+            None => exit_with_error("cannot locate the config directory"),
+        },
+        ConfigAction::Init { force } => match config::init(*force) {
+            Ok(path) => println!("Wrote {}", path.display()),
+            Err(e) => exit_with_error(e),
+        },
+        ConfigAction::Edit => {
+            let again = |error: &str| {
+                eprintln!("rusty_pomo: {error}");
+                eprint!("Edit again? [Y/n] ");
+                let mut answer = String::new();
+                let _ = io::stdin().read_line(&mut answer);
+                !answer.trim().eq_ignore_ascii_case("n")
+            };
+            match config::edit(again) {
+                Ok(path) => println!("Saved {}", path.display()),
+                Err(e) => exit_with_error(e),
+            }
+        }
+    }
+}
+
+/// `--plan`: the phases from now on, one a line, and when they'd be over.
+fn print_plan(args: &Args) {
+    let planned = state::plan(args, &History::from_args(args), wallclock::now_unix());
+    let width = planned
+        .iter()
+        .map(|phase| i18n::phase_title(phase.kind).chars().count())
+        .max()
+        .unwrap_or(0);
+    for phase in &planned {
+        println!(
+            "{}  {:width$}  {}",
+            wallclock::format_hh_mm(phase.start),
+            i18n::phase_title(phase.kind),
+            timefmt::spoken(phase.duration)
+        );
+    }
+    if let Some(last) = planned.last() {
+        let focus: Duration = planned
+            .iter()
+            .filter(|phase| phase.kind == PhaseKind::Focus)
+            .map(|phase| phase.duration)
+            .sum();
+        println!(
+            "Over at ~{}, with {} of focus",
+            wallclock::format_hh_mm(last.start + last.duration.as_secs()),
+            timefmt::spoken(focus)
+        );
+    }
+}
+
+fn context_command(action: &ContextAction) {
+    match action {
+        ContextAction::List => {
+            for name in paths::contexts() {
+                let mark = if name == paths::context() { '*' } else { ' ' };
+                println!("{mark} {name}");
+            }
+        }
+        ContextAction::Use { name } => {
+            let Some(file) = paths::context_file() else {
+                exit_with_error("cannot locate the config directory");
+            };
+            let saved = if name == paths::DEFAULT_CONTEXT {
+                fs::remove_file(&file).or_else(|e| match e.kind() {
+                    io::ErrorKind::NotFound => Ok(()),
+                    _ => Err(e),
+                })
+            } else {
+                file.parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|()| fs::write(&file, format!("{name}\n")))
+            };
+            match saved {
+                Ok(()) => println!("Using the {name} context from now on"),
+                Err(e) => exit_with_error(format!("{}: {e}", file.display())),
+            }
+        }
+    }
+}
+
+fn template_command(action: &TemplateAction) {
+    match action {
+        TemplateAction::Export { file, name } => {
+            let template =
+                config::export_template(name.as_deref()).unwrap_or_else(|e| exit_with_error(e));
+            match file {
+                Some(file) => match fs::write(file, template) {
+                    Ok(()) => println!("Wrote {}", file.display()),
+                    Err(e) => exit_with_error(format!("{}: {e}", file.display())),
+                },
+                None => print!("{template}"),
+            }
+        }
+        TemplateAction::Import { file } => match config::import_template(file) {
+            Ok((path, changed)) if changed.is_empty() => {
+                println!("{} already matches the template", path.display())
+            }
+            Ok((path, changed)) => println!("Updated {}: {}", path.display(), changed.join(", ")),
+            Err(e) => exit_with_error(e),
+        },
+    }
+}
+
+fn sync_push(config: &Config) {
+    let Some(endpoint) = Endpoint::from_config(&config.remote) else {
+        exit_with_error("no [remote] url in the config");
+    };
+    let Some(queue) = Queue::open() else {
+        exit_with_error("cannot locate the state directory");
+    };
+    match queue.flush(&endpoint) {
+        Ok(0) => println!("Nothing to upload"),
+        Ok(sent) => println!("Uploaded {sent} sessions"),
+        Err(e) => exit_with_error(e),
+    }
+}
+
+fn hue_command(action: &HueAction, config: &Config) {
+    match action {
+        HueAction::Pair { bridge } => {
+            let bridge = match bridge.clone().or_else(|| config.hue.bridge.clone()) {
+                Some(bridge) => bridge,
+                None => {
+                    let found = hue::discover().unwrap_or_else(|e| {
+                        exit_with_error(format!("bridge discovery failed: {e}"))
+                    });
+                    match found.as_slice() {
+                        [bridge] => bridge.clone(),
+                        [] => exit_with_error("no Hue bridge found; pass --bridge <ADDR>"),
+                        _ => exit_with_error(format!(
+                            "several Hue bridges found ({}); pass --bridge <ADDR>",
+                            found.join(", ")
+                        )),
+                    }
+                }
+            };
+            println!("Press the link button on the bridge at {bridge}...");
+            let username = hue::pair(&bridge).unwrap_or_else(|e| exit_with_error(e));
+            match config::update("hue", &[("bridge", &bridge), ("username", &username)]) {
+                Ok(path) => println!("Paired; credentials saved to {}", path.display()),
+                Err(e) => exit_with_error(format!("cannot save credentials: {e}")),
+            }
+        }
+        HueAction::Scenes => {
+            let (Some(bridge), Some(username)) = (&config.hue.bridge, &config.hue.username) else {
+                exit_with_error("no paired bridge; run `rusty_pomo hue pair` first");
+            };
+            match hue::scenes(bridge, username) {
+                Ok(scenes) => {
+                    for (id, name) in scenes {
+                        println!("{id}  {name}");
+                    }
+                }
+                Err(e) => exit_with_error(e),
+            }
+        }
+    }
+}
+
+fn data_dir_or_exit(args: &Args) -> PathBuf {
+    args.history_dir
+        .clone()
+        .or_else(paths::history_dir)
+        .unwrap_or_else(|| exit_with_error("cannot locate the data directory"))
+}
+
+/// `rusty_pomo status`: the phase and time left of the timer in the PID file,
+/// e.g. "Focus, 12:34 left · Write intro".
+fn status_command() {
+    #[cfg(unix)]
+    {
+        let (pid, status) = signals::running().unwrap_or_else(|e| exit_with_error(e));
+        let left = timefmt::mm_ss(
+            Duration::from_secs(status.left_at(wallclock::now_unix())),
+            ":",
+        );
+        let key = if status.paused {
+            "status.paused"
+        } else {
+            "status.running"
+        };
+        let mut line = i18n::tf(
+            key,
+            &[("phase", i18n::phase_title(status.kind)), ("left", &left)],
+        );
+        if let Some(task) = &status.task {
+            line.push_str(&format!(" · {task}"));
+        }
+        println!("{line} (PID {pid})");
+    }
+    #[cfg(not(unix))]
+    exit_with_error("status reads the PID file, which only Unix systems have");
+}
+
+fn exit_with_error(message: impl std::fmt::Display) -> ! {
+    eprintln!("rusty_pomo: {message}");
+    std::process::exit(1)
 }
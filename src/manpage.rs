@@ -0,0 +1,149 @@
+//! A roff man page rendered from the clap definition.
+
+use clap::Arg;
+
+pub fn render(mut cmd: clap::Command) -> String {
+    cmd.build();
+    let name = cmd.get_name().to_string();
+    let version = cmd.get_version().unwrap_or_default().to_string();
+    let mut out = format!(
+        ".TH {} 1 \"\" \"{name} {version}\"\n.SH NAME\n{} \\- {}\n",
+        name.to_uppercase(),
+        escape(&name),
+        escape(&about(cmd.get_about()))
+    );
+    out.push_str(&format!(
+        ".SH SYNOPSIS\n\\fB{}\\fR [\\fIOPTIONS\\fR] [\\fICOMMAND\\fR]\n",
+        escape(&name)
+    ));
+    out.push_str(".SH OPTIONS\n");
+    options(&cmd, &mut out);
+    out.push_str(".SH COMMANDS\n");
+    commands(&cmd, &name, &mut out);
+    out.push_str(&format!(
+        ".SH FILES\n.TP\n\\fI~/.config/{name}/config.toml\\fR\nProjects, history, Hue and ambient sound settings; flags given on the command line win.\n"
+    ));
+    out
+}
+
+fn options(cmd: &clap::Command, out: &mut String) {
+    for arg in cmd.get_arguments().filter(|a| !a.is_hide_set()) {
+        out.push_str(".TP\n");
+        out.push_str(&signature(arg));
+        out.push('\n');
+        let mut help = about(arg.get_long_help().or(arg.get_help()));
+        let values: Vec<_> = arg
+            .get_possible_values()
+            .iter()
+            .filter(|v| !v.is_hide_set())
+            .map(|v| v.get_name().to_string())
+            .collect();
+        let takes_value = arg.get_action().takes_values();
+        if takes_value && !values.is_empty() && values != ["true", "false"] {
+            help.push_str(&format!(" [possible values: {}]", values.join(", ")));
+        }
+        let defaults: Vec<_> = arg
+            .get_default_values()
+            .iter()
+            .map(|v| v.to_string_lossy().into_owned())
+            .collect();
+        if takes_value && !defaults.is_empty() {
+            help.push_str(&format!(" [default: {}]", defaults.join(", ")));
+        }
+        out.push_str(&escape(help.trim()));
+        out.push('\n');
+    }
+}
+
+fn signature(arg: &Arg) -> String {
+    if arg.is_positional() {
+        return format!("\\fI{}\\fR", escape(arg.get_id().as_str()).to_uppercase());
+    }
+    let mut names = Vec::new();
+    if let Some(short) = arg.get_short() {
+        names.push(format!("\\fB\\-{short}\\fR"));
+    }
+    if let Some(long) = arg.get_long() {
+        names.push(format!("\\fB\\-\\-{}\\fR", escape(long)));
+    }
+    let mut signature = names.join(", ");
+    if arg.get_action().takes_values() {
+        let value = arg
+            .get_value_names()
+            .and_then(|names| names.first())
+            .map_or_else(|| arg.get_id().as_str().to_uppercase(), |n| n.to_string());
+        signature.push_str(&format!(" \\fI{}\\fR", escape(&value)));
+    }
+    signature
+}
+
+fn commands(cmd: &clap::Command, prefix: &str, out: &mut String) {
+    for sub in cmd.get_subcommands().filter(|s| !s.is_hide_set()) {
+        if sub.get_name() == "help" {
+            continue;
+        }
+        let path = format!("{prefix} {}", sub.get_name());
+        out.push_str(&format!(".SS \"{}\"\n", escape(&path)));
+        out.push_str(&escape(&about(sub.get_about())));
+        out.push('\n');
+        let ids = |cmd: &clap::Command| -> Vec<String> {
+            cmd.get_arguments()
+                .filter(|a| !a.is_hide_set() && !["help", "version"].contains(&a.get_id().as_str()))
+                .map(|a| a.get_id().to_string())
+                .collect()
+        };
+        let own = ids(sub);
+        if !own.is_empty() && own == ids(cmd) {
+            out.push_str(&format!(
+                "\n.PP\nTakes the same options as \\fB{}\\fR.\n",
+                escape(prefix)
+            ));
+        } else if !own.is_empty() {
+            options(sub, out);
+        }
+        commands(sub, &path, out);
+    }
+}
+
+fn about(text: Option<&clap::builder::StyledStr>) -> String {
+    text.map(ToString::to_string).unwrap_or_default()
+}
+
+/// Backslashes and hyphens, plus a guard for lines that would read as requests.
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    escaped
+        .lines()
+        .map(|line| {
+            if line.starts_with('.') || line.starts_with('\'') {
+                format!("\\&{line}")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::Cli;
+    use clap::CommandFactory;
+
+    #[test]
+    fn renders_options_and_nested_commands() {
+        let page = render(Cli::command());
+        assert!(page.starts_with(".TH RUSTY_POMO 1"));
+        assert!(page.contains("\\fB\\-f\\fR, \\fB\\-\\-focus\\fR \\fIDURATION\\fR"));
+        assert!(page.contains("[default: 25m]"));
+        assert!(page.contains(".SS \"rusty_pomo sync merge\""));
+        assert!(page.contains("Takes the same options as \\fBrusty_pomo\\fR."));
+    }
+
+    #[test]
+    fn escapes_roff() {
+        assert_eq!(escape("a-b\\c"), "a\\-b\\ec");
+        assert_eq!(escape(".hidden"), "\\&.hidden");
+    }
+}
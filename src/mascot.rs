@@ -0,0 +1,136 @@
+//! `--mascot`: a little ASCII tomato or cat under the gauge that grows as a
+//! focus session goes on, and cheers for a few seconds once it's done.
+
+use std::time::Duration;
+
+use clap::ValueEnum;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::widgets::Widget;
+
+/// Rows the art takes.
+pub const HEIGHT: u16 = 4;
+/// How long the mascot cheers at the start of the break after a finished focus session.
+pub const CHEERING: Duration = Duration::from_secs(5);
+/// Time between the two frames of the cheer.
+pub const CHEER_FRAME: Duration = Duration::from_millis(500);
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Mascot {
+    #[default]
+    Off,
+    Tomato,
+    Cat,
+}
+
+/// What the mascot is doing.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Pose {
+    /// During focus, from 0 (just started) to `STAGES - 1` (nearly done).
+    Growing(usize),
+    /// After a finished focus session, frame 0 or 1.
+    Cheering(usize),
+}
+
+const STAGES: usize = 4;
+
+const TOMATO: [[&str; 4]; STAGES] = [
+    ["", "  ,", " \\|/", "~~~~~"],
+    [" \\|/", " .-.", "(   )", " '-'"],
+    ["  \\|/", " .---.", "(     )", " '---'"],
+    ["  \\|/", " .---.", "( o o )", " '---'"],
+];
+const TOMATO_CHEER: [[&str; 4]; 2] = [
+    ["\\ \\|/ /", " .---.", "( ^_^ )", " '---'"],
+    ["* \\|/ *", " .---.", "( ^o^ )", " '---'"],
+];
+
+const CAT: [[&str; 4]; STAGES] = [
+    ["", " /\\_/\\", "( -.- ) z", " (\")(\")"],
+    ["", " /\\_/\\", "( -.o )", " (\")(\")"],
+    ["", " /\\_/\\", "( o.o )", " (\")(\")"],
+    ["", " /\\_/\\", "( o.o )~", " (\")(\")"],
+];
+const CAT_CHEER: [[&str; 4]; 2] = [
+    ["\\ /\\_/\\ /", "  ( ^o^ )", "  (\")(\")", ""],
+    ["", " /\\_/\\", "( ^_^ )~", " (\")(\")"],
+];
+
+/// How far along the mascot has grown at `progress`, 0 to 1.
+pub fn stage(progress: f64) -> usize {
+    ((progress * STAGES as f64) as usize).min(STAGES - 1)
+}
+
+/// The cheer's frame `elapsed` into the break.
+pub fn cheer_frame(elapsed: Duration) -> usize {
+    (elapsed.as_millis() / CHEER_FRAME.as_millis() % 2) as usize
+}
+
+/// The mascot in its pose, centered in the area.
+pub struct MascotArt {
+    pub mascot: Mascot,
+    pub pose: Pose,
+    /// The phase's color, for the cat and the ripe tomato.
+    pub color: Color,
+}
+
+impl MascotArt {
+    fn lines(&self) -> [&'static str; 4] {
+        match (self.mascot, self.pose) {
+            (Mascot::Off, _) => [""; 4],
+            (Mascot::Tomato, Pose::Growing(stage)) => TOMATO[stage.min(STAGES - 1)],
+            (Mascot::Tomato, Pose::Cheering(frame)) => TOMATO_CHEER[frame % 2],
+            (Mascot::Cat, Pose::Growing(stage)) => CAT[stage.min(STAGES - 1)],
+            (Mascot::Cat, Pose::Cheering(frame)) => CAT_CHEER[frame % 2],
+        }
+    }
+
+    /// A tomato ripens from green through yellow to the phase's color.
+    fn art_color(&self) -> Color {
+        match (self.mascot, self.pose) {
+            (Mascot::Tomato, Pose::Growing(0 | 1)) => Color::Green,
+            (Mascot::Tomato, Pose::Growing(2)) => Color::Yellow,
+            _ => self.color,
+        }
+    }
+}
+
+impl Widget for MascotArt {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let lines = self.lines();
+        // One left edge for all lines, so the art keeps its shape
+        let width = lines.iter().map(|l| l.len()).max().unwrap_or(0) as u16;
+        let x = area.x + area.width.saturating_sub(width) / 2;
+        let style = Style::default().fg(self.art_color());
+        for (row, line) in lines.iter().enumerate().take(usize::from(area.height)) {
+            buf.set_stringn(x, area.y + row as u16, line, usize::from(area.width), style);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grows_through_the_session_and_cheers_after() {
+        assert_eq!(stage(0.0), 0);
+        assert_eq!(stage(0.3), 1);
+        assert_eq!(stage(0.74), 2);
+        assert_eq!(stage(0.99), 3);
+        assert_eq!(stage(1.0), 3);
+        assert_eq!(cheer_frame(Duration::from_millis(400)), 0);
+        assert_eq!(cheer_frame(Duration::from_millis(700)), 1);
+        assert_eq!(cheer_frame(Duration::from_millis(1200)), 0);
+        // Every frame fits the rows and a narrow window
+        for art in TOMATO
+            .iter()
+            .chain(&TOMATO_CHEER)
+            .chain(&CAT)
+            .chain(&CAT_CHEER)
+        {
+            assert!(art.iter().all(|line| line.len() <= 11), "{art:?}");
+        }
+    }
+}
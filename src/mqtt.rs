@@ -0,0 +1,292 @@
+//! A minimal MQTT 3.1.1 client (QoS 0 only) that mirrors the timer to a broker
+//! and announces it to Home Assistant through MQTT discovery.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::control::Control;
+use crate::doctor::Probe;
+use crate::json::Value;
+use crate::server::Remote;
+
+const STATE_TOPIC: &str = "rusty_pomo/state";
+const AVAILABILITY_TOPIC: &str = "rusty_pomo/availability";
+const PAUSE_COMMAND_TOPIC: &str = "rusty_pomo/pause/set";
+const DISCOVERY_PREFIX: &str = "homeassistant";
+const KEEP_ALIVE: Duration = Duration::from_secs(60);
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+const CONNACK: u8 = 2;
+const PUBLISH: u8 = 3;
+
+/// Connects to the broker at `addr`, publishes discovery configs and then the
+/// status whenever it changes. Home Assistant's pause switch comes back as commands.
+pub fn spawn(addr: &str) -> io::Result<Remote> {
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    stream.write_all(&connect_packet(&format!(
+        "rusty_pomo-{}",
+        std::process::id()
+    )))?;
+    match read_packet(&mut stream)? {
+        (CONNACK, body) if body.get(1) == Some(&0) => {}
+        _ => return Err(io::Error::other("MQTT broker refused the connection")),
+    }
+    stream.set_read_timeout(None)?;
+    for (topic, payload) in discovery() {
+        stream.write_all(&publish_packet(&topic, &payload.to_string(), true))?;
+    }
+    stream.write_all(&publish_packet(AVAILABILITY_TOPIC, "online", true))?;
+    stream.write_all(&subscribe_packet(PAUSE_COMMAND_TOPIC))?;
+
+    let (remote, commands, status) = Remote::new();
+    let mut reader = stream.try_clone()?;
+    thread::spawn(move || {
+        while let Ok((kind, body)) = read_packet(&mut reader) {
+            if kind == PUBLISH
+                && let Some((PAUSE_COMMAND_TOPIC, payload)) = parse_publish(&body)
+                && let Some(command) = pause_command(payload)
+                && commands.send(command).is_err()
+            {
+                return;
+            }
+        }
+        tracing::warn!("MQTT connection closed");
+    });
+    thread::spawn(move || {
+        let mut last = Value::Null;
+        let mut last_sent = Instant::now();
+        loop {
+            let now = status.lock().map(|s| s.clone()).unwrap_or(Value::Null);
+            let packet = if now != last {
+                let packet = publish_packet(STATE_TOPIC, &now.to_string(), true);
+                last = now;
+                packet
+            } else if last_sent.elapsed() >= KEEP_ALIVE / 2 {
+                vec![0xC0, 0]
+            } else {
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            };
+            if stream.write_all(&packet).is_err() {
+                break;
+            }
+            last_sent = Instant::now();
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+    Ok(remote)
+}
+
+/// Whether the broker is reachable, for `doctor`. Only connects; nothing is published.
+pub fn probe(addr: Option<&str>) -> Probe {
+    let Some(addr) = addr else {
+        return Probe::Off("not enabled (--mqtt)".to_string());
+    };
+    let reached = addr.to_socket_addrs().and_then(|mut addrs| {
+        let addr = addrs
+            .next()
+            .ok_or_else(|| io::Error::other("no address found"))?;
+        TcpStream::connect_timeout(&addr, PROBE_TIMEOUT)
+    });
+    match reached {
+        Ok(_) => Probe::Ok(format!("broker at {addr} is reachable")),
+        Err(e) => Probe::Fail(format!("cannot reach the broker at {addr}: {e}")),
+    }
+}
+
+/// Retained Home Assistant discovery configs: phase and remaining-time sensors
+/// and a pause switch, all grouped under one device.
+fn discovery() -> Vec<(String, Value)> {
+    let entity = |component: &str, id: &str, mut fields: Vec<(&str, Value)>| {
+        fields.extend([
+            ("unique_id", format!("rusty_pomo_{id}").as_str().into()),
+            ("state_topic", STATE_TOPIC.into()),
+            ("availability_topic", AVAILABILITY_TOPIC.into()),
+            (
+                "device",
+                Value::Object(vec![
+                    (
+                        "identifiers".to_string(),
+                        Value::Array(vec!["rusty_pomo".into()]),
+                    ),
+                    ("name".to_string(), "Rusty Pomo".into()),
+                ]),
+            ),
+        ]);
+        let payload = fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        (
+            format!("{DISCOVERY_PREFIX}/{component}/rusty_pomo/{id}/config"),
+            Value::Object(payload),
+        )
+    };
+    vec![
+        entity(
+            "sensor",
+            "phase",
+            vec![
+                ("name", "Phase".into()),
+                ("value_template", "{{ value_json.phase }}".into()),
+            ],
+        ),
+        entity(
+            "sensor",
+            "remaining",
+            vec![
+                ("name", "Remaining".into()),
+                ("device_class", "duration".into()),
+                ("unit_of_measurement", "s".into()),
+                ("value_template", "{{ value_json.remaining_secs }}".into()),
+            ],
+        ),
+        entity(
+            "switch",
+            "paused",
+            vec![
+                ("name", "Paused".into()),
+                ("command_topic", PAUSE_COMMAND_TOPIC.into()),
+                (
+                    "value_template",
+                    "{{ 'ON' if value_json.paused else 'OFF' }}".into(),
+                ),
+            ],
+        ),
+    ]
+}
+
+fn pause_command(payload: &[u8]) -> Option<Control> {
+    match payload {
+        b"ON" => Some(Control::Pause),
+        b"OFF" => Some(Control::Resume),
+        _ => None,
+    }
+}
+
+fn packet(header: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut len = body.len();
+    loop {
+        let byte = (len % 128) as u8;
+        len /= 128;
+        packet.push(if len > 0 { byte | 0x80 } else { byte });
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(body);
+    packet
+}
+
+fn push_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+/// CONNECT with a clean session and a retained "offline" will on the availability topic.
+fn connect_packet(client_id: &str) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_str(&mut body, "MQTT");
+    body.push(4);
+    body.push(0x02 | 0x04 | 0x20);
+    body.extend_from_slice(&(KEEP_ALIVE.as_secs() as u16).to_be_bytes());
+    push_str(&mut body, client_id);
+    push_str(&mut body, AVAILABILITY_TOPIC);
+    push_str(&mut body, "offline");
+    packet(0x10, &body)
+}
+
+fn publish_packet(topic: &str, payload: &str, retain: bool) -> Vec<u8> {
+    let mut body = Vec::new();
+    push_str(&mut body, topic);
+    body.extend_from_slice(payload.as_bytes());
+    packet(0x30 | u8::from(retain), &body)
+}
+
+fn subscribe_packet(topic: &str) -> Vec<u8> {
+    let mut body = vec![0, 1];
+    push_str(&mut body, topic);
+    body.push(0);
+    packet(0x82, &body)
+}
+
+/// Reads one packet, returning its type and body.
+fn read_packet(reader: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut byte = [0];
+    reader.read_exact(&mut byte)?;
+    let kind = byte[0] >> 4;
+    let mut len = 0;
+    for shift in (0..28).step_by(7) {
+        reader.read_exact(&mut byte)?;
+        len |= ((byte[0] & 0x7f) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    Ok((kind, body))
+}
+
+/// Topic and payload of a QoS 0 PUBLISH body.
+fn parse_publish(body: &[u8]) -> Option<(&str, &[u8])> {
+    let len = u16::from_be_bytes([*body.first()?, *body.get(1)?]) as usize;
+    let topic = std::str::from_utf8(body.get(2..2 + len)?).ok()?;
+    Some((topic, &body[2 + len..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_packets() {
+        assert_eq!(
+            publish_packet("a/b", "hi", true),
+            vec![0x31, 7, 0, 3, b'a', b'/', b'b', b'h', b'i']
+        );
+        let long = publish_packet("t", &"x".repeat(200), false);
+        assert_eq!(&long[..3], &[0x30, 0xCB, 0x01]);
+        let (kind, body) = read_packet(&mut long.as_slice()).unwrap();
+        assert_eq!(kind, PUBLISH);
+        assert_eq!(
+            parse_publish(&body).map(|(t, p)| (t, p.len())),
+            Some(("t", 200))
+        );
+        assert_eq!(
+            &connect_packet("id")[..9],
+            &[0x10, 48, 0, 4, b'M', b'Q', b'T', b'T', 4]
+        );
+    }
+
+    #[test]
+    fn announces_home_assistant_entities() {
+        let configs = discovery();
+        let topics: Vec<&str> = configs.iter().map(|(t, _)| t.as_str()).collect();
+        assert_eq!(
+            topics,
+            [
+                "homeassistant/sensor/rusty_pomo/phase/config",
+                "homeassistant/sensor/rusty_pomo/remaining/config",
+                "homeassistant/switch/rusty_pomo/paused/config",
+            ]
+        );
+        let switch = &configs[2].1;
+        assert_eq!(
+            switch.get("command_topic").and_then(Value::as_str),
+            Some(PAUSE_COMMAND_TOPIC)
+        );
+        assert_eq!(
+            switch.get("state_topic").and_then(Value::as_str),
+            Some(STATE_TOPIC)
+        );
+        assert_eq!(pause_command(b"ON"), Some(Control::Pause));
+        assert_eq!(pause_command(b"OFF"), Some(Control::Resume));
+        assert_eq!(pause_command(b"maybe"), None);
+    }
+}
@@ -1,8 +1,26 @@
-use crate::state::{AppState, PhaseKind};
+use crate::achievements;
+use crate::args::Args;
+use crate::audio;
+use crate::control::Control;
+use crate::doctor::Probe;
+use crate::events::{PhaseEvent, Subscriber};
+use crate::i18n::{self, t, tf};
+use crate::integrations::push::{Push, Target};
+use crate::osc;
+use crate::phases::CustomPhase;
+use crate::ssh;
+use crate::state::PhaseKind;
+use crate::wallclock;
+use crate::work_hours::WorkHours;
+use clap::ValueEnum;
 use notify_rust::Notification;
 #[cfg(target_os = "macos")]
 use notify_rust::set_application;
-use std::path::Path;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
 use std::time::Duration;
 
 #[allow(unused_variables)] // Because it's only used on macOS
@@ -13,47 +31,721 @@ pub fn maybe_init_macos_bundle(args: &crate::args::Args) {
     }
 }
 
-pub fn maybe_notify(app: &AppState) {
-    if !app.args.notifications {
-        return;
+/// AppUserModelID that toasts are attributed to unless `--windows-app-id` names another.
+#[cfg(target_os = "windows")]
+const WINDOWS_APP_ID: &str = "jorbush.RustyPomo";
+
+/// Registers the default AppUserModelID with a display name and icon, so toasts
+/// read "Rusty Pomo" instead of "Windows PowerShell". An ID given with
+/// `--windows-app-id` belongs to whatever installed it and is left alone.
+#[allow(unused_variables)] // Because it's only used on Windows
+pub fn maybe_register_windows_app_id(args: &Args) {
+    #[cfg(target_os = "windows")]
+    if args.windows_app_id.is_none() {
+        use std::process::{Command, Stdio};
+        let key = format!(r"HKCU\Software\Classes\AppUserModelId\{WINDOWS_APP_ID}");
+        let mut values = vec![("DisplayName", "Rusty Pomo".to_string())];
+        if let Some(icon) = crate::icon::path() {
+            values.push(("IconUri", icon.to_string_lossy().into_owned()));
+        }
+        for (name, data) in values {
+            let _ = Command::new("reg")
+                .args(["add", &key, "/v", name, "/t", "REG_SZ", "/d", &data, "/f"])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+        }
+    }
+}
+
+/// Whether notifications will show up, for `doctor`.
+pub fn probe(args: &Args) -> Probe {
+    if !args.notifications {
+        return Probe::Off("turned off with --notifications false".to_string());
+    }
+    if args.notifier == NotifierKind::Stdout {
+        return Probe::Ok("printed to standard output".to_string());
+    }
+    if args.notifier == NotifierKind::Terminal || ssh::active(args) {
+        let terminal = osc::Terminal::detect();
+        let tmux = if terminal.tmux {
+            "; inside tmux, they need `set -g allow-passthrough on`"
+        } else {
+            ""
+        };
+        return Probe::Ok(format!(
+            "sent as {} escapes for the terminal on your end{tmux}",
+            terminal.escape
+        ));
+    }
+    platform_probe(args)
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn platform_probe(_args: &Args) -> Probe {
+    match notify_rust::get_server_information() {
+        Ok(server) => Probe::Ok(format!(
+            "{} {} by {}",
+            server.name, server.version, server.vendor
+        )),
+        Err(e) => Probe::Fail(format!(
+            "no notification server answered on D-Bus ({e}); start one such as dunst or mako"
+        )),
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn platform_probe(args: &Args) -> Probe {
+    match &args.macos_bundle_id {
+        Some(id) => Probe::Ok(format!("sent as {id}")),
+        None => Probe::Warn(
+            "sent without an app icon; see docs/macos-notifications.md for --macos-bundle-id"
+                .to_string(),
+        ),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn platform_probe(args: &Args) -> Probe {
+    let id = args.windows_app_id.as_deref().unwrap_or(WINDOWS_APP_ID);
+    Probe::Ok(format!("toasts attributed to {id}"))
+}
+
+/// Where phase changes are announced. Delivery is best effort: failures are dropped.
+pub trait Notifier: fmt::Debug {
+    fn notify(&self, title: &str, body: &str);
+    /// Like `notify`, with a button; notifiers that have none just notify.
+    fn notify_with_button(&self, title: &str, body: &str, _button: Button) {
+        self.notify(title, body);
+    }
+}
+
+pub struct Button {
+    pub label: String,
+    pub on_click: Box<dyn FnOnce() + Send>,
+}
+
+/// The events `[notifications]` can send somewhere else than `--notifier`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Route {
+    /// A phase ended and the next one started.
+    PhaseEnd,
+    /// Like `PhaseEnd`, for a long break only; it takes precedence.
+    LongBreak,
+    /// Like `PhaseEnd`, for focus starting after a break; it takes precedence.
+    BreakEnd,
+    /// A meeting from `--calendar` is near.
+    WarnBefore,
+    /// The last of `--cycles` ended.
+    Done,
+    Achievement,
+}
+
+impl Route {
+    pub const ALL: [Route; 6] = [
+        Route::PhaseEnd,
+        Route::LongBreak,
+        Route::BreakEnd,
+        Route::WarnBefore,
+        Route::Done,
+        Route::Achievement,
+    ];
+
+    /// The key in `[notifications]`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Route::PhaseEnd => "phase_end",
+            Route::LongBreak => "long_break",
+            Route::BreakEnd => "break_end",
+            Route::WarnBefore => "warn_before",
+            Route::Done => "done",
+            Route::Achievement => "achievement",
+        }
+    }
+}
+
+/// Where a routed event goes; several can be combined.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Channel {
+    /// A desktop notification, silent unless routed to `sound` too.
+    Desktop,
+    /// The notification sound; a chime of its own without `desktop`.
+    Sound,
+    Stdout,
+    /// An escape the terminal shows as a notification, even over SSH.
+    Terminal,
+    /// A push to the ntfy topic in `[push]`.
+    Ntfy,
+    /// An email to the address in `[push]`.
+    Email,
+}
+
+impl Channel {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "desktop" => Some(Channel::Desktop),
+            "sound" => Some(Channel::Sound),
+            "stdout" => Some(Channel::Stdout),
+            "terminal" => Some(Channel::Terminal),
+            "ntfy" => Some(Channel::Ntfy),
+            "email" => Some(Channel::Email),
+            _ => None,
+        }
     }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    Normal,
+    /// Stays on screen until dismissed.
+    Critical,
+}
+
+impl Urgency {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "low" => Some(Urgency::Low),
+            "normal" => Some(Urgency::Normal),
+            "critical" => Some(Urgency::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// `[notifications.style]`: how an event's desktop notification behaves.
+/// Only XDG notification servers (Linux and the BSDs) take these.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub urgency: Option<Urgency>,
+    /// Kept in the notification center after its button is clicked.
+    pub resident: Option<bool>,
+    /// Never kept in the notification center.
+    pub transient: Option<bool>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NotifierKind {
+    /// Desktop notifications
+    Desktop,
+    /// A line on standard output, for --background and scripts
+    Stdout,
+    /// Terminal escapes (OSC 9, 777 or 99) that the terminal shows, even over SSH
+    Terminal,
+}
+
+/// The notifier selected by `--notifications` and `--notifier`.
+pub fn from_args(args: &Args) -> Box<dyn Notifier> {
+    if !args.notifications {
+        return Box::new(Silent);
+    }
+    match args.notifier {
+        NotifierKind::Desktop => desktop(args, true, Style::default()),
+        NotifierKind::Stdout => Box::new(Stdout),
+        NotifierKind::Terminal => Box::new(Terminal::escape()),
+    }
+}
+
+/// A desktop notification, or in remote mode an escape for the terminal; the
+/// bell rings for that already.
+fn desktop(args: &Args, audible: bool, style: Style) -> Box<dyn Notifier> {
+    if ssh::active(args) {
+        return Box::new(Terminal::escape());
+    }
+    Box::new(Desktop::from_args(args, audible).styled(style))
+}
 
-    let (title, body) = match app.current_phase.kind {
-        PhaseKind::Focus => ("Focus", "Let’s get to work."),
-        PhaseKind::ShortBreak => ("Short Break", "Time for a quick breather."),
-        PhaseKind::LongBreak => ("Long Break", "Enjoy a longer rest."),
+/// A notifier for each event `[notifications]` routes or styles. A styled
+/// event that isn't routed goes where it would have, in its style.
+fn routes(args: &Args) -> Vec<(Route, Box<dyn Notifier>)> {
+    let pushes = args.notification_routes.iter().any(|(_, channels)| {
+        channels.contains(&Channel::Ntfy) || channels.contains(&Channel::Email)
+    });
+    let push = if pushes && args.notifications {
+        Push::spawn(&args.push)
+    } else {
+        None
+    };
+    let channels_of = |route: Route| {
+        args.notification_routes
+            .iter()
+            .find(|(r, _)| *r == route)
+            .map(|(_, channels)| channels.as_slice())
+    };
+    let style_of = |route: Route| {
+        args.notification_styles
+            .iter()
+            .find(|(r, _)| *r == route)
+            .map_or_else(Style::default, |(_, style)| *style)
     };
+    Route::ALL
+        .into_iter()
+        .filter_map(|route| {
+            let styled = args.notification_styles.iter().any(|(r, _)| *r == route);
+            let channels = match (channels_of(route), route) {
+                (Some(channels), _) => Some(channels),
+                (None, _) if !styled => return None,
+                (None, Route::LongBreak | Route::BreakEnd) => channels_of(Route::PhaseEnd),
+                (None, _) => None,
+            };
+            let style = style_of(route);
+            let notifier: Box<dyn Notifier> = match channels {
+                _ if !args.notifications => Box::new(Silent),
+                Some(channels) => Box::new(Fanout(channels_notifiers(
+                    args,
+                    channels,
+                    push.as_ref(),
+                    style,
+                ))),
+                None => match args.notifier {
+                    NotifierKind::Desktop => desktop(args, true, style),
+                    NotifierKind::Stdout => Box::new(Stdout),
+                    NotifierKind::Terminal => Box::new(Terminal::escape()),
+                },
+            };
+            Some((route, notifier))
+        })
+        .collect()
+}
+
+fn channels_notifiers(
+    args: &Args,
+    channels: &[Channel],
+    push: Option<&Push>,
+    style: Style,
+) -> Vec<Box<dyn Notifier>> {
+    let sound = channels.contains(&Channel::Sound);
+    let desktop = channels.contains(&Channel::Desktop);
+    let remote = ssh::active(args);
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+    // Over SSH, desktop means the terminal's notifications and sound its bell
+    let escape = channels.contains(&Channel::Terminal) || (remote && desktop);
+    if escape || (remote && sound) {
+        notifiers.push(Box::new(Terminal {
+            escape: escape.then(osc::Terminal::detect),
+            bell: remote && sound,
+        }));
+    }
+    if remote {
+        // Nothing to show or play on this machine
+    } else if desktop {
+        notifiers.push(Box::new(Desktop::from_args(args, sound).styled(style)));
+    } else if sound {
+        notifiers.push(Box::new(Chime {
+            file: args
+                .notification_sound
+                .as_deref()
+                .map(PathBuf::from)
+                .filter(|path| path.is_file()),
+        }));
+    }
+    if channels.contains(&Channel::Stdout) {
+        notifiers.push(Box::new(Stdout));
+    }
+    if let Some(push) = push {
+        for (channel, target) in [
+            (Channel::Ntfy, Target::Ntfy),
+            (Channel::Email, Target::Email),
+        ] {
+            if channels.contains(&channel) {
+                notifiers.push(Box::new(push.to(target)));
+            }
+        }
+    }
+    notifiers
+}
 
-    let mut n = Notification::new();
-    n.summary(&format!("Rusty Pomo · {title}")).body(body);
+/// Turns phase events into notifications: one per new phase, with a Snooze
+/// button once connected, and one when every `--cycles` cycle is done.
+#[derive(Debug)]
+pub struct Notifications {
+    notifier: Box<dyn Notifier>,
+    snooze_minutes: u64,
+    controls: Option<Sender<Control>>,
+    /// Nothing is shown outside these hours.
+    quiet_outside: Option<WorkHours>,
+    /// `[phases]`, for the text each starts with.
+    phases: Vec<CustomPhase>,
+    /// Events `[notifications]` sends elsewhere than `notifier`.
+    routes: Vec<(Route, Box<dyn Notifier>)>,
+    /// Whether the last phase to start was a break.
+    after_break: bool,
+}
+
+impl Notifications {
+    pub fn new(notifier: Box<dyn Notifier>, snooze_minutes: u64) -> Self {
+        Self {
+            notifier,
+            snooze_minutes,
+            controls: None,
+            quiet_outside: None,
+            phases: Vec::new(),
+            routes: Vec::new(),
+            after_break: false,
+        }
+    }
+
+    /// Sends `route`'s events to `notifier` instead.
+    pub fn route(mut self, route: Route, notifier: Box<dyn Notifier>) -> Self {
+        self.routes.retain(|(r, _)| *r != route);
+        self.routes.push((route, notifier));
+        self
+    }
+
+    /// Routes the events as `[notifications]` says.
+    pub fn routes(self, args: &Args) -> Self {
+        routes(args)
+            .into_iter()
+            .fold(self, |this, (route, notifier)| this.route(route, notifier))
+    }
+
+    fn notifier_for(&self, route: Route) -> &dyn Notifier {
+        self.routes
+            .iter()
+            .find(|(r, _)| *r == route)
+            .map_or(self.notifier.as_ref(), |(_, notifier)| notifier.as_ref())
+    }
+
+    /// Keeps quiet outside `hours`, unless they allow notifications.
+    pub fn quiet_outside(mut self, hours: Option<&WorkHours>) -> Self {
+        self.quiet_outside = hours.filter(|h| !h.notifications).cloned();
+        self
+    }
+
+    /// Greets the custom `phases` with their messages.
+    pub fn phases(mut self, phases: &[CustomPhase]) -> Self {
+        self.phases = phases.to_vec();
+        self
+    }
+}
+
+impl Subscriber for Notifications {
+    fn on_event(&mut self, event: &PhaseEvent) {
+        let now = wallclock::now_unix();
+        if self
+            .quiet_outside
+            .as_ref()
+            .is_some_and(|hours| !hours.contains(now))
+        {
+            return;
+        }
+        match event {
+            PhaseEvent::Started { kind, quote } => {
+                let greeting = match kind {
+                    PhaseKind::WarmUp => t("notify.warm_up").to_string(),
+                    PhaseKind::Focus => t("notify.focus").to_string(),
+                    PhaseKind::ShortBreak => t("notify.short_break").to_string(),
+                    PhaseKind::LongBreak => t("notify.long_break").to_string(),
+                    PhaseKind::CycleReview => t("notify.cycle_review").to_string(),
+                    PhaseKind::Lunch => t("notify.lunch").to_string(),
+                    PhaseKind::Custom(name) => {
+                        let phase = self.phases.iter().find(|p| p.kind == *kind);
+                        match phase.and_then(|p| p.message.clone()) {
+                            Some(message) => message,
+                            None => tf("notify.custom", &[("phase", name)]),
+                        }
+                    }
+                };
+                let body = match quote {
+                    Some(quote) => &format!("{greeting} {quote}"),
+                    None => &greeting,
+                };
+                let kind = *kind;
+                let title = i18n::phase_title(kind);
+                let routed = |route| self.routes.iter().any(|(r, _)| *r == route);
+                let route = match kind {
+                    PhaseKind::LongBreak if routed(Route::LongBreak) => Route::LongBreak,
+                    PhaseKind::Focus if self.after_break && routed(Route::BreakEnd) => {
+                        Route::BreakEnd
+                    }
+                    _ => Route::PhaseEnd,
+                };
+                self.after_break = matches!(
+                    kind,
+                    PhaseKind::ShortBreak | PhaseKind::LongBreak | PhaseKind::Lunch
+                );
+                let notifier = self.notifier_for(route);
+                match &self.controls {
+                    Some(controls) => {
+                        let controls = controls.clone();
+                        let minutes = self.snooze_minutes.to_string();
+                        let button = Button {
+                            label: tf("notify.snooze", &[("n", &minutes)]),
+                            on_click: Box::new(move || {
+                                let _ = controls.send(Control::Snooze);
+                            }),
+                        };
+                        notifier.notify_with_button(title, body, button);
+                    }
+                    None => notifier.notify(title, body),
+                }
+            }
+            PhaseEvent::Done {
+                cycles,
+                focus_sessions,
+            } => self
+                .notifier_for(Route::Done)
+                .notify(t("done.title"), &i18n::done(*cycles, *focus_sessions)),
+            PhaseEvent::MeetingSoon {
+                summary,
+                minutes,
+                clash,
+            } => {
+                let minutes = minutes.to_string();
+                let body = i18n::meeting_name(summary);
+                let notifier = self.notifier_for(Route::WarnBefore);
+                if !*clash {
+                    let title = tf("notify.meeting", &[("n", &minutes)]);
+                    notifier.notify(&title, body);
+                    return;
+                }
+                let title = tf("notify.meeting_clash", &[("n", &minutes)]);
+                match &self.controls {
+                    Some(controls) => {
+                        let controls = controls.clone();
+                        let button = Button {
+                            label: t("notify.shorten").to_string(),
+                            on_click: Box::new(move || {
+                                let _ = controls.send(Control::FitMeeting);
+                            }),
+                        };
+                        notifier.notify_with_button(&title, body, button);
+                    }
+                    None => notifier.notify(&title, body),
+                }
+            }
+            PhaseEvent::Unlocked(key) => {
+                if let Some(rule) = achievements::RULES.iter().find(|r| r.key == *key) {
+                    let title = tf("notify.achievement", &[("name", &rule.name())]);
+                    self.notifier_for(Route::Achievement)
+                        .notify(&title, &rule.about());
+                }
+            }
+            PhaseEvent::Entered(_) | PhaseEvent::Recorded(_) | PhaseEvent::Rated { .. } => {}
+        }
+    }
+
+    fn reload(&mut self, settings: &Args) {
+        self.notifier = from_args(settings);
+        self.routes = routes(settings);
+        self.quiet_outside = settings.work_hours.clone().filter(|h| !h.notifications);
+        self.phases = settings.phases.clone();
+    }
+
+    fn connect(&mut self, controls: &Sender<Control>) {
+        self.controls = Some(controls.clone());
+    }
+}
+
+#[derive(Debug)]
+pub struct Desktop {
+    sound: Option<String>,
+    /// Whether to play `sound`, or the default one without it.
+    audible: bool,
+    timeout: Duration,
+    style: Style,
+    #[cfg(target_os = "windows")]
+    app_id: String,
+}
+
+impl Desktop {
+    fn from_args(args: &Args, audible: bool) -> Self {
+        Self {
+            sound: args.notification_sound.clone(),
+            audible,
+            timeout: Duration::from_secs(args.notification_seconds),
+            style: Style::default(),
+            #[cfg(target_os = "windows")]
+            app_id: args
+                .windows_app_id
+                .clone()
+                .unwrap_or_else(|| WINDOWS_APP_ID.to_string()),
+        }
+    }
+
+    fn styled(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    fn build(&self, title: &str, body: &str) -> Notification {
+        let mut n = Notification::new();
+        n.summary(&format!("Rusty Pomo · {title}")).body(body);
+
+        if let Some(icon_path) = crate::icon::path().and_then(Path::to_str) {
+            n.icon(icon_path);
+            #[cfg(target_os = "windows")]
+            {
+                n.image_path(icon_path);
+            }
+        }
 
-    if let Some(icon_path) = asset_icon_path() {
-        n.icon(&icon_path);
         #[cfg(target_os = "windows")]
+        n.app_id(&self.app_id);
+        if self.audible {
+            n.sound_name(self.sound.as_deref().unwrap_or("default"));
+        }
+        n.timeout(self.timeout);
+        #[cfg(all(unix, not(target_os = "macos")))]
         {
-            n.image_path(&icon_path);
+            use notify_rust::{Hint, Timeout};
+            if let Some(urgency) = self.style.urgency {
+                n.urgency(match urgency {
+                    Urgency::Low => notify_rust::Urgency::Low,
+                    Urgency::Normal => notify_rust::Urgency::Normal,
+                    Urgency::Critical => notify_rust::Urgency::Critical,
+                });
+                if urgency == Urgency::Critical {
+                    n.timeout(Timeout::Never);
+                }
+            }
+            if let Some(resident) = self.style.resident {
+                n.hint(Hint::Resident(resident));
+            }
+            if let Some(transient) = self.style.transient {
+                n.hint(Hint::Transient(transient));
+            }
         }
+        n
     }
+}
 
-    if let Some(sound) = &app.args.notification_sound {
-        n.sound_name(sound);
-    } else {
-        n.sound_name("default");
+/// Set once a desktop notification fails to show.
+static DELIVERY_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Whether a desktop notification has failed to show, for `--bell auto`.
+pub fn delivery_failed() -> bool {
+    DELIVERY_FAILED.load(Ordering::Relaxed)
+}
+
+fn failed(error: &notify_rust::error::Error) {
+    DELIVERY_FAILED.store(true, Ordering::Relaxed);
+    tracing::warn!(%error, "notification failed");
+}
+
+impl Notifier for Desktop {
+    fn notify(&self, title: &str, body: &str) {
+        if let Err(e) = self.build(title, body).show() {
+            failed(&e);
+        }
     }
 
-    n.timeout(Duration::from_secs(app.args.notification_seconds));
-    let _ = n.show();
+    /// Only XDG notification servers take buttons.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn notify_with_button(&self, title: &str, body: &str, button: Button) {
+        let mut n = self.build(title, body);
+        n.action("button", &button.label);
+        match n.show() {
+            // Returns when the notification is clicked, dismissed or times out
+            Ok(handle) => {
+                std::thread::spawn(move || {
+                    handle.wait_for_action(|action| {
+                        if action == "button" {
+                            (button.on_click)();
+                        }
+                    });
+                });
+            }
+            Err(e) => failed(&e),
+        }
+    }
 }
 
-fn asset_icon_path() -> Option<String> {
-    let candidates = [
-        format!("{}/docs/assets/rusty_pomo.png", env!("CARGO_MANIFEST_DIR")),
-        "docs/assets/rusty_pomo.png".to_string(),
-    ];
-    for candidate in candidates.iter() {
-        if Path::new(candidate).exists() {
-            return Some(candidate.clone());
+#[derive(Debug)]
+pub struct Stdout;
+
+impl Notifier for Stdout {
+    fn notify(&self, title: &str, body: &str) {
+        println!("{title}: {body}");
+    }
+}
+
+/// A notification escape the terminal on your end shows, and the bell for
+/// sound in remote mode.
+#[derive(Debug)]
+pub struct Terminal {
+    escape: Option<osc::Terminal>,
+    bell: bool,
+}
+
+impl Terminal {
+    fn escape() -> Self {
+        Self {
+            escape: Some(osc::Terminal::detect()),
+            bell: false,
+        }
+    }
+}
+
+impl Notifier for Terminal {
+    fn notify(&self, title: &str, body: &str) {
+        let mut out = String::new();
+        if let Some(terminal) = &self.escape {
+            out.push_str(&terminal.sequence(&format!("Rusty Pomo · {title}"), body));
         }
+        if self.bell {
+            out.push('\x07');
+        }
+        let mut stdout = std::io::stdout();
+        let _ = stdout
+            .write_all(out.as_bytes())
+            .and_then(|()| stdout.flush());
+    }
+}
+
+/// A sound and nothing else: `notification_sound` when it names a file,
+/// otherwise a chime.
+#[derive(Debug)]
+pub struct Chime {
+    file: Option<PathBuf>,
+}
+
+impl Notifier for Chime {
+    fn notify(&self, _title: &str, _body: &str) {
+        audio::chime(self.file.as_deref());
+    }
+}
+
+/// Several channels at once. Only the first gets the button.
+#[derive(Debug)]
+pub struct Fanout(Vec<Box<dyn Notifier>>);
+
+impl Notifier for Fanout {
+    fn notify(&self, title: &str, body: &str) {
+        for notifier in &self.0 {
+            notifier.notify(title, body);
+        }
+    }
+
+    fn notify_with_button(&self, title: &str, body: &str, button: Button) {
+        let Some((first, rest)) = self.0.split_first() else {
+            return;
+        };
+        first.notify_with_button(title, body, button);
+        for notifier in rest {
+            notifier.notify(title, body);
+        }
+    }
+}
+
+/// For `--notifications false`.
+#[derive(Debug)]
+pub struct Silent;
+
+impl Notifier for Silent {
+    fn notify(&self, _title: &str, _body: &str) {}
+}
+
+/// Keeps every notification, so tests can check what would have been shown.
+#[cfg(test)]
+#[derive(Clone, Debug, Default)]
+pub struct Recorder(pub std::rc::Rc<std::cell::RefCell<Vec<(String, String)>>>);
+
+#[cfg(test)]
+impl Notifier for Recorder {
+    fn notify(&self, title: &str, body: &str) {
+        self.0
+            .borrow_mut()
+            .push((title.to_string(), body.to_string()));
     }
-    None
 }
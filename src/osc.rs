@@ -0,0 +1,113 @@
+//! Notifications as terminal escapes: the terminal on your end shows them,
+//! so they work over SSH. Terminals disagree on the sequence, so it's picked
+//! from what the environment says the terminal is.
+
+use std::env;
+use std::fmt;
+
+/// The notification escapes terminals understand.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Escape {
+    /// `OSC 9`: iTerm2, WezTerm, Ghostty, Windows Terminal, ConEmu. Body only.
+    Osc9,
+    /// `OSC 777;notify`: foot, urxvt, WezTerm, Ghostty and patched VTE.
+    Osc777,
+    /// `OSC 99`: kitty's own protocol.
+    Osc99,
+}
+
+impl fmt::Display for Escape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Escape::Osc9 => "OSC 9",
+            Escape::Osc777 => "OSC 777",
+            Escape::Osc99 => "OSC 99",
+        })
+    }
+}
+
+/// The escape this terminal takes, and whether it runs inside tmux.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Terminal {
+    pub escape: Escape,
+    pub tmux: bool,
+}
+
+impl Terminal {
+    pub fn detect() -> Self {
+        Self::detect_with(|name| env::var(name).ok())
+    }
+
+    /// OSC 9 unless the terminal is known to prefer another; it's the most
+    /// widely understood, and `TERM_PROGRAM` rarely makes it through SSH.
+    fn detect_with(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+        let escape = if term == "xterm-kitty" || var("KITTY_WINDOW_ID").is_some() {
+            Escape::Osc99
+        } else if term.starts_with("foot")
+            || term.starts_with("rxvt")
+            || (var("VTE_VERSION").is_some() && program.is_empty())
+        {
+            Escape::Osc777
+        } else {
+            Escape::Osc9
+        };
+        Self {
+            escape,
+            tmux: var("TMUX").is_some(),
+        }
+    }
+
+    /// The bytes to write for a notification.
+    pub fn sequence(&self, title: &str, body: &str) -> String {
+        // A control character would end the escape early
+        let clean = |text: &str| -> String { text.chars().filter(|c| !c.is_control()).collect() };
+        let (title, body) = (clean(title), clean(body));
+        let sequence = match self.escape {
+            Escape::Osc9 => format!("\x1b]9;{title}: {body}\x1b\\"),
+            Escape::Osc777 => format!("\x1b]777;notify;{};{body}\x1b\\", title.replace(';', ",")),
+            Escape::Osc99 => format!(
+                "\x1b]99;i=pomo:d=0:p=title;{title}\x1b\\\x1b]99;i=pomo:d=1:p=body;{body}\x1b\\"
+            ),
+        };
+        if self.tmux {
+            // tmux passes on what's wrapped in its DCS, with each ESC doubled
+            format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+        } else {
+            sequence
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_escape_the_terminal_takes() {
+        let detect = |vars: &[(&str, &str)]| {
+            Terminal::detect_with(|name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        assert_eq!(detect(&[("TERM", "xterm-kitty")]).escape, Escape::Osc99);
+        assert_eq!(detect(&[("TERM", "foot")]).escape, Escape::Osc777);
+        assert_eq!(
+            detect(&[("TERM", "xterm-256color"), ("TERM_PROGRAM", "iTerm.app")]).escape,
+            Escape::Osc9
+        );
+        assert_eq!(
+            detect(&[("TERM", "foot"), ("TMUX", "/tmp/tmux-1000/default,1,0")])
+                .sequence("Focus", "Go"),
+            "\x1bPtmux;\x1b\x1b]777;notify;Focus;Go\x1b\x1b\\\x1b\\"
+        );
+        let osc9 = detect(&[]);
+        assert_eq!(
+            osc9.sequence("Rusty Pomo · Focus", "Time\x07 to focus."),
+            "\x1b]9;Rusty Pomo · Focus: Time to focus.\x1b\\"
+        );
+    }
+}
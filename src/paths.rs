@@ -0,0 +1,346 @@
+//! Where rusty_pomo keeps the files it writes. Each kind of directory follows
+//! the XDG base directories on Unix and the AppData folders on Windows, and has
+//! its own environment variable that replaces the whole `rusty_pomo` directory,
+//! e.g. `RUSTY_POMO_CONFIG_DIR=/tmp/pomo` for a throwaway config.
+//!
+//! A context (`--context work`) gets a folder of its own in each of them, so
+//! it has its own config, history and achievements; `--data-dir` puts all of
+//! one person's files in a single directory instead.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Looks up an environment variable; tests pass their own.
+type Env<'a> = &'a dyn Fn(&str) -> Option<OsString>;
+
+struct Base {
+    /// Replaces the app's directory outright.
+    overridden_by: &'static str,
+    windows: &'static str,
+    xdg: &'static str,
+    /// Relative to `$HOME` when the XDG variable is unset.
+    xdg_default: &'static str,
+    /// Relative to `--data-dir`.
+    in_data_dir: &'static str,
+}
+
+const CONFIG: Base = Base {
+    overridden_by: "RUSTY_POMO_CONFIG_DIR",
+    windows: "APPDATA",
+    xdg: "XDG_CONFIG_HOME",
+    xdg_default: ".config",
+    in_data_dir: "",
+};
+
+const DATA: Base = Base {
+    overridden_by: "RUSTY_POMO_DATA_DIR",
+    windows: "LOCALAPPDATA",
+    xdg: "XDG_DATA_HOME",
+    xdg_default: ".local/share",
+    in_data_dir: "",
+};
+
+const STATE: Base = Base {
+    overridden_by: "RUSTY_POMO_STATE_DIR",
+    windows: "LOCALAPPDATA",
+    xdg: "XDG_STATE_HOME",
+    xdg_default: ".local/state",
+    in_data_dir: "",
+};
+
+const CACHE: Base = Base {
+    overridden_by: "RUSTY_POMO_CACHE_DIR",
+    windows: "LOCALAPPDATA",
+    xdg: "XDG_CACHE_HOME",
+    xdg_default: ".cache",
+    in_data_dir: "cache",
+};
+
+/// Which set of files this run uses, when not the usual one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// `--data-dir`: everything in one directory.
+    Dir(PathBuf),
+    /// `--context`: a `contexts/NAME` folder in each usual directory.
+    Context(String),
+}
+
+static SCOPE: OnceLock<Scope> = OnceLock::new();
+
+/// Set once at startup, before anything looks up a path.
+pub fn use_scope(scope: Scope) {
+    let _ = SCOPE.set(scope);
+}
+
+/// The scope from `--data-dir`, else the context from `--context`, then
+/// `RUSTY_POMO_CONTEXT`, then the one picked with `context use`. The `default`
+/// context is the usual directories.
+pub fn scope(data_dir: Option<PathBuf>, context: Option<String>) -> Result<Option<Scope>, String> {
+    scope_with(data_dir, context, &system)
+}
+
+fn scope_with(
+    data_dir: Option<PathBuf>,
+    context: Option<String>,
+    var: Env,
+) -> Result<Option<Scope>, String> {
+    if let Some(dir) = data_dir {
+        return Ok(Some(Scope::Dir(dir)));
+    }
+    let name = match context {
+        Some(name) => name,
+        None => match var("RUSTY_POMO_CONTEXT").filter(|value| !value.is_empty()) {
+            Some(name) => parse_context(&name.to_string_lossy())
+                .map_err(|e| format!("RUSTY_POMO_CONTEXT: {e}"))?,
+            None => match context_file_with(var).and_then(|path| fs::read_to_string(path).ok()) {
+                Some(saved) => parse_context(saved.trim())
+                    .map_err(|e| format!("the context picked with `context use`: {e}"))?,
+                None => return Ok(None),
+            },
+        },
+    };
+    Ok((name != DEFAULT_CONTEXT).then_some(Scope::Context(name)))
+}
+
+/// The usual directories, as a context name.
+pub const DEFAULT_CONTEXT: &str = "default";
+
+/// A context name, which becomes a folder name: letters, digits, `-` and `_`.
+pub fn parse_context(name: &str) -> Result<String, String> {
+    let fits = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if name.is_empty() || name.len() > 64 || !name.chars().all(fits) {
+        return Err(format!(
+            "`{name}` is not a context name; use up to 64 letters, digits, `-` and `_`"
+        ));
+    }
+    Ok(name.to_string())
+}
+
+/// The context in use, or `default`.
+pub fn context() -> &'static str {
+    match SCOPE.get() {
+        Some(Scope::Context(name)) => name,
+        _ => DEFAULT_CONTEXT,
+    }
+}
+
+/// Holds the context `context use` picked, for runs without `--context`.
+pub fn context_file() -> Option<PathBuf> {
+    context_file_with(&system)
+}
+
+fn context_file_with(var: Env) -> Option<PathBuf> {
+    unscoped(&CONFIG, var).map(|dir| dir.join("context"))
+}
+
+/// The contexts with a config or data folder, and the one in use, `default`
+/// first.
+pub fn contexts() -> Vec<String> {
+    let mut names: Vec<String> = [&CONFIG, &DATA]
+        .into_iter()
+        .filter_map(|base| unscoped(base, &system))
+        .filter_map(|dir| fs::read_dir(dir.join("contexts")).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .chain([context().to_string()])
+        .filter(|name| parse_context(name).is_ok() && name != DEFAULT_CONTEXT)
+        .collect();
+    names.sort();
+    names.dedup();
+    names.insert(0, DEFAULT_CONTEXT.to_string());
+    names
+}
+
+fn system(name: &str) -> Option<OsString> {
+    env::var_os(name)
+}
+
+/// The shared base directory, e.g. `~/.config`, without the app's own folder.
+fn home(base: &Base, var: Env) -> Option<PathBuf> {
+    // Empty values count as unset, as the XDG spec asks
+    let var = |name: &str| var(name).filter(|value| !value.is_empty());
+    if cfg!(target_os = "windows") {
+        var(base.windows).map(PathBuf::from)
+    } else {
+        var(base.xdg)
+            .map(PathBuf::from)
+            .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(base.xdg_default)))
+    }
+}
+
+fn dir(base: &Base, var: Env) -> Option<PathBuf> {
+    scoped(base, var, SCOPE.get())
+}
+
+fn scoped(base: &Base, var: Env, scope: Option<&Scope>) -> Option<PathBuf> {
+    match scope {
+        Some(Scope::Dir(dir)) if base.in_data_dir.is_empty() => Some(dir.clone()),
+        Some(Scope::Dir(dir)) => Some(dir.join(base.in_data_dir)),
+        Some(Scope::Context(name)) => {
+            unscoped(base, var).map(|dir| dir.join("contexts").join(name))
+        }
+        None => unscoped(base, var),
+    }
+}
+
+fn unscoped(base: &Base, var: Env) -> Option<PathBuf> {
+    match var(base.overridden_by).filter(|value| !value.is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => home(base, var).map(|home| home.join("rusty_pomo")),
+    }
+}
+
+/// The config file, plugins and anything else the user sets up by hand.
+pub fn config_dir() -> Option<PathBuf> {
+    dir(&CONFIG, &system)
+}
+
+pub fn config_file() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+pub fn history_dir() -> Option<PathBuf> {
+    dir(&DATA, &system).map(|dir| dir.join("history"))
+}
+
+/// When each achievement was unlocked; see `achievements`.
+pub fn achievements_file() -> Option<PathBuf> {
+    dir(&DATA, &system).map(|dir| dir.join("achievements.json"))
+}
+
+/// Executables that get each event; see `plugins`.
+pub fn plugins_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("plugins"))
+}
+
+/// For files that can be made again, like the notification icon.
+pub fn cache_dir() -> Option<PathBuf> {
+    dir(&CACHE, &system)
+}
+
+/// For `-v` without `--log-file`.
+pub fn log_file() -> Option<PathBuf> {
+    dir(&STATE, &system).map(|dir| dir.join("rusty_pomo.log"))
+}
+
+/// Sessions waiting for the `[remote]` server to accept them.
+pub fn upload_queue() -> Option<PathBuf> {
+    dir(&STATE, &system).map(|dir| dir.join("upload-queue.jsonl"))
+}
+
+/// Where the PID of the latest timer goes. It sits directly in the state
+/// directory, not in a `rusty_pomo` folder, because scripts already look there;
+/// each context and data directory has its own.
+pub fn pid_file() -> Option<PathBuf> {
+    pid_file_with(&system, SCOPE.get())
+}
+
+/// Where the timer with the PID file says what it's doing, for `rusty_pomo
+/// status`: beside it, as `rusty_pomo.status`.
+pub fn status_file() -> Option<PathBuf> {
+    pid_file().map(|path| path.with_extension("status"))
+}
+
+fn pid_file_with(var: Env, scope: Option<&Scope>) -> Option<PathBuf> {
+    if scope.is_some() {
+        return scoped(&STATE, var, scope).map(|dir| dir.join("rusty_pomo.pid"));
+    }
+    var(STATE.overridden_by)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home(&STATE, var))
+        .map(|dir| dir.join("rusty_pomo.pid"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(set: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<OsString> {
+        move |name| {
+            set.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| OsString::from(value))
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn follows_xdg_and_falls_back_to_home() {
+        let env = vars(&[("HOME", "/home/a"), ("XDG_CONFIG_HOME", "/etc/a")]);
+        assert_eq!(dir(&CONFIG, &env), Some(PathBuf::from("/etc/a/rusty_pomo")));
+        assert_eq!(
+            dir(&DATA, &env),
+            Some(PathBuf::from("/home/a/.local/share/rusty_pomo"))
+        );
+        let empty = vars(&[("HOME", "/home/a"), ("XDG_CACHE_HOME", "")]);
+        assert_eq!(
+            dir(&CACHE, &empty),
+            Some(PathBuf::from("/home/a/.cache/rusty_pomo"))
+        );
+        assert_eq!(dir(&CONFIG, &vars(&[])), None);
+    }
+
+    #[test]
+    fn overrides_replace_the_app_directory() {
+        let env = vars(&[("HOME", "/home/a"), ("RUSTY_POMO_CONFIG_DIR", "/tmp/pomo")]);
+        assert_eq!(dir(&CONFIG, &env), Some(PathBuf::from("/tmp/pomo")));
+        let env = vars(&[("RUSTY_POMO_STATE_DIR", "/tmp/pomo")]);
+        assert_eq!(
+            pid_file_with(&env, None),
+            Some(PathBuf::from("/tmp/pomo/rusty_pomo.pid"))
+        );
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn pid_file_follows_xdg_state_home() {
+        let env = vars(&[("HOME", "/home/a"), ("XDG_STATE_HOME", "/run/state")]);
+        assert_eq!(
+            pid_file_with(&env, None),
+            Some(PathBuf::from("/run/state/rusty_pomo.pid"))
+        );
+        assert_eq!(
+            pid_file_with(&vars(&[("HOME", "/home/a")]), None),
+            Some(PathBuf::from("/home/a/.local/state/rusty_pomo.pid"))
+        );
+        assert_eq!(pid_file_with(&vars(&[]), None), None);
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    #[test]
+    fn contexts_keep_their_files_apart() {
+        let env = vars(&[("HOME", "/home/a"), ("RUSTY_POMO_CONTEXT", "work")]);
+        let work = scope_with(None, None, &env).unwrap();
+        assert_eq!(work, Some(Scope::Context("work".to_string())));
+        assert_eq!(
+            scoped(&CONFIG, &env, work.as_ref()),
+            Some(PathBuf::from("/home/a/.config/rusty_pomo/contexts/work"))
+        );
+        assert_eq!(
+            pid_file_with(&env, work.as_ref()),
+            Some(PathBuf::from(
+                "/home/a/.local/state/rusty_pomo/contexts/work/rusty_pomo.pid"
+            ))
+        );
+        // The flag wins, and the default context is the usual directories
+        assert_eq!(
+            scope_with(None, Some("default".to_string()), &env),
+            Ok(None)
+        );
+        let dir = scope_with(Some(PathBuf::from("/mnt/pomo")), None, &env).unwrap();
+        assert_eq!(
+            scoped(&DATA, &env, dir.as_ref()),
+            Some(PathBuf::from("/mnt/pomo"))
+        );
+        assert_eq!(
+            scoped(&CACHE, &env, dir.as_ref()),
+            Some(PathBuf::from("/mnt/pomo/cache"))
+        );
+        assert!(scope_with(None, None, &vars(&[("RUSTY_POMO_CONTEXT", "../x")])).is_err());
+        assert!(parse_context("home office").is_err());
+    }
+}
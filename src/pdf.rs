@@ -0,0 +1,170 @@
+//! Just enough PDF for `report --format pdf`: A4 pages of text and filled
+//! rectangles. The text is in Helvetica, one of the fonts every PDF reader
+//! has, so nothing is embedded and no library is needed.
+
+use std::io::Write;
+
+/// A4 in points.
+pub const WIDTH: f32 = 595.0;
+pub const HEIGHT: f32 = 842.0;
+
+pub struct Document {
+    /// A content stream for each page so far.
+    pages: Vec<Vec<u8>>,
+}
+
+impl Default for Document {
+    /// One blank page.
+    fn default() -> Self {
+        Self {
+            pages: vec![Vec::new()],
+        }
+    }
+}
+
+impl Document {
+    pub fn new_page(&mut self) {
+        self.pages.push(Vec::new());
+    }
+
+    fn page(&mut self) -> &mut Vec<u8> {
+        self.pages.last_mut().expect("a document has a page")
+    }
+
+    /// `text` with its baseline starting at `x`, `y` from the bottom left.
+    pub fn text(&mut self, x: f32, y: f32, size: f32, bold: bool, text: &str) {
+        let font = if bold { "F2" } else { "F1" };
+        let page = self.page();
+        let _ = write!(page, "BT /{font} {size} Tf {x:.1} {y:.1} Td (");
+        page.extend(encode(text));
+        page.extend_from_slice(b") Tj ET\n");
+    }
+
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, (r, g, b): (u8, u8, u8)) {
+        let channel = |c: u8| f32::from(c) / 255.0;
+        let _ = writeln!(
+            self.page(),
+            "{:.3} {:.3} {:.3} rg {x:.1} {y:.1} {width:.1} {height:.1} re f 0 g",
+            channel(r),
+            channel(g),
+            channel(b)
+        );
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        // Catalog, page tree and the two fonts, then a page and its content
+        // for each page
+        let count = self.pages.len();
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        let kids: Vec<String> = (0..count).map(|i| format!("{} 0 R", 5 + 2 * i)).collect();
+        objects.push(
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {count} >>",
+                kids.join(" ")
+            )
+            .into_bytes(),
+        );
+        for font in ["Helvetica", "Helvetica-Bold"] {
+            objects.push(
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{font} /Encoding /WinAnsiEncoding >>"
+                )
+                .into_bytes(),
+            );
+        }
+        for (i, content) in self.pages.into_iter().enumerate() {
+            objects.push(
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {WIDTH} {HEIGHT}] \
+                     /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                    6 + 2 * i
+                )
+                .into_bytes(),
+            );
+            let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+            stream.extend(content);
+            stream.extend_from_slice(b"\nendstream");
+            objects.push(stream);
+        }
+
+        let mut out = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            let _ = writeln!(out, "{} 0 obj", i + 1);
+            out.extend(object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = out.len();
+        let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(out, "{offset:010} 00000 n ");
+        }
+        let _ = write!(
+            out,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        );
+        out
+    }
+}
+
+/// `text` as a PDF string's bytes in WinAnsi, the standard fonts' encoding.
+/// Characters it lacks become `?`.
+fn encode(text: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => out.extend_from_slice(&[b'\\', c as u8]),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => out.push(c as u32 as u8),
+            '–' => out.push(0x96),
+            '—' => out.push(0x97),
+            '…' => out.push(0x85),
+            '•' => out.push(0x95),
+            '‘' => out.push(0x91),
+            '’' => out.push(0x92),
+            '“' => out.push(0x93),
+            '”' => out.push(0x94),
+            '€' => out.push(0x80),
+            _ => out.push(b'?'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_pages_with_a_valid_cross_reference() {
+        let mut doc = Document::default();
+        doc.text(50.0, 800.0, 12.0, true, "Focus (week) · 1–2 ✓");
+        doc.rect(50.0, 700.0, 100.0, 10.0, (255, 0, 0));
+        doc.new_page();
+        doc.text(50.0, 800.0, 12.0, false, "more");
+        let pdf = doc.finish();
+
+        assert!(pdf.starts_with(b"%PDF-1.4\n"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let find = |needle: &[u8]| pdf.windows(needle.len()).position(|w| w == needle);
+        assert!(find(b"(Focus \\(week\\) \xb7 1\x962 ?) Tj").is_some());
+        assert!(find(b"1.000 0.000 0.000 rg 50.0 700.0 100.0 10.0 re f").is_some());
+        assert!(find(b"/Kids [5 0 R 7 0 R] /Count 2").is_some());
+
+        // Every offset in the table points at its object; entries are 20 bytes
+        let at = find(b"startxref\n").unwrap() + 10;
+        let end = at + pdf[at..].iter().position(|b| *b == b'\n').unwrap();
+        let start: usize = std::str::from_utf8(&pdf[at..end]).unwrap().parse().unwrap();
+        assert!(pdf[start..].starts_with(b"xref\n0 9\n0000000000 65535 f \n"));
+        for i in 1..9 {
+            let entry = start + 9 + 20 * i;
+            let offset: usize = std::str::from_utf8(&pdf[entry..entry + 10])
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!(pdf[offset..].starts_with(format!("{i} 0 obj\n").as_bytes()));
+        }
+    }
+}
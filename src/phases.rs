@@ -0,0 +1,151 @@
+//! `[phases.NAME]`: phases of your own, such as a review after each focus
+//! session, each with its length, color and notification text, and a place in
+//! the cycle after one of the built-in phases or another of these.
+
+use std::collections::BTreeSet;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use ratatui::style::Color;
+
+use crate::state::PhaseKind;
+
+/// Built-in phase keys, which custom phases can't take.
+const BUILT_IN: [&str; 6] = [
+    "focus",
+    "short_break",
+    "long_break",
+    "lunch",
+    "warm_up",
+    "cycle_review",
+];
+
+/// A phase from `[phases.NAME]`. It runs after every `after` phase, then the
+/// cycle goes on where that phase would have led.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomPhase {
+    pub kind: PhaseKind,
+    pub duration: Duration,
+    /// The header, gauge and timeline color; the break color if not given.
+    pub color: Option<Color>,
+    /// The notification text when the phase starts.
+    pub message: Option<String>,
+    pub after: PhaseKind,
+}
+
+/// The phase for `name`, which is also its key in the history.
+pub fn kind(name: &str) -> PhaseKind {
+    PhaseKind::from_key(name).unwrap_or_else(|| PhaseKind::Custom(intern(name)))
+}
+
+/// Keeps one copy of each phase name for good, so `PhaseKind` stays `Copy`.
+/// There are only as many as the config and history have names.
+fn intern(name: &str) -> &'static str {
+    static NAMES: OnceLock<Mutex<BTreeSet<&'static str>>> = OnceLock::new();
+    let mut names = NAMES.get_or_init(Default::default).lock().unwrap();
+    if let Some(interned) = names.get(name) {
+        return interned;
+    }
+    let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+    names.insert(interned);
+    interned
+}
+
+/// Why `name` can't name a custom phase, if it can't.
+pub fn check_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() || name.chars().any(char::is_control) {
+        return Err("is not a usable phase name".to_string());
+    }
+    if BUILT_IN.contains(&name) {
+        return Err(format!("is taken by the built-in {name} phase"));
+    }
+    Ok(())
+}
+
+/// The phase that `after` leads to within `names`: a built-in one other than
+/// lunch, or one of the custom phases.
+pub fn check_after(after: &str, names: &[&str]) -> Result<PhaseKind, String> {
+    match PhaseKind::from_key(after) {
+        Some(PhaseKind::Lunch) => Err("can't be lunch, which suspends the cycle".to_string()),
+        Some(kind) => Ok(kind),
+        None if names.contains(&after) => Ok(kind(after)),
+        None => Err(format!(
+            "must be warm_up, focus, short_break, long_break, cycle_review or a phase in [phases], not \"{after}\""
+        )),
+    }
+}
+
+/// The custom phase that runs after `kind`, if any.
+pub fn following(phases: &[CustomPhase], kind: PhaseKind) -> Option<&CustomPhase> {
+    phases.iter().find(|phase| phase.after == kind)
+}
+
+/// A phase whose `after` leads back to itself without passing a built-in
+/// phase, so the cycle would never go on; the first one found.
+pub fn find_loop(phases: &[CustomPhase]) -> Option<&CustomPhase> {
+    phases.iter().find(|start| {
+        let mut at = start.after;
+        for _ in 0..phases.len() {
+            match phases.iter().find(|phase| phase.kind == at) {
+                Some(phase) => at = phase.after,
+                None => return false,
+            }
+        }
+        true
+    })
+}
+
+/// A color written as `"#rrggbb"`.
+pub fn parse_color(text: &str) -> Result<Color, String> {
+    let invalid = || format!("must be a color like \"#ff79c6\", not \"{text}\"");
+    let hex = text.strip_prefix('#').ok_or_else(invalid)?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).map_err(|_| invalid());
+    Ok(Color::Rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn phase(name: &str, after: &str) -> CustomPhase {
+        CustomPhase {
+            kind: kind(name),
+            duration: Duration::from_secs(300),
+            color: None,
+            message: None,
+            after: kind(after),
+        }
+    }
+
+    #[test]
+    fn names_and_orders_custom_phases() {
+        assert_eq!(kind("focus"), PhaseKind::Focus);
+        assert_eq!(kind("Review"), PhaseKind::Custom("Review"));
+        assert_eq!(kind("Review").key(), "Review");
+        assert!(check_name("Review").is_ok());
+        assert_eq!(
+            check_name("lunch").unwrap_err(),
+            "is taken by the built-in lunch phase"
+        );
+        assert!(check_name(" ").is_err());
+        assert_eq!(check_after("Review", &["Review"]), Ok(kind("Review")));
+        assert!(check_after("lunch", &[]).is_err());
+        assert!(check_after("Email", &["Review"]).is_err());
+
+        let phases = [phase("Review", "focus"), phase("Email", "Review")];
+        assert_eq!(following(&phases, PhaseKind::Focus), Some(&phases[0]));
+        assert_eq!(following(&phases, kind("Review")), Some(&phases[1]));
+        assert_eq!(following(&phases, PhaseKind::ShortBreak), None);
+        assert_eq!(find_loop(&phases), None);
+        let looped = [phase("A", "B"), phase("B", "A"), phase("C", "focus")];
+        assert_eq!(find_loop(&looped), Some(&looped[0]));
+
+        assert_eq!(parse_color("#ff79C6"), Ok(Color::Rgb(255, 121, 198)));
+        assert!(parse_color("ff79c6").is_err());
+        assert!(parse_color("#ff79c").is_err());
+        assert!(parse_color("#ééé").is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! Plugins: executables in the config directory's `plugins/` folder. Each event
+//! reaches every plugin as a line of JSON on its standard input, and the lines of
+//! JSON it prints are commands for the timer. See `docs/plugins.md`.
+
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::args::Args;
+use crate::control::Control;
+use crate::doctor::Probe;
+use crate::events::{PhaseEvent, Subscriber};
+use crate::json::{self, Value};
+use crate::paths;
+
+/// Version of the event and command format, sent with every event.
+pub const PROTOCOL: u64 = 1;
+/// A plugin still running after this is killed.
+const TIMEOUT: Duration = Duration::from_secs(5);
+const POLL: Duration = Duration::from_millis(10);
+/// Output past this is ignored.
+const OUTPUT_LIMIT: u64 = 64 * 1024;
+/// The only environment variables a plugin sees, besides `RUSTY_POMO_PLUGIN`.
+const PASSED_ENV: [&str; 5] = ["PATH", "HOME", "LANG", "TMPDIR", "SYSTEMROOT"];
+
+/// Runs the plugins on a thread of their own, one event at a time, so a slow
+/// one never holds up the timer.
+#[derive(Debug)]
+pub struct Plugins {
+    dir: PathBuf,
+    programs: Vec<PathBuf>,
+    /// Events for the plugin thread, once connected to the timer.
+    events: Option<Sender<String>>,
+}
+
+impl Plugins {
+    /// The plugins found, unless `--plugins=false` or there are none.
+    pub fn discover(args: &Args) -> Option<Self> {
+        if !args.plugins {
+            return None;
+        }
+        let dir = paths::plugins_dir()?;
+        let programs = programs_in(&dir);
+        if programs.is_empty() {
+            return None;
+        }
+        tracing::info!(count = programs.len(), "plugins found");
+        Some(Self {
+            dir,
+            programs,
+            events: None,
+        })
+    }
+}
+
+impl Subscriber for Plugins {
+    fn on_event(&mut self, event: &PhaseEvent) {
+        if let Some(events) = &self.events {
+            let _ = events.send(event_json(event).to_string());
+        }
+    }
+
+    fn connect(&mut self, controls: &Sender<Control>) {
+        let (events, received) = mpsc::channel::<String>();
+        let (dir, programs, controls) = (self.dir.clone(), self.programs.clone(), controls.clone());
+        thread::spawn(move || {
+            for event in received {
+                for program in &programs {
+                    let name = program.file_name().unwrap_or_default().to_string_lossy();
+                    let output = match run(program, &dir, &event, TIMEOUT) {
+                        Ok(output) => output,
+                        Err(e) => {
+                            tracing::warn!(plugin = %name, error = %e, "plugin failed");
+                            continue;
+                        }
+                    };
+                    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+                        match command(line) {
+                            Some(control) => {
+                                let _ = controls.send(control);
+                            }
+                            None => tracing::warn!(plugin = %name, line, "unknown plugin command"),
+                        }
+                    }
+                }
+            }
+        });
+        self.events = Some(events);
+    }
+}
+
+/// Executable files in `dir`, by name; hidden files are skipped.
+fn programs_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut programs: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter(|entry| {
+            entry
+                .metadata()
+                .is_ok_and(|m| m.is_file() && executable(&m))
+        })
+        .map(|entry| entry.path())
+        .collect();
+    programs.sort();
+    programs
+}
+
+#[cfg(unix)]
+fn executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn executable(_metadata: &fs::Metadata) -> bool {
+    true
+}
+
+/// Runs `program` in `dir` with `event` on its standard input and a bare
+/// environment, and returns what it printed. It's killed after `timeout`.
+fn run(program: &Path, dir: &Path, event: &str, timeout: Duration) -> Result<String, String> {
+    let mut command = Command::new(program);
+    command
+        .current_dir(dir)
+        .env_clear()
+        .envs(
+            PASSED_ENV
+                .iter()
+                .filter_map(|k| env::var_os(k).map(|v| (k, v))),
+        )
+        .env("RUSTY_POMO_PLUGIN", PROTOCOL.to_string())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    // Its own process group, so a Ctrl-C in the terminal doesn't reach it
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command.spawn().map_err(|e| format!("cannot start: {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A plugin that doesn't read its input is fine
+        let _ = writeln!(stdin, "{event}");
+    }
+    let (sender, output) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        thread::spawn(move || {
+            let mut text = String::new();
+            let _ = stdout.take(OUTPUT_LIMIT).read_to_string(&mut text);
+            let _ = sender.send(text);
+        });
+    }
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => break,
+            Ok(Some(status)) => return Err(format!("exited with {status}")),
+            Ok(None) if Instant::now() < deadline => thread::sleep(POLL),
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("killed after {} seconds", timeout.as_secs_f64()));
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    let left = deadline.saturating_duration_since(Instant::now());
+    Ok(output.recv_timeout(left).unwrap_or_default())
+}
+
+/// The JSON line a plugin gets for `event`.
+fn event_json(event: &PhaseEvent) -> Value {
+    let field = |key: &str, value: Value| (key.to_string(), value);
+    let mut fields = vec![field("version", PROTOCOL.into())];
+    match event {
+        PhaseEvent::Started { kind, quote } => {
+            fields.push(field("event", "started".into()));
+            fields.push(field("phase", kind.key().into()));
+            fields.push(field("quote", quote.as_deref().into()));
+        }
+        PhaseEvent::Entered(kind) => {
+            fields.push(field("event", "entered".into()));
+            fields.push(field("phase", kind.key().into()));
+        }
+        PhaseEvent::Done {
+            cycles,
+            focus_sessions,
+        } => {
+            fields.push(field("event", "done".into()));
+            fields.push(field("cycles", (*cycles).into()));
+            fields.push(field("focus_sessions", (*focus_sessions).into()));
+        }
+        PhaseEvent::Recorded(record) => {
+            fields.push(field("event", "recorded".into()));
+            fields.push(field("session", record.to_json()));
+        }
+        PhaseEvent::Rated { start, rating } => {
+            fields.push(field("event", "rated".into()));
+            fields.push(field("start", (*start).into()));
+            fields.push(field("rating", u64::from(*rating).into()));
+        }
+        PhaseEvent::MeetingSoon {
+            summary,
+            minutes,
+            clash,
+        } => {
+            fields.push(field("event", "meeting_soon".into()));
+            fields.push(field("summary", summary.as_str().into()));
+            fields.push(field("minutes", (*minutes).into()));
+            fields.push(field("clash", (*clash).into()));
+        }
+        PhaseEvent::Unlocked(key) => {
+            fields.push(field("event", "unlocked".into()));
+            fields.push(field("achievement", (*key).into()));
+        }
+    }
+    Value::Object(fields)
+}
+
+/// A line a plugin printed, such as `{"command": "adjust", "seconds": -60}`.
+fn command(line: &str) -> Option<Control> {
+    let value = json::parse(line).ok()?;
+    match value.get("command")?.as_str()? {
+        "message" => Some(Control::ShowMessage(
+            value.get("text")?.as_str()?.to_string(),
+        )),
+        "adjust" => match value.get("seconds")? {
+            Value::Number(secs) if secs.fract() == 0.0 => Some(Control::AdjustTime(*secs as i64)),
+            _ => None,
+        },
+        "pause" => Some(Control::Pause),
+        "resume" => Some(Control::Resume),
+        "skip" => Some(Control::Skip),
+        _ => None,
+    }
+}
+
+/// The plugins `doctor` would run.
+pub fn probe(args: &Args) -> Probe {
+    let Some(dir) = paths::plugins_dir() else {
+        return Probe::Off("cannot locate the config directory".to_string());
+    };
+    let names: Vec<String> = programs_in(&dir)
+        .iter()
+        .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .collect();
+    match (names.is_empty(), args.plugins) {
+        (true, _) => Probe::Off(format!("none in {}", dir.display())),
+        (false, false) => Probe::Off(format!("{} off with --plugins=false", names.join(", "))),
+        (false, true) => Probe::Ok(names.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PhaseKind;
+
+    #[test]
+    fn sends_events_and_reads_commands() {
+        let event = event_json(&PhaseEvent::Started {
+            kind: PhaseKind::Focus,
+            quote: None,
+        });
+        assert_eq!(
+            event.to_string(),
+            r#"{"version":1,"event":"started","phase":"focus","quote":null}"#
+        );
+        assert_eq!(
+            command(r#"{"command": "message", "text": "Stretch"}"#),
+            Some(Control::ShowMessage("Stretch".to_string()))
+        );
+        assert_eq!(
+            command(r#"{"command":"adjust","seconds":-90}"#),
+            Some(Control::AdjustTime(-90))
+        );
+        assert_eq!(command(r#"{"command":"adjust","seconds":1.5}"#), None);
+        assert_eq!(command(r#"{"command":"reboot"}"#), None);
+        assert_eq!(command("not json"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn runs_plugins_with_a_timeout() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("rusty_pomo_plugins_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let script = |name: &str, body: &str| {
+            let path = dir.join(name);
+            fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        let echo = script(
+            "echo",
+            "read line; echo \"$line $RUSTY_POMO_PLUGIN $(pwd)\"",
+        );
+        let slow = script("slow", "sleep 5");
+        script(".hidden", "true");
+        fs::write(dir.join("notes.txt"), "not a plugin").unwrap();
+        assert_eq!(programs_in(&dir), [echo.clone(), slow.clone()]);
+
+        let output = run(&echo, &dir, "{}", TIMEOUT).unwrap();
+        assert_eq!(output, format!("{{}} 1 {}\n", dir.display()));
+        let err = run(&slow, &dir, "{}", Duration::from_millis(100)).unwrap_err();
+        assert_eq!(err, "killed after 0.1 seconds");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,94 @@
+//! Lines shown during breaks, a different one each break: the user's own from
+//! `--quotes-file` or `[breaks] quotes` in the config, otherwise a few built in.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::args::Args;
+use crate::i18n::t;
+
+/// Locale keys of the built-in lines, in the order they come up.
+const BUILT_IN: [&str; 6] = [
+    "quotes.rest",
+    "quotes.small",
+    "quotes.pause",
+    "quotes.mind",
+    "quotes.settle",
+    "quotes.enough",
+];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Quotes {
+    lines: Vec<String>,
+}
+
+impl Quotes {
+    /// The lines `--quotes-file` or the config asks for. An empty file means no
+    /// quotes at all; one that can't be read, the built-in ones.
+    pub fn from_args(args: &Args) -> Self {
+        if let Some(path) = &args.quotes_file {
+            return Self::read(path).unwrap_or_else(|e| {
+                tracing::warn!(path = %path.display(), error = %e, "cannot read quotes");
+                Self::built_in()
+            });
+        }
+        if !args.quotes.is_empty() {
+            return Self::new(args.quotes.iter().map(String::as_str));
+        }
+        Self::built_in()
+    }
+
+    pub fn built_in() -> Self {
+        Self::new(BUILT_IN.map(t))
+    }
+
+    /// One quote per line; blank lines and lines starting with `#` are skipped.
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(Self::new(
+            contents
+                .lines()
+                .filter(|line| !line.trim_start().starts_with('#')),
+        ))
+    }
+
+    fn new<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+        let lines = lines
+            .into_iter()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        Self { lines }
+    }
+
+    /// The quote for the break after focus session `session`, wrapping around.
+    pub fn for_break(&self, session: u64) -> Option<&str> {
+        let len = self.lines.len() as u64;
+        (len > 0).then(|| self.lines[(session.saturating_sub(1) % len) as usize].as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_lines_and_rotates_per_break() {
+        let path =
+            std::env::temp_dir().join(format!("rusty_pomo_quotes_{}.txt", std::process::id()));
+        fs::write(&path, "# mine\nFirst\n\n  Second  \n").unwrap();
+        let quotes = Quotes::read(&path).unwrap();
+        assert_eq!(quotes.for_break(1), Some("First"));
+        assert_eq!(quotes.for_break(2), Some("Second"));
+        assert_eq!(quotes.for_break(3), Some("First"));
+        fs::write(&path, "\n").unwrap();
+        assert_eq!(Quotes::read(&path).unwrap().for_break(1), None);
+        let _ = fs::remove_file(&path);
+        assert_eq!(
+            Quotes::built_in().for_break(1),
+            Some("Rest is part of the work.")
+        );
+    }
+}
@@ -0,0 +1,452 @@
+//! `rusty_pomo report`: a week or month of the history as a review to keep or
+//! share, in Markdown with text bars, as one HTML page with an SVG chart, or
+//! as a PDF to print or archive.
+
+use std::fmt::Write;
+
+use clap::ValueEnum;
+
+use crate::history::SessionRecord;
+use crate::pdf::{self, Document};
+use crate::state::PhaseKind;
+use crate::stats::{self, Totals};
+use crate::wallclock;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Markdown, with the chart as text
+    Md,
+    /// A standalone page, with the chart as SVG
+    Html,
+    /// A4 pages to print or archive
+    Pdf,
+}
+
+/// Tasks listed under "Top tasks".
+const TOP_TASKS: usize = 10;
+
+/// Columns in the text chart's longest bar.
+const BAR: u64 = 24;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Report {
+    pub first: i64,
+    pub last: i64,
+    pub totals: Totals,
+    /// Focus sessions stopped before the end.
+    pub stopped: u64,
+    pub days: Vec<(i64, Totals)>,
+    /// "project · task", most focus first.
+    pub tasks: Vec<(String, Totals)>,
+    pub projects: Vec<(String, Totals)>,
+}
+
+impl Report {
+    /// The `days` local days ending today.
+    pub fn build(records: &[SessionRecord], now: u64, days: u64) -> Self {
+        let last = wallclock::local_day(now);
+        let first = last - days as i64 + 1;
+        let mut totals = Totals::default();
+        let mut stopped = 0;
+        for record in stats::in_days(records, first, last) {
+            totals.add(record);
+            stopped += u64::from(record.kind == PhaseKind::Focus && !record.completed);
+        }
+        let days = (first..=last)
+            .map(|day| {
+                let mut totals = Totals::default();
+                stats::in_days(records, day, day).for_each(|record| totals.add(record));
+                (day, totals)
+            })
+            .collect();
+        let mut tasks = stats::totals_by(records, first, last, |record| {
+            let task = record.task.as_deref()?;
+            Some(match &record.project {
+                Some(project) => format!("{project} · {task}"),
+                None => task.to_string(),
+            })
+        });
+        // Time without a task isn't a task
+        tasks.retain(|(name, _)| name != "(none)");
+        tasks.truncate(TOP_TASKS);
+        Self {
+            first,
+            last,
+            totals,
+            stopped,
+            days,
+            tasks,
+            projects: stats::totals_by(records, first, last, |record| record.project.clone()),
+        }
+    }
+
+    fn title(&self) -> String {
+        format!(
+            "Focus report: {} to {}",
+            wallclock::format_date(self.first),
+            wallclock::format_date(self.last)
+        )
+    }
+
+    /// The headline numbers, one sentence each.
+    fn highlights(&self) -> Vec<String> {
+        let days = self.days.len() as u64;
+        let mut lines = vec![
+            format!(
+                "{} focus sessions finished, {} stopped early",
+                self.totals.completed, self.stopped
+            ),
+            format!(
+                "{} in focus, {} a day on average",
+                stats::hours_minutes(self.totals.focus_secs),
+                stats::hours_minutes(self.totals.focus_secs / days.max(1))
+            ),
+        ];
+        if let Some((day, totals)) = self
+            .days
+            .iter()
+            .filter(|(_, totals)| totals.focus_secs > 0)
+            .max_by_key(|(day, totals)| (totals.focus_secs, -day))
+        {
+            lines.push(format!(
+                "Busiest day: {} {} with {}",
+                day_name(*day),
+                wallclock::format_date(*day),
+                stats::hours_minutes(totals.focus_secs)
+            ));
+        }
+        lines
+    }
+
+    fn most_focus(&self) -> u64 {
+        self.days
+            .iter()
+            .map(|(_, totals)| totals.focus_secs)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn day_name(day: i64) -> &'static str {
+    stats::WEEKDAYS[wallclock::weekday(day)]
+}
+
+/// The report's bytes: text for Markdown and HTML, binary for PDF.
+pub fn render(report: &Report, format: ReportFormat) -> Vec<u8> {
+    match format {
+        ReportFormat::Md => markdown(report).into_bytes(),
+        ReportFormat::Html => html(report).into_bytes(),
+        ReportFormat::Pdf => to_pdf(report),
+    }
+}
+
+fn markdown(report: &Report) -> String {
+    let mut out = format!("# {}\n\n", report.title());
+    for line in report.highlights() {
+        let _ = writeln!(out, "- {line}");
+    }
+
+    out.push_str("\n## By day\n\n```text\n");
+    let most = report.most_focus();
+    for (day, totals) in &report.days {
+        let filled = (totals.focus_secs * BAR).checked_div(most).unwrap_or(0);
+        let _ = writeln!(
+            out,
+            "{} {}  {}{}  {:>7}  {:>2} sessions",
+            day_name(*day),
+            &wallclock::format_date(*day)[5..],
+            "█".repeat(filled as usize),
+            "░".repeat((BAR - filled) as usize),
+            stats::hours_minutes(totals.focus_secs),
+            totals.completed
+        );
+    }
+    out.push_str("```\n");
+
+    for (heading, column, rows) in [
+        ("Top tasks", "Task", &report.tasks),
+        ("By project", "Project", &report.projects),
+    ] {
+        if rows.is_empty() {
+            continue;
+        }
+        let _ = write!(
+            out,
+            "\n## {heading}\n\n| {column} | Sessions | Focus |\n|---|---:|---:|\n"
+        );
+        for (name, totals) in rows {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                name.replace('|', "\\|"),
+                totals.completed,
+                stats::hours_minutes(totals.focus_secs)
+            );
+        }
+    }
+    out
+}
+
+fn html(report: &Report) -> String {
+    let title = escape(&report.title());
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>\n\
+         body {{ font-family: system-ui, sans-serif; max-width: 48rem; margin: 2rem auto; color: #222; }}\n\
+         table {{ border-collapse: collapse; }}\n\
+         th, td {{ padding: 0.25rem 0.75rem; border-bottom: 1px solid #ddd; text-align: left; }}\n\
+         td.n {{ text-align: right; }}\n\
+         </style>\n</head>\n<body>\n<h1>{title}</h1>\n<ul>\n"
+    );
+    for line in report.highlights() {
+        let _ = writeln!(out, "<li>{}</li>", escape(&line));
+    }
+    out.push_str("</ul>\n<h2>By day</h2>\n");
+    out.push_str(&svg_chart(report));
+
+    for (heading, column, rows) in [
+        ("Top tasks", "Task", &report.tasks),
+        ("By project", "Project", &report.projects),
+    ] {
+        if rows.is_empty() {
+            continue;
+        }
+        let _ = write!(
+            out,
+            "<h2>{heading}</h2>\n<table>\n<tr><th>{column}</th><th>Sessions</th><th>Focus</th></tr>\n"
+        );
+        for (name, totals) in rows {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td class=\"n\">{}</td><td class=\"n\">{}</td></tr>",
+                escape(name),
+                totals.completed,
+                stats::hours_minutes(totals.focus_secs)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Left margin, and the bottom one pages break at.
+const MARGIN: f32 = 50.0;
+
+/// Lines down an A4 page, starting a new one when it runs out.
+struct Pages {
+    doc: Document,
+    y: f32,
+}
+
+impl Pages {
+    /// Where a line `height` points tall goes.
+    fn line(&mut self, height: f32) -> f32 {
+        if self.y - height < MARGIN {
+            self.doc.new_page();
+            self.y = pdf::HEIGHT - MARGIN;
+        }
+        self.y -= height;
+        self.y
+    }
+}
+
+/// The same report as the others, with the chart as a bar per day.
+fn to_pdf(report: &Report) -> Vec<u8> {
+    const ACCENT: (u8, u8, u8) = (189, 147, 249);
+    const TRACK: (u8, u8, u8) = (230, 230, 230);
+    const BAR_WIDTH: f32 = 280.0;
+    let mut pages = Pages {
+        doc: Document::default(),
+        y: pdf::HEIGHT - MARGIN,
+    };
+    let y = pages.line(20.0);
+    pages.doc.text(MARGIN, y, 18.0, true, &report.title());
+    pages.line(8.0);
+    for line in report.highlights() {
+        let y = pages.line(16.0);
+        pages
+            .doc
+            .text(MARGIN, y, 11.0, false, &format!("•  {line}"));
+    }
+
+    let y = pages.line(36.0);
+    pages.doc.text(MARGIN, y, 14.0, true, "By day");
+    pages.line(4.0);
+    let most = report.most_focus();
+    for (day, totals) in &report.days {
+        let y = pages.line(15.0);
+        let label = format!("{} {}", day_name(*day), &wallclock::format_date(*day)[5..]);
+        pages.doc.text(MARGIN, y, 10.0, false, &label);
+        let x = MARGIN + 70.0;
+        pages.doc.rect(x, y - 1.0, BAR_WIDTH, 9.0, TRACK);
+        if let Some(share) = (totals.focus_secs * 1000).checked_div(most) {
+            let width = BAR_WIDTH * share as f32 / 1000.0;
+            pages.doc.rect(x, y - 1.0, width, 9.0, ACCENT);
+        }
+        let value = format!(
+            "{}   {} sessions",
+            stats::hours_minutes(totals.focus_secs),
+            totals.completed
+        );
+        pages.doc.text(x + BAR_WIDTH + 12.0, y, 10.0, false, &value);
+    }
+
+    for (heading, column, rows) in [
+        ("Top tasks", "Task", &report.tasks),
+        ("By project", "Project", &report.projects),
+    ] {
+        if rows.is_empty() {
+            continue;
+        }
+        let y = pages.line(36.0);
+        pages.doc.text(MARGIN, y, 14.0, true, heading);
+        let y = pages.line(20.0);
+        for (x, title) in [(MARGIN, column), (380.0, "Sessions"), (460.0, "Focus")] {
+            pages.doc.text(x, y, 10.0, true, title);
+        }
+        for (name, totals) in rows {
+            let y = pages.line(15.0);
+            // Columns don't move for a long name; it's cut short instead
+            let name: String = if name.chars().count() > 55 {
+                name.chars().take(54).chain(['…']).collect()
+            } else {
+                name.clone()
+            };
+            pages.doc.text(MARGIN, y, 10.0, false, &name);
+            pages
+                .doc
+                .text(380.0, y, 10.0, false, &totals.completed.to_string());
+            pages.doc.text(
+                460.0,
+                y,
+                10.0,
+                false,
+                &stats::hours_minutes(totals.focus_secs),
+            );
+        }
+    }
+    pages.doc.finish()
+}
+
+/// Focus minutes by day as bars, labelled with the day under each.
+fn svg_chart(report: &Report) -> String {
+    const HEIGHT: u64 = 160;
+    const STEP: u64 = 24;
+    let most = report.most_focus();
+    let width = STEP * report.days.len() as u64;
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{}\" \
+         role=\"img\" aria-label=\"Focus minutes by day\" font-size=\"10\" text-anchor=\"middle\">\n",
+        HEIGHT + 16
+    );
+    for (i, (day, totals)) in report.days.iter().enumerate() {
+        let x = i as u64 * STEP;
+        let height = (totals.focus_secs * HEIGHT).checked_div(most).unwrap_or(0);
+        let _ = writeln!(
+            out,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{height}\" fill=\"#bd93f9\">\
+             <title>{} {}: {}</title></rect>",
+            x + 2,
+            HEIGHT - height,
+            STEP - 4,
+            day_name(*day),
+            wallclock::format_date(*day),
+            stats::hours_minutes(totals.focus_secs)
+        );
+        let _ = writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\">{}</text>",
+            x + STEP / 2,
+            HEIGHT + 12,
+            wallclock::civil_from_days(*day).2
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// `text` safe to put in HTML, in content or in a quoted attribute.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_a_week_as_markdown_and_html() {
+        let focus =
+            |start: u64, minutes: u64, project: Option<&str>, task: Option<&str>| SessionRecord {
+                kind: PhaseKind::Focus,
+                start,
+                end: start + minutes * 60,
+                planned_secs: 1500,
+                completed: minutes >= 25,
+                pauses: Vec::new(),
+                snoozes: Vec::new(),
+                project: project.map(str::to_string),
+                task: task.map(str::to_string),
+                estimate: None,
+                rating: None,
+                extended_secs: 0,
+                distractions: Vec::new(),
+                rested: None,
+                utc_offset: None,
+                rolled_up: false,
+            };
+        // Tuesday 2024-05-07, noon
+        let day = wallclock::days_from_civil(2024, 5, 7);
+        let utc_noon = day as u64 * 86_400 + 12 * 3600;
+        let noon = (utc_noon as i64 - wallclock::local_offset(utc_noon)) as u64;
+        let records = [
+            focus(noon - 3600, 25, Some("thesis"), Some("outline")),
+            focus(noon - 7200, 50, Some("thesis"), Some("outline")),
+            focus(noon - 86_400, 10, Some("site <b>"), Some("a|b")),
+            focus(noon - 2 * 86_400, 25, None, None),
+        ];
+        let report = Report::build(&records, noon, 7);
+        assert_eq!(report.totals.completed, 3);
+        assert_eq!(report.stopped, 1);
+        assert_eq!(report.days.len(), 7);
+        assert_eq!(report.tasks.len(), 2);
+
+        let md = markdown(&report);
+        assert!(md.starts_with("# Focus report: 2024-05-01 to 2024-05-07\n\n"));
+        assert!(md.contains("- 3 focus sessions finished, 1 stopped early\n"));
+        assert!(md.contains("- 1h 50m in focus, 0h 15m a day on average\n"));
+        assert!(md.contains("- Busiest day: Tue 2024-05-07 with 1h 15m\n"));
+        assert!(md.contains(&format!(
+            "Tue 05-07  {}   1h 15m   2 sessions\n",
+            "█".repeat(24)
+        )));
+        assert!(
+            md.contains("| thesis · outline | 2 | 1h 15m |\n| site <b> · a\\|b | 0 | 0h 10m |\n")
+        );
+        assert!(md.contains("| (none) | 1 | 0h 25m |\n"));
+
+        let html = html(&report);
+        assert!(html.contains("<td>site &lt;b&gt; · a|b</td>"));
+        assert_eq!(html.matches("<rect ").count(), 7);
+        assert!(html.ends_with("</svg>\n<h2>Top tasks</h2>\n<table>\n<tr><th>Task</th><th>Sessions</th><th>Focus</th></tr>\n<tr><td>thesis · outline</td><td class=\"n\">2</td><td class=\"n\">1h 15m</td></tr>\n<tr><td>site &lt;b&gt; · a|b</td><td class=\"n\">0</td><td class=\"n\">0h 10m</td></tr>\n</table>\n<h2>By project</h2>\n<table>\n<tr><th>Project</th><th>Sessions</th><th>Focus</th></tr>\n<tr><td>thesis</td><td class=\"n\">2</td><td class=\"n\">1h 15m</td></tr>\n<tr><td>(none)</td><td class=\"n\">1</td><td class=\"n\">0h 25m</td></tr>\n<tr><td>site &lt;b&gt;</td><td class=\"n\">0</td><td class=\"n\">0h 10m</td></tr>\n</table>\n</body>\n</html>\n"));
+
+        let pdf = to_pdf(&report);
+        let find = |needle: &[u8]| pdf.windows(needle.len()).any(|w| w == needle);
+        assert!(find(b"(Focus report: 2024-05-01 to 2024-05-07) Tj"));
+        assert!(find(b"(\x95  Busiest day: Tue 2024-05-07 with 1h 15m) Tj"));
+        assert!(find(b"(site <b> \xb7 a|b) Tj"));
+        assert!(find(b"/Count 1 >>"));
+    }
+}
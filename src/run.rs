@@ -1,63 +1,446 @@
-use std::io;
+use std::io::{self, Stdout, Write};
+use std::panic;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
 use std::time::{Duration, Instant};
 
 use crossterm::event::{
     self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
 };
-use crossterm::execute;
 use crossterm::terminal::{
-    EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode,
+    EnterAlternateScreen, LeaveAlternateScreen, SetTitle, disable_raw_mode, enable_raw_mode,
 };
+use crossterm::{cursor, execute};
 use ratatui::Terminal;
 use ratatui::backend::CrosstermBackend;
 
+use clap::{ArgMatches, FromArgMatches};
+
+use crate::announce::{self, Announcer};
+use crate::args::Args;
+use crate::control::Control;
+use crate::graphics::Graphics;
+use crate::i18n::{t, tf};
+use crate::integrations::telegram;
 use crate::state::AppState;
-use crate::ui;
+use crate::{audio, calendar, config, control, mqtt, server, signals, stats, ui};
+
+/// Where the state goes each tick: the ratatui screen, plain announcements
+/// for `--accessible`, or nowhere for `--background`.
+enum Screen {
+    /// With pictures for terminals that show them, and the window title last set.
+    Tui(Terminal<CrosstermBackend<Stdout>>, Option<Graphics>, String),
+    Plain(Announcer),
+    Headless,
+}
+
+impl Screen {
+    /// Expects the terminal already set up by a [`TerminalGuard`].
+    fn open(app: &AppState) -> io::Result<Self> {
+        if app.args.background {
+            return Ok(Screen::Headless);
+        }
+        if app.args.accessible {
+            say(t("announce.help"))?;
+            let every = Duration::from_secs(app.args.announce_every * 60);
+            return Ok(Screen::Plain(Announcer::new(every, app.bell.rings())));
+        }
+        let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+        terminal.hide_cursor()?;
+        Ok(Screen::Tui(
+            terminal,
+            app.graphics.map(Graphics::new),
+            String::new(),
+        ))
+    }
+
+    fn draw(&mut self, app: &AppState) -> io::Result<()> {
+        match self {
+            Screen::Tui(terminal, graphics, title) => {
+                let now = app.now();
+                let wanted = app.window_title();
+                if *title != wanted {
+                    execute!(io::stdout(), SetTitle(&wanted))?;
+                    *title = wanted;
+                }
+                let frame = terminal.draw(|frame| ui::draw(frame, app, now))?;
+                if let Some(graphics) = graphics {
+                    let areas = ui::picture_areas(app, frame.area);
+                    let color = ui::phase_color(app);
+                    graphics.draw(&mut io::stdout(), areas, app.progress(now), color)?;
+                }
+            }
+            Screen::Plain(announcer) => {
+                for line in announcer.lines(app, app.now()) {
+                    say(&line)?;
+                }
+            }
+            Screen::Headless => {}
+        }
+        Ok(())
+    }
+
+    /// What a terminal event means to the loop, once the screen has followed
+    /// any resize.
+    fn input(&mut self, event: Event) -> io::Result<Option<Input>> {
+        match event {
+            Event::Key(key) if key.kind == KeyEventKind::Press => Ok(Some(Input::Key(key.code))),
+            Event::Resize(..) => {
+                if let Screen::Tui(terminal, graphics, _) = self {
+                    terminal.autoresize()?;
+                    if let Some(graphics) = graphics {
+                        graphics.forget();
+                    }
+                }
+                Ok(Some(Input::Resize))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+fn picker_key(app: &mut AppState, code: KeyCode) {
+    match code {
+        KeyCode::Up | KeyCode::Char('k') => app.theme = app.theme.cycle(-1),
+        KeyCode::Down | KeyCode::Char('j') => app.theme = app.theme.cycle(1),
+        KeyCode::Enter => {
+            app.close_theme_picker(true);
+            save_theme(app);
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('T') => app.close_theme_picker(false),
+        _ => {}
+    }
+}
+
+/// Remembers the theme for the next run. The file watcher then sees the change,
+/// which `AppState::reload` treats as already applied.
+fn save_theme(app: &AppState) {
+    if let Err(e) = config::update("timer", &[("theme", app.theme.key())]) {
+        tracing::warn!(error = %e, "cannot save the theme");
+    }
+}
+
+enum Input {
+    Key(KeyCode),
+    Resize,
+}
+
+/// What the loop wakes up for before the next frame is due.
+enum Wake {
+    Event(Event),
+    /// From a signal or a notification's button.
+    Control(Control),
+    /// SIGINT or SIGTERM; the stop flag is already up.
+    Stop,
+}
+
+/// Reads terminal events on a thread of its own, so the loop can wait on them
+/// and on signals at once.
+fn read_events(wake: Sender<Wake>) {
+    thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            if wake.send(Wake::Event(event)).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Upper bound on how long the loop sleeps while HTTP, MQTT or Telegram commands
+/// may be waiting, since those arrive on channels of their own.
+const REMOTE_LATENCY: Duration = Duration::from_millis(250);
+
+/// Raw mode, plus the alternate screen and mouse capture for the full-screen UI.
+/// Undone on drop, so an early `?` return cannot leave the terminal raw, and from
+/// a panic hook, so the panic message lands on a usable terminal.
+pub struct TerminalGuard {
+    full_screen: bool,
+}
+
+impl TerminalGuard {
+    pub fn enter(full_screen: bool) -> io::Result<Self> {
+        enable_raw_mode()?;
+        let guard = TerminalGuard { full_screen };
+        if full_screen {
+            execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+            // Saves the window title, for the timer's own to be taken back off
+            write!(io::stdout(), "\x1b[22;0t")?;
+        }
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            // Worker threads (audio, servers) may panic without ending the UI.
+            if thread::current().name() == Some("main") {
+                restore_terminal(full_screen);
+            }
+            previous(info);
+        }));
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal(self.full_screen);
+    }
+}
 
-pub fn run(mut app: AppState) -> io::Result<()> {
+/// Best effort: every step is tried even if an earlier one fails.
+fn restore_terminal(full_screen: bool) {
+    if full_screen {
+        let _ = execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            cursor::Show
+        );
+        let mut stdout = io::stdout();
+        let _ = write!(stdout, "\x1b[23;0t").and_then(|()| stdout.flush());
+    }
+    let _ = disable_raw_mode();
+}
+
+/// Prints a line while in raw mode.
+fn say(line: &str) -> io::Result<()> {
     let mut stdout = io::stdout();
-    enable_raw_mode()?;
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
-    terminal.hide_cursor()?;
+    write!(stdout, "{line}\r\n")?;
+    stdout.flush()
+}
+
+/// Exit status for a `--once` session or `--cycles` block cut short.
+pub const EXIT_ABANDONED: i32 = 3;
+
+/// Runs until quit and reports whether the run reached its goal (see
+/// [`AppState::goal_reached`]). `matches` are the timer flags as given, so a
+/// reloaded config never overrides them.
+pub fn run(mut app: AppState, matches: &ArgMatches) -> io::Result<bool> {
+    let mut remotes = Vec::new();
+    if let Some(addr) = &app.args.serve {
+        remotes.push(server::spawn(addr, app.args.serve_token.clone())?);
+    }
+    if let Some(addr) = &app.args.mqtt {
+        remotes.push(mqtt::spawn(addr)?);
+    }
+    if let Some(bot) = &app.args.telegram {
+        remotes.push(telegram::spawn(bot));
+    }
+    let (wake, woken) = mpsc::channel();
+    #[cfg(unix)]
+    let mut pid_file = {
+        let wake = wake.clone();
+        signals::spawn(move |control| {
+            let sent = match control {
+                Some(control) => wake.send(Wake::Control(control)),
+                None => wake.send(Wake::Stop),
+            };
+            sent.is_ok()
+        })?;
+        signals::PidFile::create()
+    };
+
+    let reloads = config::watch();
+    let meetings = app
+        .args
+        .calendar
+        .as_deref()
+        .and_then(|spec| calendar::Source::parse(spec).ok())
+        .map(calendar::watch);
+    let stop = signals::stop_flag();
+    // Buttons pressed on notifications
+    let (controls, clicks) = mpsc::channel();
+    app.bus.connect(&controls);
+    let forward = wake.clone();
+    thread::spawn(move || {
+        for control in clicks {
+            if forward.send(Wake::Control(control)).is_err() {
+                break;
+            }
+        }
+    });
 
-    let tick_rate = Duration::from_millis(200);
-    let mut last_tick = Instant::now();
+    // This run's phases, for the summary left in the scrollback
+    let first_record = app.history.records.len();
+    let terminal = if app.args.background {
+        None
+    } else {
+        Some(TerminalGuard::enter(!app.args.accessible)?)
+    };
+    let mut screen = Screen::open(&app)?;
+    if !matches!(screen, Screen::Headless) {
+        read_events(wake.clone());
+    }
+
+    // Frames are drawn when something changed and when the clock ticks over,
+    // rather than on a fixed timer, so an idle timer wakes about once a second.
+    let mut dirty = true;
+    let mut next_frame = Instant::now();
 
     loop {
-        terminal.draw(|frame| ui::draw(frame, &app))?;
+        let now = Instant::now();
+        if dirty || now >= next_frame {
+            screen.draw(&app)?;
+            dirty = false;
+            next_frame = now + app.clock.real(app.until_clock_changes(app.now()));
+        }
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
 
-        // Phase transitions
-        if app.time_remaining(Instant::now()).is_zero() && !app.paused {
-            app.advance_phase();
+        for remote in &remotes {
+            while let Ok(command) = remote.commands.try_recv() {
+                control::apply(&mut app, command);
+                dirty = true;
+            }
+            remote.publish(&app);
         }
+        #[cfg(unix)]
+        pid_file.publish(&app);
 
-        // Input handling with tick
-        let timeout = tick_rate.saturating_sub(last_tick.elapsed());
-        if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char(' ') => app.toggle_pause(),
-                        KeyCode::Char('n') => app.skip(),
-                        KeyCode::Char('r') => app.reset_phase(),
-                        KeyCode::Char('q') | KeyCode::Esc => break,
-                        _ => {}
+        while let Some(result) = reloads.as_ref().and_then(|r| r.try_recv().ok()) {
+            dirty = true;
+            let accessible = matches!(screen, Screen::Plain(_));
+            match result {
+                Ok(config) => {
+                    let Ok(mut settings) = Args::from_arg_matches(matches) else {
+                        continue;
+                    };
+                    config.apply(&mut settings, matches);
+                    app.reload(&settings);
+                    app.config_error = None;
+                    if accessible {
+                        say(t("announce.reloaded"))?;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "config reload failed");
+                    let line = tf("ui.config_error", &[("error", &e)]);
+                    if accessible {
+                        say(&line)?;
                     }
+                    app.config_error = Some(line);
+                }
+            }
+        }
+
+        while let Some(result) = meetings.as_ref().and_then(|m| m.try_recv().ok()) {
+            match result {
+                Ok(meetings) => {
+                    app.set_meetings(meetings);
+                    dirty = true;
                 }
+                Err(e) => tracing::warn!(error = %e, "cannot read the calendar"),
             }
         }
+        dirty |= app.check_lunch();
+        dirty |= app.check_meetings();
+        dirty |= app.wake_from_snooze();
+        app.send_recorded();
+        // Phase transitions
+        if app.time_remaining(app.now()).is_zero()
+            && !app.paused
+            && !app.finished
+            && !app.hold_for_extension()
+        {
+            app.advance_phase();
+            dirty = true;
+        }
+        app.bus.dispatch();
+        if app.finished && (app.args.exit_on_complete || app.args.once) {
+            screen.draw(&app)?;
+            break;
+        }
 
-        if last_tick.elapsed() >= tick_rate {
-            last_tick = Instant::now();
+        if dirty {
+            continue;
+        }
+        let mut timeout = next_frame.saturating_duration_since(Instant::now());
+        if !remotes.is_empty() {
+            timeout = timeout.min(REMOTE_LATENCY);
+        }
+        let event = match woken.recv_timeout(timeout) {
+            Ok(Wake::Event(event)) => event,
+            Ok(Wake::Control(command)) => {
+                control::apply(&mut app, command);
+                dirty = true;
+                continue;
+            }
+            Ok(Wake::Stop) | Err(_) => continue,
+        };
+        let key = match screen.input(event)? {
+            Some(Input::Key(key)) => key,
+            Some(Input::Resize) => {
+                dirty = true;
+                continue;
+            }
+            None => continue,
+        };
+        tracing::debug!(?key, "key");
+        dirty = true;
+        if key != KeyCode::Char('n') {
+            app.cancel_skip();
+        }
+        match key {
+            code if app.theme_picker.is_some() => picker_key(&mut app, code),
+            KeyCode::Char(digit @ '1'..='5') if app.rating_prompt.is_some() => {
+                app.rate(digit as u8 - b'0');
+            }
+            KeyCode::Esc if app.rating_prompt.is_some() => app.rating_prompt = None,
+            KeyCode::Char(digit @ '1'..='5') if app.cycle_prompt => {
+                app.rate_cycle(digit as u8 - b'0');
+            }
+            KeyCode::Esc if app.cycle_prompt => app.cycle_prompt = false,
+            KeyCode::Enter if app.extension_offer.is_some() => app.extend(),
+            KeyCode::Esc if app.extension_offer.is_some() => app.advance_phase(),
+            KeyCode::Char('g') | KeyCode::Esc if app.show_garden => app.show_garden = false,
+            KeyCode::Char('g') if !app.args.accessible => app.show_garden = true,
+            KeyCode::Char('a') | KeyCode::Esc if app.show_achievements => {
+                app.show_achievements = false;
+            }
+            KeyCode::Char('a') if !app.args.accessible => app.show_achievements = true,
+            KeyCode::Char(' ') => app.toggle_pause(),
+            KeyCode::Char('n') => app.request_skip(),
+            KeyCode::Char('r') => app.reset(),
+            KeyCode::Char('z') => app.snooze(),
+            KeyCode::Char('e') => app.start_eye_rest(),
+            KeyCode::Char('f') => {
+                app.fit_to_meeting();
+            }
+            KeyCode::Char('u') => {
+                app.undo();
+            }
+            KeyCode::Char('v') => app.show_timeline = !app.show_timeline,
+            KeyCode::Char('t') if !app.args.accessible => {
+                app.theme = app.theme.cycle(1);
+                save_theme(&app);
+            }
+            KeyCode::Char('T') if !app.args.accessible => app.open_theme_picker(),
+            KeyCode::Char('s') if app.args.accessible => {
+                say(&announce::status(&app, app.now()))?;
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                if let Some(ambient) = &mut app.ambient {
+                    ambient.change_volume(audio::VOLUME_STEP);
+                }
+            }
+            KeyCode::Char('-') => {
+                if let Some(ambient) = &mut app.ambient {
+                    ambient.change_volume(-audio::VOLUME_STEP);
+                }
+            }
+            KeyCode::Char('q') | KeyCode::Esc => break,
+            _ => {}
         }
     }
 
-    // teardown
-    terminal.show_cursor()?;
-    disable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, LeaveAlternateScreen, DisableMouseCapture)?;
-    Ok(())
+    app.finish();
+    app.bus.dispatch();
+
+    let full_screen = matches!(screen, Screen::Tui(..));
+    drop(screen);
+    drop(terminal);
+    let records = app.history.records.get(first_record..).unwrap_or_default();
+    if full_screen && !records.is_empty() {
+        print!("{}", stats::render_run(records));
+    }
+    Ok(app.goal_reached())
 }
@@ -0,0 +1,156 @@
+//! The focus score: one number from 0 to 100 for a day, from how many focus
+//! sessions were finished, how little they were paused, how few were skipped
+//! and how many breaks were rested. How much each counts is set in `[score]`.
+
+use crate::history::SessionRecord;
+use crate::state::PhaseKind;
+use crate::wallclock;
+
+/// How much each part counts, relative to the others, and the finished
+/// sessions that earn the whole of the first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Weights {
+    pub sessions: u64,
+    pub pauses: u64,
+    pub skips: u64,
+    pub rest: u64,
+    pub target: u64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            sessions: 40,
+            pauses: 20,
+            skips: 20,
+            rest: 20,
+            target: 8,
+        }
+    }
+}
+
+/// The score for `records`, all from one day. `None` without a focus session,
+/// or with every weight at 0. Breaks without `--track-rest` leave rest out.
+pub fn score(records: &[SessionRecord], weights: &Weights) -> Option<u8> {
+    let focus: Vec<&SessionRecord> = records
+        .iter()
+        .filter(|r| r.kind == PhaseKind::Focus)
+        .collect();
+    if focus.is_empty() {
+        return None;
+    }
+    let finished = focus.iter().filter(|r| r.completed).count() as f64;
+    let (mut elapsed, mut paused) = (0, 0);
+    for record in &focus {
+        elapsed += record.end.saturating_sub(record.start);
+        paused += record
+            .pauses
+            .iter()
+            .chain(&record.snoozes)
+            .map(|(s, e)| e.saturating_sub(*s))
+            .sum::<u64>();
+    }
+    let rested: Vec<bool> = records.iter().filter_map(|r| r.rested).collect();
+    let mut parts = vec![
+        (
+            weights.sessions,
+            (finished / weights.target.max(1) as f64).min(1.0),
+        ),
+        (
+            weights.pauses,
+            1.0 - (paused as f64 / elapsed.max(1) as f64).min(1.0),
+        ),
+        (weights.skips, finished / focus.len() as f64),
+    ];
+    if !rested.is_empty() {
+        let share = rested.iter().filter(|r| **r).count() as f64 / rested.len() as f64;
+        parts.push((weights.rest, share));
+    }
+    let total: u64 = parts.iter().map(|(weight, _)| weight).sum();
+    if total == 0 {
+        return None;
+    }
+    let weighted: f64 = parts
+        .iter()
+        .map(|(weight, part)| *weight as f64 * part)
+        .sum();
+    Some((weighted / total as f64 * 100.0).round() as u8)
+}
+
+/// Scores for the `days` local days ending today, oldest first.
+pub fn trend(records: &[SessionRecord], now: u64, days: u64, weights: &Weights) -> Vec<Option<u8>> {
+    let today = wallclock::local_day(now);
+    (0..days as i64)
+        .rev()
+        .map(|back| {
+            let day: Vec<SessionRecord> = records
+                .iter()
+                .filter(|r| r.day() == today - back)
+                .cloned()
+                .collect();
+            score(&day, weights)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(kind: PhaseKind, start: u64, completed: bool) -> SessionRecord {
+        SessionRecord {
+            kind,
+            completed,
+            ..SessionRecord::test_focus(start, 1000)
+        }
+    }
+
+    #[test]
+    fn weighs_sessions_pauses_skips_and_rest() {
+        let weights = Weights::default();
+        assert_eq!(score(&[], &weights), None);
+        // 2 of 8 sessions, no pauses or skips, rest not tracked: (10 + 20 + 20) / 80
+        let mut day = vec![
+            record(PhaseKind::Focus, 0, true),
+            record(PhaseKind::Focus, 2000, true),
+        ];
+        assert_eq!(score(&day, &weights), Some(63));
+        // A skipped session and a pause over a tenth of the focus time
+        day.push(record(PhaseKind::Focus, 4000, false));
+        day[0].pauses.push((100, 400));
+        // (10 + 18 + 13.3) / 80
+        assert_eq!(score(&day, &weights), Some(52));
+        // Two tracked breaks, one of them worked through: + 10 / 100
+        let mut rested = record(PhaseKind::ShortBreak, 1000, true);
+        rested.rested = Some(true);
+        day.push(rested);
+        let mut worked = record(PhaseKind::ShortBreak, 3000, true);
+        worked.rested = Some(false);
+        day.push(worked);
+        assert_eq!(score(&day, &weights), Some(51));
+
+        let only_skips = Weights {
+            sessions: 0,
+            pauses: 0,
+            rest: 0,
+            ..weights
+        };
+        assert_eq!(score(&day, &only_skips), Some(67));
+        let nothing = Weights {
+            skips: 0,
+            ..only_skips
+        };
+        assert_eq!(score(&day, &nothing), None);
+
+        let now = 1_700_000_000;
+        let midnight = now - wallclock::seconds_of_local_day(now);
+        let records = [
+            record(PhaseKind::Focus, midnight - 2 * 86_400, true),
+            record(PhaseKind::Focus, midnight, true),
+        ];
+        assert_eq!(
+            trend(&records, now, 3, &weights),
+            [Some(56), None, Some(56)]
+        );
+    }
+}
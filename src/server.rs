@@ -0,0 +1,483 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::args::Args;
+use crate::control::{Control, Durations};
+use crate::doctor::Probe;
+use crate::json::{self, Value};
+use crate::state::AppState;
+use crate::validate;
+use crate::websocket;
+
+const OVERLAY_HTML: &str = include_str!("../assets/overlay.html");
+const EVENT_INTERVAL: Duration = Duration::from_secs(1);
+const MAX_BODY: usize = 64 * 1024;
+/// Limits on the request line and headers, like the body's.
+const MAX_LINE: usize = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+/// Connections handled at once, event streams included; more are turned away.
+const MAX_CONNECTIONS: usize = 32;
+
+/// Run-loop side of a remote frontend: commands coming in and the status going out.
+pub struct Remote {
+    pub commands: Receiver<Control>,
+    status: Arc<Mutex<Value>>,
+}
+
+impl Remote {
+    /// The remote plus the ends its background threads hold.
+    pub fn new() -> (Self, Sender<Control>, Arc<Mutex<Value>>) {
+        let (tx, rx) = mpsc::channel();
+        let status = Arc::new(Mutex::new(Value::Null));
+        let remote = Remote {
+            commands: rx,
+            status: Arc::clone(&status),
+        };
+        (remote, tx, status)
+    }
+
+    pub fn publish(&self, app: &AppState) {
+        if let Ok(mut status) = self.status.lock() {
+            *status = status_json(app, app.now());
+        }
+    }
+}
+
+/// Binds `addr` and serves the API on background threads. POST endpoints need
+/// `Authorization: Bearer <token>` and are refused outright when no token is set.
+pub fn spawn(addr: &str, token: Option<String>) -> io::Result<Remote> {
+    let listener = TcpListener::bind(addr)?;
+    let (remote, tx, shared) = Remote::new();
+    let open = Arc::new(AtomicUsize::new(0));
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            if open.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                open.fetch_sub(1, Ordering::SeqCst);
+                let _ = respond(&mut stream, 503, &error_json("too many connections"));
+                continue;
+            }
+            let tx = tx.clone();
+            let status = Arc::clone(&shared);
+            let token = token.clone();
+            let open = Arc::clone(&open);
+            thread::spawn(move || {
+                if let Err(e) = handle(stream, token.as_deref(), &status, &tx) {
+                    tracing::debug!(error = %e, "API request failed");
+                }
+                open.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+    });
+    Ok(remote)
+}
+
+/// Whether `--serve` can bind and take commands, for `doctor`.
+pub fn probe(args: &Args) -> Probe {
+    let Some(addr) = &args.serve else {
+        return Probe::Off("not enabled (--serve)".to_string());
+    };
+    if let Err(e) = TcpListener::bind(addr) {
+        return Probe::Fail(format!("cannot listen on {addr}: {e}"));
+    }
+    match &args.serve_token {
+        Some(_) => Probe::Ok(format!("can listen on {addr}")),
+        None => Probe::Warn(format!(
+            "can listen on {addr}, but POST endpoints are refused without --serve-token"
+        )),
+    }
+}
+
+pub fn status_json(app: &AppState, now: Instant) -> Value {
+    Value::Object(vec![
+        ("phase".to_string(), app.current_phase.kind.key().into()),
+        (
+            "remaining_secs".to_string(),
+            app.time_remaining(now).as_secs().into(),
+        ),
+        (
+            "duration_secs".to_string(),
+            app.current_phase.duration.as_secs().into(),
+        ),
+        ("paused".to_string(), app.paused.into()),
+        ("session".to_string(), app.session_index.into()),
+        ("completed_focus".to_string(), app.completed_focus.into()),
+        ("focus_today".to_string(), app.focus_today().into()),
+        ("project".to_string(), app.args.project.as_deref().into()),
+        ("task".to_string(), app.args.task.as_deref().into()),
+    ])
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Reads one line into `line`, refusing any longer than `MAX_LINE`; 0 at the end.
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> io::Result<usize> {
+    let read = reader.take(MAX_LINE as u64 + 1).read_line(line)?;
+    if read > MAX_LINE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+    }
+    Ok(read)
+}
+
+pub fn read_request(reader: &mut impl BufRead) -> io::Result<Request> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let mut line = String::new();
+    read_line(reader, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().ok_or_else(|| invalid("empty request"))?;
+    let target = parts.next().ok_or_else(|| invalid("missing path"))?;
+    let mut request = Request {
+        method: method.to_string(),
+        path: target.split('?').next().unwrap_or(target).to_string(),
+        ..Request::default()
+    };
+    loop {
+        line.clear();
+        if read_line(reader, &mut line)? == 0 {
+            break;
+        }
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if request.headers.len() == MAX_HEADERS {
+            return Err(invalid("too many headers"));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            request
+                .headers
+                .push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let length: usize = request
+        .header("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    if length > MAX_BODY {
+        return Err(invalid("body too large"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    request.body = String::from_utf8(body).map_err(|_| invalid("body is not UTF-8"))?;
+    Ok(request)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Route {
+    Status,
+    Events,
+    /// WebSocket upgrade carrying the client's `Sec-WebSocket-Key`.
+    WebSocket(String),
+    Overlay,
+    Command(Control),
+}
+
+/// Maps a request to what it asks for, or to an HTTP error status and message.
+pub fn route(request: &Request, token: Option<&str>) -> Result<Route, (u16, String)> {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/api/status") => return Ok(Route::Status),
+        ("GET", "/api/events") => return Ok(Route::Events),
+        ("GET", "/overlay") => return Ok(Route::Overlay),
+        ("GET", "/ws") => {
+            return match request.header("sec-websocket-key") {
+                Some(key) => Ok(Route::WebSocket(key.to_string())),
+                None => Err((400, "expected a WebSocket upgrade".to_string())),
+            };
+        }
+        ("POST", _) => {}
+        _ => return Err((404, "not found".to_string())),
+    }
+    let Some(token) = token else {
+        return Err((403, "control endpoints need --serve-token".to_string()));
+    };
+    let authorized = request
+        .header("authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|given| constant_time_eq(given.as_bytes(), token.as_bytes()));
+    if !authorized {
+        return Err((401, "missing or invalid bearer token".to_string()));
+    }
+    let body = || {
+        if request.body.trim().is_empty() {
+            return Ok(Value::Object(Vec::new()));
+        }
+        json::parse(&request.body).map_err(|e| (400, format!("invalid JSON: {e}")))
+    };
+    let control = match request.path.as_str() {
+        "/api/pause" => Control::Pause,
+        "/api/resume" => Control::Resume,
+        "/api/toggle" => Control::TogglePause,
+        "/api/skip" => Control::Skip,
+        "/api/snooze" => Control::Snooze,
+        "/api/label" => match body()?.get("task") {
+            Some(Value::String(task)) if !task.trim().is_empty() => {
+                Control::SetTask(Some(task.trim().to_string()))
+            }
+            Some(Value::Null) | Some(Value::String(_)) => Control::SetTask(None),
+            _ => return Err((400, "expected {\"task\": string or null}".to_string())),
+        },
+        "/api/config" => {
+            let body = body()?;
+            let integer = |key: &str| match body.get(key) {
+                None => Ok(None),
+                Some(v) => v
+                    .as_u64()
+                    .map(Some)
+                    .ok_or((400, format!("`{key}` must be a non-negative integer"))),
+            };
+            let invalid = |key: &'static str| move |e: String| (400, format!("`{key}` {e}"));
+            let minutes = |key: &'static str| {
+                integer(key)?
+                    .map(|n| validate::phase_length(Duration::from_secs(n.saturating_mul(60))))
+                    .transpose()
+                    .map_err(invalid(key))
+            };
+            Control::SetDurations(Durations {
+                focus: minutes("focus")?,
+                short: minutes("short")?,
+                long: minutes("long")?,
+                long_every: integer("long_every")?
+                    .map(validate::long_every)
+                    .transpose()
+                    .map_err(invalid("long_every"))?,
+            })
+        }
+        _ => return Err((404, "not found".to_string())),
+    };
+    Ok(Route::Command(control))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn handle(
+    stream: TcpStream,
+    token: Option<&str>,
+    status: &Mutex<Value>,
+    commands: &Sender<Control>,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut stream = stream;
+    let request = match read_request(&mut reader) {
+        Ok(request) => request,
+        Err(e) => return respond(&mut stream, 400, &error_json(&e.to_string())),
+    };
+    let current = || status.lock().map(|s| s.clone()).unwrap_or(Value::Null);
+    match route(&request, token) {
+        Ok(Route::Status) => respond(&mut stream, 200, &current()),
+        Ok(Route::Events) => {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n"
+            )?;
+            watch(status, |value| match value {
+                Some(value) => write!(stream, "data: {value}\n\n"),
+                None => write!(stream, ":\n\n"),
+            })
+        }
+        Ok(Route::WebSocket(key)) => {
+            write!(
+                stream,
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                websocket::accept_key(&key)
+            )?;
+            watch(status, |value| match value {
+                Some(value) => stream.write_all(&websocket::text_frame(&value.to_string())),
+                None => stream.write_all(&websocket::PING),
+            })
+        }
+        Ok(Route::Overlay) => {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{OVERLAY_HTML}",
+                OVERLAY_HTML.len()
+            )?;
+            stream.flush()
+        }
+        Ok(Route::Command(control)) => {
+            if commands.send(control).is_err() {
+                return respond(&mut stream, 503, &error_json("timer is shutting down"));
+            }
+            respond(
+                &mut stream,
+                202,
+                &Value::Object(vec![("ok".to_string(), true.into())]),
+            )
+        }
+        Err((code, message)) => respond(&mut stream, code, &error_json(&message)),
+    }
+}
+
+/// Sends the status through `send` whenever it changes, until the client goes
+/// away. In between, `send` gets `None` for a keepalive, so a client that left
+/// while nothing changed doesn't hold its connection slot.
+fn watch(
+    status: &Mutex<Value>,
+    mut send: impl FnMut(Option<&Value>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut last = Value::Null;
+    loop {
+        let now = status.lock().map(|s| s.clone()).unwrap_or(Value::Null);
+        if now != last {
+            send(Some(&now))?;
+            last = now;
+        } else {
+            send(None)?;
+        }
+        thread::sleep(EVENT_INTERVAL);
+    }
+}
+
+fn error_json(message: &str) -> Value {
+    Value::Object(vec![("error".to_string(), message.into())])
+}
+
+fn respond(stream: &mut TcpStream, code: u16, body: &Value) -> io::Result<()> {
+    let reason = match code {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        _ => "Service Unavailable",
+    };
+    let body = body.to_string();
+    write!(
+        stream,
+        "HTTP/1.1 {code} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str, auth: Option<&str>, body: &str) -> Request {
+        let mut headers = Vec::new();
+        if let Some(token) = auth {
+            headers.push(("Authorization".to_string(), format!("Bearer {token}")));
+        }
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers,
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_request_with_body() {
+        let raw = "POST /api/label?x=1 HTTP/1.1\r\nHost: x\r\nContent-Length: 15\r\n\r\n{\"task\":\"Docs\"}";
+        let parsed = read_request(&mut raw.as_bytes()).unwrap();
+        assert_eq!(parsed.method, "POST");
+        assert_eq!(parsed.path, "/api/label");
+        assert_eq!(parsed.header("content-length"), Some("15"));
+        assert_eq!(parsed.body, "{\"task\":\"Docs\"}");
+
+        let long = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        assert!(read_request(&mut long.as_bytes()).is_err());
+        let many = format!(
+            "GET / HTTP/1.1\r\n{}\r\n",
+            "X: y\r\n".repeat(MAX_HEADERS + 1)
+        );
+        assert!(read_request(&mut many.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn status_is_public_but_commands_need_token() {
+        let status = request("GET", "/api/status", None, "");
+        assert_eq!(route(&status, None), Ok(Route::Status));
+        let skip = request("POST", "/api/skip", None, "");
+        assert_eq!(route(&skip, None).unwrap_err().0, 403);
+        assert_eq!(route(&skip, Some("s3cret")).unwrap_err().0, 401);
+        let wrong = request("POST", "/api/skip", Some("guess"), "");
+        assert_eq!(route(&wrong, Some("s3cret")).unwrap_err().0, 401);
+        let skip = request("POST", "/api/skip", Some("s3cret"), "");
+        assert_eq!(
+            route(&skip, Some("s3cret")),
+            Ok(Route::Command(Control::Skip))
+        );
+    }
+
+    #[test]
+    fn routes_websocket_upgrades_and_overlay() {
+        let mut upgrade = request("GET", "/ws", None, "");
+        assert_eq!(route(&upgrade, None).unwrap_err().0, 400);
+        upgrade
+            .headers
+            .push(("Sec-WebSocket-Key".to_string(), "abc==".to_string()));
+        assert_eq!(
+            route(&upgrade, None),
+            Ok(Route::WebSocket("abc==".to_string()))
+        );
+        let overlay = request("GET", "/overlay", None, "");
+        assert_eq!(route(&overlay, None), Ok(Route::Overlay));
+    }
+
+    #[test]
+    fn parses_label_and_config_bodies() {
+        let label = request("POST", "/api/label", Some("t"), r#"{"task": "Write"}"#);
+        assert_eq!(
+            route(&label, Some("t")),
+            Ok(Route::Command(Control::SetTask(Some("Write".to_string()))))
+        );
+        let clear = request("POST", "/api/label", Some("t"), r#"{"task": null}"#);
+        assert_eq!(
+            route(&clear, Some("t")),
+            Ok(Route::Command(Control::SetTask(None)))
+        );
+        let config = request("POST", "/api/config", Some("t"), r#"{"focus": 50}"#);
+        assert_eq!(
+            route(&config, Some("t")),
+            Ok(Route::Command(Control::SetDurations(Durations {
+                focus: Some(Duration::from_secs(50 * 60)),
+                ..Durations::default()
+            })))
+        );
+        let bad = request("POST", "/api/config", Some("t"), r#"{"focus": 0}"#);
+        assert_eq!(route(&bad, Some("t")).unwrap_err().0, 400);
+        let bad = request("POST", "/api/config", Some("t"), r#"{"long_every": 101}"#);
+        assert_eq!(route(&bad, Some("t")).unwrap_err().0, 400);
+        let missing = request("POST", "/api/nope", Some("t"), "");
+        assert_eq!(route(&missing, Some("t")).unwrap_err().0, 404);
+    }
+
+    #[test]
+    fn watching_ends_when_a_keepalive_fails() {
+        let status = Mutex::new(Value::from("focus"));
+        let mut sent = Vec::new();
+        let result = watch(&status, |value| match value {
+            Some(value) => {
+                sent.push(value.clone());
+                Ok(())
+            }
+            None => Err(io::ErrorKind::BrokenPipe.into()),
+        });
+        assert!(result.is_err());
+        assert_eq!(sent, [Value::from("focus")]);
+    }
+}
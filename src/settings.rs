@@ -0,0 +1,142 @@
+//! Where a setting comes from, lowest first: the built-in default, the config
+//! file (`[timer]`, then the profile's durations, then the project's), a
+//! `RUSTY_POMO_*` environment variable, then a flag. The variables let a
+//! container or script set things up without writing a config file.
+
+use std::env;
+
+use crate::config::{self, Config};
+
+/// Each variable, with the config key it stands for.
+pub const VARS: &[(&str, &str, &str)] = &[
+    ("RUSTY_POMO_FOCUS", "timer", "focus"),
+    ("RUSTY_POMO_SHORT", "timer", "short"),
+    ("RUSTY_POMO_LONG", "timer", "long"),
+    ("RUSTY_POMO_LONG_EVERY", "timer", "long_every"),
+    ("RUSTY_POMO_WARM_UP", "timer", "warm_up"),
+    ("RUSTY_POMO_CYCLE_REVIEW", "timer", "cycle_review"),
+    ("RUSTY_POMO_PARTIAL_CREDIT", "timer", "partial_credit"),
+    ("RUSTY_POMO_OFFER_EXTENSION", "timer", "offer_extension"),
+    ("RUSTY_POMO_THEME", "timer", "theme"),
+    ("RUSTY_POMO_MASCOT", "timer", "mascot"),
+    ("RUSTY_POMO_NOTIFICATIONS", "timer", "notifications"),
+    (
+        "RUSTY_POMO_NOTIFICATION_SOUND",
+        "timer",
+        "notification_sound",
+    ),
+    ("RUSTY_POMO_HISTORY_BACKEND", "history", "backend"),
+    ("RUSTY_POMO_HISTORY_DIR", "history", "dir"),
+    ("RUSTY_POMO_TZ", "history", "tz"),
+    ("RUSTY_POMO_AMBIENT", "ambient", "focus"),
+    ("RUSTY_POMO_AMBIENT_BREAK", "ambient", "break"),
+    ("RUSTY_POMO_AMBIENT_VOLUME", "ambient", "volume"),
+    ("RUSTY_POMO_BREATHING", "breaks", "breathing"),
+    ("RUSTY_POMO_TRACK_REST", "breaks", "track_rest"),
+    ("RUSTY_POMO_QUOTES_FILE", "breaks", "quotes_file"),
+    ("RUSTY_POMO_CALENDAR", "calendar", "source"),
+    ("RUSTY_POMO_MEETING_WARNING", "calendar", "warning"),
+    ("RUSTY_POMO_FIT_TO_MEETINGS", "calendar", "fit"),
+];
+
+/// The variables that are set, read as a config file would be; `None` when
+/// there are none.
+pub fn from_env() -> Result<Option<Config>, String> {
+    from_env_with(&|name| env::var(name).ok())
+}
+
+fn from_env_with(var: &dyn Fn(&str) -> Option<String>) -> Result<Option<Config>, String> {
+    let mut contents = String::new();
+    for (name, table, key) in VARS {
+        let Some(value) = var(name) else {
+            continue;
+        };
+        let line = [(*key, toml_value(&value))];
+        // One at a time first, so an error names the variable, not a line
+        Config::parse(&config::set_values("", table, &line)).map_err(|e| {
+            let problem = match e.strip_prefix("line ") {
+                Some(rest) => rest.split_once(": ").map_or(rest, |(_, problem)| problem),
+                None => &e,
+            };
+            format!("{name}={value}: {problem}")
+        })?;
+        contents = config::set_values(&contents, table, &line);
+    }
+    if contents.is_empty() {
+        return Ok(None);
+    }
+    Config::parse(&contents).map(Some)
+}
+
+/// Numbers and `true`/`false` as they are, anything else as a string: `50`
+/// minutes, but `"90s"`.
+fn toml_value(value: &str) -> String {
+    if value.parse::<i64>().is_ok() || value == "true" || value == "false" {
+        value.to_string()
+    } else {
+        config::quote(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use clap::{CommandFactory, FromArgMatches};
+
+    use super::*;
+    use crate::args::Cli;
+    use crate::theme::Theme;
+
+    fn vars(set: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |name| {
+            set.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    #[test]
+    fn variables_sit_between_the_file_and_flags() {
+        let file = Config::parse(
+            "[timer]\nfocus = 30\nshort = 6\ntheme = \"gruvbox-dark\"\n\n[projects.thesis]\nfocus = 45\nlong = 20\n",
+        )
+        .unwrap();
+        let env = vars(&[
+            ("RUSTY_POMO_FOCUS", "50"),
+            ("RUSTY_POMO_SHORT", "90s"),
+            ("RUSTY_POMO_THEME", "dracula"),
+        ]);
+        let config = Config {
+            env: from_env_with(&env).unwrap().map(Box::new),
+            ..file
+        };
+        let resolve = |argv: &[&str]| {
+            let matches = Cli::command().get_matches_from(argv);
+            let mut args = Cli::from_arg_matches(&matches).unwrap().args;
+            config.apply(&mut args, &matches);
+            args
+        };
+
+        // Over the project's durations, under flags; the file fills the rest
+        let args = resolve(&["rusty_pomo", "--project", "thesis"]);
+        assert_eq!(args.focus, Duration::from_secs(50 * 60));
+        assert_eq!(args.short, Duration::from_secs(90));
+        assert_eq!(args.long, Duration::from_secs(20 * 60));
+        assert_eq!(args.theme, Theme::Dracula);
+        let args = resolve(&["rusty_pomo", "--focus", "10", "--theme", "solarized-dark"]);
+        assert_eq!(args.focus, Duration::from_secs(10 * 60));
+        assert_eq!(args.theme, Theme::SolarizedDark);
+
+        assert!(from_env_with(&vars(&[])).unwrap().is_none());
+        assert_eq!(
+            from_env_with(&vars(&[("RUSTY_POMO_AMBIENT_VOLUME", "150")])).unwrap_err(),
+            "RUSTY_POMO_AMBIENT_VOLUME=150: `ambient.volume` must be between 0 and 100"
+        );
+        assert!(
+            from_env_with(&vars(&[("RUSTY_POMO_NOTIFICATIONS", "yes")]))
+                .unwrap_err()
+                .starts_with("RUSTY_POMO_NOTIFICATIONS=yes: ")
+        );
+    }
+}
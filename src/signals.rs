@@ -0,0 +1,250 @@
+//! Unix signals. SIGINT and SIGTERM end a run the same way `q` does, so the phase
+//! in progress is logged and the terminal restored even when nobody is at the
+//! keyboard. SIGUSR1 toggles pause and SIGUSR2 skips, for window-manager keybindings:
+//!
+//! ```sh
+//! kill -USR1 "$(cat ~/.local/state/rusty_pomo.pid)"
+//! ```
+//!
+//! Beside the PID file, the timer keeps its phase and time left up to date for
+//! `rusty_pomo status`.
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+/// A flag raised once a stop signal arrives.
+pub fn stop_flag() -> Arc<AtomicBool> {
+    let flag = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+        let _ = signal_hook::flag::register(signal, Arc::clone(&flag));
+    }
+    flag
+}
+
+#[cfg(unix)]
+pub use unix::{PidFile, running, spawn};
+
+#[cfg(unix)]
+mod unix {
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+    use std::thread;
+
+    use signal_hook::consts::{SIGINT, SIGTERM, SIGUSR1, SIGUSR2};
+    use signal_hook::iterator::Signals;
+
+    use crate::control::Control;
+    use crate::json;
+    use crate::state::{AppState, PhaseKind};
+    use crate::{paths, phases, wallclock};
+
+    /// Hands each signal to `wake` as it arrives: SIGUSR1 and SIGUSR2 as pause
+    /// and skip commands, and SIGINT and SIGTERM as none, after the stop flag.
+    /// Stops once `wake` returns false.
+    pub fn spawn(mut wake: impl FnMut(Option<Control>) -> bool + Send + 'static) -> io::Result<()> {
+        let mut signals = Signals::new([SIGUSR1, SIGUSR2, SIGINT, SIGTERM])?;
+        thread::spawn(move || {
+            for signal in signals.forever() {
+                let control = match signal {
+                    SIGUSR1 => Some(Control::TogglePause),
+                    SIGUSR2 => Some(Control::Skip),
+                    _ => None,
+                };
+                if !wake(control) {
+                    break;
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// The PID file for this run, with the status file beside it; both are
+    /// removed again on drop unless another timer has taken them over since.
+    pub struct PidFile {
+        path: Option<PathBuf>,
+        /// The status last written, so it's only written again when it changes.
+        published: Option<Status>,
+    }
+
+    impl PidFile {
+        pub fn create() -> Self {
+            let path = paths::pid_file();
+            let written = path.filter(|path| {
+                path.parent()
+                    .is_some_and(|dir| fs::create_dir_all(dir).is_ok())
+                    && fs::write(path, format!("{}\n", std::process::id())).is_ok()
+            });
+            PidFile {
+                path: written,
+                published: None,
+            }
+        }
+
+        /// Writes the status file if the phase, the pause or the time left
+        /// changed since it was last written.
+        pub fn publish(&mut self, app: &AppState) {
+            let status = Status::of(app);
+            let (Some(_), Some(path)) = (&self.path, paths::status_file()) else {
+                return;
+            };
+            if self
+                .published
+                .as_ref()
+                .is_some_and(|last| !status.changed_from(last))
+            {
+                return;
+            }
+            if let Err(e) = fs::write(&path, format!("{}\n", status.to_json())) {
+                tracing::warn!(error = %e, "cannot write the status file");
+            }
+            self.published = Some(status);
+        }
+
+        fn ours(&self) -> bool {
+            self.path.as_ref().is_some_and(|path| {
+                fs::read_to_string(path)
+                    .is_ok_and(|pid| pid.trim() == std::process::id().to_string())
+            })
+        }
+    }
+
+    impl Drop for PidFile {
+        fn drop(&mut self) {
+            if !self.ours() {
+                return;
+            }
+            for path in [self.path.clone(), paths::status_file()]
+                .into_iter()
+                .flatten()
+            {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    /// What a running timer is doing, as `rusty_pomo status` reads it.
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct Status {
+        pub kind: PhaseKind,
+        pub paused: bool,
+        /// Seconds left when it was written.
+        pub left_secs: u64,
+        /// While running, the unix time the phase ends at.
+        pub ends_at: Option<u64>,
+        pub task: Option<String>,
+    }
+
+    impl Status {
+        fn of(app: &AppState) -> Self {
+            // Real seconds from the real now, so `--simulate` and `--demo`
+            // don't move the end on every tick
+            let left_secs = app.clock.real(app.time_remaining(app.now())).as_secs();
+            Status {
+                kind: app.current_phase.kind,
+                paused: app.paused,
+                left_secs,
+                ends_at: (!app.paused).then(|| wallclock::now_unix() + left_secs),
+                task: app.args.task.clone(),
+            }
+        }
+
+        /// Seconds left at unix time `now`.
+        pub fn left_at(&self, now: u64) -> u64 {
+            self.ends_at
+                .map_or(self.left_secs, |end| end.saturating_sub(now))
+        }
+
+        /// Whether this says something `last` doesn't; a running phase's end
+        /// moving by the second lost to rounding doesn't count.
+        fn changed_from(&self, last: &Status) -> bool {
+            let end_moved = match (self.ends_at, last.ends_at) {
+                (Some(end), Some(last)) => end.abs_diff(last) > 1,
+                _ => self.left_secs != last.left_secs,
+            };
+            self.kind != last.kind
+                || self.paused != last.paused
+                || self.task != last.task
+                || end_moved
+        }
+
+        fn to_json(&self) -> json::Value {
+            json::Value::Object(vec![
+                ("phase".to_string(), self.kind.key().into()),
+                ("paused".to_string(), self.paused.into()),
+                ("left_secs".to_string(), self.left_secs.into()),
+                ("ends_at".to_string(), self.ends_at.into()),
+                ("task".to_string(), self.task.as_deref().into()),
+            ])
+        }
+
+        fn from_json(value: &json::Value) -> Option<Self> {
+            Some(Status {
+                kind: phases::kind(value.get("phase")?.as_str()?),
+                paused: value.get("paused")?.as_bool()?,
+                left_secs: value.get("left_secs")?.as_u64()?,
+                ends_at: value.get("ends_at").and_then(json::Value::as_u64),
+                task: value
+                    .get("task")
+                    .and_then(json::Value::as_str)
+                    .map(str::to_string),
+            })
+        }
+    }
+
+    /// The PID of the timer in the PID file and what it's doing; an error
+    /// says why there's none.
+    pub fn running() -> Result<(i32, Status), String> {
+        let no_timer = || "no timer is running".to_string();
+        let pid: i32 = paths::pid_file()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|pid| pid.trim().parse().ok())
+            .ok_or_else(no_timer)?;
+        // Signal 0 only checks that the process is there
+        let alive = unsafe { libc::kill(pid, 0) } == 0
+            || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM);
+        if !alive {
+            return Err(no_timer());
+        }
+        paths::status_file()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|line| json::parse(line.trim()).ok())
+            .and_then(|value| Status::from_json(&value))
+            .map(|status| (pid, status))
+            .ok_or_else(|| format!("timer {pid} hasn't said what it's doing yet"))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn status_round_trips_and_counts_down() {
+            let status = Status {
+                kind: PhaseKind::Focus,
+                paused: false,
+                left_secs: 600,
+                ends_at: Some(1_700_000_600),
+                task: Some("Docs".to_string()),
+            };
+            assert_eq!(Status::from_json(&status.to_json()).as_ref(), Some(&status));
+            assert_eq!(status.left_at(1_700_000_100), 500);
+            let rounded = Status {
+                left_secs: 599,
+                ends_at: Some(1_700_000_601),
+                task: Some("Docs".to_string()),
+                ..status
+            };
+            assert!(!rounded.changed_from(&status));
+            let paused = Status {
+                paused: true,
+                ends_at: None,
+                task: None,
+                ..rounded
+            };
+            assert_eq!(paused.left_at(1_800_000_000), 599);
+            assert!(paused.changed_from(&status));
+        }
+    }
+}
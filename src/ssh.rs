@@ -0,0 +1,63 @@
+//! Remote mode, for running the timer over SSH: desktop notifications and
+//! sounds would reach the remote machine rather than you, and 24-bit color
+//! often doesn't survive the trip. Instead notifications become terminal
+//! escapes (OSC 9) and bells, the themes fall back to the 256-color palette
+//! and nothing is played.
+
+use std::env;
+
+use crate::args::Args;
+use crate::doctor::Probe;
+
+/// Whether this looks like an SSH session, from what `sshd` sets.
+pub fn detected() -> bool {
+    detected_with(|name| env::var(name).ok())
+}
+
+fn detected_with(var: impl Fn(&str) -> Option<String>) -> bool {
+    ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+        .into_iter()
+        .any(|name| var(name).is_some_and(|value| !value.is_empty()))
+}
+
+/// `--remote-mode`, or whether this is an SSH session without it.
+pub fn active(args: &Args) -> bool {
+    args.remote_mode.unwrap_or_else(detected)
+}
+
+/// Whether remote mode is on and why, for `doctor`.
+pub fn probe(args: &Args) -> Probe {
+    let what = "notifications as terminal escapes and bells, 256 colors, no sound";
+    match args.remote_mode {
+        Some(true) => Probe::Ok(format!("remote mode forced: {what}")),
+        Some(false) if detected() => {
+            Probe::Warn("over SSH, but remote mode is off (--remote-mode=false)".to_string())
+        }
+        Some(false) => Probe::Off("remote mode is off (--remote-mode=false)".to_string()),
+        None if detected() => Probe::Ok(format!("over SSH, so remote mode: {what}")),
+        None => Probe::Off("a local session".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_ssh_from_the_environment() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+        assert!(detected_with(env(&[(
+            "SSH_CONNECTION",
+            "10.0.0.2 52311 10.0.0.1 22"
+        )])));
+        assert!(detected_with(env(&[("SSH_TTY", "/dev/pts/3")])));
+        assert!(!detected_with(env(&[("SSH_CLIENT", "")])));
+        assert!(!detected_with(env(&[("TERM", "xterm-256color")])));
+    }
+}
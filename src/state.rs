@@ -1,14 +1,70 @@
 use std::time::{Duration, Instant};
 
+use crate::achievements::Achievements;
+use crate::activity::Activity;
 use crate::args::Args;
-use crate::notifications::maybe_notify;
+use crate::audio::{self, Ambient};
+use crate::bell::Bell;
+use crate::calendar::Meeting;
+use crate::clock::{Clock, ScaledClock, SystemClock};
+use crate::events::{Bus, PhaseEvent};
+use crate::graphics;
+use crate::history::{History, SessionRecord};
+use crate::i18n;
+use crate::integrations::media::Music;
+use crate::mascot::{self, Mascot, Pose};
+use crate::notifications::{self, Notifications};
+use crate::phases::{self, CustomPhase};
+use crate::plugins::Plugins;
+use crate::quotes::Quotes;
+use crate::score;
+use crate::ssh;
+use crate::stats;
+use crate::suggestions::{self, Suggestions};
 use crate::theme::Theme;
+use crate::wallclock;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum PhaseKind {
+    /// The `--warm-up` before the first focus session of the day.
+    WarmUp,
     Focus,
     ShortBreak,
     LongBreak,
+    /// The `--cycle-review` after each long break, which asks how the cycle went.
+    CycleReview,
+    /// The daily `[lunch]` pause, which suspends the cycle.
+    Lunch,
+    /// A phase from `[phases.NAME]`, by name.
+    Custom(&'static str),
+}
+
+impl PhaseKind {
+    /// Stable identifier used in the history file.
+    pub fn key(self) -> &'static str {
+        match self {
+            PhaseKind::WarmUp => "warm_up",
+            PhaseKind::Focus => "focus",
+            PhaseKind::ShortBreak => "short_break",
+            PhaseKind::LongBreak => "long_break",
+            PhaseKind::CycleReview => "cycle_review",
+            PhaseKind::Lunch => "lunch",
+            PhaseKind::Custom(name) => name,
+        }
+    }
+
+    /// The built-in phase for `key`; see `phases::kind` for custom ones.
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "warm_up" => Some(PhaseKind::WarmUp),
+            "focus" => Some(PhaseKind::Focus),
+            "short_break" => Some(PhaseKind::ShortBreak),
+            "long_break" => Some(PhaseKind::LongBreak),
+            "cycle_review" => Some(PhaseKind::CycleReview),
+            "lunch" => Some(PhaseKind::Lunch),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -17,35 +73,237 @@ pub struct Phase {
     pub duration: Duration,
 }
 
+/// With `--low-power`, the clock moves in these steps until the final minute.
+const LOW_POWER_STEP: Duration = Duration::from_secs(15);
+const LOW_POWER_COUNTDOWN: Duration = Duration::from_secs(60);
+/// Time between frames of the breathing exercise.
+const BREATHING_FRAME: Duration = Duration::from_millis(100);
+
+/// `--fit-to-meetings` leaves sessions alone that would get shorter than this;
+/// the warning before the meeting asks instead.
+const SHORTEST_FIT: Duration = Duration::from_secs(5 * 60);
+
+/// How long `--offer-extension` holds the break back, waiting for an answer.
+const EXTENSION_OFFER: Duration = Duration::from_secs(10);
+
+const UNDO_WINDOW: Duration = Duration::from_secs(10);
+const SKIP_CONFIRM_WINDOW: Duration = Duration::from_secs(3);
+
+/// What ending a phase leads to; see `AppState::transition`.
+struct Transition {
+    next: PhaseKind,
+    /// A focus session that brings the long break closer.
+    counted: bool,
+    /// A long break or its review that finishes a cycle.
+    ends_cycle: bool,
+}
+
+/// Start and end of each pause or snooze, in Unix seconds.
+pub type Intervals = Vec<(u64, u64)>;
+
+/// State captured before a skip or reset so it can be undone.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub phase: Phase,
+    pub session_index: u64,
+    pub completed_focus: u64,
+    pub completed_cycles: u64,
+    pub elapsed: Duration,
+    pub paused: bool,
+    pub phase_started_wall: u64,
+    pub pauses: Intervals,
+    pub snoozes: Intervals,
+    pub history_len: usize,
+    pub resume: Option<PhaseKind>,
+    pub taken_at: Instant,
+}
+
 #[derive(Debug)]
 pub struct AppState {
     pub args: Args,
     pub theme: Theme,
     pub session_index: u64,
+    pub completed_focus: u64,
+    /// Long breaks ended, each closing a cycle.
+    pub completed_cycles: u64,
+    /// Set once `--cycles` are done; the timer stops on the last long break, or
+    /// its review.
+    pub finished: bool,
     pub current_phase: Phase,
     pub phase_started_at: Instant,
+    pub phase_started_wall: u64,
+    pub pauses: Intervals,
     pub paused: bool,
     pub paused_at: Option<Instant>,
+    /// While snoozed, when the phase picks up again by itself.
+    pub snoozed_until: Option<Instant>,
+    pub snoozes: Intervals,
+    pub history: History,
+    pub show_timeline: bool,
+    /// While the garden grown by the history is open, over everything else.
+    pub show_garden: bool,
+    /// While the achievements are listed, over everything else.
+    pub show_achievements: bool,
+    /// What the history has earned; none without a history.
+    pub achievements: Option<Achievements>,
+    pub undo: Option<Snapshot>,
+    /// Sessions saved since the last skip, held back from the bus until it can
+    /// no longer be undone, so uploads and plugins never see an undone skip.
+    pub unsent: Vec<SessionRecord>,
+    pub skip_requested_at: Option<Instant>,
+    pub ambient: Option<Ambient>,
+    /// Time in distracting apps during focus, with `[distractions]`, and
+    /// typing during breaks, with `--track-rest`.
+    pub activity: Option<Activity>,
+    /// Why the last edit to the config file was not applied.
+    pub config_error: Option<String>,
+    /// While the theme picker is open, the theme to go back to on cancel.
+    pub theme_picker: Option<Theme>,
+    /// While asking how a focus session went, where it is in the history.
+    pub rating_prompt: Option<usize>,
+    /// While the cycle review asks how the cycle went.
+    pub cycle_prompt: bool,
+    /// The answer, saved with the review when it ends.
+    pub cycle_rating: Option<u8>,
+    /// While offering more time for the focus session that ran out, when the
+    /// offer lapses and the break starts.
+    pub extension_offer: Option<Instant>,
+    /// Time added to the running phase by taking up the offer.
+    pub extended: Duration,
+    /// Lines for the break screen and notifications.
+    pub quotes: Quotes,
+    pub suggestions: Suggestions,
+    /// What to do during the current break, or the last one during focus.
+    pub suggestion: Option<String>,
+    /// While the 20-20-20 eye rest runs, when it ends.
+    pub eye_rest_until: Option<Instant>,
+    /// Meetings from `--calendar`, earliest first.
+    pub meetings: Vec<Meeting>,
+    /// When the meeting last warned about starts, so each gets one warning.
+    pub meeting_warned: Option<u64>,
+    /// A line from a plugin, shown until the next phase.
+    pub message: Option<String>,
+    /// During lunch, the phase it suspended, with the time that was left.
+    pub after_lunch: Option<Phase>,
+    /// The local day of the last lunch, so skipping it doesn't start it again.
+    pub lunch_day: Option<i64>,
+    /// During the `[phases]` that follow a phase, the one the cycle goes on with.
+    pub resume: Option<PhaseKind>,
+    /// Everything time-dependent reads the time from here.
+    pub clock: Box<dyn Clock>,
+    /// Phase changes for notifications, lights, music and ambient sound.
+    pub bus: Bus,
+    /// A copy of the bell on the bus, for the screen to flash with it.
+    pub bell: Bell,
+    /// Over SSH or with `--remote-mode`: the screen uses 256 colors.
+    pub remote: bool,
+    /// How the terminal shows pictures, if it does and `--graphics` allows.
+    pub graphics: Option<graphics::Protocol>,
 }
 
 impl AppState {
     pub fn new(args: Args) -> Self {
-        let theme = args.theme;
-        let current_phase = Phase {
-            kind: PhaseKind::Focus,
-            duration: Duration::from_secs(args.focus * 60),
+        let clock: Box<dyn Clock> = match (args.simulate, args.demo) {
+            (Some(simulation), _) => Box::new(ScaledClock::new(simulation.speed(&args))),
+            (None, Some(speed)) => Box::new(ScaledClock::new(f64::from(speed))),
+            (None, None) => Box::new(SystemClock),
         };
+        Self::with_clock(args, clock)
+    }
+
+    /// Times phases with `clock` rather than the system's (or `--simulate`'s
+    /// or `--demo`'s).
+    pub fn with_clock(args: Args, clock: Box<dyn Clock>) -> Self {
+        let theme = args.theme;
+        let history = History::from_args(&args);
+        let current_phase = first_phase(&args, &history, clock.unix());
+        // What the history earned before there were achievements unlocks quietly
+        let mut achievements = Achievements::from_args(&args);
+        if let Some(achievements) = &mut achievements {
+            achievements.check(&history.records, clock.unix());
+        }
+        let ambient = Ambient::spawn(audio::Profile::from_args(&args), args.ambient_volume);
+        let mut bus = Bus::default();
+        bus.subscribe(Box::new(
+            Notifications::new(notifications::from_args(&args), args.snooze_minutes)
+                .quiet_outside(args.work_hours.as_ref())
+                .phases(&args.phases)
+                .routes(&args),
+        ));
+        if let Some(music) = Music::spawn(args.music) {
+            bus.subscribe(Box::new(music));
+        }
+        if let Some(plugins) = Plugins::discover(&args) {
+            bus.subscribe(Box::new(plugins));
+        }
+        if let Some(phases) = ambient.as_ref().and_then(Ambient::follower) {
+            bus.subscribe(Box::new(phases));
+        }
+        let activity = Activity::spawn(&args);
+        if let Some(activity) = &activity {
+            bus.subscribe(Box::new(activity.follower()));
+        }
+        let bell = Bell::from_args(&args);
+        let remote = ssh::active(&args);
+        let pictures = args.graphics.then(graphics::detect).flatten();
+        bus.subscribe(Box::new(bell.clone()));
+        bus.send(PhaseEvent::Entered(current_phase.kind));
+        let quotes = Quotes::from_args(&args);
+        let suggestions = Suggestions::from_args(&args);
         Self {
             args,
             theme,
             session_index: 0,
+            completed_focus: 0,
+            completed_cycles: 0,
+            finished: false,
             current_phase,
-            phase_started_at: Instant::now(),
+            phase_started_at: clock.now(),
+            phase_started_wall: clock.unix(),
+            pauses: Vec::new(),
             paused: false,
             paused_at: None,
+            snoozed_until: None,
+            snoozes: Vec::new(),
+            history,
+            show_timeline: false,
+            show_garden: false,
+            show_achievements: false,
+            achievements,
+            undo: None,
+            unsent: Vec::new(),
+            skip_requested_at: None,
+            ambient,
+            activity,
+            config_error: None,
+            theme_picker: None,
+            rating_prompt: None,
+            cycle_prompt: false,
+            cycle_rating: None,
+            extension_offer: None,
+            extended: Duration::ZERO,
+            quotes,
+            suggestions,
+            suggestion: None,
+            eye_rest_until: None,
+            meetings: Vec::new(),
+            meeting_warned: None,
+            message: None,
+            after_lunch: None,
+            lunch_day: None,
+            resume: None,
+            clock,
+            bus,
+            bell,
+            remote,
+            graphics: pictures,
         }
     }
 
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
     pub fn elapsed_in_phase(&self, now: Instant) -> Duration {
         if self.paused {
             if let Some(paused_at) = self.paused_at {
@@ -62,6 +320,40 @@ impl AppState {
             .saturating_sub(self.elapsed_in_phase(now))
     }
 
+    /// How long until the remaining time crosses a whole second, which is when
+    /// the clock shows something new; a second while paused. `--low-power`
+    /// stretches that to 15 seconds except in the final minute. While snoozed,
+    /// the snooze countdown sets the pace instead. The eye rest countdown and
+    /// the breathing exercise (every 100 ms) can ask for frames sooner.
+    pub fn until_clock_changes(&self, now: Instant) -> Duration {
+        if let Some(left) = self.snoozed_for(now) {
+            return until_whole_second(left);
+        }
+        let remaining = self.time_remaining(now);
+        let step = if self.args.low_power && (self.paused || remaining > LOW_POWER_COUNTDOWN) {
+            LOW_POWER_STEP
+        } else {
+            Duration::from_secs(1)
+        };
+        let into_step = remaining.as_nanos() % step.as_nanos();
+        let next = if self.paused || into_step == 0 {
+            step
+        } else {
+            Duration::from_nanos(into_step as u64)
+        };
+        let next = match self.eye_rest_left(now) {
+            Some(left) => next.min(until_whole_second(left)),
+            None => next,
+        };
+        if self.breathing() {
+            next.min(BREATHING_FRAME)
+        } else if let Some(Pose::Cheering(_)) = self.mascot_pose(now) {
+            next.min(mascot::CHEER_FRAME)
+        } else {
+            next
+        }
+    }
+
     pub fn progress(&self, now: Instant) -> f64 {
         let elapsed = self.elapsed_in_phase(now).as_secs_f64();
         let total = self.current_phase.duration.as_secs_f64().max(1.0);
@@ -71,84 +363,1150 @@ impl AppState {
     pub fn toggle_pause(&mut self) {
         if self.paused {
             if let Some(paused_at) = self.paused_at.take() {
-                let paused_duration = Instant::now().saturating_duration_since(paused_at);
+                let paused_duration = self.now().saturating_duration_since(paused_at);
                 self.phase_started_at += paused_duration;
+                let now = self.clock.unix();
+                let interval = (now.saturating_sub(paused_duration.as_secs()), now);
+                if self.snoozed_until.take().is_some() {
+                    self.snoozes.push(interval);
+                } else {
+                    self.pauses.push(interval);
+                }
             }
             self.paused = false;
         } else {
             self.paused = true;
-            self.paused_at = Some(Instant::now());
+            self.paused_at = Some(self.now());
+        }
+    }
+
+    /// Holds the phase for `--snooze-minutes`, or that much longer when already
+    /// snoozed. It picks up again by itself, or on resume.
+    pub fn snooze(&mut self) {
+        if self.finished {
+            return;
         }
+        let now = self.now();
+        if !self.paused {
+            self.toggle_pause();
+        }
+        let by = Duration::from_secs(self.args.snooze_minutes * 60);
+        self.snoozed_until = Some(self.snoozed_until.unwrap_or(now) + by);
+    }
+
+    /// How much of the snooze is left, if snoozed.
+    pub fn snoozed_for(&self, now: Instant) -> Option<Duration> {
+        self.snoozed_until
+            .map(|until| until.saturating_duration_since(now))
+    }
+
+    /// Resumes once the snooze is over; true if it did.
+    pub fn wake_from_snooze(&mut self) -> bool {
+        let due = self
+            .snoozed_for(self.now())
+            .is_some_and(|left| left.is_zero());
+        if due {
+            self.toggle_pause();
+        }
+        due
     }
 
     pub fn skip(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.take_snapshot();
         self.advance_phase();
     }
 
+    /// Skips, except during focus with `--confirm-skip` where the first press
+    /// only asks for confirmation.
+    pub fn request_skip(&mut self) {
+        let needs_confirm = self.args.confirm_skip && self.current_phase.kind == PhaseKind::Focus;
+        if needs_confirm && !self.skip_pending(self.now()) {
+            self.skip_requested_at = Some(self.now());
+            return;
+        }
+        self.skip_requested_at = None;
+        self.skip();
+    }
+
+    pub fn skip_pending(&self, now: Instant) -> bool {
+        self.skip_requested_at
+            .is_some_and(|at| now.saturating_duration_since(at) <= SKIP_CONFIRM_WINDOW)
+    }
+
+    pub fn cancel_skip(&mut self) {
+        self.skip_requested_at = None;
+    }
+
+    pub fn reset(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.take_snapshot();
+        self.reset_phase();
+    }
+
+    pub fn can_undo(&self, now: Instant) -> bool {
+        self.undo
+            .as_ref()
+            .is_some_and(|s| now.saturating_duration_since(s.taken_at) <= UNDO_WINDOW)
+    }
+
+    /// Restores the phase and elapsed time from before the last skip or reset.
+    pub fn undo(&mut self) -> bool {
+        let now = self.now();
+        if !self.can_undo(now) {
+            return false;
+        }
+        let Some(snapshot) = self.undo.take() else {
+            return false;
+        };
+        self.current_phase = snapshot.phase;
+        self.session_index = snapshot.session_index;
+        self.completed_focus = snapshot.completed_focus;
+        self.completed_cycles = snapshot.completed_cycles;
+        self.finished = false;
+        self.phase_started_at = now - snapshot.elapsed;
+        self.phase_started_wall = snapshot.phase_started_wall;
+        self.pauses = snapshot.pauses;
+        self.snoozes = snapshot.snoozes;
+        self.resume = snapshot.resume;
+        self.snoozed_until = None;
+        self.rating_prompt = None;
+        self.cycle_prompt = false;
+        self.eye_rest_until = None;
+        // Time between the skip/reset and the undo doesn't count as work
+        let wall_now = self.clock.unix();
+        let gap = now.saturating_duration_since(snapshot.taken_at).as_secs();
+        if gap > 0 {
+            self.pauses.push((wall_now - gap, wall_now));
+        }
+        self.paused = snapshot.paused;
+        self.paused_at = snapshot.paused.then_some(now);
+        let undone = self
+            .history
+            .records
+            .len()
+            .saturating_sub(snapshot.history_len);
+        self.unsent
+            .truncate(self.unsent.len().saturating_sub(undone));
+        let _ = self.history.truncate(snapshot.history_len);
+        self.bus.send(PhaseEvent::Entered(self.current_phase.kind));
+        true
+    }
+
+    fn take_snapshot(&mut self) {
+        let now = self.now();
+        let (pauses, snoozes) = self.intervals_until_now();
+        self.undo = Some(Snapshot {
+            phase: self.current_phase.clone(),
+            session_index: self.session_index,
+            completed_focus: self.completed_focus,
+            completed_cycles: self.completed_cycles,
+            elapsed: self.elapsed_in_phase(now),
+            paused: self.paused,
+            phase_started_wall: self.phase_started_wall,
+            pauses,
+            snoozes,
+            history_len: self.history.records.len(),
+            resume: self.resume,
+            taken_at: now,
+        });
+    }
+
     pub fn reset_phase(&mut self) {
-        self.phase_started_at = Instant::now();
+        self.phase_started_at = self.now();
+        self.phase_started_wall = self.clock.unix();
+        self.pauses.clear();
+        self.snoozes.clear();
         self.paused = false;
         self.paused_at = None;
+        self.snoozed_until = None;
+        self.eye_rest_until = None;
+        self.message = None;
+        self.extension_offer = None;
+        self.extended = Duration::ZERO;
+    }
+
+    /// Lengthens the running phase by `secs`, or shortens it for negative ones,
+    /// though not below the time it has already run.
+    pub fn adjust_time(&mut self, secs: i64) {
+        if self.finished {
+            return;
+        }
+        let by = Duration::from_secs(secs.unsigned_abs());
+        let duration = self.current_phase.duration;
+        let adjusted = if secs < 0 {
+            duration.saturating_sub(by)
+        } else {
+            duration + by
+        };
+        self.current_phase.duration = adjusted.max(self.elapsed_in_phase(self.now()));
+    }
+
+    /// The phase in progress as a history record ending now.
+    pub fn current_record(&self, completed: bool) -> SessionRecord {
+        let (pauses, snoozes) = self.intervals_until_now();
+        let end = self.clock.unix();
+        let stopped = [&pauses[..], &snoozes].concat();
+        let (distractions, rested) = match (&self.activity, self.current_phase.kind) {
+            (Some(activity), PhaseKind::Focus) => {
+                (activity.tally(self.phase_started_wall, end, &stopped), None)
+            }
+            (Some(activity), PhaseKind::ShortBreak | PhaseKind::LongBreak) => (
+                Vec::new(),
+                activity.rested(self.phase_started_wall, end, &stopped),
+            ),
+            _ => (Vec::new(), None),
+        };
+        SessionRecord {
+            kind: self.current_phase.kind,
+            start: self.phase_started_wall,
+            end,
+            planned_secs: self.current_phase.duration.as_secs(),
+            completed,
+            pauses,
+            snoozes,
+            project: self.args.project.clone(),
+            task: self.args.task.clone(),
+            estimate: self.args.estimate,
+            rating: self
+                .cycle_rating
+                .filter(|_| self.current_phase.kind == PhaseKind::CycleReview),
+            extended_secs: self.extended.as_secs(),
+            distractions,
+            rested,
+            utc_offset: Some(wallclock::local_offset(self.phase_started_wall)),
+            rolled_up: false,
+        }
+    }
+
+    /// Finished pauses and snoozes, plus the one in progress, if any.
+    fn intervals_until_now(&self) -> (Intervals, Intervals) {
+        let mut pauses = self.pauses.clone();
+        let mut snoozes = self.snoozes.clone();
+        if let Some(paused_at) = self.paused_at {
+            let paused_for = self.now().saturating_duration_since(paused_at).as_secs();
+            let now = self.clock.unix();
+            let interval = (now.saturating_sub(paused_for), now);
+            if self.snoozed_until.is_some() {
+                snoozes.push(interval);
+            } else {
+                pauses.push(interval);
+            }
+        }
+        (pauses, snoozes)
+    }
+
+    /// Logs the phase in progress as abandoned; called on quit.
+    pub fn finish(&mut self) {
+        let now = self.now();
+        if !self.finished && !self.elapsed_in_phase(now).is_zero() {
+            self.record(self.credited(now));
+        }
+        for record in self.unsent.drain(..) {
+            self.bus.send(PhaseEvent::Recorded(record));
+        }
+    }
+
+    /// Sends `Recorded` for the held sessions an undo can no longer take back.
+    pub fn send_recorded(&mut self) {
+        let undoable = match &self.undo {
+            Some(snapshot) if self.can_undo(self.now()) => self
+                .history
+                .records
+                .len()
+                .saturating_sub(snapshot.history_len),
+            _ => 0,
+        };
+        let ready = self.unsent.len().saturating_sub(undoable);
+        for record in self.unsent.drain(..ready) {
+            self.bus.send(PhaseEvent::Recorded(record));
+        }
+    }
+
+    /// Whether the phase in progress counts as done: it ran to the end, or it's
+    /// a focus session past `--partial-credit` percent of its length.
+    fn credited(&self, now: Instant) -> bool {
+        if self.time_remaining(now).is_zero() {
+            return true;
+        }
+        let Some(percent) = self.args.partial_credit else {
+            return false;
+        };
+        let (elapsed, planned) = (self.elapsed_in_phase(now), self.current_phase.duration);
+        self.current_phase.kind == PhaseKind::Focus
+            && elapsed.as_millis() * 100 >= planned.as_millis() * u128::from(percent)
+    }
+
+    /// Appends the phase in progress to the history; false if that failed.
+    fn record(&mut self, completed: bool) -> bool {
+        let record = self.current_record(completed);
+        let result = self.history.append(record.clone());
+        if let Some(activity) = &self.activity {
+            activity.forget(record.end);
+        }
+        match &result {
+            Err(e) => tracing::warn!(error = %e, "cannot save to the history"),
+            Ok(()) if self.args.history => self.unsent.push(record),
+            Ok(()) => {}
+        }
+        if let (Some(achievements), Ok(())) = (&mut self.achievements, &result) {
+            for rule in achievements.check(&self.history.records, self.clock.unix()) {
+                self.bus.send(PhaseEvent::Unlocked(rule.key));
+            }
+        }
+        result.is_ok()
+    }
+
+    /// Saves the answer to the rating prompt and closes it.
+    pub fn rate(&mut self, rating: u8) {
+        let Some(index) = self.rating_prompt.take() else {
+            return;
+        };
+        match self.history.rate(index, rating) {
+            // A session still held back goes out with its rating
+            Ok(()) => match index.checked_sub(self.history.records.len() - self.unsent.len()) {
+                Some(held) => self.unsent[held].rating = Some(rating),
+                None => {
+                    let start = self.history.records[index].start;
+                    self.bus.send(PhaseEvent::Rated { start, rating });
+                }
+            },
+            Err(e) => tracing::warn!(error = %e, "cannot save the rating"),
+        }
+    }
+
+    /// Saves how the cycle went, for the review's history record, and closes
+    /// the question.
+    pub fn rate_cycle(&mut self, rating: u8) {
+        if self.cycle_prompt {
+            self.cycle_rating = Some(rating);
+            self.cycle_prompt = false;
+        }
+    }
+
+    /// Once a focus session runs out with `--offer-extension`, holds the break
+    /// back while the offer is open; true while it is.
+    pub fn hold_for_extension(&mut self) -> bool {
+        let offered = self.args.offer_extension.is_some()
+            && self.current_phase.kind == PhaseKind::Focus
+            && !self.args.background;
+        if !offered {
+            return false;
+        }
+        let now = self.now();
+        match self.extension_offer {
+            None => {
+                self.extension_offer = Some(now + EXTENSION_OFFER);
+                true
+            }
+            Some(lapses) => now < lapses,
+        }
+    }
+
+    /// Takes up the offer: the focus session goes on for `--offer-extension`
+    /// from now.
+    pub fn extend(&mut self) {
+        let (Some(_), Some(more)) = (self.extension_offer.take(), self.args.offer_extension) else {
+            return;
+        };
+        let duration = self.elapsed_in_phase(self.now()) + more;
+        self.extended += duration.saturating_sub(self.current_phase.duration);
+        self.current_phase.duration = duration;
+        tracing::info!(secs = more.as_secs(), "focus extended");
     }
 
     pub fn advance_phase(&mut self) {
-        let next_kind = match self.current_phase.kind {
+        let completed = self.credited(self.now());
+        let recorded = self.record(completed);
+        // Nobody is at the keyboard in the background to answer
+        let ask = self.args.rate_sessions && !self.args.background;
+        self.rating_prompt =
+            (ask && recorded && completed && self.current_phase.kind == PhaseKind::Focus)
+                .then(|| self.history.records.len() - 1);
+        let transition = self.transition(completed);
+        match self.current_phase.kind {
+            PhaseKind::Focus if completed => self.completed_focus += 1,
+            PhaseKind::Custom(_) => self.resume = None,
+            _ => {}
+        }
+        if transition.counted {
+            self.session_index += 1;
+        }
+        if transition.ends_cycle {
+            self.completed_cycles += 1;
+        }
+        let next_kind = transition.next;
+        // Custom phases come between a phase and the one it leads to
+        let next_kind = match phases::following(&self.args.phases, self.current_phase.kind) {
+            Some(custom) => {
+                self.resume.get_or_insert(next_kind);
+                custom.kind
+            }
+            None => next_kind,
+        };
+        if let Some(n) = self.args.cycles
+            && self.completed_cycles >= n
+        {
+            self.finished = true;
+            tracing::info!(cycles = self.completed_cycles, "all cycles done");
+            self.bus.send(PhaseEvent::Done {
+                cycles: self.completed_cycles,
+                focus_sessions: self.completed_focus,
+            });
+            return;
+        }
+        if self.args.once && next_kind != PhaseKind::Focus {
+            self.finished = true;
+            return;
+        }
+        self.current_phase = match self.after_lunch.take() {
+            Some(phase) => phase,
+            None => Phase {
+                kind: next_kind,
+                duration: self.length_of(next_kind),
+            },
+        };
+        self.reset_phase();
+        self.cycle_prompt = next_kind == PhaseKind::CycleReview && !self.args.background;
+        self.cycle_rating = None;
+        if next_kind != PhaseKind::Focus {
+            let seed = self.clock.unix() ^ self.session_index;
+            let last = self.suggestion.as_deref();
+            self.suggestion = self.suggestions.pick(seed, last).map(str::to_string);
+        }
+        if next_kind == PhaseKind::Focus {
+            self.plan_around_meetings();
+        }
+        tracing::info!(phase = next_kind.key(), completed, "phase started");
+        self.bus.send(PhaseEvent::Started {
+            kind: next_kind,
+            quote: self.break_quote().map(str::to_string),
+        });
+    }
+
+    /// Where the phase in progress leads once it ends, `completed` or not, before
+    /// any custom phase that comes in between. Decides for both `advance_phase`
+    /// and `peek_next_phase`.
+    fn transition(&self, completed: bool) -> Transition {
+        let mut counted = false;
+        let mut ends_cycle = false;
+        let next = match self.current_phase.kind {
+            PhaseKind::WarmUp | PhaseKind::ShortBreak => PhaseKind::Focus,
             PhaseKind::Focus => {
-                self.session_index += 1;
-                if self.session_index % self.args.long_every == 0 {
+                // Under `--partial-credit`, a void session doesn't bring the long break closer
+                counted = completed || self.args.partial_credit.is_none();
+                if counted && (self.session_index + 1).is_multiple_of(self.args.long_every) {
                     PhaseKind::LongBreak
                 } else {
                     PhaseKind::ShortBreak
                 }
             }
-            PhaseKind::ShortBreak | PhaseKind::LongBreak => PhaseKind::Focus,
+            PhaseKind::LongBreak if self.args.cycle_review.is_some() => PhaseKind::CycleReview,
+            PhaseKind::LongBreak | PhaseKind::CycleReview => {
+                ends_cycle = true;
+                PhaseKind::Focus
+            }
+            PhaseKind::Lunch => self
+                .after_lunch
+                .as_ref()
+                .map_or(PhaseKind::Focus, |phase| phase.kind),
+            PhaseKind::Custom(_) => self.resume.unwrap_or(PhaseKind::Focus),
         };
-        self.current_phase = match next_kind {
-            PhaseKind::Focus => Phase {
-                kind: PhaseKind::Focus,
-                duration: Duration::from_secs(self.args.focus * 60),
-            },
-            PhaseKind::ShortBreak => Phase {
-                kind: PhaseKind::ShortBreak,
-                duration: Duration::from_secs(self.args.short * 60),
-            },
-            PhaseKind::LongBreak => Phase {
-                kind: PhaseKind::LongBreak,
-                duration: Duration::from_secs(self.args.long * 60),
-            },
+        Transition {
+            next,
+            counted,
+            ends_cycle,
+        }
+    }
+
+    /// The phase after this one if it runs to the end, and the unix time it
+    /// would start; none when the run would stop there instead. Follows the
+    /// same rules as `advance_phase`, without changing anything.
+    pub fn peek_next_phase(&self, now: Instant) -> Option<Planned> {
+        if self.finished {
+            return None;
+        }
+        let start = self.clock.unix() + self.time_remaining(now).as_secs();
+        if let Some(phase) = &self.after_lunch {
+            return Some(Planned {
+                kind: phase.kind,
+                start,
+                duration: phase.duration,
+            });
+        }
+        // Run to the end, it's done whatever `--partial-credit` says
+        let transition = self.transition(true);
+        let cycles = self.completed_cycles + u64::from(transition.ends_cycle);
+        let next = phases::following(&self.args.phases, self.current_phase.kind)
+            .map_or(transition.next, |custom| custom.kind);
+        if self.args.cycles.is_some_and(|n| cycles >= n)
+            || (self.args.once && next != PhaseKind::Focus)
+        {
+            return None;
+        }
+        Some(Planned {
+            kind: next,
+            start,
+            duration: self.length_of(next),
+        })
+    }
+
+    /// Focus sessions left before the long break, this one included; none
+    /// without long breaks, or during the long break and its review.
+    pub fn until_long_break(&self) -> Option<u64> {
+        let every = self.args.long_every;
+        let between = !matches!(
+            self.current_phase.kind,
+            PhaseKind::LongBreak | PhaseKind::CycleReview
+        );
+        (every > 0 && between && !self.finished).then(|| every - self.session_index % every)
+    }
+
+    /// Whether `--once` or `--cycles` got to the end; always true without them.
+    pub fn goal_reached(&self) -> bool {
+        match (self.args.once, self.args.cycles) {
+            (true, _) => self.finished && self.completed_focus > 0,
+            (false, Some(_)) => self.finished,
+            (false, None) => true,
+        }
+    }
+
+    pub fn length_of(&self, kind: PhaseKind) -> Duration {
+        length_of(&self.args, kind)
+    }
+
+    /// Takes over the settings that can change while running, from a reloaded config.
+    /// The running phase gets its new length too, unless that much time has already
+    /// passed: then the new length waits for the next phase, so saving never ends it.
+    pub fn reload(&mut self, settings: &Args) {
+        let args = &mut self.args;
+        args.focus = settings.focus;
+        args.short = settings.short;
+        args.long = settings.long;
+        args.long_every = settings.long_every;
+        args.warm_up = settings.warm_up;
+        args.cycle_review = settings.cycle_review;
+        args.partial_credit = settings.partial_credit;
+        args.daily_goal = settings.daily_goal;
+        args.offer_extension = settings.offer_extension;
+        // Only a changed theme: one picked with `t` stays when `--theme` pins the file's.
+        if settings.theme != args.theme {
+            self.theme = settings.theme;
+        }
+        args.theme = settings.theme;
+        args.mascot = settings.mascot;
+        args.layout = settings.layout;
+        args.counter_icon = settings.counter_icon.clone();
+        args.notifications = settings.notifications;
+        args.notification_sound = settings.notification_sound.clone();
+        args.breathing = settings.breathing;
+        args.quotes_file = settings.quotes_file.clone();
+        args.quotes = settings.quotes.clone();
+        args.suggestions = settings.suggestions.clone();
+        args.meeting_warning = settings.meeting_warning;
+        args.fit_to_meetings = settings.fit_to_meetings;
+        args.work_hours = settings.work_hours.clone();
+        args.lunch = settings.lunch;
+        args.phases = settings.phases.clone();
+        self.quotes = Quotes::from_args(args);
+        self.suggestions = Suggestions::from_args(args);
+        self.bus.reload(settings);
+        let length = self.length_of(self.current_phase.kind);
+        // Lunch ends at the same time of day whenever it started
+        if self.current_phase.kind != PhaseKind::Lunch && length > self.elapsed_in_phase(self.now())
+        {
+            self.current_phase.duration = length;
+        }
+    }
+
+    /// Whether the breathing exercise is running: a short break on screen that
+    /// isn't paused, and `--low-power` isn't saving redraws.
+    pub fn breathing(&self) -> bool {
+        self.args.breathing
+            && self.current_phase.kind == PhaseKind::ShortBreak
+            && !(self.paused || self.finished || self.args.low_power)
+            && !(self.args.background || self.args.accessible)
+    }
+
+    /// Today's focus score so far, from the history; none before the first
+    /// focus session of the day.
+    pub fn today_score(&self) -> Option<u8> {
+        let today = wallclock::local_day(self.clock.unix());
+        // The history is in order, so today's records are at the end
+        let records: Vec<SessionRecord> = self
+            .history
+            .records
+            .iter()
+            .rev()
+            .take_while(|r| r.day() == today)
+            .cloned()
+            .collect();
+        score::score(&records, &self.args.score_weights)
+    }
+
+    /// Focus sessions finished today, in this run and earlier ones.
+    pub fn focus_today(&self) -> u64 {
+        self.history
+            .today(self.clock.unix())
+            .filter(|r| r.kind == PhaseKind::Focus && r.completed)
+            .count() as u64
+    }
+
+    /// Focus sessions finished on `--task`, in this run and earlier ones.
+    pub fn task_sessions(&self) -> u64 {
+        let Some(task) = &self.args.task else {
+            return 0;
+        };
+        self.history
+            .records
+            .iter()
+            .filter(|r| r.kind == PhaseKind::Focus && r.completed && r.task.as_ref() == Some(task))
+            .count() as u64
+    }
+
+    /// Focus time today, without pauses: the history's sessions, finished or
+    /// not, and the one running.
+    pub fn focus_time_today(&self, now: Instant) -> Duration {
+        let recorded: u64 = self
+            .history
+            .today(self.clock.unix())
+            .filter(|r| r.kind == PhaseKind::Focus)
+            .map(stats::active_secs)
+            .sum();
+        let running = match self.current_phase.kind {
+            PhaseKind::Focus => self.elapsed_in_phase(now),
+            _ => Duration::ZERO,
+        };
+        Duration::from_secs(recorded) + running
+    }
+
+    /// When `--daily-goal` would be met at today's pace: the time from the
+    /// day's first focus session per finished one, breaks and all. Before the
+    /// first is finished, a focus session and a short break each. None
+    /// without a goal or once it's met.
+    pub fn goal_eta(&self, now: Instant) -> Option<u64> {
+        let goal = self.args.daily_goal?;
+        let done = self.focus_today();
+        if done >= goal {
+            return None;
+        }
+        let unix = self.clock.unix();
+        let first = self
+            .history
+            .today(unix)
+            .filter(|r| r.kind == PhaseKind::Focus)
+            .map(|r| r.start)
+            .min();
+        match first {
+            Some(first) if done > 0 => Some(first + (unix - first) * goal / done),
+            _ => {
+                let each = (self.args.focus + self.args.short).as_secs();
+                let begun = self.focus_time_today(now).as_secs();
+                Some((unix + goal * each).saturating_sub(begun))
+            }
+        }
+    }
+
+    /// `--counter-icon` and today's count, e.g. "🍅 x3"; none before the first
+    /// finished focus session or with an empty icon.
+    pub fn counter(&self) -> Option<String> {
+        let count = self.focus_today();
+        (count > 0 && !self.args.counter_icon.is_empty())
+            .then(|| format!("{} x{count}", self.args.counter_icon))
+    }
+
+    /// What the terminal's window title says: the phase and today's count.
+    pub fn window_title(&self) -> String {
+        let mut title = format!(
+            "Rusty Pomo · {}",
+            i18n::phase_title(self.current_phase.kind)
+        );
+        if let Some(counter) = self.counter() {
+            title.push_str(&format!(" · {counter}"));
+        }
+        title
+    }
+
+    /// What `--mascot` is doing: growing during focus, and cheering at the
+    /// start of a break after a finished focus session, unless paused. None
+    /// otherwise.
+    pub fn mascot_pose(&self, now: Instant) -> Option<Pose> {
+        if self.args.mascot == Mascot::Off
+            || self.finished
+            || self.args.background
+            || self.args.accessible
+        {
+            return None;
+        }
+        let elapsed = self.elapsed_in_phase(now);
+        match self.current_phase.kind {
+            PhaseKind::Focus => Some(Pose::Growing(mascot::stage(self.progress(now)))),
+            PhaseKind::ShortBreak | PhaseKind::LongBreak
+                if elapsed < mascot::CHEERING
+                    && !self.paused
+                    && self
+                        .history
+                        .records
+                        .last()
+                        .is_some_and(|r| r.kind == PhaseKind::Focus && r.completed) =>
+            {
+                Some(Pose::Cheering(mascot::cheer_frame(elapsed)))
+            }
+            _ => None,
+        }
+    }
+
+    /// The quote shown during the current break; none outside breaks.
+    pub fn break_quote(&self) -> Option<&str> {
+        match self.current_phase.kind {
+            PhaseKind::WarmUp
+            | PhaseKind::Focus
+            | PhaseKind::CycleReview
+            | PhaseKind::Lunch
+            | PhaseKind::Custom(_) => None,
+            PhaseKind::ShortBreak | PhaseKind::LongBreak => {
+                self.quotes.for_break(self.session_index)
+            }
+        }
+    }
+
+    /// What to do during the current break; none outside breaks.
+    pub fn break_suggestion(&self) -> Option<&str> {
+        match self.current_phase.kind {
+            PhaseKind::WarmUp
+            | PhaseKind::Focus
+            | PhaseKind::CycleReview
+            | PhaseKind::Lunch
+            | PhaseKind::Custom(_) => None,
+            PhaseKind::ShortBreak | PhaseKind::LongBreak => self.suggestion.as_deref(),
+        }
+    }
+
+    /// The `[phases]` entry for the current phase, if it's a custom one.
+    pub fn custom_phase(&self) -> Option<&CustomPhase> {
+        let kind = self.current_phase.kind;
+        self.args.phases.iter().find(|phase| phase.kind == kind)
+    }
+
+    /// Starts the 20-second eye rest; only breaks have one.
+    pub fn start_eye_rest(&mut self) {
+        if self.current_phase.kind != PhaseKind::Focus && !self.finished {
+            self.eye_rest_until = Some(self.now() + suggestions::EYE_REST);
+        }
+    }
+
+    /// Time left in the eye rest, if one is running.
+    pub fn eye_rest_left(&self, now: Instant) -> Option<Duration> {
+        self.eye_rest_until
+            .map(|until| until.saturating_duration_since(now))
+            .filter(|left| !left.is_zero())
+    }
+
+    /// Whether it's outside `[work_hours]`, when the timer suggests stopping.
+    pub fn off_hours(&self) -> bool {
+        self.args
+            .work_hours
+            .as_ref()
+            .is_some_and(|hours| !hours.contains(self.clock.unix()))
+    }
+
+    /// Once it's `[lunch]` time, logs the phase in progress so far and suspends
+    /// it until lunch is over; true if lunch started.
+    pub fn check_lunch(&mut self) -> bool {
+        let unix = self.clock.unix();
+        let today = wallclock::local_day(unix);
+        let Some(left) = self.args.lunch.and_then(|lunch| lunch.left(unix)) else {
+            return false;
+        };
+        if self.finished || self.lunch_day == Some(today) {
+            return false;
+        }
+        self.lunch_day = Some(today);
+        let now = self.now();
+        // Starting up at lunch has nothing to log yet
+        if self.elapsed_in_phase(now).as_secs() > 0 {
+            self.record(false);
+        }
+        self.after_lunch = Some(Phase {
+            kind: self.current_phase.kind,
+            duration: self.time_remaining(now),
+        });
+        self.current_phase = Phase {
+            kind: PhaseKind::Lunch,
+            duration: Duration::from_secs(left),
         };
         self.reset_phase();
-        maybe_notify(self);
+        self.undo = None;
+        self.skip_requested_at = None;
+        tracing::info!(secs = left, "lunch started");
+        self.bus.send(PhaseEvent::Started {
+            kind: PhaseKind::Lunch,
+            quote: None,
+        });
+        true
+    }
+
+    /// Unix time at `now`.
+    fn unix_at(&self, now: Instant) -> u64 {
+        self.clock.unix() + now.saturating_duration_since(self.now()).as_secs()
+    }
+
+    /// Takes over meetings read from the calendar.
+    pub fn set_meetings(&mut self, meetings: Vec<Meeting>) {
+        self.meetings = meetings;
+        self.plan_around_meetings();
+    }
+
+    /// The next meeting to start after `now`.
+    pub fn next_meeting(&self, now: Instant) -> Option<&Meeting> {
+        let unix = self.unix_at(now);
+        self.meetings.iter().find(|m| m.start > unix)
+    }
+
+    /// The next meeting, if it starts before the running focus session ends.
+    pub fn meeting_clash(&self, now: Instant) -> Option<&Meeting> {
+        if self.current_phase.kind != PhaseKind::Focus || self.finished {
+            return None;
+        }
+        let ends = self.unix_at(now) + self.time_remaining(now).as_secs();
+        self.next_meeting(now).filter(|m| m.start < ends)
+    }
+
+    /// Shortens the running focus session to end as the next meeting starts;
+    /// false if it already ends in time.
+    pub fn fit_to_meeting(&mut self) -> bool {
+        self.fit_before_meeting(Duration::ZERO)
+    }
+
+    fn fit_before_meeting(&mut self, shortest: Duration) -> bool {
+        let now = self.now();
+        let Some(start) = self.meeting_clash(now).map(|m| m.start) else {
+            return false;
+        };
+        let gap = Duration::from_secs(start - self.unix_at(now));
+        if gap < shortest {
+            return false;
+        }
+        self.current_phase.duration = self.elapsed_in_phase(now) + gap;
+        tracing::info!(
+            secs = self.current_phase.duration.as_secs(),
+            "focus fitted to a meeting"
+        );
+        true
+    }
+
+    /// With `--fit-to-meetings`, fits focus to the next meeting, when there's
+    /// enough time before it for a session worth having.
+    fn plan_around_meetings(&mut self) {
+        if self.args.fit_to_meetings {
+            self.fit_before_meeting(SHORTEST_FIT);
+        }
+    }
+
+    /// Warns once the next meeting is `--meeting-warning` minutes away; true if it did.
+    pub fn check_meetings(&mut self) -> bool {
+        let now = self.now();
+        let unix = self.unix_at(now);
+        let warn_from = unix + self.args.meeting_warning * 60;
+        let Some(meeting) = self
+            .next_meeting(now)
+            .filter(|m| m.start <= warn_from && Some(m.start) != self.meeting_warned)
+        else {
+            return false;
+        };
+        if self.finished {
+            return false;
+        }
+        let start = meeting.start;
+        let event = PhaseEvent::MeetingSoon {
+            summary: meeting.summary.clone(),
+            minutes: (start - unix).div_ceil(60),
+            clash: self.meeting_clash(now).is_some(),
+        };
+        self.meeting_warned = Some(start);
+        self.bus.send(event);
+        true
+    }
+
+    pub fn open_theme_picker(&mut self) {
+        self.theme_picker = Some(self.theme);
+    }
+
+    /// Closes the picker, keeping the previewed theme or going back to the old one.
+    pub fn close_theme_picker(&mut self, keep: bool) {
+        if let Some(original) = self.theme_picker.take()
+            && !keep
+        {
+            self.theme = original;
+        }
+    }
+}
+
+/// How long a phase of `kind` lasts with `args`.
+fn length_of(args: &Args, kind: PhaseKind) -> Duration {
+    match kind {
+        PhaseKind::WarmUp => args.warm_up.unwrap_or_default(),
+        PhaseKind::Focus => args.focus,
+        PhaseKind::ShortBreak => args.short,
+        PhaseKind::LongBreak => args.long,
+        PhaseKind::CycleReview => args.cycle_review.unwrap_or_default(),
+        PhaseKind::Lunch => args
+            .lunch
+            .map_or(Duration::ZERO, |l| Duration::from_secs(l.end - l.start)),
+        PhaseKind::Custom(_) => args
+            .phases
+            .iter()
+            .find(|phase| phase.kind == kind)
+            .map_or(Duration::ZERO, |phase| phase.duration),
+    }
+}
+
+/// A phase of a `plan`, with the unix time it would start.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Planned {
+    pub kind: PhaseKind,
+    pub start: u64,
+    pub duration: Duration,
+}
+
+/// Enough for any cycle; a plan stops here if `--cycles` asks for more.
+const PLAN_LIMIT: usize = 200;
+/// Without long breaks there's no cycle to end on, so a plan covers as many
+/// focus sessions as a default cycle has.
+const PLAN_WITHOUT_LONG_BREAKS: u64 = 4;
+
+/// The phases a run starting at `unix` would go through if each ran its full
+/// length: through `--cycles` cycles (one without it), the first focus
+/// session with `--once`, or four focus sessions with `--long-every 0`.
+/// `[lunch]` cuts into the phase it falls in, as it would in the timer.
+/// Follows the same rules as `advance_phase`.
+pub fn plan(args: &Args, history: &History, unix: u64) -> Vec<Planned> {
+    let mut planned = Vec::new();
+    let mut phase = first_phase(args, history, unix);
+    let mut at = unix;
+    let mut session_index: u64 = 0;
+    let mut cycles = 0;
+    let mut resume = None;
+    let mut lunch_day = None;
+    while planned.len() < PLAN_LIMIT {
+        if let Some(lunch) = args.lunch
+            && lunch_day != Some(wallclock::local_day(at))
+        {
+            let secs = wallclock::seconds_of_local_day(at);
+            let before = lunch.start.saturating_sub(secs);
+            if lunch.left(at).is_some() || (before > 0 && before < phase.duration.as_secs()) {
+                lunch_day = Some(wallclock::local_day(at));
+                if before > 0 {
+                    planned.push(Planned {
+                        kind: phase.kind,
+                        start: at,
+                        duration: Duration::from_secs(before),
+                    });
+                    phase.duration -= Duration::from_secs(before);
+                    at += before;
+                }
+                let left = lunch.left(at).unwrap_or_default();
+                planned.push(Planned {
+                    kind: PhaseKind::Lunch,
+                    start: at,
+                    duration: Duration::from_secs(left),
+                });
+                at += left;
+                continue;
+            }
+        }
+        planned.push(Planned {
+            kind: phase.kind,
+            start: at,
+            duration: phase.duration,
+        });
+        at += phase.duration.as_secs();
+        let next = match phase.kind {
+            PhaseKind::WarmUp | PhaseKind::ShortBreak | PhaseKind::Lunch => PhaseKind::Focus,
+            PhaseKind::Focus if args.once => break,
+            PhaseKind::Focus if args.long_every == 0 => {
+                session_index += 1;
+                if session_index == PLAN_WITHOUT_LONG_BREAKS {
+                    break;
+                }
+                PhaseKind::ShortBreak
+            }
+            PhaseKind::Focus => {
+                session_index += 1;
+                if session_index.is_multiple_of(args.long_every) {
+                    PhaseKind::LongBreak
+                } else {
+                    PhaseKind::ShortBreak
+                }
+            }
+            PhaseKind::LongBreak if args.cycle_review.is_some() => PhaseKind::CycleReview,
+            PhaseKind::LongBreak | PhaseKind::CycleReview => {
+                cycles += 1;
+                PhaseKind::Focus
+            }
+            PhaseKind::Custom(_) => resume.take().unwrap_or(PhaseKind::Focus),
+        };
+        let next = match phases::following(&args.phases, phase.kind) {
+            Some(custom) => {
+                resume.get_or_insert(next);
+                custom.kind
+            }
+            None => next,
+        };
+        if cycles >= args.cycles.unwrap_or(1) {
+            break;
+        }
+        phase = Phase {
+            kind: next,
+            duration: length_of(args, next),
+        };
+    }
+    planned
+}
+
+/// Where the timer starts: with the warm-up, unless there's none or today
+/// already has a focus session; otherwise with focus.
+fn first_phase(args: &Args, history: &History, unix: u64) -> Phase {
+    let focused_today = || history.today(unix).any(|r| r.kind == PhaseKind::Focus);
+    match args.warm_up {
+        Some(duration) if !focused_today() => Phase {
+            kind: PhaseKind::WarmUp,
+            duration,
+        },
+        _ => Phase {
+            kind: PhaseKind::Focus,
+            duration: args.focus,
+        },
+    }
+}
+
+/// How long until a countdown with `left` to go shows a new second.
+fn until_whole_second(left: Duration) -> Duration {
+    match left.as_nanos() % Duration::from_secs(1).as_nanos() {
+        0 => Duration::from_secs(1),
+        nanos => Duration::from_nanos(nanos as u64),
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::bell::BellMode;
+    use crate::clock::MockClock;
+    use crate::config::PushConfig;
+    use crate::events::Subscriber;
+    use crate::history::StoreKind;
+    use crate::integrations::media::MusicMode;
+    use crate::notifications::{NotifierKind, Recorder, Route};
+    use crate::score::Weights;
+    use crate::timefmt::TimeFormat;
+    use crate::ui::LayoutPreset;
+    use crate::work_hours::{Lunch, WorkHours};
 
-    fn make_args() -> Args {
+    pub(crate) fn make_args() -> Args {
         Args {
-            focus: 1,
-            short: 1,
-            long: 2,
+            focus: Duration::from_secs(60),
+            short: Duration::from_secs(60),
+            long: Duration::from_secs(120),
             long_every: 2,
             theme: Theme::Dracula,
+            mascot: Mascot::Off,
+            layout: LayoutPreset::Default,
+            counter_icon: "🍅".to_string(),
             notifications: false,
             notification_sound: None,
             notification_seconds: 1,
             macos_bundle_id: None,
+            windows_app_id: None,
+            project: None,
+            profile: None,
+            task: None,
+            estimate: None,
+            confirm_skip: true,
+            history: false,
+            history_backend: StoreKind::Jsonl,
+            history_dir: None,
+            data_dir: None,
+            context: None,
+            tz: None,
+            serve: None,
+            serve_token: None,
+            mqtt: None,
+            music: MusicMode::Off,
+            plugins: false,
+            ambient: None,
+            ambient_break: None,
+            ambient_volume: 50,
+            accessible: false,
+            announce_every: 5,
+            bell: BellMode::Off,
+            remote_mode: Some(false),
+            lang: None,
+            time_format: TimeFormat::Auto,
+            cycles: None,
+            exit_on_complete: false,
+            once: false,
+            plan: false,
+            background: false,
+            detach: false,
+            low_power: false,
+            bench_render: None,
+            snooze_minutes: 5,
+            rate_sessions: false,
+            quotes_file: None,
+            breathing: false,
+            graphics: false,
+            track_rest: false,
+            quotes: Vec::new(),
+            suggestions: Vec::new(),
+            calendar: None,
+            meeting_warning: 10,
+            fit_to_meetings: false,
+            work_hours: None,
+            lunch: None,
+            phases: Vec::new(),
+            distraction_apps: Vec::new(),
+            score_weights: Weights::default(),
+            notification_routes: Vec::new(),
+            notification_styles: Vec::new(),
+            push: PushConfig::default(),
+            telegram: None,
+            warm_up: None,
+            cycle_review: None,
+            partial_credit: None,
+            daily_goal: None,
+            offer_extension: None,
+            simulate: None,
+            demo: None,
+            log_file: None,
+            verbose: 0,
+            notifier: NotifierKind::Desktop,
         }
     }
 
+    /// An app whose clock only moves when the returned one is advanced.
+    pub(crate) fn mock_app(args: Args) -> (AppState, MockClock) {
+        let clock = MockClock::new();
+        (AppState::with_clock(args, Box::new(clock.clone())), clock)
+    }
+
+    pub(crate) fn test_app() -> AppState {
+        mock_app(make_args()).0
+    }
+
     #[test]
     fn progress_and_remaining_are_correct() {
-        let args = make_args();
-        let mut app = AppState::new(args);
-        let start = Instant::now();
-        app.phase_started_at = start - Duration::from_secs(30);
-        let now = start;
-        let progress = app.progress(now);
-        let remaining = app.time_remaining(now);
+        let (app, clock) = mock_app(make_args());
+        clock.advance(Duration::from_secs(30));
+        let progress = app.progress(app.now());
+        let remaining = app.time_remaining(app.now());
         assert!(
             progress > 0.49 && progress < 0.51,
             "progress was {progress}"
@@ -156,10 +1514,88 @@ mod tests {
         assert_eq!(remaining.as_secs(), 30);
     }
 
+    #[test]
+    fn wakes_when_the_clock_ticks_over() {
+        let (mut app, clock) = mock_app(make_args());
+        assert_eq!(app.until_clock_changes(app.now()), Duration::from_secs(1));
+        clock.advance(Duration::from_millis(1300));
+        assert_eq!(
+            app.until_clock_changes(app.now()),
+            Duration::from_millis(700)
+        );
+        app.toggle_pause();
+        assert_eq!(app.until_clock_changes(app.now()), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn breathing_animates_short_breaks_only() {
+        let mut args = make_args();
+        args.breathing = true;
+        let (mut app, _clock) = mock_app(args);
+        assert!(!app.breathing());
+        app.skip();
+        assert!(app.breathing());
+        assert_eq!(
+            app.until_clock_changes(app.now()),
+            Duration::from_millis(100)
+        );
+        app.toggle_pause();
+        assert!(!app.breathing());
+        app.toggle_pause();
+        app.skip();
+        app.skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::LongBreak);
+        assert!(!app.breathing());
+    }
+
+    #[test]
+    fn suggests_something_new_each_break_with_an_eye_rest() {
+        let (mut app, clock) = mock_app(make_args());
+        app.start_eye_rest();
+        assert_eq!(app.eye_rest_left(app.now()), None);
+        assert_eq!(app.break_suggestion(), None);
+        app.skip();
+        let first = app.break_suggestion().unwrap().to_string();
+        app.skip();
+        assert_eq!(app.break_suggestion(), None);
+        app.skip();
+        assert_ne!(app.break_suggestion(), Some(first.as_str()));
+
+        clock.advance(Duration::from_millis(700));
+        app.start_eye_rest();
+        assert_eq!(app.eye_rest_left(app.now()), Some(Duration::from_secs(20)));
+        clock.advance(Duration::from_millis(500));
+        // The eye rest's second turns over before the phase's
+        assert_eq!(
+            app.until_clock_changes(app.now()),
+            Duration::from_millis(500)
+        );
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(app.eye_rest_left(app.now()), None);
+    }
+
+    #[test]
+    fn low_power_ticks_coarsely_until_the_final_minute() {
+        let mut args = make_args();
+        args.focus = Duration::from_secs(25 * 60);
+        args.low_power = true;
+        let (mut app, clock) = mock_app(args);
+        // 24:50 left: next stop at 24:45
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(app.until_clock_changes(app.now()), Duration::from_secs(5));
+        // 0:59.5 left: back to whole seconds
+        clock.advance(Duration::from_millis(23 * 60_000 + 50_500));
+        assert_eq!(
+            app.until_clock_changes(app.now()),
+            Duration::from_millis(500)
+        );
+        app.toggle_pause();
+        assert_eq!(app.until_clock_changes(app.now()), Duration::from_secs(15));
+    }
+
     #[test]
     fn short_then_focus_transition() {
-        let args = make_args();
-        let mut app = AppState::new(args);
+        let mut app = test_app();
         assert_eq!(app.current_phase.kind, PhaseKind::Focus);
         app.advance_phase();
         assert_eq!(app.current_phase.kind, PhaseKind::ShortBreak);
@@ -169,8 +1605,7 @@ mod tests {
 
     #[test]
     fn long_break_after_two_focus_sessions() {
-        let args = make_args();
-        let mut app = AppState::new(args);
+        let mut app = test_app();
         // Focus -> ShortBreak
         app.advance_phase();
         // ShortBreak -> Focus
@@ -180,15 +1615,859 @@ mod tests {
         assert_eq!(app.current_phase.kind, PhaseKind::LongBreak);
     }
 
+    #[test]
+    fn counts_down_to_the_long_break_unless_there_are_none() {
+        let (mut app, clock) = mock_app(make_args());
+        let mut left = vec![app.until_long_break()];
+        for _ in 0..4 {
+            clock.advance(app.current_phase.duration);
+            app.advance_phase();
+            left.push(app.until_long_break());
+        }
+        assert_eq!(app.current_phase.kind, PhaseKind::Focus);
+        assert_eq!(left, [Some(2), Some(1), Some(1), None, Some(2)]);
+
+        let mut args = make_args();
+        args.long_every = 0;
+        let (mut app, clock) = mock_app(args);
+        for _ in 0..10 {
+            assert_eq!(app.until_long_break(), None);
+            assert_ne!(
+                app.peek_next_phase(app.now()).unwrap().kind,
+                PhaseKind::LongBreak
+            );
+            clock.advance(app.current_phase.duration);
+            app.advance_phase();
+            assert_ne!(app.current_phase.kind, PhaseKind::LongBreak);
+        }
+        let focus = plan(&app.args, &History::default(), clock.unix())
+            .iter()
+            .filter(|p| p.kind == PhaseKind::Focus)
+            .count();
+        assert_eq!(focus, 4);
+    }
+
     #[test]
     fn paused_freezes_elapsed_time() {
-        let args = make_args();
-        let mut app = AppState::new(args);
-        let start = Instant::now();
-        app.phase_started_at = start;
-        app.paused = true;
-        app.paused_at = Some(start + Duration::from_secs(10));
-        let later = start + Duration::from_secs(1000);
-        assert_eq!(app.elapsed_in_phase(later).as_secs(), 10);
+        let (mut app, clock) = mock_app(make_args());
+        clock.advance(Duration::from_secs(10));
+        app.toggle_pause();
+        clock.advance(Duration::from_secs(990));
+        assert_eq!(app.elapsed_in_phase(app.now()).as_secs(), 10);
+        app.toggle_pause();
+        assert_eq!(app.elapsed_in_phase(app.now()).as_secs(), 10);
+        let start = MockClock::STARTED_UNIX;
+        assert_eq!(app.pauses, vec![(start + 10, start + 1000)]);
+    }
+
+    #[test]
+    fn only_finished_focus_sessions_count_as_completed() {
+        let (mut app, clock) = mock_app(make_args());
+        app.skip();
+        app.skip();
+        assert_eq!(app.completed_focus, 0);
+        clock.advance(Duration::from_secs(60));
+        app.advance_phase();
+        assert_eq!(app.completed_focus, 1);
+    }
+
+    #[test]
+    fn undo_restores_skipped_phase() {
+        let (mut app, clock) = mock_app(make_args());
+        clock.advance(Duration::from_secs(50));
+        app.skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::ShortBreak);
+        clock.advance(Duration::from_secs(5));
+        assert!(app.undo());
+        assert_eq!(app.current_phase.kind, PhaseKind::Focus);
+        assert_eq!(app.session_index, 0);
+        assert_eq!(app.elapsed_in_phase(app.now()).as_secs(), 50);
+        // The time spent on the break counts as a pause
+        let start = MockClock::STARTED_UNIX;
+        assert_eq!(app.pauses, vec![(start + 50, start + 55)]);
+        assert!(!app.undo());
+    }
+
+    #[test]
+    fn holds_recorded_sessions_until_the_skip_cannot_be_undone() {
+        #[derive(Debug)]
+        struct Saved(Rc<RefCell<Vec<PhaseKind>>>);
+        impl Subscriber for Saved {
+            fn on_event(&mut self, event: &PhaseEvent) {
+                if let PhaseEvent::Recorded(record) = event {
+                    self.0.borrow_mut().push(record.kind);
+                }
+            }
+        }
+        let mut args = make_args();
+        args.history = true;
+        args.history_backend = StoreKind::Memory;
+        let (mut app, clock) = mock_app(args);
+        let saved = Rc::new(RefCell::new(Vec::new()));
+        app.bus.subscribe(Box::new(Saved(Rc::clone(&saved))));
+        app.skip();
+        app.send_recorded();
+        app.bus.dispatch();
+        assert!(saved.borrow().is_empty());
+        assert!(app.undo());
+        clock.advance(Duration::from_secs(30));
+        app.skip();
+        clock.advance(Duration::from_secs(11));
+        app.send_recorded();
+        app.bus.dispatch();
+        assert_eq!(*saved.borrow(), [PhaseKind::Focus]);
+    }
+
+    #[test]
+    fn undo_restores_paused_reset() {
+        let (mut app, clock) = mock_app(make_args());
+        clock.advance(Duration::from_secs(20));
+        app.toggle_pause();
+        app.reset();
+        assert!(!app.paused);
+        assert!(app.undo());
+        assert!(app.paused);
+        assert_eq!(app.elapsed_in_phase(app.now()).as_secs(), 20);
+    }
+
+    #[test]
+    fn undo_expires_after_grace_window() {
+        let (mut app, clock) = mock_app(make_args());
+        app.skip();
+        clock.advance(Duration::from_secs(11));
+        assert!(!app.undo());
+        assert_eq!(app.current_phase.kind, PhaseKind::ShortBreak);
+    }
+
+    #[test]
+    fn skipping_focus_needs_confirmation() {
+        let mut app = test_app();
+        app.request_skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::Focus);
+        assert!(app.skip_pending(app.now()));
+        app.request_skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::ShortBreak);
+        // Breaks skip straight away
+        app.request_skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::Focus);
+    }
+
+    #[test]
+    fn cancelled_or_disabled_confirmation() {
+        let mut app = test_app();
+        app.request_skip();
+        app.cancel_skip();
+        app.request_skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::Focus);
+
+        let mut args = make_args();
+        args.confirm_skip = false;
+        let mut app = mock_app(args).0;
+        app.request_skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::ShortBreak);
+    }
+
+    #[test]
+    fn reload_resizes_the_running_phase_unless_already_past() {
+        let (mut app, clock) = mock_app(make_args());
+        clock.advance(Duration::from_secs(90));
+        let mut settings = make_args();
+        settings.focus = Duration::from_secs(120);
+        settings.theme = Theme::GruvboxDark;
+        app.reload(&settings);
+        assert_eq!(app.current_phase.duration, Duration::from_secs(120));
+        assert_eq!(app.theme, Theme::GruvboxDark);
+
+        settings.focus = Duration::from_secs(30);
+        app.reload(&settings);
+        assert_eq!(app.current_phase.duration, Duration::from_secs(120));
+        assert_eq!(app.args.focus, Duration::from_secs(30));
+        app.skip();
+        app.skip();
+        assert_eq!(app.current_phase.duration, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn theme_picker_previews_and_cancels() {
+        let mut app = test_app();
+        app.open_theme_picker();
+        app.theme = app.theme.cycle(1);
+        app.close_theme_picker(false);
+        assert_eq!(app.theme, Theme::Dracula);
+        app.open_theme_picker();
+        app.theme = app.theme.cycle(-1);
+        app.close_theme_picker(true);
+        assert_eq!(app.theme, Theme::GruvboxDark);
+        assert!(app.theme_picker.is_none());
+
+        // A reload that leaves the theme alone keeps the picked one
+        app.reload(&make_args());
+        assert_eq!(app.theme, Theme::GruvboxDark);
+    }
+
+    #[test]
+    fn stops_after_the_last_cycle() {
+        let mut args = make_args();
+        args.cycles = Some(1);
+        let mut app = mock_app(args).0;
+        for _ in 0..3 {
+            app.skip();
+        }
+        assert_eq!(app.current_phase.kind, PhaseKind::LongBreak);
+        assert!(!app.finished);
+        app.skip();
+        assert!(app.finished);
+        assert_eq!(app.completed_cycles, 1);
+        assert_eq!(app.history.records.len(), 4);
+        // Nothing moves once done, and quitting logs nothing more
+        app.skip();
+        app.finish();
+        assert_eq!(app.history.records.len(), 4);
+        assert!(app.undo());
+        assert!(!app.finished);
+        assert_eq!(app.current_phase.kind, PhaseKind::LongBreak);
+    }
+
+    #[test]
+    fn once_ends_after_the_first_focus_session() {
+        let mut args = make_args();
+        args.once = true;
+        let (mut app, clock) = mock_app(args);
+        assert!(!app.goal_reached());
+        clock.advance(Duration::from_secs(60));
+        app.advance_phase();
+        assert!(app.finished);
+        assert!(app.goal_reached());
+        assert_eq!(app.current_phase.kind, PhaseKind::Focus);
+
+        let mut args = make_args();
+        args.once = true;
+        let mut app = mock_app(args).0;
+        app.skip();
+        assert!(app.finished);
+        assert!(!app.goal_reached());
+    }
+
+    #[test]
+    fn transitions_are_recorded_in_history() {
+        let (mut app, clock) = mock_app(make_args());
+        clock.advance(Duration::from_secs(60));
+        app.advance_phase();
+        app.skip();
+        let kinds: Vec<_> = app.history.records.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, vec![PhaseKind::Focus, PhaseKind::ShortBreak]);
+        assert!(app.history.records[0].completed);
+        assert!(!app.history.records[1].completed);
+        assert!(app.undo());
+        assert_eq!(app.history.records.len(), 1);
+    }
+
+    #[test]
+    fn counts_the_focus_sessions_finished_today() {
+        let (mut app, clock) = mock_app(make_args());
+        assert_eq!(app.counter(), None);
+        assert_eq!(app.window_title(), "Rusty Pomo · Focus");
+        // An earlier run's session counts, yesterday's doesn't
+        let record = app.current_record(true);
+        let earlier = |start| SessionRecord {
+            start,
+            end: start + 60,
+            ..record.clone()
+        };
+        let (now, day) = (app.clock.unix(), 24 * 3600);
+        app.history.append(earlier(now - day)).unwrap();
+        app.history.append(earlier(now - 120)).unwrap();
+        clock.advance(Duration::from_secs(60));
+        app.advance_phase();
+        // Breaks and skipped sessions don't count
+        app.skip();
+        clock.advance(Duration::from_secs(10));
+        app.history.append(app.current_record(false)).unwrap();
+        assert_eq!(app.focus_today(), 2);
+        assert_eq!(app.counter().as_deref(), Some("🍅 x2"));
+        assert_eq!(app.window_title(), "Rusty Pomo · Focus · 🍅 x2");
+        app.args.counter_icon = "●".to_string();
+        assert_eq!(app.counter().as_deref(), Some("● x2"));
+        app.args.counter_icon.clear();
+        assert_eq!(app.counter(), None);
+    }
+
+    #[test]
+    fn projects_when_the_daily_goal_is_met() {
+        let mut args = make_args();
+        args.daily_goal = Some(3);
+        let (mut app, clock) = mock_app(args);
+        let start = MockClock::STARTED_UNIX;
+        // Before any finished session, a focus session and a short break each
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(app.focus_time_today(app.now()), Duration::from_secs(20));
+        assert_eq!(app.goal_eta(app.now()), Some(start + 20 + 3 * 120 - 20));
+
+        // Then the pace so far: one session in 200 seconds
+        clock.advance(Duration::from_secs(40));
+        app.advance_phase();
+        clock.advance(Duration::from_secs(140));
+        assert_eq!(app.focus_time_today(app.now()), Duration::from_secs(60));
+        assert_eq!(app.goal_eta(app.now()), Some(start + 3 * 200));
+
+        app.history
+            .append(SessionRecord {
+                start: start - 600,
+                end: start - 540,
+                ..app.history.records[0].clone()
+            })
+            .unwrap();
+        app.history
+            .append(SessionRecord {
+                start: start - 300,
+                end: start - 240,
+                ..app.history.records[0].clone()
+            })
+            .unwrap();
+        assert_eq!(app.goal_eta(app.now()), None);
+        app.args.daily_goal = None;
+        assert_eq!(app.goal_eta(app.now()), None);
+    }
+
+    #[test]
+    fn snooze_pauses_then_resumes_by_itself() {
+        let (mut app, clock) = mock_app(make_args());
+        clock.advance(Duration::from_secs(60));
+        app.snooze();
+        assert!(app.paused);
+        clock.advance(Duration::from_secs(120));
+        app.snooze();
+        assert_eq!(app.snoozed_for(app.now()), Some(Duration::from_secs(480)));
+        clock.advance(Duration::from_secs(479));
+        assert!(!app.wake_from_snooze());
+        clock.advance(Duration::from_secs(1));
+        assert!(app.wake_from_snooze());
+        assert!(!app.paused);
+        assert_eq!(app.snoozed_for(app.now()), None);
+        assert_eq!(app.elapsed_in_phase(app.now()), Duration::from_secs(60));
+        let start = MockClock::STARTED_UNIX;
+        assert_eq!(app.snoozes, vec![(start + 60, start + 660)]);
+        assert!(app.pauses.is_empty());
+    }
+
+    #[test]
+    fn asks_for_a_rating_only_after_finished_focus() {
+        let mut args = make_args();
+        args.rate_sessions = true;
+        let (mut app, clock) = mock_app(args);
+        app.skip();
+        assert_eq!(app.rating_prompt, None);
+        app.skip();
+        clock.advance(Duration::from_secs(60));
+        app.advance_phase();
+        assert_eq!(app.rating_prompt, Some(2));
+        app.rate(4);
+        assert_eq!(app.rating_prompt, None);
+        assert_eq!(app.history.records[2].rating, Some(4));
+    }
+
+    #[test]
+    fn notifies_each_new_phase_and_the_end() {
+        let mut args = make_args();
+        args.cycles = Some(1);
+        let mut app = mock_app(args).0;
+        let sent = Recorder::default();
+        app.bus
+            .subscribe(Box::new(Notifications::new(Box::new(sent.clone()), 5)));
+        for _ in 0..4 {
+            app.skip();
+        }
+        assert!(sent.0.borrow().is_empty());
+        app.bus.dispatch();
+        let titles: Vec<_> = sent.0.borrow().iter().map(|(t, _)| t.clone()).collect();
+        assert_eq!(titles, ["Short Break", "Focus", "Long Break", "All done"]);
+        assert_eq!(
+            sent.0.borrow()[0].1,
+            "Time for a quick breather. Rest is part of the work."
+        );
+        assert_eq!(
+            sent.0.borrow()[2].1,
+            "Enjoy a longer rest. Small steps still move you forward."
+        );
+        assert_eq!(
+            sent.0.borrow()[3].1,
+            "1 cycle and 0 focus sessions completed."
+        );
+    }
+
+    #[test]
+    fn routes_events_to_their_own_notifiers() {
+        let mut args = make_args();
+        args.cycles = Some(1);
+        let mut app = mock_app(args).0;
+        let (phases, long, back, done) = (
+            Recorder::default(),
+            Recorder::default(),
+            Recorder::default(),
+            Recorder::default(),
+        );
+        app.bus.subscribe(Box::new(
+            Notifications::new(Box::new(phases.clone()), 5)
+                .route(Route::LongBreak, Box::new(long.clone()))
+                .route(Route::BreakEnd, Box::new(back.clone()))
+                .route(Route::Done, Box::new(done.clone())),
+        ));
+        for _ in 0..4 {
+            app.skip();
+        }
+        app.bus.dispatch();
+        let titles = |sent: &Recorder| -> Vec<String> {
+            sent.0.borrow().iter().map(|(t, _)| t.clone()).collect()
+        };
+        assert_eq!(titles(&phases), ["Short Break"]);
+        assert_eq!(titles(&long), ["Long Break"]);
+        assert_eq!(titles(&back), ["Focus"]);
+        assert_eq!(titles(&done), ["All done"]);
+    }
+
+    #[test]
+    fn keeps_notifications_quiet_outside_work_hours() {
+        let mut args = make_args();
+        args.work_hours = Some(WorkHours {
+            days: [false; 7],
+            ..WorkHours::default()
+        });
+        let mut app = mock_app(args).0;
+        assert!(app.off_hours());
+        let sent = Recorder::default();
+        app.bus.subscribe(Box::new(
+            Notifications::new(Box::new(sent.clone()), 5)
+                .quiet_outside(app.args.work_hours.as_ref()),
+        ));
+        app.skip();
+        app.bus.dispatch();
+        assert!(sent.0.borrow().is_empty());
+
+        let hours = WorkHours {
+            notifications: true,
+            ..app.args.work_hours.clone().unwrap()
+        };
+        app.bus.subscribe(Box::new(
+            Notifications::new(Box::new(sent.clone()), 5).quiet_outside(Some(&hours)),
+        ));
+        app.skip();
+        app.bus.dispatch();
+        assert_eq!(sent.0.borrow().len(), 1);
+    }
+
+    #[test]
+    fn suspends_the_cycle_for_lunch() {
+        let mut args = make_args();
+        args.focus = Duration::from_secs(25 * 60);
+        let (mut app, clock) = mock_app(args);
+        clock.advance(Duration::from_secs(5 * 60));
+        let secs = wallclock::seconds_of_local_day(app.clock.unix());
+        app.args.lunch = Some(Lunch {
+            start: secs,
+            end: secs + 30 * 60,
+        });
+        assert!(app.check_lunch());
+        assert_eq!(app.current_phase.kind, PhaseKind::Lunch);
+        assert_eq!(app.current_phase.duration, Duration::from_secs(30 * 60));
+        assert!(!app.history.records[0].completed);
+        clock.advance(Duration::from_secs(10 * 60));
+        assert!(!app.check_lunch());
+
+        // Skipping ends lunch early, and it doesn't start again that day
+        app.skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::Focus);
+        assert_eq!(app.current_phase.duration, Duration::from_secs(20 * 60));
+        assert_eq!(app.session_index, 0);
+        assert!(!app.check_lunch());
+        let kinds: Vec<_> = app.history.records.iter().map(|r| r.kind).collect();
+        assert_eq!(kinds, [PhaseKind::Focus, PhaseKind::Lunch]);
+        app.skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::ShortBreak);
+    }
+
+    #[test]
+    fn peeks_at_the_phase_that_comes_next() {
+        let mut args = make_args();
+        args.cycle_review = Some(Duration::from_secs(20));
+        args.cycles = Some(1);
+        args.phases = vec![CustomPhase {
+            kind: PhaseKind::Custom("stretch"),
+            after: PhaseKind::ShortBreak,
+            duration: Duration::from_secs(10),
+            color: None,
+            message: None,
+        }];
+        let (mut app, clock) = mock_app(args);
+        clock.advance(Duration::from_secs(15));
+        let next = app.peek_next_phase(app.now()).unwrap();
+        assert_eq!(next.kind, PhaseKind::ShortBreak);
+        assert_eq!(next.start, MockClock::STARTED_UNIX + 60);
+        assert_eq!(next.duration, app.args.short);
+
+        // Each peek is what advancing then starts, until the last cycle ends
+        while let Some(next) = app.peek_next_phase(app.now()) {
+            app.advance_phase();
+            assert_eq!(
+                (app.current_phase.kind, app.current_phase.duration),
+                (next.kind, next.duration)
+            );
+        }
+        assert_eq!(app.current_phase.kind, PhaseKind::CycleReview);
+        app.advance_phase();
+        assert!(app.finished);
+        assert_eq!(app.peek_next_phase(app.now()), None);
+    }
+
+    #[test]
+    fn plans_the_cycle_the_timer_would_run() {
+        let mut args = make_args();
+        args.warm_up = Some(Duration::from_secs(30));
+        args.cycle_review = Some(Duration::from_secs(20));
+        args.phases = vec![CustomPhase {
+            kind: PhaseKind::Custom("stretch"),
+            after: PhaseKind::ShortBreak,
+            duration: Duration::from_secs(10),
+            color: None,
+            message: None,
+        }];
+        let (mut app, _clock) = mock_app(args);
+        let start = 1_700_000_000;
+        let cycle = plan(&app.args, &History::default(), start);
+
+        // The timer goes through the same phases
+        let mut kinds = vec![app.current_phase.kind];
+        while !app.finished && kinds.len() < cycle.len() + 1 {
+            app.advance_phase();
+            kinds.push(app.current_phase.kind);
+        }
+        let planned: Vec<_> = cycle.iter().map(|p| p.kind).collect();
+        assert_eq!(planned, kinds[..planned.len()]);
+        assert_eq!(planned.last(), Some(&PhaseKind::CycleReview));
+        assert_eq!(planned.len(), 2 * (app.args.long_every as usize) + 3);
+        for pair in cycle.windows(2) {
+            assert_eq!(pair[1].start, pair[0].start + pair[0].duration.as_secs());
+        }
+
+        // Lunch cuts into the phase it falls in
+        let mut args = app.args;
+        let secs = wallclock::seconds_of_local_day(start);
+        args.lunch = Some(Lunch {
+            start: secs + 40,
+            end: secs + 100,
+        });
+        args.once = true;
+        let lunch: Vec<_> = plan(&args, &History::default(), start)
+            .iter()
+            .map(|p| (p.kind, p.duration.as_secs()))
+            .collect();
+        assert_eq!(
+            lunch,
+            [
+                (PhaseKind::WarmUp, 30),
+                (PhaseKind::Focus, 10),
+                (PhaseKind::Lunch, 60),
+                (PhaseKind::Focus, 50)
+            ]
+        );
+    }
+
+    #[test]
+    fn offers_more_focus_before_the_break() {
+        let mut args = make_args();
+        args.focus = Duration::from_secs(60);
+        args.offer_extension = Some(Duration::from_secs(5 * 60));
+        let (mut app, clock) = mock_app(args);
+        clock.advance(Duration::from_secs(60));
+        assert!(app.hold_for_extension());
+        clock.advance(Duration::from_secs(5));
+        assert!(app.hold_for_extension());
+        app.extend();
+        assert_eq!(app.extension_offer, None);
+        assert_eq!(app.current_phase.duration, Duration::from_secs(65 + 5 * 60));
+        assert_eq!(app.time_remaining(app.now()), Duration::from_secs(5 * 60));
+
+        // Unanswered, the offer lapses and the break starts
+        clock.advance(Duration::from_secs(5 * 60));
+        assert!(app.hold_for_extension());
+        clock.advance(Duration::from_secs(10));
+        assert!(!app.hold_for_extension());
+        app.advance_phase();
+        let record = &app.history.records[0];
+        assert!(record.completed);
+        assert_eq!((record.planned_secs, record.extended_secs), (365, 305));
+        assert!(!app.hold_for_extension());
+    }
+
+    #[test]
+    fn peeks_past_void_sessions_with_partial_credit() {
+        let mut args = make_args();
+        args.focus = Duration::from_secs(100);
+        args.partial_credit = Some(80);
+        let (mut app, clock) = mock_app(args);
+        // Run to the end, each focus session counts
+        let next = app.peek_next_phase(app.now()).unwrap();
+        clock.advance(Duration::from_secs(100));
+        app.advance_phase();
+        assert_eq!(
+            (next.kind, app.current_phase.kind),
+            (PhaseKind::ShortBreak, PhaseKind::ShortBreak)
+        );
+        app.skip();
+
+        // A void session leaves the long break where it was, for the preview too
+        clock.advance(Duration::from_secs(10));
+        app.skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::ShortBreak);
+        app.skip();
+        let next = app.peek_next_phase(app.now()).unwrap();
+        assert_eq!(next.kind, PhaseKind::LongBreak);
+        clock.advance(Duration::from_secs(100));
+        app.advance_phase();
+        assert_eq!(app.current_phase.kind, next.kind);
+    }
+
+    #[test]
+    fn gives_partial_credit_past_the_threshold() {
+        let mut args = make_args();
+        args.focus = Duration::from_secs(100);
+        args.partial_credit = Some(80);
+        let (mut app, clock) = mock_app(args);
+        clock.advance(Duration::from_secs(79));
+        app.skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::ShortBreak);
+        assert_eq!((app.session_index, app.completed_focus), (0, 0));
+        assert!(!app.history.records[0].completed);
+        app.skip();
+
+        clock.advance(Duration::from_secs(80));
+        app.skip();
+        assert_eq!((app.session_index, app.completed_focus), (1, 1));
+        assert!(app.history.records[2].completed);
+        app.skip();
+        clock.advance(Duration::from_secs(90));
+        app.finish();
+        assert!(app.history.records[4].completed);
+
+        // Without the policy, every session brings the long break closer
+        app.args.partial_credit = None;
+        app.reset_phase();
+        clock.advance(Duration::from_secs(90));
+        app.skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::LongBreak);
+        assert_eq!(app.completed_focus, 1);
+    }
+
+    #[test]
+    fn reviews_each_cycle_after_its_long_break() {
+        let mut args = make_args();
+        args.cycle_review = Some(Duration::from_secs(3 * 60));
+        args.cycles = Some(1);
+        let (mut app, clock) = mock_app(args);
+        for _ in 0..3 {
+            app.advance_phase();
+        }
+        assert_eq!(app.current_phase.kind, PhaseKind::LongBreak);
+        app.advance_phase();
+        assert_eq!(app.current_phase.kind, PhaseKind::CycleReview);
+        assert_eq!(app.current_phase.duration, Duration::from_secs(3 * 60));
+        assert_eq!(app.completed_cycles, 0);
+        assert!(app.cycle_prompt);
+        app.rate_cycle(4);
+        assert!(!app.cycle_prompt);
+
+        // The review closes the cycle, and its record keeps the answer
+        clock.advance(Duration::from_secs(60));
+        app.advance_phase();
+        assert!(app.finished);
+        assert_eq!(app.completed_cycles, 1);
+        let review = app.history.records.last().unwrap();
+        assert_eq!(
+            (review.kind, review.rating),
+            (PhaseKind::CycleReview, Some(4))
+        );
+    }
+
+    #[test]
+    fn warms_up_before_the_first_focus_of_the_day() {
+        let mut args = make_args();
+        args.warm_up = Some(Duration::from_secs(5 * 60));
+        let (mut app, clock) = mock_app(args);
+        assert_eq!(app.current_phase.kind, PhaseKind::WarmUp);
+        assert_eq!(app.current_phase.duration, Duration::from_secs(5 * 60));
+        clock.advance(Duration::from_secs(5 * 60));
+        app.advance_phase();
+        assert_eq!(app.current_phase.kind, PhaseKind::Focus);
+        assert_eq!((app.session_index, app.completed_focus), (0, 0));
+        assert_eq!(app.history.records[0].kind, PhaseKind::WarmUp);
+        assert!(app.history.records[0].completed);
+
+        // Once today has a focus session, starting again goes straight to focus
+        clock.advance(Duration::from_secs(60));
+        app.advance_phase();
+        let unix = app.clock.unix();
+        assert_eq!(
+            first_phase(&app.args, &app.history, unix).kind,
+            PhaseKind::Focus
+        );
+        let tomorrow = unix + 24 * 3600;
+        assert_eq!(
+            first_phase(&app.args, &app.history, tomorrow).kind,
+            PhaseKind::WarmUp
+        );
+        app.args.warm_up = None;
+        assert_eq!(
+            first_phase(&app.args, &app.history, tomorrow).kind,
+            PhaseKind::Focus
+        );
+    }
+
+    #[test]
+    fn runs_custom_phases_in_their_place_in_the_cycle() {
+        let mut args = make_args();
+        let custom = |name, after| CustomPhase {
+            kind: phases::kind(name),
+            duration: Duration::from_secs(5 * 60),
+            color: None,
+            message: None,
+            after: phases::kind(after),
+        };
+        args.phases = vec![custom("Review", "focus"), custom("Email", "Review")];
+        let (mut app, _) = mock_app(args);
+        let mut kinds = Vec::new();
+        for _ in 0..7 {
+            app.advance_phase();
+            kinds.push(app.current_phase.kind.key());
+        }
+        assert_eq!(
+            kinds,
+            [
+                "Review",
+                "Email",
+                "short_break",
+                "focus",
+                "Review",
+                "Email",
+                "long_break"
+            ]
+        );
+        assert_eq!(app.current_phase.duration, app.args.long);
+
+        // Undo goes back into the run of custom phases, and picks up after it
+        app.advance_phase();
+        app.advance_phase();
+        assert_eq!(app.current_phase.kind, phases::kind("Review"));
+        assert_eq!(app.current_phase.duration, Duration::from_secs(5 * 60));
+        app.skip();
+        app.skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::ShortBreak);
+        assert!(app.undo());
+        assert_eq!(app.current_phase.kind, phases::kind("Email"));
+        app.skip();
+        assert_eq!(app.current_phase.kind, PhaseKind::ShortBreak);
+    }
+
+    fn meeting(start: u64, summary: &str) -> Meeting {
+        Meeting {
+            start,
+            end: start + 1800,
+            summary: summary.to_string(),
+        }
+    }
+
+    #[test]
+    fn fits_focus_to_the_gap_before_a_meeting() {
+        let mut args = make_args();
+        args.focus = Duration::from_secs(25 * 60);
+        args.fit_to_meetings = true;
+        let (mut app, clock) = mock_app(args);
+        let now = app.clock.unix();
+        app.set_meetings(vec![meeting(now + 3 * 60, "Too soon")]);
+        // Three minutes isn't worth a session on its own; the warning asks instead
+        assert_eq!(app.current_phase.duration, Duration::from_secs(25 * 60));
+        assert!(app.meeting_clash(app.now()).is_some());
+        clock.advance(Duration::from_secs(60));
+        assert!(app.fit_to_meeting());
+        assert_eq!(app.current_phase.duration, Duration::from_secs(3 * 60));
+        assert_eq!(app.meeting_clash(app.now()), None);
+        assert!(!app.fit_to_meeting());
+
+        app.set_meetings(vec![meeting(now + 20 * 60, "Review")]);
+        app.skip();
+        app.skip();
+        // The next focus starts a minute in, so it gets the 19 before the meeting
+        assert_eq!(app.current_phase.kind, PhaseKind::Focus);
+        assert_eq!(app.current_phase.duration, Duration::from_secs(19 * 60));
+    }
+
+    #[test]
+    fn notifies_achievements_as_the_history_earns_them() {
+        let (mut app, clock) = mock_app(make_args());
+        app.achievements = Some(Achievements::default());
+        let sent = Recorder::default();
+        app.bus
+            .subscribe(Box::new(Notifications::new(Box::new(sent.clone()), 5)));
+        // A skipped focus session earns nothing
+        app.skip();
+        app.skip();
+        clock.advance(app.current_phase.duration);
+        app.advance_phase();
+        app.skip();
+        clock.advance(app.current_phase.duration);
+        app.advance_phase();
+        app.bus.dispatch();
+        let unlocked: Vec<_> = sent
+            .0
+            .borrow()
+            .iter()
+            .filter(|(title, _)| title.starts_with("Achievement"))
+            .cloned()
+            .collect();
+        assert_eq!(
+            unlocked,
+            [(
+                "Achievement unlocked: First focus".to_string(),
+                "Finish a focus session.".to_string()
+            )]
+        );
+        let achievements = app.achievements.as_ref().unwrap();
+        assert!(achievements.unlocked_at("first_focus").is_some());
+        assert_eq!(achievements.unlocked_at("ten_focus"), None);
+    }
+
+    #[test]
+    fn warns_once_before_each_meeting() {
+        let mut args = make_args();
+        args.focus = Duration::from_secs(25 * 60);
+        let (mut app, clock) = mock_app(args);
+        let sent = Recorder::default();
+        app.bus
+            .subscribe(Box::new(Notifications::new(Box::new(sent.clone()), 5)));
+        let start = app.clock.unix() + 15 * 60;
+        app.set_meetings(vec![meeting(start, "Standup"), meeting(start + 3600, "")]);
+        assert!(!app.check_meetings());
+        clock.advance(Duration::from_secs(5 * 60));
+        assert!(app.check_meetings());
+        assert!(!app.check_meetings());
+        app.skip();
+        clock.advance(Duration::from_secs(65 * 60));
+        assert!(app.check_meetings());
+        app.bus.dispatch();
+        let sent: Vec<_> = sent
+            .0
+            .borrow()
+            .iter()
+            .filter(|(title, _)| title.starts_with("Meeting"))
+            .cloned()
+            .collect();
+        assert_eq!(
+            sent,
+            [
+                (
+                    "Meeting in 10 min — shorten this session?".to_string(),
+                    "Standup".to_string()
+                ),
+                ("Meeting in 5 min".to_string(), "Busy".to_string()),
+            ]
+        );
     }
 }
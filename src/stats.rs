@@ -0,0 +1,670 @@
+//! `rusty_pomo stats`: focus totals from the history, as plain text, and with
+//! `--insights` the patterns behind them. Nothing leaves the machine.
+
+use std::collections::BTreeMap;
+
+use crate::history::SessionRecord;
+use crate::i18n;
+use crate::state::PhaseKind;
+use crate::wallclock;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Totals {
+    /// Focus sessions that ran to the end.
+    pub completed: u64,
+    /// Time spent in focus, finished or not, minus pauses and snoozes.
+    pub focus_secs: u64,
+}
+
+impl Totals {
+    pub fn add(&mut self, record: &SessionRecord) {
+        if record.kind != PhaseKind::Focus {
+            return;
+        }
+        self.focus_secs += active_secs(record);
+        self.completed += u64::from(record.completed);
+    }
+}
+
+/// How long `record` ran, minus pauses and snoozes.
+pub fn active_secs(record: &SessionRecord) -> u64 {
+    let paused: u64 = record
+        .pauses
+        .iter()
+        .chain(&record.snoozes)
+        .map(|(s, e)| e.saturating_sub(*s))
+        .sum();
+    record
+        .end
+        .saturating_sub(record.start)
+        .saturating_sub(paused)
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub today: Totals,
+    pub period: Totals,
+    pub by_project: BTreeMap<String, Totals>,
+    /// For each app from `[distractions]`, the focus sessions over the period
+    /// it came up in and the time spent in it.
+    pub distractions: BTreeMap<String, (u64, u64)>,
+    /// Tasks given an `--estimate` and worked on over the period.
+    pub estimates: Vec<Estimate>,
+}
+
+/// A task's estimate against the focus sessions it has taken so far.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Estimate {
+    pub task: String,
+    /// The latest estimate given for it.
+    pub estimated: u64,
+    /// Focus sessions finished on it, before the period too.
+    pub actual: u64,
+}
+
+/// Totals for today and for the `days` local days ending today.
+pub fn summarize(records: &[SessionRecord], now: u64, days: u64) -> Summary {
+    let today = wallclock::local_day(now);
+    let first = today - days.saturating_sub(1) as i64;
+    let mut summary = Summary::default();
+    for record in records {
+        let day = record.day();
+        if day < first || day > today {
+            continue;
+        }
+        if day == today {
+            summary.today.add(record);
+        }
+        summary.period.add(record);
+        let project = record
+            .project
+            .clone()
+            .unwrap_or_else(|| "(none)".to_string());
+        summary.by_project.entry(project).or_default().add(record);
+        for (app, secs) in &record.distractions {
+            let (sessions, total) = summary.distractions.entry(app.clone()).or_default();
+            *sessions += 1;
+            *total += secs;
+        }
+    }
+    summary.by_project.retain(|_, totals| totals.focus_secs > 0);
+    summary.estimates = estimates(records, first, today);
+    summary
+}
+
+/// Tasks with an estimate that had a session from day `first` to `last`, in
+/// the order they were first estimated.
+fn estimates(records: &[SessionRecord], first: i64, last: i64) -> Vec<Estimate> {
+    let mut estimates: Vec<Estimate> = Vec::new();
+    for record in records {
+        let (Some(task), Some(estimated)) = (&record.task, record.estimate) else {
+            continue;
+        };
+        match estimates.iter_mut().find(|e| e.task == *task) {
+            Some(estimate) => estimate.estimated = estimated,
+            None => estimates.push(Estimate {
+                task: task.clone(),
+                estimated,
+                actual: 0,
+            }),
+        }
+    }
+    estimates.retain(|e| in_days(records, first, last).any(|r| r.task.as_ref() == Some(&e.task)));
+    for estimate in &mut estimates {
+        estimate.actual = records
+            .iter()
+            .filter(|r| r.kind == PhaseKind::Focus && r.completed)
+            .filter(|r| r.task.as_ref() == Some(&estimate.task))
+            .count() as u64;
+    }
+    estimates
+}
+
+/// Records started from local day `first` to `last`.
+pub fn in_days(
+    records: &[SessionRecord],
+    first: i64,
+    last: i64,
+) -> impl Iterator<Item = &SessionRecord> {
+    records.iter().filter(move |record| {
+        let day = record.day();
+        first <= day && day <= last
+    })
+}
+
+/// Totals for each value of `key` from day `first` to `last`, "(none)" for
+/// records without one, most focus first.
+pub fn totals_by(
+    records: &[SessionRecord],
+    first: i64,
+    last: i64,
+    key: impl Fn(&SessionRecord) -> Option<String>,
+) -> Vec<(String, Totals)> {
+    let mut totals: Vec<(String, Totals)> = Vec::new();
+    for record in in_days(records, first, last) {
+        let name = key(record).unwrap_or_else(|| "(none)".to_string());
+        match totals.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, t)) => t.add(record),
+            None => {
+                let mut t = Totals::default();
+                t.add(record);
+                totals.push((name, t));
+            }
+        }
+    }
+    totals.retain(|(_, t)| t.focus_secs > 0);
+    totals.sort_by(|(a, x), (b, y)| y.focus_secs.cmp(&x.focus_secs).then(a.cmp(b)));
+    totals
+}
+
+pub fn render(summary: &Summary, days: u64) -> String {
+    let row = |label: &str, totals: &Totals| {
+        format!(
+            "{label:<16}{:>4} sessions  {:>8}\n",
+            totals.completed,
+            hours_minutes(totals.focus_secs)
+        )
+    };
+    let mut out = row("Today", &summary.today);
+    let period = if days == 1 {
+        "Last day".to_string()
+    } else {
+        format!("Last {days} days")
+    };
+    out.push_str(&row(&period, &summary.period));
+    if !summary.by_project.is_empty() {
+        out.push_str("\nBy project\n");
+        for (project, totals) in &summary.by_project {
+            out.push_str(&row(&format!("  {project}"), totals));
+        }
+    }
+    if !summary.distractions.is_empty() {
+        out.push_str("\nDistractions\n");
+        for (app, (sessions, secs)) in &summary.distractions {
+            let label = format!("  {app}");
+            let time = hours_minutes(*secs);
+            out.push_str(&format!("{label:<16}{sessions:>4} sessions  {time:>8}\n"));
+        }
+    }
+    if !summary.estimates.is_empty() {
+        // How far the sessions taken were from the estimates, over all of them
+        let estimated: u64 = summary.estimates.iter().map(|e| e.estimated).sum();
+        let off: u64 = summary
+            .estimates
+            .iter()
+            .map(|e| e.actual.abs_diff(e.estimated))
+            .sum();
+        let percent = (off * 100).checked_div(estimated).unwrap_or(0);
+        out.push_str(&format!("\nEstimates, off by {percent}% on average\n"));
+        for estimate in &summary.estimates {
+            let label = format!("  {}", estimate.task);
+            out.push_str(&format!(
+                "{label:<16}{:>4} sessions  {:>3} estimated\n",
+                estimate.actual, estimate.estimated
+            ));
+        }
+    }
+    out
+}
+
+/// The daily focus scores from `score::trend` as a sparkline, oldest day
+/// first, with a dot for days without one.
+pub fn render_scores(scores: &[Option<u8>]) -> String {
+    let known: Vec<u64> = scores.iter().flatten().map(|s| u64::from(*s)).collect();
+    let Some(average) = known.iter().sum::<u64>().checked_div(known.len() as u64) else {
+        return String::new();
+    };
+    let line: String = scores
+        .iter()
+        .map(|score| match score {
+            Some(score) => SPARKS[usize::from(*score) * (SPARKS.len() - 1) / 100],
+            None => '·',
+        })
+        .collect();
+    let today = match scores.last() {
+        Some(Some(score)) => format!("{score} today, "),
+        _ => String::new(),
+    };
+    format!(
+        "\n{:<16}{today}{average} on average\n  {line}\n",
+        "Focus score"
+    )
+}
+
+/// Short weekday names, Monday first as in [`wallclock::weekday`].
+pub const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Columns in each phase's bar in `render_run`.
+const BAR: usize = 20;
+
+/// The phases of one run, each with a bar of how much of it ran, and the
+/// totals; printed on quit so they stay in the scrollback.
+pub fn render_run(records: &[SessionRecord]) -> String {
+    let mut totals = Totals::default();
+    let mut breaks = 0;
+    let mut out = String::new();
+    for record in records {
+        totals.add(record);
+        let rest = matches!(record.kind, PhaseKind::ShortBreak | PhaseKind::LongBreak);
+        breaks += u64::from(rest && record.completed);
+        let active = active_secs(record);
+        let filled = (active as usize * BAR)
+            .checked_div(record.planned_secs as usize)
+            .unwrap_or(BAR)
+            .min(BAR);
+        let mut line = format!(
+            "{}–{}  {:<13}{}{}  {:>3}:{:02}  {}",
+            wallclock::format_hh_mm(record.start),
+            wallclock::format_hh_mm(record.end),
+            i18n::phase_title(record.kind),
+            "█".repeat(filled),
+            "░".repeat(BAR - filled),
+            active / 60,
+            active % 60,
+            if record.completed { "done" } else { "stopped" },
+        );
+        let about: Vec<&str> = [record.project.as_deref(), record.task.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if !about.is_empty() {
+            line.push_str(&format!("  {}", about.join(" · ")));
+        }
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "\n{} focus sessions finished, {} in focus, {breaks} breaks taken\n",
+        totals.completed,
+        hours_minutes(totals.focus_secs)
+    ));
+    out
+}
+
+/// Patterns in focus sessions over a period, plus the last two weeks for the trend.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Insights {
+    /// Finished focus sessions by the local hour they started in.
+    pub by_hour: [u64; 24],
+    /// Finished focus sessions by weekday, Monday first.
+    pub by_weekday: [u64; 7],
+    /// How many of each weekday the period spans.
+    pub weekdays: [u64; 7],
+    /// Focus sessions started, finished or not.
+    pub focus: u64,
+    pub skipped: u64,
+    /// Focus sessions paused or snoozed at least once.
+    pub paused: u64,
+    /// Sum and count of `--rate-sessions` ratings before noon, in the afternoon
+    /// and from 5 pm.
+    pub ratings: [(u64, u64); 3],
+    /// Focus time in the last 7 days and the 7 before, whatever the period.
+    pub this_week_secs: u64,
+    pub last_week_secs: u64,
+    /// Breaks checked for input with `--track-rest`, and those worked through.
+    pub breaks: u64,
+    pub worked_through: u64,
+}
+
+impl Insights {
+    /// The hours with the most finished sessions, busiest first.
+    pub fn best_hours(&self) -> Vec<usize> {
+        let mut hours: Vec<usize> = (0..24).filter(|h| self.by_hour[*h] > 0).collect();
+        hours.sort_by_key(|h| std::cmp::Reverse(self.by_hour[*h]));
+        hours.truncate(3);
+        hours
+    }
+}
+
+pub fn insights(records: &[SessionRecord], now: u64, days: u64) -> Insights {
+    let today = wallclock::local_day(now);
+    let first = today - days.saturating_sub(1) as i64;
+    let mut insights = Insights::default();
+    for day in first..=today {
+        insights.weekdays[wallclock::weekday(day)] += 1;
+    }
+    for record in records {
+        let day = record.day();
+        if let (Some(rested), true) = (record.rested, (first..=today).contains(&day)) {
+            insights.breaks += 1;
+            insights.worked_through += u64::from(!rested);
+        }
+    }
+    for record in records.iter().filter(|r| r.kind == PhaseKind::Focus) {
+        let day = record.day();
+        let mut week = Totals::default();
+        week.add(record);
+        match today - day {
+            0..7 => insights.this_week_secs += week.focus_secs,
+            7..14 => insights.last_week_secs += week.focus_secs,
+            _ => {}
+        }
+        if day < first || day > today {
+            continue;
+        }
+        insights.focus += 1;
+        if record.completed {
+            let hour = record.seconds_of_day() / 3600;
+            insights.by_hour[hour as usize] += 1;
+            insights.by_weekday[wallclock::weekday(day)] += 1;
+        } else {
+            insights.skipped += 1;
+        }
+        if !record.pauses.is_empty() || !record.snoozes.is_empty() {
+            insights.paused += 1;
+        }
+        if let Some(rating) = record.rating {
+            let part = match record.seconds_of_day() / 3600 {
+                0..12 => 0,
+                12..17 => 1,
+                _ => 2,
+            };
+            insights.ratings[part].0 += u64::from(rating);
+            insights.ratings[part].1 += 1;
+        }
+    }
+    insights
+}
+
+pub fn render_insights(insights: &Insights, days: u64) -> String {
+    if insights.focus == 0 && insights.this_week_secs + insights.last_week_secs == 0 {
+        return format!("No focus sessions in the last {days} days\n");
+    }
+    let percent = |part: u64| part * 100 / insights.focus.max(1);
+    let hours: Vec<_> = insights
+        .best_hours()
+        .iter()
+        .map(|h| format!("{h:02}:00 ({})", insights.by_hour[*h]))
+        .collect();
+    let weekdays: Vec<_> = WEEKDAYS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| insights.weekdays[*i] > 0)
+        .map(|(i, name)| {
+            let average = insights.by_weekday[i] as f64 / insights.weekdays[i] as f64;
+            format!("{name} {average:.1}")
+        })
+        .collect();
+    let trend = match (insights.this_week_secs, insights.last_week_secs) {
+        (_, 0) => "nothing the week before".to_string(),
+        (now, before) if now >= before => format!("up {}%", (now - before) * 100 / before),
+        (now, before) => format!("down {}%", (before - now) * 100 / before),
+    };
+    let mut out = String::new();
+    if !hours.is_empty() {
+        out.push_str(&format!("{:<18}{}\n", "Best hours", hours.join("  ")));
+    }
+    out.push_str(&format!(
+        "{:<18}{}\n",
+        "Sessions per day",
+        weekdays.join("  ")
+    ));
+    out.push_str(&format!(
+        "{:<18}{}% of {} focus sessions\n",
+        "Skipped",
+        percent(insights.skipped),
+        insights.focus
+    ));
+    out.push_str(&format!(
+        "{:<18}{}% paused or snoozed\n",
+        "Interrupted",
+        percent(insights.paused)
+    ));
+    if let Some(worked) = (insights.worked_through * 100).checked_div(insights.breaks) {
+        out.push_str(&format!(
+            "{:<18}you worked through {worked}% of {} breaks\n",
+            "Breaks", insights.breaks
+        ));
+    }
+    // Label in the averages, and how the best one reads in a sentence
+    let parts = [
+        ("before noon", "before noon"),
+        ("afternoon", "in the afternoon"),
+        ("evening", "in the evening"),
+    ];
+    let rated: Vec<_> = parts
+        .iter()
+        .zip(insights.ratings)
+        .filter(|(_, (_, count))| *count > 0)
+        .map(|(part, (sum, count))| (part, sum as f64 / count as f64))
+        .collect();
+    if !rated.is_empty() {
+        let averages: Vec<_> = rated
+            .iter()
+            .map(|((label, _), average)| format!("{label} {average:.1}"))
+            .collect();
+        out.push_str(&format!("{:<18}{}", "Ratings", averages.join("  ")));
+        if rated.len() > 1
+            && let Some(((_, when), _)) = rated.iter().max_by(|a, b| a.1.total_cmp(&b.1))
+        {
+            out.push_str(&format!(", you focus best {when}"));
+        }
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "{:<18}{}, {trend} on the week before\n",
+        "This week",
+        hours_minutes(insights.this_week_secs)
+    ));
+    out
+}
+
+pub fn hours_minutes(secs: u64) -> String {
+    format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn focus(start: u64, minutes: u64, project: Option<&str>) -> SessionRecord {
+        SessionRecord {
+            planned_secs: 1500,
+            project: project.map(str::to_string),
+            ..SessionRecord::test_focus(start, minutes * 60)
+        }
+    }
+
+    fn rest(start: u64, rested: bool) -> SessionRecord {
+        SessionRecord {
+            kind: PhaseKind::ShortBreak,
+            rested: Some(rested),
+            ..focus(start, 5, None)
+        }
+    }
+
+    #[test]
+    fn totals_today_and_period_by_project() {
+        let now = 1_700_000_000 - wallclock::seconds_of_local_day(1_700_000_000) + 12 * 3600;
+        let mut paused = focus(now - 3600, 25, Some("thesis"));
+        paused.pauses = vec![(now - 3000, now - 2820)];
+        paused.snoozes = vec![(now - 2820, now - 2700)];
+        paused.distractions = vec![("Slack".to_string(), 300), ("YouTube".to_string(), 60)];
+        let mut distracted = focus(now - 86_400, 50, None);
+        distracted.distractions = vec![("Slack".to_string(), 600)];
+        let records = [
+            paused,
+            distracted,
+            focus(now - 10 * 86_400, 25, Some("thesis")),
+            SessionRecord {
+                kind: PhaseKind::ShortBreak,
+                ..focus(now - 600, 5, Some("thesis"))
+            },
+        ];
+        let summary = summarize(&records, now, 7);
+        assert_eq!(
+            summary.today,
+            Totals {
+                completed: 1,
+                focus_secs: 20 * 60
+            }
+        );
+        assert_eq!(summary.period.completed, 2);
+        assert_eq!(summary.period.focus_secs, 70 * 60);
+        assert_eq!(summary.by_project["(none)"].focus_secs, 50 * 60);
+        let text = render(&summary, 7);
+        assert!(
+            text.starts_with("Today              1 sessions    0h 20m\n"),
+            "{text}"
+        );
+        assert!(text.contains("  thesis"), "{text}");
+        assert!(
+            text.ends_with("Distractions\n  Slack            2 sessions    0h 15m\n  YouTube          1 sessions    0h 01m\n"),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn compares_tasks_with_their_estimates() {
+        let now = 1_700_000_000 - wallclock::seconds_of_local_day(1_700_000_000) + 12 * 3600;
+        let on = |start, task: &str, estimate| SessionRecord {
+            task: Some(task.to_string()),
+            estimate,
+            ..focus(start, 25, None)
+        };
+        let mut stopped = on(now - 600, "Docs", Some(3));
+        stopped.completed = false;
+        let records = [
+            // Estimated before the period, finished in it
+            on(now - 20 * 86_400, "Write intro", Some(4)),
+            on(now - 86_400, "Write intro", None),
+            on(now - 7200, "Write intro", Some(2)),
+            on(now - 3600, "Docs", Some(3)),
+            stopped,
+            on(now - 3600, "Unplanned", None),
+            on(now - 30 * 86_400, "Old", Some(1)),
+        ];
+        let summary = summarize(&records, now, 7);
+        assert_eq!(
+            summary.estimates,
+            [
+                Estimate {
+                    task: "Write intro".to_string(),
+                    estimated: 2,
+                    actual: 3,
+                },
+                Estimate {
+                    task: "Docs".to_string(),
+                    estimated: 3,
+                    actual: 1,
+                },
+            ]
+        );
+        let text = render(&summary, 7);
+        assert!(
+            text.ends_with(
+                "Estimates, off by 60% on average\n  Write intro      3 sessions    2 estimated\n  Docs             1 sessions    3 estimated\n"
+            ),
+            "{text}"
+        );
+    }
+
+    #[test]
+    fn draws_the_focus_score_trend() {
+        assert_eq!(render_scores(&[None, None]), "");
+        assert_eq!(
+            render_scores(&[Some(0), None, Some(50), Some(100)]),
+            "\nFocus score     100 today, 50 on average\n  ▁·▄█\n"
+        );
+        assert_eq!(
+            render_scores(&[Some(70), None]),
+            "\nFocus score     70 on average\n  ▅·\n"
+        );
+    }
+
+    #[test]
+    fn summarizes_a_run_with_a_bar_per_phase() {
+        let start = 1_700_000_000 - wallclock::seconds_of_local_day(1_700_000_000) + 9 * 3600;
+        let mut stopped = focus(start + 1800, 10, Some("thesis"));
+        stopped.completed = false;
+        stopped.task = Some("Write intro".to_string());
+        let short = SessionRecord {
+            planned_secs: 300,
+            ..rest(start + 1500, true)
+        };
+        let text = render_run(&[focus(start, 25, None), short, stopped]);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(
+            lines[0],
+            "09:00–09:25  Focus        ████████████████████   25:00  done"
+        );
+        assert_eq!(
+            lines[1],
+            "09:25–09:30  Short Break  ████████████████████    5:00  done"
+        );
+        assert_eq!(
+            lines[2],
+            "09:30–09:40  Focus        ████████░░░░░░░░░░░░   10:00  stopped  thesis · Write intro"
+        );
+        assert_eq!(
+            lines[4],
+            "1 focus sessions finished, 0h 35m in focus, 1 breaks taken"
+        );
+    }
+
+    #[test]
+    fn finds_patterns_and_the_weekly_trend() {
+        let midnight = 1_700_000_000 - wallclock::seconds_of_local_day(1_700_000_000);
+        let now = midnight + 18 * 3600;
+        let mut skipped = focus(midnight + 14 * 3600, 10, None);
+        skipped.completed = false;
+        skipped.pauses = vec![(midnight + 14 * 3600 + 60, midnight + 14 * 3600 + 120)];
+        let mut rated = focus(midnight + 9 * 3600, 25, None);
+        rated.rating = Some(5);
+        let records = [
+            rated,
+            focus(midnight + 9 * 3600 + 1800, 25, None),
+            skipped,
+            SessionRecord {
+                rating: Some(2),
+                extended_secs: 0,
+                ..focus(midnight + 15 * 3600 - 86_400, 25, None)
+            },
+            focus(midnight + 9 * 3600 - 8 * 86_400, 100, None),
+            rest(midnight + 10 * 3600, true),
+            rest(midnight + 11 * 3600, false),
+            rest(midnight + 12 * 3600, false),
+            rest(midnight + 13 * 3600, true),
+            rest(midnight + 16 * 3600, false),
+            // Before the period
+            rest(midnight - 10 * 86_400, false),
+        ];
+        let insights = insights(&records, now, 7);
+        assert_eq!(insights.best_hours(), vec![9, 15]);
+        assert_eq!(insights.ratings, [(5, 1), (2, 1), (0, 0)]);
+        assert_eq!(insights.focus, 4);
+        assert_eq!((insights.skipped, insights.paused), (1, 1));
+        assert_eq!(insights.weekdays, [1; 7]);
+        assert_eq!(wallclock::weekday(0), 3, "1970-01-01 was a Thursday");
+        assert_eq!(
+            insights.by_weekday[wallclock::weekday(wallclock::local_day(now))],
+            2
+        );
+        assert_eq!(insights.this_week_secs, 84 * 60);
+        assert_eq!(insights.last_week_secs, 100 * 60);
+        assert_eq!((insights.breaks, insights.worked_through), (5, 3));
+        let text = render_insights(&insights, 7);
+        assert!(
+            text.contains("Best hours        09:00 (2)  15:00 (1)\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("before noon 5.0  afternoon 2.0, you focus best before noon\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("Skipped           25% of 4 focus sessions\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("Breaks            you worked through 60% of 5 breaks\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("1h 24m, down 16% on the week before"),
+            "{text}"
+        );
+    }
+}
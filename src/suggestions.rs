@@ -0,0 +1,80 @@
+//! Something to do during a break, picked at random so one break isn't like
+//! the next: a few built in, plus any from `[breaks] suggestions` in the config.
+
+use std::time::Duration;
+
+use crate::args::Args;
+use crate::i18n::t;
+
+/// Locale keys of the built-in suggestions.
+const BUILT_IN: [&str; 6] = [
+    "suggest.stand",
+    "suggest.eyes",
+    "suggest.water",
+    "suggest.shoulders",
+    "suggest.walk",
+    "suggest.window",
+];
+
+/// The 20-20-20 rule: look 20 feet away for 20 seconds.
+pub const EYE_REST: Duration = Duration::from_secs(20);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Suggestions {
+    items: Vec<String>,
+}
+
+impl Suggestions {
+    pub fn from_args(args: &Args) -> Self {
+        let built_in = BUILT_IN.iter().map(|key| t(key).to_string());
+        let items = built_in
+            .chain(args.suggestions.iter().map(|s| s.trim().to_string()))
+            .filter(|s| !s.is_empty())
+            .collect();
+        Self { items }
+    }
+
+    /// A suggestion chosen by `seed`, other than `last` so two breaks in a row
+    /// never ask for the same thing.
+    pub fn pick(&self, seed: u64, last: Option<&str>) -> Option<&str> {
+        let choices: Vec<_> = self
+            .items
+            .iter()
+            .filter(|s| Some(s.as_str()) != last || self.items.len() == 1)
+            .collect();
+        let n = choices.len() as u64;
+        (n > 0).then(|| choices[(mix(seed) % n) as usize].as_str())
+    }
+}
+
+/// SplitMix64: spreads nearby seeds, like consecutive seconds, far apart.
+fn mix(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::tests::make_args;
+
+    #[test]
+    fn adds_config_suggestions_and_avoids_repeats() {
+        let mut args = make_args();
+        args.suggestions = vec!["Water the plants.".to_string(), " ".to_string()];
+        let suggestions = Suggestions::from_args(&args);
+        assert_eq!(suggestions.items.len(), BUILT_IN.len() + 1);
+        assert!(suggestions.items.contains(&"Water the plants.".to_string()));
+        for seed in 0..50 {
+            let first = suggestions.pick(seed, None).unwrap();
+            assert_ne!(suggestions.pick(seed, Some(first)), Some(first));
+        }
+        let one = Suggestions {
+            items: vec!["Stretch.".to_string()],
+        };
+        assert_eq!(one.pick(7, Some("Stretch.")), Some("Stretch."));
+        assert_eq!(Suggestions::default().pick(7, None), None);
+    }
+}
@@ -0,0 +1,158 @@
+//! Just enough of the ustar format for `backup`: regular files with their
+//! permissions, readable by `tar` everywhere. Directories come from the paths.
+
+/// Each header and each file's data is padded to whole blocks.
+const BLOCK: usize = 512;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// Relative, separated by `/`.
+    pub path: String,
+    pub mode: u32,
+    /// Unix seconds.
+    pub mtime: u64,
+    pub data: Vec<u8>,
+}
+
+/// An archive of `entries`, or the path that doesn't fit in a header.
+pub fn write(entries: &[Entry]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    for entry in entries {
+        out.extend(header(entry).ok_or_else(|| format!("{}: path too long", entry.path))?);
+        out.extend(&entry.data);
+        out.resize(out.len().next_multiple_of(BLOCK), 0);
+    }
+    // Two empty blocks end the archive
+    out.resize(out.len() + 2 * BLOCK, 0);
+    Ok(out)
+}
+
+fn header(entry: &Entry) -> Option<[u8; BLOCK]> {
+    // Names over 100 bytes are split at a `/` into the 155-byte prefix field
+    let path = entry.path.as_bytes();
+    let (prefix, name) = if path.len() <= 100 {
+        (&[][..], path)
+    } else {
+        let split = (0..path.len())
+            .rev()
+            .find(|&i| path[i] == b'/' && i <= 155 && path.len() - i - 1 <= 100)?;
+        (&path[..split], &path[split + 1..])
+    };
+    let mut block = [0u8; BLOCK];
+    let mut put = |at: usize, bytes: &[u8]| block[at..at + bytes.len()].copy_from_slice(bytes);
+    put(0, name);
+    put(100, format!("{:07o}\0", entry.mode & 0o7777).as_bytes());
+    put(108, b"0000000\0");
+    put(116, b"0000000\0");
+    put(124, format!("{:011o}\0", entry.data.len()).as_bytes());
+    put(136, format!("{:011o}\0", entry.mtime).as_bytes());
+    put(148, b"        ");
+    put(156, b"0");
+    put(257, b"ustar\0");
+    put(263, b"00");
+    put(345, prefix);
+    let sum: u32 = block.iter().map(|b| u32::from(*b)).sum();
+    block[148..156].copy_from_slice(format!("{sum:06o}\0 ").as_bytes());
+    Some(block)
+}
+
+/// The regular files in an archive; directories, links and the like are
+/// skipped.
+pub fn read(bytes: &[u8]) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    let mut at = 0;
+    while let Some(block) = bytes.get(at..at + BLOCK) {
+        if block.iter().all(|b| *b == 0) {
+            return Ok(entries);
+        }
+        let octal = |range: std::ops::Range<usize>| {
+            let field = String::from_utf8_lossy(&block[range]);
+            let digits = field.trim_matches(|c: char| c == '\0' || c == ' ');
+            u64::from_str_radix(digits, 8).map_err(|_| format!("bad header at byte {at}"))
+        };
+        let stored = octal(148..156)?;
+        let sum: u64 = block
+            .iter()
+            .enumerate()
+            .map(|(i, b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u64::from(*b)
+                }
+            })
+            .sum();
+        if sum != stored {
+            return Err(format!("bad checksum at byte {at}"));
+        }
+        let size = octal(124..136)? as usize;
+        let data_at = at + BLOCK;
+        let data = bytes
+            .get(data_at..data_at + size)
+            .ok_or_else(|| "archive cut short".to_string())?;
+        if matches!(block[156], b'0' | 0) {
+            let text = |range: std::ops::Range<usize>| {
+                let field = &block[range];
+                let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+                String::from_utf8_lossy(&field[..end]).into_owned()
+            };
+            let (prefix, name) = (text(345..500), text(0..100));
+            entries.push(Entry {
+                path: if prefix.is_empty() {
+                    name
+                } else {
+                    format!("{prefix}/{name}")
+                },
+                mode: octal(100..108)? as u32,
+                mtime: octal(136..148)?,
+                data: data.to_vec(),
+            });
+        }
+        at = data_at + size.next_multiple_of(BLOCK);
+    }
+    Err("archive cut short".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let long = format!("rusty_pomo/{}plugin.sh", "nested/".repeat(20));
+        let entries = vec![
+            Entry {
+                path: "rusty_pomo/config/config.toml".to_string(),
+                mode: 0o644,
+                mtime: 1_700_000_000,
+                data: b"[timer]\nfocus = 25\n".to_vec(),
+            },
+            Entry {
+                path: long,
+                mode: 0o755,
+                mtime: 0,
+                data: vec![b'x'; BLOCK],
+            },
+            Entry {
+                path: "empty".to_string(),
+                mode: 0o600,
+                mtime: 5,
+                data: Vec::new(),
+            },
+        ];
+        let archive = write(&entries).unwrap();
+        assert_eq!(archive.len(), BLOCK * (3 + 2 + 2));
+        assert_eq!(&archive[257..265], b"ustar\x0000");
+        assert_eq!(read(&archive).unwrap(), entries);
+
+        let mut broken = archive.clone();
+        broken[0] = b'X';
+        assert!(read(&broken).unwrap_err().contains("checksum"));
+        assert!(read(&archive[..BLOCK + 10]).is_err());
+        let too_long = Entry {
+            path: "x".repeat(120),
+            ..entries[2].clone()
+        };
+        assert!(write(&[too_long]).is_err());
+    }
+}
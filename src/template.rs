@@ -0,0 +1,222 @@
+//! `template export` and `template import`: the shareable part of a config file,
+//! durations, theme and sounds, as a file of its own that can be merged into
+//! another config.
+
+use toml_edit::{ImDocument, Item, TableLike};
+
+use crate::config::{self, Config};
+
+/// The template format written here. Older versions are read as well.
+pub const VERSION: i64 = 1;
+
+/// What a template carries: tables and their keys, or the whole table for `None`.
+const SHARED: &[(&str, Option<&[&str]>)] = &[
+    (
+        "timer",
+        Some(&[
+            "focus",
+            "short",
+            "long",
+            "long_every",
+            "warm_up",
+            "cycle_review",
+            "theme",
+            "mascot",
+            "counter_icon",
+            "notification_sound",
+        ]),
+    ),
+    ("ambient", Some(&["focus", "break", "volume"])),
+    ("profiles", None),
+    ("schedule", None),
+];
+
+/// One shared `key = value`, with the key and value as written in the file.
+#[derive(Debug, PartialEq, Eq)]
+struct Setting {
+    table: String,
+    key: String,
+    value: String,
+}
+
+/// The shared settings of `config` as a template, stamped with the format version
+/// and `name`.
+pub fn export(config: &str, name: Option<&str>) -> Result<String, String> {
+    Config::parse(config)?;
+    let settings = shared(config)?;
+    if settings.is_empty() {
+        return Err("the config has no durations, theme or sounds to share".to_string());
+    }
+    let mut out = format!("[template]\nversion = {VERSION}\n");
+    if let Some(name) = name {
+        out.push_str(&format!("name = {}\n", config::quote(name)));
+    }
+    let mut table = "template";
+    for setting in &settings {
+        if setting.table != table {
+            table = &setting.table;
+            out.push_str(&format!("\n[{table}]\n"));
+        }
+        out.push_str(&format!("{} = {}\n", setting.key, setting.value));
+    }
+    Ok(out)
+}
+
+/// `config` with the template's settings merged in: each key in the template
+/// replaces the same key in the config, and everything else stays as written.
+/// Returns the new contents and the keys that changed, such as `timer.focus`.
+pub fn import(config: &str, template: &str) -> Result<(String, Vec<String>), String> {
+    let doc = ImDocument::parse(template).map_err(|e| format!("{e}"))?;
+    let version = doc
+        .get("template")
+        .and_then(|t| t.get("version"))
+        .and_then(Item::as_integer)
+        .ok_or("not a rusty_pomo template: it has no [template] version")?;
+    if !(1..=VERSION).contains(&version) {
+        return Err(format!(
+            "template version {version} needs a newer rusty_pomo; this one reads up to {VERSION}"
+        ));
+    }
+    Config::parse(template).map_err(|e| format!("invalid template: {e}"))?;
+    let current = shared(config)?;
+    let mut merged = config.to_string();
+    let mut changed = Vec::new();
+    for setting in shared(template)? {
+        if current.contains(&setting) {
+            continue;
+        }
+        let value = [(setting.key.as_str(), setting.value.clone())];
+        merged = config::set_values(&merged, &setting.table, &value);
+        changed.push(format!("{}.{}", setting.table, setting.key));
+    }
+    Config::parse(&merged)?;
+    Ok((merged, changed))
+}
+
+/// The settings in `text` that templates carry, in file order.
+fn shared(text: &str) -> Result<Vec<Setting>, String> {
+    let doc = ImDocument::parse(text).map_err(|e| format!("{e}"))?;
+    let mut settings = Vec::new();
+    for (name, keys) in SHARED {
+        let Some(table) = doc.get(name).and_then(Item::as_table_like) else {
+            continue;
+        };
+        for (key, item) in table.iter() {
+            if keys.is_some_and(|keys| !keys.contains(&key)) {
+                continue;
+            }
+            match item.as_table() {
+                // A table within, such as [profiles.deep]
+                Some(inner) => {
+                    let table = format!("{name}.{}", written_key(text, table, key));
+                    collect(text, &table, inner, &mut settings);
+                }
+                None => collect_one(text, name, table, key, item, &mut settings),
+            }
+        }
+    }
+    Ok(settings)
+}
+
+fn collect(text: &str, name: &str, table: &dyn TableLike, settings: &mut Vec<Setting>) {
+    for (key, item) in table.iter() {
+        collect_one(text, name, table, key, item, settings);
+    }
+}
+
+fn collect_one(
+    text: &str,
+    name: &str,
+    table: &dyn TableLike,
+    key: &str,
+    item: &Item,
+    settings: &mut Vec<Setting>,
+) {
+    if let Some(span) = item.span() {
+        settings.push(Setting {
+            table: name.to_string(),
+            key: written_key(text, table, key),
+            value: text[span].to_string(),
+        });
+    }
+}
+
+/// A key as it appears in the file, quotes included.
+fn written_key(text: &str, table: &dyn TableLike, key: &str) -> String {
+    table
+        .key(key)
+        .and_then(|k| k.span())
+        .map_or_else(|| key.to_string(), |span| text[span].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"# Mine
+[timer]
+focus = 50
+theme = "dracula"
+notifications = false
+
+[profiles.deep]
+focus = "90m"
+
+[schedule]
+"fri,sat" = "deep"
+
+[hue]
+username = "secret"
+"#;
+
+    #[test]
+    fn exports_only_what_is_shared() {
+        let template = export(CONFIG, Some("team")).unwrap();
+        assert_eq!(
+            template,
+            r#"[template]
+version = 1
+name = "team"
+
+[timer]
+focus = 50
+theme = "dracula"
+
+[profiles.deep]
+focus = "90m"
+
+[schedule]
+"fri,sat" = "deep"
+"#
+        );
+        assert!(export("[hue]\nbridge = \"x\"\n", None).is_err());
+    }
+
+    #[test]
+    fn merges_a_template_into_the_config() {
+        let template = "[template]\nversion = 1\n\n[timer]\nfocus = 25\ntheme = \"dracula\"\n\n[ambient]\nvolume = 40\n";
+        let (merged, changed) = import(CONFIG, template).unwrap();
+        assert_eq!(changed, ["timer.focus", "ambient.volume"]);
+        assert!(
+            merged.starts_with("# Mine\n[timer]\nfocus = 25\n"),
+            "{merged}"
+        );
+        assert!(merged.contains("notifications = false"), "{merged}");
+        assert!(merged.ends_with("[ambient]\nvolume = 40\n"), "{merged}");
+        let config = Config::parse(&merged).unwrap();
+        assert_eq!(config.hue.username.as_deref(), Some("secret"));
+        // Round trip: importing an export changes nothing
+        let (_, changed) = import(CONFIG, &export(CONFIG, None).unwrap()).unwrap();
+        assert!(changed.is_empty());
+
+        let err = import(CONFIG, "[timer]\nfocus = 25\n").unwrap_err();
+        assert_eq!(
+            err,
+            "not a rusty_pomo template: it has no [template] version"
+        );
+        let err = import(CONFIG, "[template]\nversion = 2\n").unwrap_err();
+        assert!(err.starts_with("template version 2 needs a newer"), "{err}");
+        let err = import(CONFIG, "[template]\nversion = 1\n[timer]\nfocus = -1\n").unwrap_err();
+        assert!(err.starts_with("invalid template: line 4"), "{err}");
+    }
+}
@@ -1,7 +1,20 @@
 use clap::ValueEnum;
 use ratatui::style::Color;
+use std::env;
 
-#[derive(Copy, Clone, Debug, ValueEnum)]
+use crate::doctor::Probe;
+
+/// Stands in for the phase colors outside `[work_hours]`: an evening amber that
+/// reads the same in every theme.
+pub const OFF_HOURS: Color = Color::Rgb(255, 140, 66);
+
+/// The `[lunch]` pause's color, apart from the breaks in every theme.
+pub const LUNCH: Color = Color::Rgb(139, 233, 253);
+
+/// The `--warm-up` phase's color, a morning yellow apart from focus in every theme.
+pub const WARM_UP: Color = Color::Rgb(241, 250, 140);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
 pub enum Theme {
     Dracula,
     SolarizedDark,
@@ -9,6 +22,22 @@ pub enum Theme {
 }
 
 impl Theme {
+    /// Name as written in `--theme` and the config file.
+    pub fn key(self) -> &'static str {
+        match self {
+            Theme::Dracula => "dracula",
+            Theme::SolarizedDark => "solarized-dark",
+            Theme::GruvboxDark => "gruvbox-dark",
+        }
+    }
+
+    /// The theme `step` places after this one, wrapping around.
+    pub fn cycle(self, step: isize) -> Self {
+        let all = Self::value_variants();
+        let at = all.iter().position(|t| *t == self).unwrap_or(0) as isize;
+        all[(at + step).rem_euclid(all.len() as isize) as usize]
+    }
+
     pub fn colors(self) -> (Color, Color, Color) {
         match self {
             Theme::Dracula => (
@@ -29,3 +58,79 @@ impl Theme {
         }
     }
 }
+
+/// The nearest color in the 256-color palette, for terminals without 24-bit
+/// color; others are left alone.
+pub fn indexed(color: Color) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |c: u8| (0..6).min_by_key(|i| LEVELS[*i].abs_diff(c)).unwrap_or(0);
+    let distance = |(x, y, z): (u8, u8, u8)| {
+        [(x, r), (y, g), (z, b)]
+            .iter()
+            .map(|(a, b)| u32::from(a.abs_diff(*b)).pow(2))
+            .sum::<u32>()
+    };
+    let cube = (level(r), level(g), level(b));
+    let cube_distance = distance((LEVELS[cube.0], LEVELS[cube.1], LEVELS[cube.2]));
+    // The 24 grays run from 8 to 238 in steps of 10
+    let mean = (u32::from(r) + u32::from(g) + u32::from(b)) / 3;
+    let gray = (mean.saturating_sub(3) / 10).min(23) as u8;
+    let shade = 8 + 10 * gray;
+    if distance((shade, shade, shade)) < cube_distance {
+        Color::Indexed(232 + gray)
+    } else {
+        Color::Indexed(16 + 36 * cube.0 as u8 + 6 * cube.1 as u8 + cube.2 as u8)
+    }
+}
+
+/// The themes are 24-bit colors, which some terminals can't show.
+pub fn probe() -> Probe {
+    probe_with(env::var("COLORTERM").ok(), env::var("TERM").ok())
+}
+
+fn probe_with(colorterm: Option<String>, term: Option<String>) -> Probe {
+    match (colorterm.as_deref(), term.as_deref()) {
+        (Some("truecolor" | "24bit"), _) => Probe::Ok("24-bit color".to_string()),
+        (_, Some(term)) if term.contains("256color") => Probe::Warn(
+            "only 256 colors advertised; themes may look off. If the terminal does 24-bit color, set COLORTERM=truecolor".to_string(),
+        ),
+        _ => Probe::Warn(
+            "the terminal doesn't advertise 24-bit color (COLORTERM); themes may look off".to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycles_both_ways_and_keys_match_the_flag() {
+        assert_eq!(Theme::Dracula.cycle(1), Theme::SolarizedDark);
+        assert_eq!(Theme::Dracula.cycle(-1), Theme::GruvboxDark);
+        assert_eq!(Theme::GruvboxDark.cycle(1), Theme::Dracula);
+        for theme in Theme::value_variants() {
+            assert_eq!(Theme::from_str(theme.key(), false), Ok(*theme));
+        }
+    }
+
+    #[test]
+    fn probes_color_support_from_the_environment() {
+        let ok = probe_with(Some("truecolor".into()), Some("xterm-256color".into()));
+        assert_eq!(ok, Probe::Ok("24-bit color".to_string()));
+        let limited = probe_with(None, Some("xterm-256color".into()));
+        assert!(matches!(limited, Probe::Warn(s) if s.contains("COLORTERM=truecolor")));
+    }
+
+    #[test]
+    fn falls_back_to_the_nearest_indexed_color() {
+        // Dracula's background is nearly gray, its accent a lavender
+        assert_eq!(indexed(Color::Rgb(40, 42, 54)), Color::Indexed(236));
+        assert_eq!(indexed(Color::Rgb(189, 147, 249)), Color::Indexed(141));
+        assert_eq!(indexed(Color::Rgb(255, 0, 0)), Color::Indexed(196));
+        assert_eq!(indexed(Color::White), Color::White);
+    }
+}
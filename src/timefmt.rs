@@ -0,0 +1,192 @@
+//! How durations are written: the timer's clock face and the spelled-out form
+//! used by announcements.
+
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use crate::i18n::{catalog, t, tf};
+
+const HOUR: u64 = 3600;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum TimeFormat {
+    /// MM:SS, or HH:MM:SS for phases an hour or longer
+    #[default]
+    Auto,
+    Mmss,
+    Hhmmss,
+    /// "25 min left"
+    Human,
+}
+
+/// The remaining time of a phase lasting `total`, as the timer shows it.
+pub fn clock(remaining: Duration, total: Duration, format: TimeFormat) -> String {
+    let separator = t("time.separator");
+    match format {
+        TimeFormat::Auto if total.as_secs() >= HOUR => hh_mm_ss(remaining, separator),
+        TimeFormat::Auto | TimeFormat::Mmss => mm_ss(remaining, separator),
+        TimeFormat::Hhmmss => hh_mm_ss(remaining, separator),
+        TimeFormat::Human => tf("time.left", &[("duration", &compact(remaining))]),
+    }
+}
+
+/// Minutes keep counting past 59: an hour and a half reads `90:00`.
+pub fn mm_ss(d: Duration, separator: &str) -> String {
+    let total = d.as_secs();
+    format!("{:02}{separator}{:02}", total / 60, total % 60)
+}
+
+pub fn hh_mm_ss(d: Duration, separator: &str) -> String {
+    let total = d.as_secs();
+    format!(
+        "{:02}{separator}{:02}{separator}{:02}",
+        total / HOUR,
+        total % HOUR / 60,
+        total % 60
+    )
+}
+
+/// "1 h 5 min", "25 min", "45 s". Minutes round up, so a countdown never reads
+/// "0 min" while time is left.
+pub fn compact(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs < 60 {
+        return tf("time.seconds", &[("n", &secs.to_string())]);
+    }
+    let minutes = secs.div_ceil(60);
+    if minutes < 60 {
+        return tf("time.minutes", &[("n", &minutes.to_string())]);
+    }
+    tf(
+        "time.hours_minutes",
+        &[
+            ("h", &(minutes / 60).to_string()),
+            ("m", &(minutes % 60).to_string()),
+        ],
+    )
+}
+
+/// Parses `25m`, `90s`, `2m30s` or `1h15m`; a bare number is minutes, as before.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    if let Ok(minutes) = value.parse::<u64>() {
+        return minutes
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or_else(|| format!("`{value}` is too long"));
+    }
+    let invalid = || format!("`{value}` is not a duration like 25m, 90s or 1h30m");
+    let mut total: u64 = 0;
+    let mut digits = String::new();
+    let mut last_unit = u64::MAX;
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => HOUR,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        // Units must come largest first and only once, so `30s5m` is rejected.
+        if digits.is_empty() || unit >= last_unit {
+            return Err(invalid());
+        }
+        let n: u64 = digits.parse().map_err(|_| invalid())?;
+        total = n
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        digits.clear();
+        last_unit = unit;
+    }
+    if !digits.is_empty() || last_unit == u64::MAX {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// "25 minutes", "1 minute 30 seconds", "45 seconds", in the current language.
+pub fn spoken(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let catalog = catalog();
+    match (secs / 60, secs % 60) {
+        (0, s) => catalog.plural("duration.seconds", s),
+        (m, 0) => catalog.plural("duration.minutes", m),
+        (m, s) => catalog.format(
+            "duration.joiner",
+            &[
+                ("minutes", &catalog.plural("duration.minutes", m)),
+                ("seconds", &catalog.plural("duration.seconds", s)),
+            ],
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn formats_mm_ss() {
+        assert_eq!(mm_ss(secs(0), ":"), "00:00");
+        assert_eq!(mm_ss(secs(59), ":"), "00:59");
+        assert_eq!(mm_ss(secs(125), ":"), "02:05");
+        assert_eq!(mm_ss(secs(5400), "."), "90.00");
+    }
+
+    #[test]
+    fn switches_to_hours_for_long_phases() {
+        let hour_and_half = secs(5400);
+        assert_eq!(
+            clock(secs(3725), hour_and_half, TimeFormat::Auto),
+            "01:02:05"
+        );
+        assert_eq!(
+            clock(secs(125), hour_and_half, TimeFormat::Auto),
+            "00:02:05"
+        );
+        assert_eq!(clock(secs(125), secs(1500), TimeFormat::Auto), "02:05");
+        assert_eq!(clock(secs(125), secs(1500), TimeFormat::Hhmmss), "00:02:05");
+        assert_eq!(clock(secs(3725), hour_and_half, TimeFormat::Mmss), "62:05");
+    }
+
+    #[test]
+    fn formats_human_durations() {
+        assert_eq!(
+            clock(secs(1500), secs(1500), TimeFormat::Human),
+            "25 min left"
+        );
+        assert_eq!(
+            clock(secs(1441), secs(1500), TimeFormat::Human),
+            "25 min left"
+        );
+        assert_eq!(clock(secs(45), secs(1500), TimeFormat::Human), "45 s left");
+        assert_eq!(
+            clock(secs(3900), secs(5400), TimeFormat::Human),
+            "1 h 5 min left"
+        );
+        assert_eq!(spoken(secs(1500)), "25 minutes");
+        assert_eq!(spoken(secs(90)), "1 minute 30 seconds");
+        assert_eq!(spoken(secs(1)), "1 second");
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("25"), Ok(secs(1500)));
+        assert_eq!(parse_duration("25m"), Ok(secs(1500)));
+        assert_eq!(parse_duration("90s"), Ok(secs(90)));
+        assert_eq!(parse_duration("2m30s"), Ok(secs(150)));
+        assert_eq!(parse_duration("1h15m"), Ok(secs(4500)));
+        for bad in ["", "m", "25x", "5m10", "30s5m", "1m1m", "-5m"] {
+            assert!(parse_duration(bad).is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::widgets::Widget;
+
+use crate::history::SessionRecord;
+use crate::phases::CustomPhase;
+use crate::state::PhaseKind;
+use crate::wallclock;
+
+const HOUR: u64 = 3600;
+
+/// Horizontal time axis with one colored bar per recorded phase and hour labels underneath.
+pub struct Timeline<'a> {
+    pub records: &'a [SessionRecord],
+    pub now: u64,
+    pub focus: Color,
+    pub rest: Color,
+    pub pause: Color,
+    pub lunch: Color,
+    pub warm_up: Color,
+    /// Colors of the `[phases]` that have one; the others are drawn as rest.
+    pub phases: &'a [CustomPhase],
+}
+
+impl Timeline<'_> {
+    /// Whole hours covering every record up to `now`.
+    pub fn range(&self) -> (u64, u64) {
+        let first = self
+            .records
+            .iter()
+            .map(|r| r.start)
+            .min()
+            .unwrap_or(self.now)
+            .min(self.now);
+        let start = first - wallclock::seconds_of_local_day(first) % HOUR;
+        let end = self.now + (HOUR - wallclock::seconds_of_local_day(self.now) % HOUR) % HOUR;
+        (start, end.max(start + HOUR))
+    }
+
+    /// Color of the timeline at `t`, if anything was recorded then.
+    pub fn color_at(&self, t: u64) -> Option<Color> {
+        let record = self
+            .records
+            .iter()
+            .rev()
+            .find(|r| r.start <= t && t < r.end)?;
+        let mut stopped = record.pauses.iter().chain(&record.snoozes);
+        if stopped.any(|(s, e)| *s <= t && t < *e) {
+            return Some(self.pause);
+        }
+        Some(match record.kind {
+            PhaseKind::WarmUp => self.warm_up,
+            PhaseKind::Focus => self.focus,
+            PhaseKind::ShortBreak | PhaseKind::LongBreak | PhaseKind::CycleReview => self.rest,
+            PhaseKind::Lunch => self.lunch,
+            PhaseKind::Custom(_) => self
+                .phases
+                .iter()
+                .find(|phase| phase.kind == record.kind)
+                .and_then(|phase| phase.color)
+                .unwrap_or(self.rest),
+        })
+    }
+}
+
+impl Widget for Timeline<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.width == 0 || area.height == 0 {
+            return;
+        }
+        let (start, end) = self.range();
+        let span = end - start;
+        let width = u64::from(area.width);
+        let bar_rows = area.height.saturating_sub(1).max(1);
+
+        for x in 0..area.width {
+            let t = start + (2 * u64::from(x) + 1) * span / (2 * width);
+            let (symbol, style) = match self.color_at(t) {
+                Some(color) => ("█", Style::default().fg(color)),
+                None => ("─", Style::default().fg(Color::DarkGray)),
+            };
+            for y in 0..bar_rows {
+                buf.get_mut(area.x + x, area.y + y)
+                    .set_symbol(symbol)
+                    .set_style(style);
+            }
+        }
+
+        if area.height < 2 {
+            return;
+        }
+        let label_y = area.y + area.height - 1;
+        let mut hour = start;
+        let mut next_free = 0;
+        while hour < end {
+            let x = ((hour - start) * width / span) as u16;
+            if x >= next_free && x + 2 <= area.width {
+                let label = &wallclock::format_hh_mm(hour)[..2];
+                buf.set_string(area.x + x, label_y, label, Style::default().fg(Color::Gray));
+                next_free = x + 3;
+            }
+            hour += HOUR;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(kind: PhaseKind, start: u64, end: u64) -> SessionRecord {
+        SessionRecord {
+            kind,
+            start,
+            end,
+            planned_secs: end - start,
+            completed: true,
+            pauses: Vec::new(),
+            snoozes: Vec::new(),
+            project: None,
+            task: None,
+            estimate: None,
+            rating: None,
+            extended_secs: 0,
+            distractions: Vec::new(),
+            rested: None,
+            utc_offset: None,
+            rolled_up: false,
+        }
+    }
+
+    fn timeline(records: &[SessionRecord], now: u64) -> Timeline<'_> {
+        Timeline {
+            records,
+            now,
+            focus: Color::Magenta,
+            rest: Color::Green,
+            pause: Color::Gray,
+            lunch: Color::Cyan,
+            warm_up: Color::Yellow,
+            phases: &[],
+        }
+    }
+
+    #[test]
+    fn range_covers_whole_hours() {
+        let midnight = 1_700_000_000 - wallclock::seconds_of_local_day(1_700_000_000);
+        let records = [record(
+            PhaseKind::Focus,
+            midnight + 9 * HOUR + 600,
+            midnight + 9 * HOUR + 2100,
+        )];
+        let (start, end) = timeline(&records, midnight + 10 * HOUR + 5).range();
+        assert_eq!(start, midnight + 9 * HOUR);
+        assert_eq!(end, midnight + 11 * HOUR);
+    }
+
+    #[test]
+    fn colors_phases_and_pauses() {
+        let mut focus = record(PhaseKind::Focus, 100, 200);
+        focus.pauses.push((150, 160));
+        let records = [focus, record(PhaseKind::ShortBreak, 200, 260)];
+        let t = timeline(&records, 300);
+        assert_eq!(t.color_at(120), Some(Color::Magenta));
+        assert_eq!(t.color_at(155), Some(Color::Gray));
+        assert_eq!(t.color_at(230), Some(Color::Green));
+        assert_eq!(t.color_at(280), None);
+    }
+
+    #[test]
+    fn renders_bars_and_labels() {
+        let midnight = 1_700_000_000 - wallclock::seconds_of_local_day(1_700_000_000);
+        let records = [record(
+            PhaseKind::Focus,
+            midnight + 9 * HOUR,
+            midnight + 9 * HOUR + 1800,
+        )];
+        let area = Rect::new(0, 0, 20, 2);
+        let mut buf = Buffer::empty(area);
+        timeline(&records, midnight + 10 * HOUR).render(area, &mut buf);
+        assert_eq!(buf.get(0, 0).symbol(), "█");
+        assert_eq!(buf.get(0, 0).fg, Color::Magenta);
+        assert_eq!(buf.get(19, 0).symbol(), "─");
+        assert_eq!(buf.get(0, 1).symbol(), "0");
+        assert_eq!(buf.get(1, 1).symbol(), "9");
+    }
+}
@@ -0,0 +1,84 @@
+use std::borrow::Cow;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use ratatui::widgets::{Block, Borders, Gauge, Widget};
+
+use super::Palette;
+
+/// The phase's progress as a bar in a titled box, with the clock on it.
+pub struct TimerGauge<'a> {
+    palette: Palette,
+    title: Cow<'a, str>,
+    label: Cow<'a, str>,
+    ratio: f64,
+    color: Color,
+    inset: Option<Rect>,
+    framed: bool,
+}
+
+impl<'a> TimerGauge<'a> {
+    /// `ratio` is clamped to 0 to 1 by the caller, as `AppState::progress` does.
+    pub fn new(palette: Palette, ratio: f64, color: Color) -> Self {
+        TimerGauge {
+            palette,
+            title: Cow::Borrowed(""),
+            label: Cow::Borrowed(""),
+            ratio,
+            color,
+            inset: None,
+            framed: true,
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<Cow<'a, str>>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// The text on the bar, usually the clock.
+    pub fn label(mut self, label: impl Into<Cow<'a, str>>) -> Self {
+        self.label = label.into();
+        self
+    }
+
+    /// Draws the bar only in `area`, inside the box, leaving the rest of it
+    /// to the pictures on either side.
+    pub fn inset(mut self, area: Option<Rect>) -> Self {
+        self.inset = area;
+        self
+    }
+
+    /// The bar alone, without the box and its title.
+    pub fn unframed(mut self) -> Self {
+        self.framed = false;
+        self
+    }
+}
+
+impl Widget for TimerGauge<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.framed {
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(self.palette.border))
+                .title(Span::styled(
+                    self.title,
+                    Style::default().fg(self.palette.muted),
+                ))
+                .render(area, buf);
+        }
+        Gauge::default()
+            .gauge_style(Style::default().fg(self.color))
+            .ratio(self.ratio)
+            .label(Span::styled(
+                self.label,
+                Style::default()
+                    .fg(self.palette.text)
+                    .add_modifier(Modifier::BOLD),
+            ))
+            .render(self.inset.unwrap_or(area), buf);
+    }
+}
@@ -0,0 +1,88 @@
+use std::borrow::Cow;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Widget};
+
+use super::Palette;
+
+/// "Rusty Pomo · <title>" and any parts after it, centered over a rule.
+pub struct Header<'a> {
+    palette: Palette,
+    title: Span<'a>,
+    parts: Vec<Span<'a>>,
+    stacked: bool,
+}
+
+impl<'a> Header<'a> {
+    pub fn new(palette: Palette, title: impl Into<Cow<'a, str>>, color: Color) -> Self {
+        Header {
+            palette,
+            title: Span::styled(
+                title,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            parts: Vec::new(),
+            stacked: false,
+        }
+    }
+
+    /// A part in the text color, such as the project.
+    pub fn part(mut self, text: impl Into<Cow<'a, str>>) -> Self {
+        self.parts.push(Span::styled(text, self.palette.text));
+        self
+    }
+
+    /// A part in the muted color, such as the profile.
+    pub fn aside(mut self, text: impl Into<Cow<'a, str>>) -> Self {
+        self.parts.push(Span::styled(text, self.palette.muted));
+        self
+    }
+
+    /// Each part on a line of its own under the title, for narrow windows.
+    pub fn stacked(mut self) -> Self {
+        self.stacked = true;
+        self
+    }
+
+    /// Rows the header takes, the rule included.
+    pub fn height(&self) -> u16 {
+        let parts = if self.stacked { self.parts.len() } else { 0 };
+        u16::try_from(parts + 2).unwrap_or(u16::MAX)
+    }
+}
+
+impl Widget for Header<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let separator = || Span::styled(" · ", self.palette.muted);
+        let mut title = vec![
+            Span::styled(
+                "Rusty Pomo · ",
+                Style::default()
+                    .fg(self.palette.muted)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            self.title,
+        ];
+        let mut lines = Vec::new();
+        if self.stacked {
+            lines.extend(self.parts.into_iter().map(Line::from));
+        } else {
+            for part in self.parts {
+                title.push(separator());
+                title.push(part);
+            }
+        }
+        lines.insert(0, Line::from(title));
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::BOTTOM)
+                    .border_style(Style::default().fg(self.palette.border)),
+            )
+            .render(area, buf);
+    }
+}
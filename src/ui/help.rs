@@ -0,0 +1,107 @@
+use std::borrow::Cow;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Widget, Wrap};
+
+use super::Palette;
+
+/// The keys and what they do, centered under a rule, with any notes above
+/// them. Wraps when the window is narrow; `rows` tells how far.
+pub struct HelpBar<'a> {
+    palette: Palette,
+    notes: Vec<Line<'a>>,
+    keys: Vec<(&'a str, Cow<'a, str>)>,
+    framed: bool,
+}
+
+impl<'a> HelpBar<'a> {
+    pub fn new(palette: Palette) -> Self {
+        HelpBar {
+            palette,
+            notes: Vec::new(),
+            keys: Vec::new(),
+            framed: true,
+        }
+    }
+
+    /// A line above the keys, such as a message or the task.
+    pub fn note(mut self, line: impl Into<Line<'a>>) -> Self {
+        self.notes.push(line.into());
+        self
+    }
+
+    pub fn key(mut self, key: &'a str, label: impl Into<Cow<'a, str>>) -> Self {
+        self.keys.push((key, label.into()));
+        self
+    }
+
+    /// Without the rule and the background, for a single row.
+    pub fn unframed(mut self) -> Self {
+        self.framed = false;
+        self
+    }
+
+    /// Rows the notes and the keys take at `width`, the rule left out.
+    pub fn rows(&self, width: u16) -> usize {
+        let width = usize::from(width).max(1);
+        self.notes
+            .iter()
+            .chain([&self.keys_line()])
+            .map(|line| line.width().div_ceil(width).max(1))
+            .sum()
+    }
+
+    fn keys_line(&self) -> Line<'a> {
+        let mut spans = Vec::new();
+        for (i, (key, label)) in self.keys.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw("  "));
+            }
+            spans.push(Span::styled(format!("{key} "), self.palette.muted));
+            spans.push(Span::styled(label.clone(), self.palette.text));
+        }
+        Line::from(spans)
+    }
+}
+
+impl Widget for HelpBar<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let keys = self.keys_line();
+        let mut lines = self.notes;
+        lines.push(keys);
+        let help = Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .alignment(Alignment::Center);
+        if self.framed {
+            help.block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .border_style(Style::default().fg(self.palette.border))
+                    .style(Style::default().bg(self.palette.bg)),
+            )
+            .render(area, buf);
+        } else {
+            help.render(area, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::Theme;
+
+    #[test]
+    fn counts_the_rows_the_keys_wrap_to() {
+        let help = HelpBar::new(Palette::from(Theme::Dracula))
+            .note("Lunch until 13:00")
+            .key("n", "next")
+            .key("q", "quit");
+        assert_eq!(help.keys_line().to_string(), "n next  q quit");
+        assert_eq!(help.rows(40), 2);
+        assert_eq!(help.rows(10), 4);
+    }
+}
@@ -0,0 +1,239 @@
+//! `--layout`: the arrangements of the timer screen besides the default one,
+//! for wide monitors, tall and narrow panes, and tiny windows.
+
+use std::time::Instant;
+
+use clap::ValueEnum;
+use ratatui::Frame;
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::text::Line;
+use ratatui::widgets::{Paragraph, Wrap};
+
+use super::{
+    MIN_HEIGHT, MIN_WIDTH, Palette, StatusLine, TimerGauge, centered_rect, draw_breathing,
+    draw_mascot, header, help_bar, next_phase, phase_color, quote_line, suggestion, timer_text,
+};
+use crate::breathing;
+use crate::i18n::{self, t};
+use crate::mascot;
+use crate::state::AppState;
+
+/// The narrowest window the wide layout is worth it in; the default one
+/// takes over below it.
+const WIDE_WIDTH: u16 = 100;
+/// The smallest pane the vertical layout fits: the header, the gauge and
+/// the keys wrapped over a few rows.
+const VERTICAL_WIDTH: u16 = 24;
+const VERTICAL_HEIGHT: u16 = 18;
+/// The minimal layout's phase, bar and status line, and the width they need.
+const MINIMAL_WIDTH: u16 = 20;
+const MINIMAL_HEIGHT: u16 = 3;
+/// The minimal layout is no wider than this, however wide the window.
+const MINIMAL_BAR: u16 = 48;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum LayoutPreset {
+    /// Header, gauge and footer stacked, with the extras between them.
+    #[default]
+    Default,
+    /// The gauge beside today's timeline, for wide monitors.
+    Wide,
+    /// Everything in one narrow column, for a tmux pane split down the side.
+    Vertical,
+    /// The phase, a bar and one line of status in the middle, and no keys.
+    Minimal,
+}
+
+impl LayoutPreset {
+    /// The layout drawn in `size`: this one, the default one for the wide and
+    /// vertical layouts when they don't fit, or none for the compact line.
+    pub fn fitting(self, size: Rect) -> Option<Self> {
+        let fits = |width, height| size.width >= width && size.height >= height;
+        match self {
+            LayoutPreset::Wide if fits(WIDE_WIDTH, MIN_HEIGHT) => Some(self),
+            LayoutPreset::Vertical if fits(VERTICAL_WIDTH, VERTICAL_HEIGHT) => Some(self),
+            LayoutPreset::Minimal => fits(MINIMAL_WIDTH, MINIMAL_HEIGHT).then_some(self),
+            _ => fits(MIN_WIDTH, MIN_HEIGHT).then_some(LayoutPreset::Default),
+        }
+    }
+}
+
+/// The header and footer across the top and bottom; between them, the gauge
+/// and what's said under it on the left, and today's timeline on the right
+/// with the breathing exercise or the mascot below.
+pub(super) fn draw_wide(frame: &mut Frame, app: &AppState, palette: Palette, now: Instant) {
+    let size = frame.size();
+    let help = help_bar(app, palette, now);
+    // Its rows and the rule, leaving the header and the gauge theirs
+    let footer = u16::try_from(help.rows(size.width) + 1)
+        .unwrap_or(u16::MAX)
+        .clamp(3, size.height - 8);
+    let [top, body, bottom] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(5),
+        Constraint::Length(footer),
+    ])
+    .areas(size);
+    frame.render_widget(header(app, palette), top);
+
+    let [left, right] =
+        Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)]).areas(body);
+    let [gauge, said] = Layout::vertical([Constraint::Length(5), Constraint::Min(0)]).areas(left);
+    let gauge_widget = TimerGauge::new(palette, app.progress(now), phase_color(app))
+        .title(t("ui.session"))
+        .label(timer_text(app, now));
+    frame.render_widget(gauge_widget, gauge);
+    let mut lines = Vec::new();
+    if let Some(next) = next_phase(app, now) {
+        lines.push(StatusLine::muted(palette, next).into());
+    }
+    if !app.finished {
+        if let Some(suggestion) = suggestion(app, palette, now) {
+            lines.extend([Line::default(), suggestion.into()]);
+        }
+        if let Some(quote) = app.break_quote() {
+            lines.extend([Line::default(), quote_line(quote, palette)]);
+        }
+    }
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .alignment(Alignment::Center),
+        said,
+    );
+
+    let [timeline, below] =
+        Layout::vertical([Constraint::Length(5), Constraint::Min(0)]).areas(right);
+    super::draw_timeline(frame, app, palette, t("ui.today").to_string(), timeline);
+    if app.breathing() && below.height >= breathing::HEIGHT {
+        draw_breathing(frame, app, palette, now, below);
+    } else if let Some(pose) = app
+        .mascot_pose(now)
+        .filter(|_| below.height > mascot::HEIGHT)
+    {
+        draw_mascot(frame, app, pose, below);
+    }
+
+    frame.render_widget(help, bottom);
+}
+
+/// One column: the header with its parts stacked, the gauge, then the next
+/// phase, suggestion, quote, breathing exercise and mascot while there's
+/// room, over the keys wrapped to the pane's width.
+pub(super) fn draw_vertical(frame: &mut Frame, app: &AppState, palette: Palette, now: Instant) {
+    let size = frame.size();
+    let width = usize::from(size.width);
+    let rows = |line: &Line| u16::try_from(line.width().div_ceil(width).max(1)).unwrap_or(u16::MAX);
+    let head = header(app, palette).stacked();
+    let help = help_bar(app, palette, now);
+    let footer = u16::try_from(help.rows(size.width) + 1).unwrap_or(u16::MAX);
+    let mut spare = size
+        .height
+        .saturating_sub(head.height() + 5)
+        .saturating_sub(footer);
+
+    let mut candidates: Vec<Line> = Vec::new();
+    if let Some(next) = next_phase(app, now) {
+        candidates.push(StatusLine::muted(palette, next).into());
+    }
+    if !app.finished {
+        candidates.extend(suggestion(app, palette, now).map(Line::from));
+        candidates.extend(app.break_quote().map(|quote| quote_line(quote, palette)));
+    }
+    // Each with a blank line above it but the first, while they fit
+    let mut said = Vec::new();
+    for line in candidates {
+        let needed = rows(&line) + u16::from(!said.is_empty());
+        if needed > spare {
+            break;
+        }
+        spare -= needed;
+        if !said.is_empty() {
+            said.push(Line::default());
+        }
+        said.push(line);
+    }
+    let said_rows = said.iter().map(rows).sum::<u16>();
+    let breathing = app.breathing() && spare >= breathing::HEIGHT;
+    if breathing {
+        spare -= breathing::HEIGHT;
+    }
+    let pose = app.mascot_pose(now).filter(|_| spare > mascot::HEIGHT);
+
+    let [top, gauge, text, picture, _, bottom] = Layout::vertical([
+        Constraint::Length(head.height()),
+        Constraint::Length(5),
+        Constraint::Length(said_rows),
+        Constraint::Length(if breathing {
+            breathing::HEIGHT
+        } else if pose.is_some() {
+            mascot::HEIGHT + 1
+        } else {
+            0
+        }),
+        Constraint::Min(0),
+        Constraint::Length(footer),
+    ])
+    .areas(size);
+    frame.render_widget(head, top);
+    let gauge_widget = TimerGauge::new(palette, app.progress(now), phase_color(app))
+        .title(t("ui.session"))
+        .label(timer_text(app, now));
+    frame.render_widget(gauge_widget, gauge);
+    frame.render_widget(
+        Paragraph::new(said)
+            .wrap(Wrap { trim: true })
+            .alignment(Alignment::Center),
+        text,
+    );
+    if breathing {
+        draw_breathing(frame, app, palette, now, picture);
+    } else if let Some(pose) = pose {
+        draw_mascot(frame, app, pose, picture);
+    }
+    frame.render_widget(help, bottom);
+}
+
+/// The phase and today's count, the bar with the clock on it, and the latest
+/// message or else the next phase, in the middle of the window. The keys
+/// still work; they just aren't listed.
+pub(super) fn draw_minimal(frame: &mut Frame, app: &AppState, palette: Palette, now: Instant) {
+    let size = frame.size();
+    // A blank line between each when there's room for it
+    let step = if size.height >= 5 { 2 } else { 1 };
+    let area = centered_rect(MINIMAL_BAR, 2 * step + 1, size);
+    let row = |at: u16| Rect {
+        y: area.y + at * step,
+        height: 1,
+        ..area
+    };
+
+    let mut title = StatusLine::new(palette, i18n::phase_title(app.current_phase.kind))
+        .color(phase_color(app))
+        .bold();
+    if let Some(counter) = app.counter() {
+        title = title.then(counter, palette.muted);
+    }
+    frame.render_widget(title, row(0));
+    let bar = TimerGauge::new(palette, app.progress(now), phase_color(app))
+        .label(timer_text(app, now))
+        .unframed();
+    frame.render_widget(bar, row(1));
+    let status = match &app.message {
+        Some(message) => Some(StatusLine::new(palette, message.as_str())),
+        None => next_phase(app, now).map(|next| StatusLine::muted(palette, next)),
+    };
+    // Wrapped into the rows below it when the window is narrow
+    if let Some(status) = status {
+        let below = Rect {
+            height: size.bottom() - row(2).y,
+            ..row(2)
+        };
+        frame.render_widget(
+            Paragraph::new(Line::from(status))
+                .wrap(Wrap { trim: true })
+                .alignment(Alignment::Center),
+            below,
+        );
+    }
+}
@@ -0,0 +1,889 @@
+use clap::ValueEnum;
+use std::io;
+use std::time::{Duration, Instant};
+
+use ratatui::backend::TestBackend;
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Margin, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use ratatui::{Frame, Terminal};
+
+use crate::achievements;
+use crate::breathing::{self, Breathing};
+use crate::garden::{self, Garden};
+use crate::i18n::{self, t, tf};
+use crate::mascot::{self, MascotArt, Pose};
+use crate::state::{AppState, PhaseKind};
+use crate::theme::{self, Theme};
+use crate::timefmt;
+use crate::timeline::Timeline;
+use crate::wallclock;
+
+mod gauge;
+mod header;
+mod help;
+mod layout;
+mod modal;
+mod palette;
+mod status;
+
+pub use gauge::TimerGauge;
+pub use header::Header;
+pub use help::HelpBar;
+pub use layout::LayoutPreset;
+pub use modal::Modal;
+pub use palette::Palette;
+pub use status::StatusLine;
+
+/// The smallest area the full layout fits: header, gauge and a footer line.
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 11;
+/// A blank line, then up to two lines of quote.
+const QUOTE_HEIGHT: u16 = 3;
+/// Columns for the icon and the progress ring; cells are about twice as
+/// tall as wide, so three rows make them square.
+const PICTURE_WIDTH: u16 = 6;
+
+/// Draws `app` as of `now`, which only matters to the clock and the skip prompt.
+/// In 256 colors in remote mode, and inverted for a moment when the bell rings.
+pub fn draw(frame: &mut Frame, app: &AppState, now: Instant) {
+    draw_screen(frame, app, now);
+    if app.remote {
+        for cell in &mut frame.buffer_mut().content {
+            cell.fg = theme::indexed(cell.fg);
+            cell.bg = theme::indexed(cell.bg);
+        }
+    }
+    if app.bell.flashing() {
+        let area = frame.size();
+        frame
+            .buffer_mut()
+            .set_style(area, Style::default().add_modifier(Modifier::REVERSED));
+    }
+}
+
+fn draw_screen(frame: &mut Frame, app: &AppState, now: Instant) {
+    let palette = Palette::from(app.theme);
+    match app.args.layout.fitting(frame.size()) {
+        Some(LayoutPreset::Default) => draw_default(frame, app, palette, now),
+        Some(LayoutPreset::Wide) => layout::draw_wide(frame, app, palette, now),
+        Some(LayoutPreset::Vertical) => layout::draw_vertical(frame, app, palette, now),
+        Some(LayoutPreset::Minimal) => layout::draw_minimal(frame, app, palette, now),
+        None => {
+            draw_compact(frame, app, now);
+            return;
+        }
+    }
+    draw_overlays(frame, app, palette, now);
+}
+
+/// Header, gauge and the footer, with the extras between them as room allows.
+fn draw_default(frame: &mut Frame, app: &AppState, palette: Palette, now: Instant) {
+    let size = frame.size();
+    let help = help_bar(app, palette, now);
+
+    // Room for the break quote, the suggestion, the breathing exercise, the
+    // mascot and then the next phase, only when the footer keeps its own. Its
+    // border and first two rows are in MIN_HEIGHT.
+    let extra = u16::try_from(help.rows(size.width).saturating_sub(2)).unwrap_or(u16::MAX);
+    let mut spare = (size.height - MIN_HEIGHT).saturating_sub(extra);
+    let quote = app
+        .break_quote()
+        .filter(|_| !app.finished && spare >= QUOTE_HEIGHT);
+    if quote.is_some() {
+        spare -= QUOTE_HEIGHT;
+    }
+    // Right under the quote, or a line below the gauge without one
+    let suggestion_height = if quote.is_some() { 1 } else { 2 };
+    let suggestion =
+        suggestion(app, palette, now).filter(|_| !app.finished && spare >= suggestion_height);
+    if suggestion.is_some() {
+        spare -= suggestion_height;
+    }
+    let breathing = app.breathing() && spare >= breathing::HEIGHT;
+    if breathing {
+        spare -= breathing::HEIGHT;
+    }
+    // With a blank line above it
+    let pose = app.mascot_pose(now).filter(|_| spare > mascot::HEIGHT);
+    if pose.is_some() {
+        spare -= mascot::HEIGHT + 1;
+    }
+
+    let next = next_phase(app, now).filter(|_| spare >= 1);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(5),
+            Constraint::Length(if next.is_some() { 1 } else { 0 }),
+            Constraint::Length(if quote.is_some() { QUOTE_HEIGHT } else { 0 }),
+            Constraint::Length(if suggestion.is_some() {
+                suggestion_height
+            } else {
+                0
+            }),
+            Constraint::Length(if breathing { breathing::HEIGHT } else { 0 }),
+            Constraint::Length(if pose.is_some() {
+                mascot::HEIGHT + 1
+            } else {
+                0
+            }),
+            Constraint::Min(3),
+        ])
+        .split(size);
+
+    frame.render_widget(header(app, palette), chunks[0]);
+
+    // Timer + Gauge
+    let timer_text = timer_text(app, now);
+    if app.show_timeline {
+        let title = format!("{} · {timer_text}", t("ui.today"));
+        draw_timeline(frame, app, palette, title, chunks[1]);
+    } else {
+        // Between the pictures, inside the border they leave
+        let inset = picture_areas(app, size).map(|(icon, ring)| Rect {
+            x: icon.right() + 2,
+            y: icon.y,
+            width: ring.x.saturating_sub(icon.right() + 4),
+            height: icon.height,
+        });
+        let gauge = TimerGauge::new(palette, app.progress(now), phase_color(app))
+            .title(t("ui.session"))
+            .label(timer_text)
+            .inset(inset);
+        frame.render_widget(gauge, chunks[1]);
+    }
+
+    if let Some(next) = next {
+        frame.render_widget(StatusLine::muted(palette, next), chunks[2]);
+    }
+
+    // Break quote
+    if let Some(quote) = quote {
+        let quote = Paragraph::new(vec![Line::default(), quote_line(quote, palette)])
+            .wrap(Wrap { trim: true })
+            .alignment(Alignment::Center);
+        frame.render_widget(quote, chunks[3]);
+    }
+
+    if let Some(suggestion) = suggestion {
+        frame.render_widget(suggestion, chunks[4].rows().last().unwrap_or(chunks[4]));
+    }
+    if breathing {
+        draw_breathing(frame, app, palette, now, chunks[5]);
+    }
+    if let Some(pose) = pose {
+        draw_mascot(frame, app, pose, chunks[6]);
+    }
+
+    frame.render_widget(help, chunks[7]);
+}
+
+/// The footer: whatever needs saying, over the keys.
+fn help_bar(app: &AppState, palette: Palette, now: Instant) -> HelpBar<'_> {
+    let mut help = HelpBar::new(palette);
+    if let Some(error) = &app.config_error {
+        help = help.note(StatusLine::new(palette, error.as_str()).color(Color::Red));
+    }
+    if let Some(hours) = app.args.work_hours.as_ref().filter(|_| app.off_hours()) {
+        let note = tf("ui.off_hours", &[("hours", &hours.span())]);
+        help = help.note(StatusLine::new(palette, note).color(theme::OFF_HOURS));
+    }
+    if let Some(next) = &app.after_lunch {
+        let back = wallclock::format_hh_mm(app.clock.unix() + app.time_remaining(now).as_secs());
+        let note = tf(
+            "ui.after_lunch",
+            &[("phase", i18n::phase_inline(next.kind)), ("time", &back)],
+        );
+        help = help.note(StatusLine::new(palette, note).color(theme::LUNCH));
+    }
+    if let Some(message) = &app.message {
+        help = help.note(StatusLine::new(palette, message.as_str()));
+    }
+    if let Some(meeting) = app.meeting_clash(now) {
+        let at = tf(
+            "ui.meeting",
+            &[
+                ("summary", i18n::meeting_name(&meeting.summary)),
+                ("time", &wallclock::format_hh_mm(meeting.start)),
+            ],
+        );
+        help = help.note(
+            StatusLine::new(palette, at)
+                .color(palette.accent)
+                .hint("f", t("ui.fit")),
+        );
+    }
+    // Today's focus time and goal, and the count to the long break, in one line
+    let mut progress = Vec::new();
+    let focused = app.focus_time_today(now);
+    if focused.as_secs() >= 60 || app.args.daily_goal.is_some() {
+        progress.push(tf(
+            "ui.focus_today",
+            &[("duration", &timefmt::compact(focused))],
+        ));
+    }
+    if let Some(goal) = app.args.daily_goal {
+        let goal = goal.to_string();
+        progress.push(match app.goal_eta(now) {
+            Some(at) => tf(
+                "ui.goal_eta",
+                &[("goal", &goal), ("time", &wallclock::format_hh_mm(at))],
+            ),
+            None => tf("ui.goal_met", &[("goal", &goal)]),
+        });
+    }
+    if let Some(left) = app.until_long_break() {
+        progress.push(i18n::catalog().plural("ui.until_long_break", left));
+    }
+    if !progress.is_empty() {
+        help = help.note(StatusLine::muted(palette, progress.join(" · ")));
+    }
+    if let Some(task) = &app.args.task {
+        help = help.note(StatusLine::new(palette, task.as_str()).then(
+            task_progress(app.task_sessions(), app.args.estimate),
+            palette.accent,
+        ));
+    }
+    help = help
+        .key("␣", t("ui.pause"))
+        .key("n", t("ui.next"))
+        .key("r", t("ui.reset"))
+        .key("z", t("ui.snooze"))
+        .key("u", t("ui.undo"))
+        .key("v", t("ui.timeline"))
+        .key("t/T", t("ui.theme"));
+    if let Some(ambient) = &app.ambient {
+        help = help.key(
+            "+/-",
+            tf("ui.volume", &[("volume", &ambient.volume().to_string())]),
+        );
+    }
+    help.key("q", t("ui.quit"))
+}
+
+/// The phase, then the project, profile, today's count and score.
+fn header(app: &AppState, palette: Palette) -> Header<'_> {
+    let title = i18n::phase_title(app.current_phase.kind);
+    let mut header = Header::new(palette, title, phase_color(app));
+    if let Some(project) = &app.args.project {
+        header = header.part(project.as_str());
+    }
+    if let Some(profile) = &app.args.profile {
+        header = header.aside(profile.as_str());
+    }
+    if let Some(counter) = app.counter() {
+        header = header.part(counter);
+    }
+    if let Some(score) = app.today_score() {
+        header = header.aside(tf("ui.score", &[("score", &score.to_string())]));
+    }
+    header
+}
+
+/// The clock, or what's left of a snooze.
+fn timer_text(app: &AppState, now: Instant) -> String {
+    match app.snoozed_for(now) {
+        Some(left) => tf(
+            "ui.snoozed",
+            &[("time", &timefmt::mm_ss(left, t("time.separator")))],
+        ),
+        None => timefmt::clock(
+            app.time_remaining(now),
+            app.current_phase.duration,
+            app.args.time_format,
+        ),
+    }
+}
+
+/// What comes after this phase, when and for how long. Lunch says when it's
+/// over in the footer already.
+fn next_phase(app: &AppState, now: Instant) -> Option<String> {
+    let next = app
+        .peek_next_phase(now)
+        .filter(|_| app.after_lunch.is_none())?;
+    Some(tf(
+        "ui.next_phase",
+        &[
+            ("phase", i18n::phase_title(next.kind)),
+            ("length", &timefmt::compact(next.duration)),
+            (
+                "left",
+                &timefmt::mm_ss(app.time_remaining(now), t("time.separator")),
+            ),
+            ("time", &wallclock::format_hh_mm(next.start)),
+        ],
+    ))
+}
+
+/// The eye-rest countdown, or the break's suggestion with the key to start it.
+fn suggestion(app: &AppState, palette: Palette, now: Instant) -> Option<StatusLine<'_>> {
+    match app.eye_rest_left(now) {
+        Some(left) => {
+            let left = tf(
+                "ui.eye_rest_left",
+                &[("time", &timefmt::mm_ss(left, t("time.separator")))],
+            );
+            Some(StatusLine::new(palette, left).color(palette.ok).bold())
+        }
+        None => app
+            .break_suggestion()
+            .map(|s| StatusLine::new(palette, s).hint("e", t("ui.eye_rest"))),
+    }
+}
+
+fn quote_line(quote: &str, palette: Palette) -> Line<'_> {
+    Line::from(Span::styled(
+        quote,
+        Style::default()
+            .fg(palette.muted)
+            .add_modifier(Modifier::ITALIC),
+    ))
+}
+
+/// Today's sessions so far and the running one, in a box titled `title`.
+fn draw_timeline(frame: &mut Frame, app: &AppState, palette: Palette, title: String, area: Rect) {
+    let wall_now = app.clock.unix();
+    let mut records: Vec<_> = app.history.today(wall_now).cloned().collect();
+    records.push(app.current_record(false));
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(palette.border))
+        .title(Span::styled(title, Style::default().fg(palette.muted)));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    frame.render_widget(
+        Timeline {
+            records: &records,
+            now: wall_now,
+            focus: palette.accent,
+            rest: palette.ok,
+            pause: palette.muted,
+            lunch: theme::LUNCH,
+            warm_up: theme::WARM_UP,
+            phases: &app.args.phases,
+        },
+        inner,
+    );
+}
+
+fn draw_breathing(frame: &mut Frame, app: &AppState, palette: Palette, now: Instant, area: Rect) {
+    let exercise = Breathing {
+        elapsed: app.elapsed_in_phase(now),
+        color: palette.ok,
+    };
+    frame.render_widget(exercise, area);
+}
+
+/// The mascot in `pose`, a blank line down from the top of `area`.
+fn draw_mascot(frame: &mut Frame, app: &AppState, pose: Pose, area: Rect) {
+    let art = MascotArt {
+        mascot: app.args.mascot,
+        pose,
+        color: phase_color(app),
+    };
+    let below_blank = Rect {
+        y: area.y + 1,
+        height: mascot::HEIGHT.min(area.height.saturating_sub(1)),
+        ..area
+    };
+    frame.render_widget(art, below_blank);
+}
+
+/// The prompts, then the theme picker, garden and achievements, over any layout.
+fn draw_overlays(frame: &mut Frame, app: &AppState, palette: Palette, now: Instant) {
+    let size = frame.size();
+    // Skip confirmation
+    if app.skip_pending(now) {
+        let modal = Modal::new(
+            palette,
+            t("ui.skip_title"),
+            t("ui.skip_body"),
+            palette.accent,
+        );
+        frame.render_widget(modal, size);
+    }
+    if app.finished {
+        let body = format!(
+            "{} {}",
+            i18n::done(app.completed_cycles, app.completed_focus),
+            t("done.quit")
+        );
+        frame.render_widget(Modal::new(palette, t("done.title"), body, palette.ok), size);
+    }
+    if app.rating_prompt.is_some() {
+        let modal = Modal::new(
+            palette,
+            t("ui.rate_title"),
+            t("ui.rate_body"),
+            palette.accent,
+        );
+        frame.render_widget(modal, size);
+    }
+    if let (Some(_), Some(more)) = (app.extension_offer, app.args.offer_extension) {
+        let body = tf("ui.extend_body", &[("duration", &timefmt::spoken(more))]);
+        let modal = Modal::new(palette, t("ui.extend_title"), body, palette.accent);
+        frame.render_widget(modal, size);
+    }
+    if app.cycle_prompt {
+        let mut body = t("ui.rate_body").to_string();
+        if let Some(task) = &app.args.task {
+            body = format!("{}\n{body}", tf("ui.review_task", &[("task", task)]));
+        }
+        let modal = Modal::new(palette, t("ui.review_title"), body, palette.ok);
+        frame.render_widget(modal, size);
+    }
+    if app.theme_picker.is_some() {
+        draw_theme_picker(frame, app.theme);
+    }
+    if app.show_garden {
+        draw_garden(frame, app);
+    }
+    if app.show_achievements {
+        draw_achievements(frame, app);
+    }
+}
+
+/// Every achievement: when it was unlocked, or how close the history is.
+fn draw_achievements(frame: &mut Frame, app: &AppState) {
+    let palette = Palette::from(app.theme);
+    let ok = palette.ok;
+    let unlocked = |key| app.achievements.as_ref().and_then(|a| a.unlocked_at(key));
+    let count = achievements::RULES
+        .iter()
+        .filter(|rule| unlocked(rule.key).is_some())
+        .count();
+    let title = tf(
+        "ui.achievements_title",
+        &[
+            ("count", &count.to_string()),
+            ("total", &achievements::RULES.len().to_string()),
+        ],
+    );
+    let mut lines = Vec::new();
+    for rule in &achievements::RULES {
+        let (mark, status, color) = match unlocked(rule.key) {
+            Some(at) => ("✓ ", wallclock::format_date(wallclock::local_day(at)), ok),
+            None => (
+                "  ",
+                format!("{}/{}", rule.progress(&app.history.records), rule.goal),
+                palette.muted,
+            ),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(mark, Style::default().fg(ok)),
+            Span::styled(
+                rule.name(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!("  {status}"), Style::default().fg(palette.muted)),
+        ]));
+        lines.push(Line::from(Span::styled(
+            format!("  {}", rule.about()),
+            Style::default().fg(palette.text),
+        )));
+    }
+    let size = frame.size();
+    let height = (lines.len() as u16 + 2).min(size.height);
+    let area = centered_rect(size.width.saturating_sub(4).min(60), height, size);
+    let list = Paragraph::new(lines).block(Modal::frame(title, palette.accent).title_bottom(
+        Line::from(Span::styled(
+            t("ui.achievements_help"),
+            Style::default().fg(palette.muted),
+        )),
+    ));
+    frame.render_widget(Clear, area);
+    frame.render_widget(list, area);
+}
+
+/// The garden grown by the history, over most of the screen.
+fn draw_garden(frame: &mut Frame, app: &AppState) {
+    let palette = Palette::from(app.theme);
+    let plants = garden::grow(&app.history.records);
+    let wilted = plants.iter().filter(|p| p.wilted).count();
+    let title = tf(
+        "ui.garden_title",
+        &[
+            ("grown", &(plants.len() - wilted).to_string()),
+            ("wilted", &wilted.to_string()),
+        ],
+    );
+    let size = frame.size();
+    let area = centered_rect(
+        size.width.saturating_sub(4).min(64),
+        size.height.saturating_sub(2).min(17),
+        size,
+    );
+    let block = Modal::frame(title, palette.ok).title_bottom(Line::from(Span::styled(
+        t("ui.garden_help"),
+        Style::default().fg(palette.muted),
+    )));
+    let inner = block.inner(area).inner(Margin::new(1, 0));
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    if plants.is_empty() {
+        let empty = Paragraph::new(t("ui.garden_empty"))
+            .wrap(Wrap { trim: true })
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(palette.muted));
+        frame.render_widget(empty, inner);
+        return;
+    }
+    let garden = Garden {
+        plants: &plants,
+        flower: palette.accent,
+        stem: palette.ok,
+    };
+    frame.render_widget(garden, inner);
+}
+
+/// One row per theme with swatches of its colors; the whole screen behind it
+/// already shows the highlighted one.
+fn draw_theme_picker(frame: &mut Frame, current: Theme) {
+    let themes = Theme::value_variants();
+    let palette = Palette::from(current);
+    let mut lines: Vec<Line> = themes
+        .iter()
+        .map(|theme| {
+            let (bg, accent, ok) = theme.colors();
+            let selected = *theme == current;
+            let name = Style::default().fg(palette.text);
+            vec![
+                Span::raw(if selected { "▸ " } else { "  " }),
+                Span::styled("  ", Style::default().bg(bg)),
+                Span::styled("  ", Style::default().bg(accent)),
+                Span::styled("  ", Style::default().bg(ok)),
+                Span::styled(
+                    format!("  {}", theme.key()),
+                    if selected {
+                        name.add_modifier(Modifier::BOLD)
+                    } else {
+                        name
+                    },
+                ),
+            ]
+            .into()
+        })
+        .collect();
+    lines.push(Line::default());
+    lines.push(Line::styled(
+        t("ui.theme_help"),
+        Style::default().fg(palette.muted),
+    ));
+    let area = centered_rect(40, themes.len() as u16 + 4, frame.size());
+    let picker = Paragraph::new(lines).block(Modal::frame(t("ui.theme_title"), palette.accent));
+    frame.render_widget(Clear, area);
+    frame.render_widget(picker, area);
+}
+
+/// Draws `frames` frames of `app` into an off-screen 120x40 terminal, one
+/// clock second apart so each frame differs from the last, with the timeline
+/// shown for every other one. Returns the time taken.
+pub fn bench(app: &mut AppState, frames: u64) -> io::Result<Duration> {
+    let mut terminal = Terminal::new(TestBackend::new(120, 40))?;
+    let start = Instant::now();
+    let now = app.now();
+    for frame in 0..frames {
+        app.phase_started_at = now.checked_sub(Duration::from_secs(frame)).unwrap_or(now);
+        app.show_timeline = frame % 2 == 1;
+        terminal.draw(|f| draw(f, app, now))?;
+    }
+    Ok(start.elapsed())
+}
+
+/// A single centered line for small windows: phase and clock, the clock alone,
+/// or a note that the window is too small.
+fn draw_compact(frame: &mut Frame, app: &AppState, now: Instant) {
+    let palette = Palette::from(app.theme);
+    let area = frame.size();
+    let color = phase_color(app);
+    let remaining = app.time_remaining(now);
+    let clock = timefmt::clock(remaining, app.current_phase.duration, app.args.time_format);
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let candidates = [
+        Line::from(vec![
+            Span::styled(i18n::phase_title(app.current_phase.kind), bold.fg(color)),
+            Span::styled(" · ", Style::default().fg(palette.muted)),
+            Span::styled(clock.clone(), bold.fg(palette.text)),
+        ]),
+        Line::styled(clock, bold.fg(palette.text)),
+    ];
+    let line = candidates
+        .into_iter()
+        .find(|line| line.width() <= usize::from(area.width))
+        .unwrap_or_else(|| Line::styled(t("ui.too_small"), Style::default().fg(palette.muted)));
+    let middle = Rect {
+        y: area.y + area.height.saturating_sub(1) / 2,
+        height: area.height.min(1),
+        ..area
+    };
+    frame.render_widget(Paragraph::new(line).alignment(Alignment::Center), middle);
+}
+
+/// Where the icon and the progress ring go, on either side of the gauge, when
+/// the terminal shows pictures and nothing is drawn over them.
+pub fn picture_areas(app: &AppState, size: Rect) -> Option<(Rect, Rect)> {
+    let covered = app.finished
+        || app.show_timeline
+        || app.rating_prompt.is_some()
+        || app.extension_offer.is_some()
+        || app.cycle_prompt
+        || app.theme_picker.is_some()
+        || app.show_garden
+        || app.show_achievements;
+    if app.graphics.is_none()
+        || covered
+        || size.width < MIN_WIDTH + 4 * PICTURE_WIDTH
+        || app.args.layout.fitting(size) != Some(LayoutPreset::Default)
+    {
+        return None;
+    }
+    // The gauge's box starts under the three-line header
+    let y = size.y + 4;
+    Some((
+        Rect::new(size.x + 2, y, PICTURE_WIDTH, 3),
+        Rect::new(size.right() - 2 - PICTURE_WIDTH, y, PICTURE_WIDTH, 3),
+    ))
+}
+
+/// The header and gauge color: the theme's for the phase, the warm-up's,
+/// lunch's or a custom phase's own, or the evening tint outside `[work_hours]`.
+pub fn phase_color(app: &AppState) -> Color {
+    let Palette { accent, ok, .. } = Palette::from(app.theme);
+    if app.off_hours() {
+        return theme::OFF_HOURS;
+    }
+    match app.current_phase.kind {
+        PhaseKind::WarmUp => theme::WARM_UP,
+        PhaseKind::Focus => accent,
+        PhaseKind::ShortBreak | PhaseKind::LongBreak | PhaseKind::CycleReview => ok,
+        PhaseKind::Lunch => theme::LUNCH,
+        PhaseKind::Custom(_) => app.custom_phase().and_then(|p| p.color).unwrap_or(ok),
+    }
+}
+
+pub fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+pub fn task_progress(completed: u64, estimate: Option<u64>) -> String {
+    match estimate {
+        Some(estimate) => format!("{completed}/{estimate} 🍅"),
+        None => format!("{completed} 🍅"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::Meeting;
+    use crate::mascot::Mascot;
+    use crate::state::tests::{make_args, mock_app, test_app};
+
+    #[test]
+    fn formats_task_progress() {
+        assert_eq!(task_progress(3, Some(5)), "3/5 🍅");
+        assert_eq!(task_progress(2, None), "2 🍅");
+    }
+
+    fn render(app: &AppState, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| draw(frame, app, app.now())).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer.content().iter().map(|cell| cell.symbol()).collect()
+    }
+
+    /// The screen as text, one line per row without trailing spaces.
+    fn screen(app: &AppState, now: Instant) -> String {
+        tall_screen(app, now, 16)
+    }
+
+    fn tall_screen(app: &AppState, now: Instant, height: u16) -> String {
+        sized_screen(app, now, 60, height)
+    }
+
+    fn sized_screen(app: &AppState, now: Instant, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| draw(frame, app, now)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let rows = buffer.content().chunks(usize::from(buffer.area.width));
+        rows.map(|row| {
+            let line: String = row.iter().map(|cell| cell.symbol()).collect();
+            line.trim_end().to_string() + "\n"
+        })
+        .collect()
+    }
+
+    /// Compares against `src/snapshots/<name>.txt`; `UPDATE_SNAPSHOTS=1` rewrites it.
+    fn assert_snapshot(name: &str, actual: &str) {
+        let path = format!("{}/src/snapshots/{name}.txt", env!("CARGO_MANIFEST_DIR"));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            expected == actual,
+            "{name} changed; rerun with UPDATE_SNAPSHOTS=1 if that's intended\n\
+             expected:\n{expected}\nactual:\n{actual}"
+        );
+    }
+
+    #[test]
+    fn snapshots_of_each_layout() {
+        let mut args = make_args();
+        args.project = Some("thesis".to_string());
+        args.task = Some("Write intro".to_string());
+        args.layout = LayoutPreset::Wide;
+        let (mut app, _) = mock_app(args);
+        let now = app.now() + Duration::from_secs(15);
+        assert_snapshot("layout_wide", &sized_screen(&app, now, 110, 14));
+        // Too narrow for it, so the default one
+        assert_eq!(sized_screen(&app, now, 60, 16), screen(&app, now));
+        app.args.layout = LayoutPreset::Vertical;
+        assert_snapshot("layout_vertical", &sized_screen(&app, now, 28, 26));
+        app.args.layout = LayoutPreset::Minimal;
+        assert_snapshot("layout_minimal", &sized_screen(&app, now, 30, 7));
+        let tiny = sized_screen(&app, now, 24, 3);
+        assert!(tiny.contains("Focus") && tiny.contains("00:45"), "{tiny}");
+    }
+
+    #[test]
+    fn snapshots_of_key_screens() {
+        let mut args = make_args();
+        args.project = Some("thesis".to_string());
+        args.task = Some("Write intro".to_string());
+        args.estimate = Some(4);
+        let (mut app, clock) = mock_app(args);
+        assert_snapshot("focus", &screen(&app, app.now() + Duration::from_secs(15)));
+
+        // Paused with 40 seconds left, however much later it's drawn
+        clock.advance(Duration::from_secs(20));
+        app.toggle_pause();
+        assert_snapshot(
+            "paused",
+            &screen(&app, app.now() + Duration::from_secs(600)),
+        );
+        app.toggle_pause();
+
+        app.request_skip();
+        assert_snapshot("skip", &screen(&app, app.now()));
+        app.request_skip();
+        assert_snapshot("break", &screen(&app, app.now() + Duration::from_secs(45)));
+
+        app.open_theme_picker();
+        app.theme = app.theme.cycle(1);
+        assert_snapshot("theme_picker", &screen(&app, app.now()));
+        app.close_theme_picker(true);
+
+        app.args.cycles = Some(1);
+        app.skip();
+        app.skip();
+        app.skip();
+        assert_snapshot("done", &screen(&app, app.now()));
+    }
+
+    #[test]
+    fn breathing_exercise_fills_spare_rows_in_short_breaks() {
+        let mut args = make_args();
+        args.breathing = true;
+        let (mut app, _clock) = mock_app(args);
+        app.skip();
+        // Nine seconds in: a second into breathing out
+        let now = app.now() + Duration::from_secs(9);
+        assert_snapshot("breathing", &tall_screen(&app, now, 24));
+        assert!(!screen(&app, now).contains("breathe out"));
+    }
+
+    #[test]
+    fn mascot_ripens_during_focus_and_cheers_after() {
+        let mut args = make_args();
+        args.mascot = Mascot::Tomato;
+        let (mut app, clock) = mock_app(args);
+        assert_snapshot("mascot", &tall_screen(&app, app.now(), 20));
+        clock.advance(app.current_phase.duration);
+        app.advance_phase();
+        assert_snapshot(
+            "mascot_cheering",
+            &tall_screen(&app, app.now() + Duration::from_millis(600), 21),
+        );
+        // Gone once the cheer is over, and never with too few rows
+        assert!(!tall_screen(&app, app.now() + mascot::CHEERING, 21).contains("'---'"));
+        app.skip();
+        // The footer has a row for the count to the long break
+        assert!(tall_screen(&app, app.now(), 17).contains("~~~~~"));
+        assert!(!tall_screen(&app, app.now(), 16).contains("~~~~~"));
+    }
+
+    #[test]
+    fn shows_the_garden_grown_by_the_history() {
+        let (mut app, clock) = mock_app(make_args());
+        app.show_garden = true;
+        assert!(screen(&app, app.now()).contains("Finish a focus session"));
+        for _ in 0..3 {
+            clock.advance(app.current_phase.duration);
+            app.advance_phase();
+            app.skip();
+        }
+        clock.advance(Duration::from_secs(10));
+        app.skip();
+        assert_snapshot("garden", &screen(&app, app.now()));
+    }
+
+    #[test]
+    fn offers_to_fit_focus_before_a_meeting() {
+        let (mut app, clock) = mock_app(make_args());
+        let start = app.clock.unix() + 40;
+        app.set_meetings(vec![Meeting {
+            start,
+            end: start + 900,
+            summary: "Standup".to_string(),
+        }]);
+        let line = format!(
+            "Standup at {}  f end focus then",
+            wallclock::format_hh_mm(start)
+        );
+        assert!(screen(&app, app.now()).contains(&line));
+        clock.advance(Duration::from_secs(10));
+        app.fit_to_meeting();
+        assert!(!screen(&app, app.now()).contains("Standup"));
+    }
+
+    #[test]
+    fn degrades_in_small_windows() {
+        let mut app = test_app();
+        // Paused at the very start, so the clock reads a full minute
+        app.paused = true;
+        app.paused_at = Some(app.phase_started_at);
+        assert!(render(&app, 80, 24).contains("Session"));
+        let small = render(&app, 30, 5);
+        assert!(small.contains("Focus · 01:00"), "{small:?}");
+        let narrow = render(&app, 6, 2);
+        assert!(narrow.contains("01:00") && !narrow.contains("Focus"));
+        assert!(render(&app, 3, 1).contains("Ter"));
+        render(&app, 0, 0);
+    }
+
+    #[test]
+    fn benchmark_renders_every_frame() {
+        let mut app = test_app();
+        assert!(bench(&mut app, 4).is_ok());
+        assert!(app.show_timeline);
+    }
+
+    #[test]
+    fn centers_and_clamps_rect() {
+        let area = Rect::new(0, 0, 80, 24);
+        assert_eq!(centered_rect(40, 4, area), Rect::new(20, 10, 40, 4));
+        assert_eq!(centered_rect(100, 30, area), area);
+    }
+}
@@ -0,0 +1,58 @@
+use std::borrow::Cow;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap};
+
+use super::{Palette, centered_rect};
+
+/// A short question or notice in a box over the middle of its area.
+pub struct Modal<'a> {
+    palette: Palette,
+    title: Cow<'a, str>,
+    body: Cow<'a, str>,
+    color: Color,
+}
+
+impl<'a> Modal<'a> {
+    pub fn new(
+        palette: Palette,
+        title: impl Into<Cow<'a, str>>,
+        body: impl Into<Cow<'a, str>>,
+        color: Color,
+    ) -> Self {
+        Modal {
+            palette,
+            title: title.into(),
+            body: body.into(),
+            color,
+        }
+    }
+
+    /// The box alone, bordered and titled in `color`, for overlays with more
+    /// than a line of text in them.
+    pub fn frame(title: impl Into<Cow<'a, str>>, color: Color) -> Block<'a> {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(color))
+            .title(Span::styled(
+                title,
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            ))
+    }
+}
+
+impl Widget for Modal<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area = centered_rect(44, 5, area);
+        Clear.render(area, buf);
+        Paragraph::new(self.body)
+            .wrap(Wrap { trim: true })
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(self.palette.text))
+            .block(Modal::frame(self.title, self.color))
+            .render(area, buf);
+    }
+}
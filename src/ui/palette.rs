@@ -0,0 +1,31 @@
+use ratatui::style::Color;
+
+use crate::theme::Theme;
+
+/// The colors every component draws with: the theme's three, and the grays
+/// the timer uses the same way in every theme.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub bg: Color,
+    pub accent: Color,
+    pub ok: Color,
+    /// Labels and anything worth reading.
+    pub text: Color,
+    /// Separators, hints and the keys in the help.
+    pub muted: Color,
+    pub border: Color,
+}
+
+impl From<Theme> for Palette {
+    fn from(theme: Theme) -> Self {
+        let (bg, accent, ok) = theme.colors();
+        Palette {
+            bg,
+            accent,
+            ok,
+            text: Color::White,
+            muted: Color::Gray,
+            border: Color::DarkGray,
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::borrow::Cow;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Color, Modifier};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Paragraph, Widget};
+
+use super::Palette;
+
+/// A centered line of text, with a key hint or a note of its own color after
+/// it. Also a line of the footer, through `Line::from`.
+pub struct StatusLine<'a> {
+    palette: Palette,
+    spans: Vec<Span<'a>>,
+}
+
+impl<'a> StatusLine<'a> {
+    /// `text` in the text color.
+    pub fn new(palette: Palette, text: impl Into<Cow<'a, str>>) -> Self {
+        StatusLine {
+            palette,
+            spans: vec![Span::styled(text, palette.text)],
+        }
+    }
+
+    /// `text` in the muted color, as for the hints under the gauge.
+    pub fn muted(palette: Palette, text: impl Into<Cow<'a, str>>) -> Self {
+        StatusLine::new(palette, text).color(palette.muted)
+    }
+
+    /// Recolors what the line holds so far.
+    pub fn color(mut self, color: Color) -> Self {
+        for span in &mut self.spans {
+            span.style = span.style.fg(color);
+        }
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        for span in &mut self.spans {
+            span.style = span.style.add_modifier(Modifier::BOLD);
+        }
+        self
+    }
+
+    /// `text` in its own color, two spaces on.
+    pub fn then(mut self, text: impl Into<Cow<'a, str>>, color: Color) -> Self {
+        self.spans.push(Span::raw("  "));
+        self.spans.push(Span::styled(text, color));
+        self
+    }
+
+    /// The key that acts on the line, and what it does, two spaces on.
+    pub fn hint(mut self, key: &'a str, label: impl Into<Cow<'a, str>>) -> Self {
+        self.spans.push(Span::raw("  "));
+        self.spans
+            .push(Span::styled(format!("{key} "), self.palette.muted));
+        self.spans.push(Span::styled(label, self.palette.text));
+        self
+    }
+}
+
+impl<'a> From<StatusLine<'a>> for Line<'a> {
+    fn from(status: StatusLine<'a>) -> Self {
+        Line::from(status.spans)
+    }
+}
+
+impl Widget for StatusLine<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        Paragraph::new(Line::from(self))
+            .alignment(Alignment::Center)
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,66 @@
+//! Limits on timer settings, shared by the flag parsers, the config file and the
+//! HTTP API so they all reject the same values with the same messages.
+
+use std::time::Duration;
+
+use crate::timefmt::parse_duration;
+
+pub const MAX_PHASE: Duration = Duration::from_secs(24 * 3600);
+pub const MAX_LONG_EVERY: u64 = 100;
+
+pub fn phase_length(length: Duration) -> Result<Duration, String> {
+    if length.is_zero() {
+        Err("must be at least 1 second".to_string())
+    } else if length > MAX_PHASE {
+        Err("must be at most 24 hours".to_string())
+    } else {
+        Ok(length)
+    }
+}
+
+/// Zero turns long breaks off.
+pub fn long_every(n: u64) -> Result<u64, String> {
+    if n <= MAX_LONG_EVERY {
+        Ok(n)
+    } else {
+        Err(format!("must be between 0 and {MAX_LONG_EVERY}"))
+    }
+}
+
+pub fn parse_phase_length(value: &str) -> Result<Duration, String> {
+    parse_duration(value).and_then(phase_length)
+}
+
+pub fn parse_long_every(value: &str) -> Result<u64, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("`{value}` is not a whole number"))
+        .and_then(long_every)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_empty_and_absurd_phases() {
+        assert_eq!(parse_phase_length("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_phase_length("24h"), Ok(MAX_PHASE));
+        assert!(parse_phase_length("0").unwrap_err().contains("at least"));
+        assert!(parse_phase_length("0m0s").is_err());
+        assert!(parse_phase_length("24h1s").unwrap_err().contains("at most"));
+    }
+
+    #[test]
+    fn bounds_long_every() {
+        assert_eq!(parse_long_every("4"), Ok(4));
+        assert_eq!(parse_long_every("0"), Ok(0));
+        assert!(parse_long_every("101").is_err());
+        assert!(
+            parse_long_every("four")
+                .unwrap_err()
+                .contains("whole number")
+        );
+    }
+}
@@ -0,0 +1,309 @@
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DAY: i64 = 86_400;
+
+/// A time zone from `--tz`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Zone {
+    /// Seconds east of UTC, all year.
+    Fixed(i64),
+    /// A tz database name, with daylight saving time as the system knows it.
+    Named(String),
+}
+
+/// Value parser for `--tz`: `UTC`, `+HH:MM`, `-HH:MM` or a name such as
+/// `Europe/Madrid`.
+pub fn parse_zone(value: &str) -> Result<Zone, String> {
+    if value.eq_ignore_ascii_case("utc") || value.eq_ignore_ascii_case("z") {
+        return Ok(Zone::Fixed(0));
+    }
+    if value.starts_with(['+', '-']) {
+        return parse_offset(value)
+            .filter(|offset| offset.abs() <= 14 * 3600)
+            .map(Zone::Fixed)
+            .ok_or_else(|| format!("`{value}` is not a UTC offset like +02:00"));
+    }
+    if cfg!(unix) && named_zone_exists(value) {
+        Ok(Zone::Named(value.to_string()))
+    } else {
+        Err(format!(
+            "unknown time zone `{value}`: use UTC, an offset like +02:00 or a name like Europe/Madrid"
+        ))
+    }
+}
+
+/// Whether the system's tz database has `name`.
+fn named_zone_exists(name: &str) -> bool {
+    if name.is_empty() || name.split('/').any(|part| part.is_empty() || part == "..") {
+        return false;
+    }
+    let dir = std::env::var("TZDIR").unwrap_or_else(|_| "/usr/share/zoneinfo".to_string());
+    std::path::Path::new(&dir).join(name).is_file()
+}
+
+/// `--tz`'s fixed offset, which takes the place of the system's.
+static FIXED: OnceLock<i64> = OnceLock::new();
+
+#[cfg(unix)]
+unsafe extern "C" {
+    fn tzset();
+}
+
+/// Makes `zone` local time for the rest of the process. Call it before any
+/// threads start: a named zone goes through `TZ`.
+pub fn use_zone(zone: &Zone) {
+    match zone {
+        Zone::Fixed(offset) => {
+            let _ = FIXED.set(*offset);
+        }
+        #[cfg(unix)]
+        Zone::Named(name) => {
+            // SAFETY: called at startup, before other threads read the
+            // environment; `tzset` takes the new `TZ` into account.
+            unsafe {
+                std::env::set_var("TZ", name);
+                tzset();
+            }
+        }
+        #[cfg(not(unix))]
+        Zone::Named(_) => {}
+    }
+}
+
+pub fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Offset of local time from UTC in seconds at the given instant.
+pub fn local_offset(unix: u64) -> i64 {
+    FIXED.get().copied().unwrap_or_else(|| system_offset(unix))
+}
+
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // `tm_gmtoff` is a c_long
+fn system_offset(unix: u64) -> i64 {
+    let time = unix as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: both pointers are valid for the duration of the call.
+    let result = unsafe { libc::localtime_r(&time, &mut tm) };
+    if result.is_null() {
+        0
+    } else {
+        tm.tm_gmtoff as i64
+    }
+}
+
+#[cfg(not(unix))]
+fn system_offset(_unix: u64) -> i64 {
+    0
+}
+
+/// Days since the epoch in local time.
+pub fn local_day(unix: u64) -> i64 {
+    day_at(unix, local_offset(unix))
+}
+
+pub fn seconds_of_local_day(unix: u64) -> u64 {
+    seconds_of_day_at(unix, local_offset(unix))
+}
+
+/// Days since the epoch where the clocks are `offset` seconds ahead of UTC.
+pub fn day_at(unix: u64, offset: i64) -> i64 {
+    (unix as i64 + offset).div_euclid(DAY)
+}
+
+pub fn seconds_of_day_at(unix: u64, offset: i64) -> u64 {
+    (unix as i64 + offset).rem_euclid(DAY) as u64
+}
+
+pub fn format_hh_mm(unix: u64) -> String {
+    format_hh_mm_at(unix, local_offset(unix))
+}
+
+pub fn format_hh_mm_at(unix: u64, offset: i64) -> String {
+    let secs = seconds_of_day_at(unix, offset);
+    format!("{:02}:{:02}", secs / 3600, secs % 3600 / 60)
+}
+
+/// `YYYY-MM-DD HH:MM:SS±HH:MM` in local time, which [`parse_datetime`] reads back.
+pub fn format_datetime(unix: u64) -> String {
+    format_datetime_at(unix, local_offset(unix))
+}
+
+pub fn format_datetime_at(unix: u64, offset: i64) -> String {
+    let secs = seconds_of_day_at(unix, offset);
+    let sign = if offset < 0 { '-' } else { '+' };
+    format!(
+        "{} {:02}:{:02}:{:02}{sign}{:02}:{:02}",
+        format_date(day_at(unix, offset)),
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        offset.abs() / 3600,
+        offset.abs() % 3600 / 60
+    )
+}
+
+/// Weekday of a day from [`local_day`]: Monday is 0; the epoch was a Thursday.
+pub fn weekday(day: i64) -> usize {
+    (day + 3).rem_euclid(7) as usize
+}
+
+/// Days since the epoch for a proleptic Gregorian date.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Inverse of [`days_from_civil`]: (year, month, day).
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// `YYYY-MM-DD` for a day number from [`local_day`].
+pub fn format_date(day: i64) -> String {
+    let (year, month, day) = civil_from_days(day);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+pub fn parse_date(input: &str) -> Option<i64> {
+    let mut parts = input.split('-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+/// Parses `YYYY-MM-DD[ T]HH:MM[:SS][Z|±HH:MM]` or plain unix seconds.
+/// Timestamps without an offset are read as local time.
+pub fn parse_datetime(input: &str) -> Option<u64> {
+    let input = input.trim();
+    if let Ok(unix) = input.parse::<u64>() {
+        return Some(unix);
+    }
+    let (date, rest) = input.split_at_checked(10)?;
+    let days = parse_date(date)?;
+    let rest = rest.strip_prefix(['T', ' ']).unwrap_or(rest);
+    let (time, offset) = match rest.find(['Z', 'z', '+', '-']) {
+        Some(i) => (&rest[..i], Some(parse_offset(&rest[i..])?)),
+        None => (rest, None),
+    };
+    let mut fields = time.split(':').filter(|f| !f.is_empty());
+    let hour: i64 = fields.next().map_or(Some(0), |f| f.parse().ok())?;
+    let minute: i64 = fields.next().map_or(Some(0), |f| f.parse().ok())?;
+    let second: i64 = fields.next().map_or(Some(0), |f| f.parse().ok())?;
+    if hour > 23 || minute > 59 || second > 60 || fields.next().is_some() {
+        return None;
+    }
+    let naive = days * DAY + hour * 3600 + minute * 60 + second;
+    let offset = offset.unwrap_or_else(|| local_offset(naive.max(0) as u64));
+    u64::try_from(naive - offset).ok()
+}
+
+fn parse_offset(input: &str) -> Option<i64> {
+    if input.eq_ignore_ascii_case("z") {
+        return Some(0);
+    }
+    let sign = if input.starts_with('-') { -1 } else { 1 };
+    let (hours, minutes) = input[1..].split_once(':').unwrap_or((&input[1..], "0"));
+    Some(sign * (hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn day_boundaries_are_consistent() {
+        let now = 1_700_000_000;
+        let midnight = now - seconds_of_local_day(now);
+        assert!(midnight <= now && now - midnight < DAY as u64);
+        assert_eq!(local_day(midnight), local_day(now));
+        assert_eq!(local_day(midnight - 1), local_day(now) - 1);
+    }
+
+    #[test]
+    fn formats_local_clock_time() {
+        let midnight = 1_700_000_000 - seconds_of_local_day(1_700_000_000);
+        assert_eq!(format_hh_mm(midnight), "00:00");
+        assert_eq!(format_hh_mm(midnight + 9 * 3600 + 5 * 60 + 59), "09:05");
+    }
+
+    #[test]
+    fn parses_time_zones() {
+        assert_eq!(parse_zone("UTC"), Ok(Zone::Fixed(0)));
+        assert_eq!(parse_zone("+05:30"), Ok(Zone::Fixed(5 * 3600 + 30 * 60)));
+        assert_eq!(parse_zone("-08:00"), Ok(Zone::Fixed(-8 * 3600)));
+        assert!(parse_zone("+25:00").is_err());
+        assert!(parse_zone("Mars/Olympus_Mons").is_err());
+        assert!(parse_zone("../etc/passwd").is_err());
+        // 23:30 UTC is already the next day in Tokyo, still the same in New York
+        let late = 1_700_000_000 - 1_700_000_000 % 86_400 + 23 * 3600 + 1800;
+        assert_eq!(day_at(late, 9 * 3600), day_at(late, 0) + 1);
+        assert_eq!(day_at(late, -5 * 3600), day_at(late, 0));
+        assert_eq!(seconds_of_day_at(late, 9 * 3600), 8 * 3600 + 1800);
+    }
+
+    #[test]
+    fn converts_civil_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(2023, 11, 14), 19_675);
+    }
+
+    #[test]
+    fn parses_datetimes() {
+        assert_eq!(parse_datetime("1700000000"), Some(1_700_000_000));
+        assert_eq!(parse_datetime("2023-11-14T22:13:20Z"), Some(1_700_000_000));
+        assert_eq!(
+            parse_datetime("2023-11-15 00:13:20+02:00"),
+            Some(1_700_000_000)
+        );
+        assert_eq!(
+            parse_datetime("2023-11-14T17:13:20-05:00"),
+            Some(1_700_000_000)
+        );
+        let local = parse_datetime("2023-11-14 09:30").unwrap();
+        assert_eq!(seconds_of_local_day(local), 9 * 3600 + 30 * 60);
+        assert_eq!(parse_datetime("2023-13-01 10:00"), None);
+        assert_eq!(parse_datetime("yesterday"), None);
+    }
+
+    #[test]
+    fn formatted_datetimes_round_trip() {
+        let text = format_datetime(1_700_000_000);
+        assert_eq!(text.len(), "2023-11-14 22:13:20+00:00".len());
+        assert_eq!(parse_datetime(&text), Some(1_700_000_000));
+    }
+
+    #[test]
+    fn formats_and_parses_dates() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(19_675), "2023-11-14");
+        assert_eq!(format_date(days_from_civil(2024, 2, 29)), "2024-02-29");
+        assert_eq!(parse_date("2023-11-14"), Some(19_675));
+        assert_eq!(parse_date("2023-11"), None);
+        assert_eq!(parse_date("2023-11-14-1"), None);
+    }
+}
@@ -0,0 +1,174 @@
+//! `[work_hours]`: when the working day is, so outside it the timer can suggest
+//! stopping and keep notifications quiet. `[lunch]`: a daily pause in it.
+
+use crate::wallclock;
+
+const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkHours {
+    /// Seconds into the local day.
+    pub start: u64,
+    pub end: u64,
+    /// Working weekdays, Monday first.
+    pub days: [bool; 7],
+    /// Whether notifications still come outside the hours.
+    pub notifications: bool,
+}
+
+/// 09:00–18:00, Monday to Friday, quiet outside.
+impl Default for WorkHours {
+    fn default() -> Self {
+        Self {
+            start: 9 * 3600,
+            end: 18 * 3600,
+            days: [true, true, true, true, true, false, false],
+            notifications: false,
+        }
+    }
+}
+
+impl WorkHours {
+    /// Whether `unix` is within the hours. A span past midnight, such as
+    /// 22:00–06:00, belongs to the day it starts on.
+    pub fn contains(&self, unix: u64) -> bool {
+        let day = wallclock::local_day(unix);
+        let secs = wallclock::seconds_of_local_day(unix);
+        let works = |day| self.days[wallclock::weekday(day)];
+        if self.start < self.end {
+            works(day) && (self.start..self.end).contains(&secs)
+        } else {
+            (secs >= self.start && works(day)) || (secs < self.end && works(day - 1))
+        }
+    }
+
+    /// "09:00–18:00".
+    pub fn span(&self) -> String {
+        let hh_mm = |secs: u64| format!("{:02}:{:02}", secs / 3600, secs % 3600 / 60);
+        format!("{}–{}", hh_mm(self.start), hh_mm(self.end))
+    }
+}
+
+/// A pause at the same time every day, such as 13:00–14:00, that suspends the
+/// cycle until it's over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lunch {
+    /// Seconds into the local day; `end` comes after `start`.
+    pub start: u64,
+    pub end: u64,
+}
+
+/// 13:00–14:00.
+impl Default for Lunch {
+    fn default() -> Self {
+        Self {
+            start: 13 * 3600,
+            end: 14 * 3600,
+        }
+    }
+}
+
+impl Lunch {
+    /// Seconds left of the pause at `unix`, if it's on.
+    pub fn left(&self, unix: u64) -> Option<u64> {
+        let secs = wallclock::seconds_of_local_day(unix);
+        (self.start..self.end)
+            .contains(&secs)
+            .then(|| self.end - secs)
+    }
+}
+
+/// `HH:MM` in seconds since midnight; `24:00` is the end of the day.
+pub fn parse_time(text: &str) -> Option<u64> {
+    let (hours, minutes) = text.trim().split_once(':')?;
+    let (hours, minutes): (u64, u64) = (hours.parse().ok()?, minutes.parse().ok()?);
+    let secs = hours * 3600 + minutes * 60;
+    (minutes < 60 && secs <= 24 * 3600).then_some(secs)
+}
+
+/// Weekdays by name (`"mon"`, `"Friday"`) or as ranges (`"mon-fri"`), Monday first.
+pub fn parse_days(names: &[String]) -> Result<[bool; 7], String> {
+    let day = |name: &str| {
+        let name = name.trim().to_ascii_lowercase();
+        DAYS.iter()
+            .position(|d| name.len() >= 3 && d.starts_with(&name[..3]))
+            .ok_or_else(|| format!("has an unknown day \"{name}\""))
+    };
+    let mut days = [false; 7];
+    for name in names {
+        let (first, last) = match name.split_once(['-', '–']) {
+            Some((first, last)) => (day(first)?, day(last)?),
+            None => (day(name)?, day(name)?),
+        };
+        // Ranges may wrap around the week, as in "sat-mon"
+        let mut i = first;
+        loop {
+            days[i] = true;
+            if i == last {
+                break;
+            }
+            i = (i + 1) % 7;
+        }
+    }
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `unix` of a local time on 2026-10-12, a Monday, plus `days`.
+    fn at(days: i64, hour: i64, minute: i64) -> u64 {
+        let naive =
+            (wallclock::days_from_civil(2026, 10, 12) + days) * 86_400 + hour * 3600 + minute * 60;
+        (naive - wallclock::local_offset(naive as u64)) as u64
+    }
+
+    #[test]
+    fn checks_weekdays_and_hours() {
+        let hours = WorkHours::default();
+        assert!(hours.contains(at(0, 9, 0)));
+        assert!(!hours.contains(at(0, 18, 0)));
+        assert!(!hours.contains(at(0, 8, 59)));
+        assert!(hours.contains(at(4, 17, 30)));
+        assert!(!hours.contains(at(5, 12, 0)));
+        assert_eq!(hours.span(), "09:00–18:00");
+        let night = WorkHours {
+            start: 22 * 3600,
+            end: 6 * 3600,
+            ..WorkHours::default()
+        };
+        // Friday night runs into Saturday morning, but Sunday night doesn't start
+        assert!(night.contains(at(5, 3, 0)));
+        assert!(!night.contains(at(6, 23, 0)));
+        assert!(!night.contains(at(0, 3, 0)));
+    }
+
+    #[test]
+    fn times_the_lunch_pause() {
+        let lunch = Lunch::default();
+        assert_eq!(lunch.left(at(0, 12, 59)), None);
+        assert_eq!(lunch.left(at(0, 13, 0)), Some(3600));
+        assert_eq!(lunch.left(at(6, 13, 45)), Some(15 * 60));
+        assert_eq!(lunch.left(at(0, 14, 0)), None);
+    }
+
+    #[test]
+    fn parses_times_and_days() {
+        assert_eq!(parse_time("09:30"), Some(9 * 3600 + 30 * 60));
+        assert_eq!(parse_time("24:00"), Some(24 * 3600));
+        assert_eq!(parse_time("9"), None);
+        assert_eq!(parse_time("12:60"), None);
+        let names =
+            |list: &[&str]| parse_days(&list.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        assert_eq!(
+            names(&["Mon-Wed", "friday"]),
+            Ok([true, true, true, false, true, false, false])
+        );
+        assert_eq!(
+            names(&["sat-mon"]),
+            Ok([true, false, false, false, false, true, true])
+        );
+        assert!(names(&["someday"]).is_err());
+    }
+}